@@ -12,12 +12,10 @@ use super::{
     Safe,
 };
 use crate::{
-    ipc::{IpcMsg, IpcResp},
+    ipc::{AuthGranted, IpcMsg, IpcResp},
     Error, Result,
 };
 
-use sn_interface::types::Keypair;
-
 use log::{debug, info};
 use serde_json::json;
 use std::path::{Path, PathBuf};
@@ -27,14 +25,15 @@ const SN_AUTHD_METHOD_AUTHORISE: &str = "authorise";
 
 impl Safe {
     /// Generate an authorisation request string and send it to a SAFE Authenticator.
-    /// It returns the credentials necessary to connect to the network, encoded in a single string.
+    /// It returns the credentials necessary to connect to the network, along with the key of
+    /// the Safe which authorised the application, to be set as the `register_owner`.
     pub async fn auth_app(
         app_id: &str,
         app_name: &str,
         app_vendor: &str,
         endpoint: Option<&str>,
         authd_cert_path: impl AsRef<Path>,
-    ) -> Result<Keypair> {
+    ) -> Result<AuthGranted> {
         // TODO: allow to accept all type of permissions to be passed as args to this API
         info!("Sending authorisation request to SAFE Authenticator...");
 
@@ -57,7 +56,7 @@ impl Safe {
         match IpcMsg::from_string(&auth_res) {
             Ok(IpcMsg::Resp(IpcResp::Auth(Ok(auth_granted)))) => {
                 info!("Application '{}' was authorised!", app_id);
-                Ok(auth_granted.app_keypair)
+                Ok(auth_granted)
             }
             Ok(other) => {
                 info!("Unexpected messages received: {:?}", other);
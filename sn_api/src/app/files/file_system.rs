@@ -6,51 +6,160 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{metadata::get_metadata, FilesMapChange, ProcessedFiles};
-use crate::{Error, Result, Safe, XorUrl};
-use bytes::Bytes;
-use log::info;
-use sn_client::Error as ClientError;
+use super::{
+    content_type_for, metadata::get_metadata, upload_journal::upload_chunks_with_journal,
+    FilesMapChange, ProcessedFiles,
+};
+use crate::{ContentType, Error, Result, Safe, SafeUrl, XorUrl};
+use log::{debug, info};
+use sn_client::{
+    api::{EncryptedBatch, StreamEncryptor, CMD_BATCH_MAX_SIZE},
+    Error as ClientError,
+};
+use sn_interface::types::Chunk;
 use std::{
-    fs,
+    mem,
     path::{Path, PathBuf},
 };
+use tokio::fs::File;
 use walkdir::{DirEntry, WalkDir};
 
 const MAX_RECURSIVE_DEPTH: usize = 10_000;
 
-// Upload a file to the Network
-pub(crate) async fn upload_file_to_net(safe: &Safe, path: &Path) -> Result<XorUrl> {
-    let data = fs::read(path).map_err(|err| {
-        Error::InvalidInput(format!("Failed to read file from local location: {}", err))
-    })?;
-    let data = Bytes::from(data);
-
-    let mut mime_type_for_xorurl = mime_guess::from_path(path).first_raw();
-    let result = match safe.store_bytes(data.clone(), mime_type_for_xorurl).await {
-        Ok(xorurl) => Ok(xorurl),
-        Err(Error::InvalidMediaType(_)) => {
-            // Let's then upload it and set media-type to be simply raw content
-            mime_type_for_xorurl = None;
-            safe.store_bytes(data.clone(), mime_type_for_xorurl).await
+// Chunks of the files being uploaded, bundled across files so they are sent to the network in
+// batches of up to `CMD_BATCH_MAX_SIZE` chunks, rather than a few at a time for each file.
+// A file is only reported as uploaded once all its chunks were stored.
+#[derive(Default)]
+struct ChunksBundle {
+    chunks: Vec<Chunk>,
+    // files whose last chunks are in the bundle, with the XOR-URL they are stored at
+    files: Vec<(PathBuf, PathBuf, XorUrl)>,
+}
+
+impl ChunksBundle {
+    // Self-encrypt the file into the bundle, uploading the chunks bundled whenever a full batch
+    // is reached, and returning the XOR-URL the file is to be stored at.
+    async fn add_file(
+        &mut self,
+        safe: &Safe,
+        path: &Path,
+        processed_files: &mut ProcessedFiles,
+    ) -> Result<XorUrl> {
+        let journal = safe.upload_journal.as_ref();
+        let mut encryptor = StreamEncryptor::new(open_file(path).await?);
+        let address = loop {
+            match encryptor.next_batch().await? {
+                EncryptedBatch::Chunks(chunks) => {
+                    self.chunks.extend(chunks.into_iter().filter(|chunk| {
+                        journal
+                            .filter(|j| j.is_chunk_stored(chunk.name()))
+                            .is_none()
+                    }))
+                }
+                EncryptedBatch::Done(address) => break address,
+            }
+            if self.chunks.len() >= CMD_BATCH_MAX_SIZE {
+                self.upload(safe, processed_files).await?;
+            }
+        };
+
+        // content of a media type not supported is stored as raw content
+        let content_type =
+            content_type_for(mime_guess::from_path(path).first_raw()).unwrap_or(ContentType::Raw);
+        Ok(SafeUrl::from_bytes(address, content_type)?.encode(safe.xorurl_base))
+    }
+
+    // Upload the chunks bundled, reporting the files they complete as uploaded, or as failed
+    // along with the error, which is also returned.
+    async fn upload(&mut self, safe: &Safe, processed_files: &mut ProcessedFiles) -> Result<()> {
+        let chunks = mem::take(&mut self.chunks);
+        let files = mem::take(&mut self.files);
+        debug!(
+            "Storing bundle of {} chunks completing {} files",
+            chunks.len(),
+            files.len()
+        );
+
+        let result = async {
+            let client = safe.get_safe_client()?;
+            match &safe.upload_journal {
+                Some(journal) => upload_chunks_with_journal(client, journal, chunks).await,
+                None => Ok(client.upload_chunks(&chunks, true).await?),
+            }
         }
-        other_err => other_err,
-    };
-
-    // If the upload verification failed, the file could still have been uploaded successfully,
-    // thus let's report the error but providing the xorurl for the user to be aware of.
-    if let Err(Error::ClientError(ClientError::NotEnoughChunksRetrieved { .. })) = result {
-        // Let's obtain the xorurl with using dry-run mode.
-        // Use a dry runner only for this next operation
-        let dry_runner = Safe::dry_runner(Some(safe.xorurl_base));
-        let xorurl = dry_runner.store_bytes(data, mime_type_for_xorurl).await?;
-
-        Err(Error::ContentUploadVerificationFailed(xorurl))
-    } else {
+        .await;
+
+        for (path, normalised_path, xorurl) in files {
+            let change = match &result {
+                Ok(()) => match safe
+                    .upload_journal
+                    .as_ref()
+                    .map(|j| j.record_file(&path, &xorurl))
+                {
+                    Some(Err(err)) => FilesMapChange::Failed(format!("{}", err)),
+                    _ => FilesMapChange::Added(xorurl),
+                },
+                // the file could still have been uploaded successfully, thus let's report
+                // the error but providing the xorurl for the user to be aware of
+                Err(Error::ClientError(ClientError::NotEnoughChunksRetrieved { .. })) => {
+                    let err = Error::ContentUploadVerificationFailed(xorurl);
+                    FilesMapChange::Failed(format!("{}", err))
+                }
+                Err(err) => FilesMapChange::Failed(format!("{}", err)),
+            };
+            if let FilesMapChange::Failed(err) = &change {
+                info!("Skipping file \"{}\". {}", normalised_path.display(), err);
+            }
+            processed_files.insert(normalised_path, change);
+        }
+
         result
     }
 }
 
+// Upload a file to the Network
+pub(crate) async fn upload_file_to_net(safe: &Safe, path: &Path) -> Result<XorUrl> {
+    let journal = safe.upload_journal.as_ref().filter(|_| !safe.dry_run_mode);
+    if let Some(xorurl) = journal_file_xorurl(safe, path) {
+        return Ok(xorurl);
+    }
+
+    // The file is streamed to the network, so it doesn't need to fit in memory.
+    // Content of a media type not supported is stored as raw content.
+    let media_type = mime_guess::from_path(path)
+        .first_raw()
+        .filter(|media_type| SafeUrl::is_media_type_supported(media_type));
+    let xorurl = safe
+        .store_from_reader(open_file(path).await?, media_type)
+        .await?;
+
+    if let Some(journal) = journal {
+        journal.record_file(path, &xorurl)?;
+    }
+    Ok(xorurl)
+}
+
+// If we are resuming an upload, the file may have been uploaded already
+fn journal_file_xorurl(safe: &Safe, path: &Path) -> Option<XorUrl> {
+    let journal = safe
+        .upload_journal
+        .as_ref()
+        .filter(|_| !safe.dry_run_mode)?;
+    let xorurl = journal.file_xorurl(path)?;
+    info!(
+        "Skipping upload of \"{}\" as it was already uploaded to {}",
+        path.display(),
+        xorurl
+    );
+    Some(xorurl)
+}
+
+async fn open_file(path: &Path) -> Result<File> {
+    File::open(path).await.map_err(|err| {
+        Error::InvalidInput(format!("Failed to read file from local location: {}", err))
+    })
+}
+
 // Simply change Windows style path separator into `/`
 pub(crate) fn normalise_path_separator(from: &str) -> String {
     str::replace(from, "\\", "/")
@@ -73,6 +182,10 @@ pub(crate) async fn file_system_dir_walk(
         // We now compare both FilesMaps to upload the missing files
         let max_depth = if recursive { MAX_RECURSIVE_DEPTH } else { 1 };
         let mut processed_files = ProcessedFiles::default();
+        // the chunks of the files are uploaded in bundles, unless we are only
+        // calculating their XOR-URLs
+        let bundle_chunks = !safe.dry_run_mode;
+        let mut bundle = ChunksBundle::default();
         let children_to_process = WalkDir::new(location)
             .follow_links(follow_links)
             .into_iter()
@@ -119,7 +232,29 @@ pub(crate) async fn file_system_dir_walk(
                         );
                     }
 
-                    if metadata.file_type().is_file() {
+                    if metadata.file_type().is_file() && bundle_chunks {
+                        if let Some(xorurl) = journal_file_xorurl(safe, current_file_path) {
+                            processed_files.insert(normalised_path, FilesMapChange::Added(xorurl));
+                            continue;
+                        }
+                        match bundle
+                            .add_file(safe, current_file_path, &mut processed_files)
+                            .await
+                        {
+                            Ok(xorurl) => bundle.files.push((
+                                current_file_path.to_path_buf(),
+                                normalised_path,
+                                xorurl,
+                            )),
+                            Err(err) => {
+                                info!("Skipping file \"{}\". {}", normalised_path.display(), err);
+                                processed_files.insert(
+                                    normalised_path,
+                                    FilesMapChange::Failed(format!("{}", err)),
+                                );
+                            }
+                        }
+                    } else if metadata.file_type().is_file() {
                         match upload_file_to_net(safe, current_file_path).await {
                             Ok(xorurl) => {
                                 processed_files
@@ -145,6 +280,11 @@ pub(crate) async fn file_system_dir_walk(
             }
         }
 
+        if bundle_chunks {
+            // errors were reported for each of the files whose chunks couldn't be stored
+            let _ = bundle.upload(safe, &mut processed_files).await;
+        }
+
         Ok(processed_files)
     } else {
         // Recursive only works on a dir path. Let's error as the user may be making a mistake
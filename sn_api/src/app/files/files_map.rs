@@ -14,7 +14,11 @@ use super::{
 use crate::{app::consts::*, Error, Result, Safe, XorUrl};
 use log::{debug, info};
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, fs, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 // To use for mapping files names (with path in a flattened hierarchy) to FileInfos
 pub type FilesMap = BTreeMap<String, FileInfo>;
@@ -282,3 +286,88 @@ fn filesmap_chroot(urlpath: &str, files_map: &FilesMap) -> Result<FilesMap> {
         Ok(filtered_filesmap)
     }
 }
+
+/// Returns the changes to be applied to the `base` FilesMap to obtain the `target` FilesMap.
+/// An item is considered updated if it changed its type, its link, or its symlink target.
+pub(crate) fn files_maps_diff(base: &FilesMap, target: &FilesMap) -> ProcessedFiles {
+    let mut changes = ProcessedFiles::new();
+
+    for (path, file_info) in target {
+        let change = match base.get(path) {
+            None => FilesMapChange::Added(file_item_link(file_info)),
+            Some(base_info) if is_file_item_changed(base_info, file_info) => {
+                FilesMapChange::Updated(file_item_link(file_info))
+            }
+            Some(_) => continue,
+        };
+        changes.insert(PathBuf::from(path), change);
+    }
+
+    for (path, file_info) in base {
+        if !target.contains_key(path) {
+            changes.insert(
+                PathBuf::from(path),
+                FilesMapChange::Removed(file_item_link(file_info)),
+            );
+        }
+    }
+
+    changes
+}
+
+fn is_file_item_changed(base: &FileInfo, target: &FileInfo) -> bool {
+    [PREDICATE_TYPE, PREDICATE_LINK, "symlink_target"]
+        .iter()
+        .any(|predicate| base.get(*predicate) != target.get(*predicate))
+}
+
+// note: files have link property, dirs and symlinks do not
+fn file_item_link(file_item: &FileInfo) -> XorUrl {
+    file_item.get(PREDICATE_LINK).cloned().unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{files_maps_diff, FileInfo, FilesMap, FilesMapChange};
+    use crate::app::consts::*;
+    use std::path::Path;
+
+    fn file_item(link: &str, modified: &str) -> FileInfo {
+        [
+            (PREDICATE_TYPE, "text/plain"),
+            (PREDICATE_LINK, link),
+            (PREDICATE_MODIFIED, modified),
+        ]
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.to_string()))
+        .collect()
+    }
+
+    #[test]
+    fn files_maps_diff_should_report_all_changes() {
+        let mut base = FilesMap::new();
+        base.insert("/same".to_string(), file_item("safe://same", "1"));
+        base.insert("/touched".to_string(), file_item("safe://touched", "1"));
+        base.insert("/updated".to_string(), file_item("safe://old", "1"));
+        base.insert("/removed".to_string(), file_item("safe://removed", "1"));
+
+        let mut target = FilesMap::new();
+        target.insert("/same".to_string(), file_item("safe://same", "1"));
+        // only the content is compared, not metadata like the modification time
+        target.insert("/touched".to_string(), file_item("safe://touched", "2"));
+        target.insert("/updated".to_string(), file_item("safe://new", "1"));
+        target.insert("/added".to_string(), file_item("safe://added", "1"));
+
+        let changes = files_maps_diff(&base, &target);
+        assert_eq!(changes.len(), 3);
+        assert!(
+            matches!(changes.get(Path::new("/added")), Some(FilesMapChange::Added(link)) if link == "safe://added")
+        );
+        assert!(
+            matches!(changes.get(Path::new("/updated")), Some(FilesMapChange::Updated(link)) if link == "safe://new")
+        );
+        assert!(
+            matches!(changes.get(Path::new("/removed")), Some(FilesMapChange::Removed(link)) if link == "safe://removed")
+        );
+    }
+}
@@ -10,6 +10,7 @@ mod file_system;
 mod files_map;
 mod metadata;
 mod realpath;
+mod upload_journal;
 
 use crate::{
     app::consts::*, app::nrs::VersionHash, resolver::Range, ContentType, DataType, Error, Result,
@@ -19,16 +20,21 @@ use bytes::{Buf, Bytes};
 use file_system::{
     file_system_dir_walk, file_system_single_file, normalise_path_separator, upload_file_to_net,
 };
-use files_map::add_or_update_file_item;
+use files_map::{add_or_update_file_item, files_maps_diff};
 use log::{debug, info, warn};
 use relative_path::RelativePath;
-use sn_client::Client;
+use sn_client::{
+    api::{EncryptedBatch, StreamEncryptor},
+    Client, Error as ClientError,
+};
 use std::{
     collections::{BTreeMap, HashSet},
     iter::FromIterator,
     path::{Path, PathBuf},
     str,
 };
+use tokio::io::AsyncRead;
+use upload_journal::{upload_chunks_with_journal, upload_with_journal};
 use xor_name::XorName;
 
 pub(crate) use files_map::{file_map_for_path, get_file_link_and_metadata};
@@ -36,10 +42,21 @@ pub(crate) use metadata::FileMeta;
 pub(crate) use realpath::RealPath;
 
 pub use files_map::{FileInfo, FilesMap, FilesMapChange, GetAttr};
+pub use sn_client::api::FileReader;
+pub use upload_journal::UploadJournal;
 
 // List of files uploaded with details if they were added, updated or removed from FilesContainer
 pub type ProcessedFiles = BTreeMap<PathBuf, FilesMapChange>;
 
+/// Target to compare a `FilesContainer` against with [`Safe::files_container_diff`]
+#[derive(Debug, Clone, Copy)]
+pub enum FilesContainerDiffTarget<'a> {
+    /// A `FilesContainer`, or a specific version of it, at the given URL
+    Url(&'a str),
+    /// A local folder, or file
+    LocalPath(&'a Path),
+}
+
 const ERROR_MSG_NO_FILES_CONTAINER_FOUND: &str = "No FilesContainer found at this address";
 // Type tag to use for the FilesContainer stored on Register
 pub(crate) const FILES_CONTAINER_TYPE_TAG: u64 = 1_100;
@@ -301,6 +318,80 @@ impl Safe {
         .await
     }
 
+    /// # Compare a `FilesContainer` against another version of it, or against a local folder.
+    ///
+    /// Returns the changes which would turn the `FilesContainer` at the URL provided (at the
+    /// version specified in the URL, or latest otherwise) into the target, without uploading or
+    /// writing anything to the network. When the target is a local folder, the changes are those
+    /// that a recursive [`Safe::files_container_sync`] with the `delete` flag set would make.
+    /// Paths in the returned change set are those of the items in the `FilesContainer`.
+    ///
+    /// ## Example
+    ///
+    /// ```no_run
+    /// # use sn_api::{files::FilesContainerDiffTarget, Safe};
+    /// # use std::path::Path;
+    /// # let rt = tokio::runtime::Runtime::new().unwrap();
+    /// # rt.block_on(async {
+    /// #   let safe = Safe::connected(None, None, None, None).await.unwrap();
+    ///     let (xorurl, _processed_files, _files_map) = safe.files_container_create_from("./testdata", None, true, false).await.unwrap();
+    ///     let target = FilesContainerDiffTarget::LocalPath(Path::new("./testdata"));
+    ///     let changes = safe.files_container_diff(&xorurl, target, false).await.unwrap();
+    ///     println!("Changes a sync would make: {:?}", changes);
+    /// # });
+    /// ```
+    pub async fn files_container_diff(
+        &self,
+        url: &str,
+        target: FilesContainerDiffTarget<'_>,
+        follow_links: bool,
+    ) -> Result<ProcessedFiles> {
+        let safe_url = self.parse_and_resolve_url(url).await?;
+        let base_files_map = match self.fetch_files_container(&safe_url).await? {
+            Some((_, files_map)) => files_map,
+            None => FilesMap::default(),
+        };
+
+        match target {
+            FilesContainerDiffTarget::Url(target_url) => {
+                let target_files_map = match self.files_container_get(target_url).await? {
+                    Some((_, files_map)) => files_map,
+                    None => FilesMap::default(),
+                };
+                Ok(files_maps_diff(&base_files_map, &target_files_map))
+            }
+            FilesContainerDiffTarget::LocalPath(location) => {
+                // Calculate what a sync would result in, using a dry runner
+                // so nothing gets uploaded while processing the local files
+                let dry_runner = Safe::dry_runner(Some(self.xorurl_base));
+                let processed_files =
+                    file_system_dir_walk(&dry_runner, location, true, follow_links).await?;
+
+                let (sync_results, target_files_map, _) = files_map_sync(
+                    &dry_runner,
+                    base_files_map.clone(),
+                    location,
+                    processed_files,
+                    Some(Path::new(safe_url.path())),
+                    true,
+                    false,
+                    true,
+                    follow_links,
+                )
+                .await?;
+
+                // Report the changes along with any local file which failed to be processed
+                let mut changes = files_maps_diff(&base_files_map, &target_files_map);
+                changes.extend(
+                    sync_results
+                        .into_iter()
+                        .filter(|(_, change)| !change.is_success()),
+                );
+                Ok(changes)
+            }
+        }
+    }
+
     /// # Add a file, either a local path or an already uploaded file, on an existing `FilesContainer`.
     ///
     /// ## Example
@@ -600,19 +691,7 @@ impl Safe {
     /// # });
     /// ```
     pub async fn store_bytes(&self, bytes: Bytes, media_type: Option<&str>) -> Result<XorUrl> {
-        let content_type = media_type.map_or_else(
-            || Ok(ContentType::Raw),
-            |media_type_str| {
-                if SafeUrl::is_media_type_supported(media_type_str) {
-                    Ok(ContentType::MediaType(media_type_str.to_string()))
-                } else {
-                    Err(Error::InvalidMediaType(format!(
-                        "Media-type '{}' not supported. You can pass 'None' as the 'media_type' for this content to be treated as raw",
-                        media_type_str
-                    )))
-                }
-            },
-        )?;
+        let content_type = content_type_for(media_type)?;
 
         let address = if self.dry_run_mode {
             debug!(
@@ -623,13 +702,77 @@ impl Safe {
         } else {
             debug!("Storing {} bytes of data", bytes.len());
             let client = self.get_safe_client()?;
-            client.upload_and_verify(bytes).await?
+            match &self.upload_journal {
+                Some(journal) => upload_with_journal(client, journal, bytes).await?,
+                None => client.upload_and_verify(bytes).await?,
+            }
         };
         let xorurl = SafeUrl::from_bytes(address, content_type)?.encode(self.xorurl_base);
 
         Ok(xorurl)
     }
 
+    /// # Store a file from a reader
+    ///
+    /// Store the content read from the given reader onto the network, in the same way as with
+    /// [`Safe::store_bytes`] but without ever holding the whole content in memory. The content is
+    /// self-encrypted incrementally as it's read, and each batch of chunks produced is uploaded
+    /// before reading further, which allows storing files larger than the available memory.
+    /// If some of the chunks couldn't be verified as stored, the whole content is still uploaded
+    /// and [`Error::ContentUploadVerificationFailed`] returned with its XOR-URL.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// # use sn_api::Safe;
+    /// # let rt = tokio::runtime::Runtime::new().unwrap();
+    /// # rt.block_on(async {
+    /// #   let safe = Safe::connected(None, None, None, None).await.unwrap();
+    ///     let file = tokio::fs::File::open("./testdata/test.md").await.unwrap();
+    ///     let xorurl = safe.store_from_reader(file, Some("text/markdown")).await.unwrap();
+    ///     println!("File stored at: {}", xorurl);
+    /// # });
+    /// ```
+    pub async fn store_from_reader<R: AsyncRead + Unpin>(
+        &self,
+        reader: R,
+        media_type: Option<&str>,
+    ) -> Result<XorUrl> {
+        let content_type = content_type_for(media_type)?;
+
+        let mut encryptor = StreamEncryptor::new(reader);
+        let mut unverified = false;
+        let address = loop {
+            let chunks = match encryptor.next_batch().await? {
+                EncryptedBatch::Chunks(chunks) => chunks,
+                EncryptedBatch::Done(address) => break address,
+            };
+            if self.dry_run_mode {
+                continue;
+            }
+
+            debug!("Storing batch of {} chunks", chunks.len());
+            let client = self.get_safe_client()?;
+            let result = match &self.upload_journal {
+                Some(journal) => upload_chunks_with_journal(client, journal, chunks).await,
+                None => Ok(client.upload_chunks(&chunks, true).await?),
+            };
+            match result {
+                // The chunks could still have been stored successfully, thus the upload carries
+                // on, so the error can be reported along with the xorurl of the content
+                Err(Error::ClientError(ClientError::NotEnoughChunksRetrieved { .. })) => {
+                    unverified = true;
+                }
+                other => other?,
+            }
+        };
+        let xorurl = SafeUrl::from_bytes(address, content_type)?.encode(self.xorurl_base);
+
+        if unverified {
+            return Err(Error::ContentUploadVerificationFailed(xorurl));
+        }
+        Ok(xorurl)
+    }
+
     /// # Get a file
     /// Get file from the network.
     ///
@@ -652,6 +795,41 @@ impl Safe {
         self.fetch_data(&safe_url, range).await
     }
 
+    /// # Get a file as a stream
+    /// Get a reader of a file's content which fetches its chunks from the network lazily,
+    /// thus not requiring to hold the whole file in memory.
+    ///
+    /// ## Example
+    /// ```no_run
+    /// # use sn_api::Safe;
+    /// # use bytes::Bytes;
+    /// # use futures::TryStreamExt;
+    /// # let rt = tokio::runtime::Runtime::new().unwrap();
+    /// # rt.block_on(async {
+    /// #   let safe = Safe::connected(None, None, None, None).await.unwrap();
+    ///     let data = Bytes::from("Something super good");
+    ///     let xorurl = safe.store_bytes(data.clone(), None).await.unwrap();
+    ///     let reader = safe.files_get_stream(&xorurl).await.unwrap();
+    ///     let received_data: Vec<Bytes> = reader.try_collect().await.unwrap();
+    ///     assert_eq!(received_data.concat(), data);
+    /// # });
+    /// ```
+    pub async fn files_get_stream(&self, url: &str) -> Result<FileReader> {
+        let safe_url = self.parse_and_resolve_url(url).await?;
+        match safe_url.data_type() {
+            DataType::File => {
+                let address = safe_url.xorname();
+                debug!("Attempting to stream data from {:?}", address);
+                let client = self.get_safe_client()?;
+                client
+                    .read_stream(address)
+                    .await
+                    .map_err(|e| Error::NetDataError(format!("Failed to GET file: {:?}", e)))
+            }
+            other => Err(Error::ContentError(format!("{}", other))),
+        }
+    }
+
     /// Fetch a file from a `SafeUrl` without performing any type of URL resolution
     pub(crate) async fn fetch_data(&self, safe_url: &SafeUrl, range: Range) -> Result<Bytes> {
         match safe_url.data_type() {
@@ -1199,6 +1377,23 @@ async fn files_map_create(
     Ok(files_map)
 }
 
+// Get the ContentType to be encoded in the XOR-URL of content with the given media type
+fn content_type_for(media_type: Option<&str>) -> Result<ContentType> {
+    media_type.map_or_else(
+        || Ok(ContentType::Raw),
+        |media_type_str| {
+            if SafeUrl::is_media_type_supported(media_type_str) {
+                Ok(ContentType::MediaType(media_type_str.to_string()))
+            } else {
+                Err(Error::InvalidMediaType(format!(
+                    "Media-type '{}' not supported. You can pass 'None' as the 'media_type' for this content to be treated as raw",
+                    media_type_str
+                )))
+            }
+        },
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1632,6 +1827,57 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_files_container_diff() -> Result<()> {
+        let safe = new_safe_instance().await?;
+        let (xorurl, _, _) = new_files_container_from_testdata(&safe).await?;
+
+        // nothing changed locally since the FilesContainer was created
+        let changes = safe
+            .files_container_diff(
+                &xorurl,
+                FilesContainerDiffTarget::LocalPath(Path::new(TEST_DATA_FOLDER)),
+                false,
+            )
+            .await?;
+        assert!(changes.is_empty());
+
+        let (version0, _) = safe
+            .files_container_get(&xorurl)
+            .await?
+            .ok_or_else(|| anyhow!("files container was unexpectedly empty"))?;
+        let _ = safe
+            .files_container_sync("./testdata/subfolder/", &xorurl, true, true, false, false)
+            .await?;
+
+        let mut safe_url = SafeUrl::from_url(&xorurl)?;
+        safe_url.set_content_version(Some(version0));
+        let changes = safe
+            .files_container_diff(
+                &safe_url.to_string(),
+                FilesContainerDiffTarget::Url(&xorurl),
+                false,
+            )
+            .await?;
+        assert_eq!(changes.len(), 2);
+        assert!(changes[Path::new("/subexists.md")].is_added());
+        assert!(changes[Path::new("/sub2.md")].is_added());
+
+        // a sync with 'delete' from the original folder would remove the files just added
+        let changes = safe
+            .files_container_diff(
+                &xorurl,
+                FilesContainerDiffTarget::LocalPath(Path::new(TEST_DATA_FOLDER)),
+                false,
+            )
+            .await?;
+        assert_eq!(changes.len(), 2);
+        assert!(changes[Path::new("/subexists.md")].is_removed());
+        assert!(changes[Path::new("/sub2.md")].is_removed());
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_files_container_sync_dry_run() -> Result<()> {
         let mut safe = new_safe_instance().await?;
@@ -0,0 +1,358 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{app::helpers::systemtime_to_rfc3339, Error, Result, XorUrl};
+use bytes::Bytes;
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use sn_client::{api::CMD_BATCH_MAX_SIZE, Client};
+use sn_interface::types::Chunk;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+use xor_name::XorName;
+
+// Each line of the journal file is one of these records, serialised as JSON.
+// Records are only ever appended, so an interrupted process can at most
+// leave a truncated last line behind, which is ignored when reloading.
+#[derive(Debug, Serialize, Deserialize)]
+enum JournalRecord {
+    Chunk(XorName),
+    File {
+        path: PathBuf,
+        size: u64,
+        modified: Option<String>,
+        xorurl: XorUrl,
+    },
+}
+
+// Local file details used to tell if a journaled file was modified since it was uploaded
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FileStamp {
+    size: u64,
+    modified: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct JournalState {
+    chunks: BTreeSet<XorName>,
+    files: BTreeMap<PathBuf, (FileStamp, XorUrl)>,
+}
+
+/// On-disk journal of the chunks and files confirmed as stored on the network during an upload.
+///
+/// When set on a [`crate::Safe`] instance, files and chunks already recorded in the journal are
+/// not uploaded again, so an interrupted `files_container_create_from` or `files_container_sync`
+/// can be resumed by re-running it with a journal opened from the same path.
+#[derive(Debug, Clone)]
+pub struct UploadJournal {
+    path: PathBuf,
+    state: Arc<Mutex<JournalState>>,
+}
+
+impl UploadJournal {
+    /// Open the journal found at the given path, or start an empty one if it doesn't exist.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let mut state = JournalState::default();
+
+        if path.exists() {
+            let content = fs::read_to_string(&path).map_err(|err| journal_error(&path, err))?;
+            for line in content.lines() {
+                match serde_json::from_str(line) {
+                    Ok(JournalRecord::Chunk(name)) => {
+                        let _ = state.chunks.insert(name);
+                    }
+                    Ok(JournalRecord::File {
+                        path,
+                        size,
+                        modified,
+                        xorurl,
+                    }) => {
+                        let _ = state
+                            .files
+                            .insert(path, (FileStamp { size, modified }, xorurl));
+                    }
+                    Err(err) => {
+                        warn!(
+                            "Ignoring invalid record in upload journal at {}: {:?}",
+                            path.display(),
+                            err
+                        );
+                    }
+                }
+            }
+            // make sure new records don't get appended onto a truncated one
+            if !content.is_empty() && !content.ends_with('\n') {
+                let mut file = OpenOptions::new()
+                    .append(true)
+                    .open(&path)
+                    .map_err(|err| journal_error(&path, err))?;
+                file.write_all(b"\n")
+                    .map_err(|err| journal_error(&path, err))?;
+            }
+
+            debug!(
+                "Upload journal loaded from {}: {} chunks and {} files already uploaded",
+                path.display(),
+                state.chunks.len(),
+                state.files.len()
+            );
+        } else if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|err| journal_error(&path, err))?;
+        }
+
+        Ok(Self {
+            path,
+            state: Arc::new(Mutex::new(state)),
+        })
+    }
+
+    /// Start a new empty journal at the given path, discarding any existing one.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if path.exists() {
+            fs::remove_file(path).map_err(|err| journal_error(path, err))?;
+        }
+        Self::open(path)
+    }
+
+    /// Path of the journal file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Number of chunks recorded as stored on the network so far.
+    pub fn chunks_count(&self) -> usize {
+        self.lock().chunks.len()
+    }
+
+    /// Number of local files recorded as uploaded so far.
+    pub fn files_count(&self) -> usize {
+        self.lock().files.len()
+    }
+
+    /// Remove the journal file, e.g. once the upload it tracks has completed.
+    pub fn remove(self) -> Result<()> {
+        if self.path.exists() {
+            fs::remove_file(&self.path).map_err(|err| journal_error(&self.path, err))?;
+        }
+        Ok(())
+    }
+
+    /// Returns the XOR-URL a local file was uploaded to, as long as it
+    /// hasn't been modified since it was recorded in the journal.
+    pub(crate) fn file_xorurl(&self, path: &Path) -> Option<XorUrl> {
+        let stamp = file_stamp(path).ok()?;
+        match self.lock().files.get(path) {
+            Some((recorded, xorurl)) if *recorded == stamp => Some(xorurl.clone()),
+            _ => None,
+        }
+    }
+
+    /// Record a local file as uploaded to the given XOR-URL.
+    pub(crate) fn record_file(&self, path: &Path, xorurl: &str) -> Result<()> {
+        let stamp = file_stamp(path)?;
+        let record = JournalRecord::File {
+            path: path.to_path_buf(),
+            size: stamp.size,
+            modified: stamp.modified.clone(),
+            xorurl: xorurl.to_string(),
+        };
+
+        let mut state = self.lock();
+        self.append(&[record])?;
+        let _ = state
+            .files
+            .insert(path.to_path_buf(), (stamp, xorurl.to_string()));
+        Ok(())
+    }
+
+    pub(crate) fn is_chunk_stored(&self, name: &XorName) -> bool {
+        self.lock().chunks.contains(name)
+    }
+
+    /// Record a set of chunks as stored on the network.
+    pub(crate) fn record_chunks(&self, names: &[XorName]) -> Result<()> {
+        let records = names
+            .iter()
+            .map(|name| JournalRecord::Chunk(*name))
+            .collect::<Vec<_>>();
+
+        let mut state = self.lock();
+        self.append(&records)?;
+        state.chunks.extend(names);
+        Ok(())
+    }
+
+    // Appends records to the journal file, flushing them to disk before returning
+    fn append(&self, records: &[JournalRecord]) -> Result<()> {
+        let mut lines = String::new();
+        for record in records {
+            let line = serde_json::to_string(record).map_err(|err| {
+                Error::Serialisation(format!("Couldn't serialise upload journal record: {err}"))
+            })?;
+            lines.push_str(&line);
+            lines.push('\n');
+        }
+
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| journal_error(&self.path, err))?;
+        file.write_all(lines.as_bytes())
+            .and_then(|_| file.sync_data())
+            .map_err(|err| journal_error(&self.path, err))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, JournalState> {
+        // the state is never left inconsistent by a panicking holder, so it's safe to recover it
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+// Self-encrypt the bytes and upload only those chunks not yet recorded in the journal.
+pub(crate) async fn upload_with_journal(
+    client: &Client,
+    journal: &UploadJournal,
+    bytes: Bytes,
+) -> Result<XorName> {
+    let (address, chunks) = Client::chunk_bytes(bytes)?;
+    debug!("Uploading chunks not found in upload journal for content at {address:?}");
+    upload_chunks_with_journal(client, journal, chunks).await?;
+    Ok(address)
+}
+
+// Upload those chunks not yet recorded in the journal, recording
+// each batch of chunks as soon as it was verified as stored on the network.
+pub(crate) async fn upload_chunks_with_journal(
+    client: &Client,
+    journal: &UploadJournal,
+    chunks: Vec<Chunk>,
+) -> Result<()> {
+    let pending = chunks
+        .into_iter()
+        .filter(|chunk| !journal.is_chunk_stored(chunk.name()))
+        .collect::<Vec<_>>();
+
+    for batch in pending.chunks(CMD_BATCH_MAX_SIZE) {
+        client.upload_chunks(batch, true).await?;
+        let names = batch.iter().map(|chunk| *chunk.name()).collect::<Vec<_>>();
+        journal.record_chunks(&names)?;
+    }
+
+    Ok(())
+}
+
+fn file_stamp(path: &Path) -> Result<FileStamp> {
+    let metadata = fs::metadata(path).map_err(|err| {
+        Error::FileSystemError(format!(
+            "Failed to read metadata of {}: {}",
+            path.display(),
+            err
+        ))
+    })?;
+
+    Ok(FileStamp {
+        size: metadata.len(),
+        modified: metadata.modified().ok().map(systemtime_to_rfc3339),
+    })
+}
+
+fn journal_error(path: &Path, err: std::io::Error) -> Error {
+    Error::FileSystemError(format!(
+        "Failed to access upload journal at {}: {}",
+        path.display(),
+        err
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UploadJournal;
+
+    use assert_fs::prelude::*;
+    use color_eyre::Result;
+    use std::io::Write;
+    use xor_name::XorName;
+
+    #[test]
+    fn upload_journal_should_survive_reopening() -> Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let journal_path = tmp_dir.child("journal");
+        let file = tmp_dir.child("file.txt");
+        file.write_str("some content")?;
+
+        let chunk = XorName::from_content(b"chunk");
+        let journal = UploadJournal::create(journal_path.path())?;
+        journal.record_chunks(&[chunk])?;
+        journal.record_file(file.path(), "safe://file")?;
+
+        let reopened = UploadJournal::open(journal_path.path())?;
+        assert!(reopened.is_chunk_stored(&chunk));
+        assert!(!reopened.is_chunk_stored(&XorName::from_content(b"other")));
+        assert_eq!(
+            reopened.file_xorurl(file.path()),
+            Some("safe://file".to_string())
+        );
+        assert_eq!(reopened.chunks_count(), 1);
+        assert_eq!(reopened.files_count(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn upload_journal_should_ignore_modified_files() -> Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let file = tmp_dir.child("file.txt");
+        file.write_str("some content")?;
+
+        let journal = UploadJournal::create(tmp_dir.child("journal").path())?;
+        journal.record_file(file.path(), "safe://file")?;
+        file.write_str("some other content")?;
+
+        assert_eq!(journal.file_xorurl(file.path()), None);
+        Ok(())
+    }
+
+    #[test]
+    fn upload_journal_should_skip_truncated_records() -> Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let journal_path = tmp_dir.child("journal");
+
+        let chunk = XorName::from_content(b"chunk");
+        let journal = UploadJournal::create(journal_path.path())?;
+        journal.record_chunks(&[chunk])?;
+
+        // simulate a process dying halfway through appending a record
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(journal_path.path())?;
+        file.write_all(b"{\"Chunk\":[1,2,")?;
+
+        let reopened = UploadJournal::open(journal_path.path())?;
+        assert_eq!(reopened.chunks_count(), 1);
+
+        // records appended after reopening are not lost
+        let other_chunk = XorName::from_content(b"other");
+        reopened.record_chunks(&[other_chunk])?;
+        let reopened = UploadJournal::open(journal_path.path())?;
+        assert_eq!(reopened.chunks_count(), 2);
+
+        // starting a new journal discards previous records
+        let fresh = UploadJournal::create(journal_path.path())?;
+        assert_eq!(fresh.chunks_count(), 0);
+        Ok(())
+    }
+}
@@ -7,15 +7,72 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::Safe;
-use crate::{Error, Result, SafeUrl};
+use crate::{crypto::derive_hardened_key, Error, Result, SafeUrl};
 
+use bip39::Mnemonic;
 use bls::SecretKey as BlsSecretKey;
 use sn_interface::types::{Keypair, SecretKey};
 
 use hex::encode;
-use std::path::Path;
+use std::{fmt, path::Path, str::FromStr};
+use tiny_keccak::{Hasher, Sha3};
 use xor_name::XorName;
 
+/// Number of words of the seed phrases generated.
+const SEED_PHRASE_WORD_COUNT: usize = 24;
+
+// Domain separator used when deriving the master key from the seed of a seed phrase.
+const MASTER_KEY_DOMAIN: &[u8] = b"safe-network-bls-master-key";
+
+/// The purpose a key is derived for from a master key, each purpose being
+/// a separate branch of the hierarchy of keys derived from the master key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyPurpose {
+    /// Signing the messages sent to the network, e.g. by the CLI.
+    Signing,
+    /// Owning DBCs.
+    DbcOwnership,
+    /// Authoring registers.
+    RegisterAuthorship,
+}
+
+impl KeyPurpose {
+    // Index the branch of keys for this purpose is derived with from the master key.
+    fn derivation_index(&self) -> &'static [u8] {
+        match self {
+            Self::Signing => b"signing",
+            Self::DbcOwnership => b"dbc-ownership",
+            Self::RegisterAuthorship => b"register-authorship",
+        }
+    }
+}
+
+impl fmt::Display for KeyPurpose {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Signing => write!(f, "signing"),
+            Self::DbcOwnership => write!(f, "dbc"),
+            Self::RegisterAuthorship => write!(f, "register"),
+        }
+    }
+}
+
+impl FromStr for KeyPurpose {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "signing" => Ok(Self::Signing),
+            "dbc" => Ok(Self::DbcOwnership),
+            "register" => Ok(Self::RegisterAuthorship),
+            other => Err(Error::InvalidInput(format!(
+                "Unknown key purpose '{}', it must be one of 'signing', 'dbc' or 'register'",
+                other
+            ))),
+        }
+    }
+}
+
 impl Safe {
     /// Check the XOR/NRS-URL corresponds to the public key derived from the provided client id.
     pub async fn validate_sk_for_url(&self, secret_key: &SecretKey, url: &str) -> Result<String> {
@@ -50,6 +107,62 @@ impl Safe {
         Ok((keypair, url))
     }
 
+    /// Generate a new random BIP39 seed phrase, which a master key can be derived from
+    /// with [`Safe::master_key_from_seed_phrase`].
+    pub fn new_seed_phrase() -> Result<String> {
+        let mnemonic = Mnemonic::generate(SEED_PHRASE_WORD_COUNT).map_err(|err| {
+            Error::InvalidInput(format!("Failed to generate seed phrase: {}", err))
+        })?;
+        Ok(mnemonic.to_string())
+    }
+
+    /// Derive the master key from a BIP39 seed phrase and an optional passphrase.
+    ///
+    /// All the keys of a user can be derived from the master key with [`Safe::derive_child_key`],
+    /// so they can all be restored from the seed phrase.
+    pub fn master_key_from_seed_phrase(
+        seed_phrase: &str,
+        passphrase: &str,
+    ) -> Result<BlsSecretKey> {
+        let mnemonic = Mnemonic::parse(seed_phrase)
+            .map_err(|err| Error::InvalidInput(format!("Invalid seed phrase: {}", err)))?;
+        let seed = mnemonic.to_seed(passphrase);
+
+        // Not every 32 bytes value is a valid BLS secret key, thus we
+        // hash the seed with a counter until we obtain a valid one.
+        for counter in 0..=u8::MAX {
+            let mut hasher = Sha3::v256();
+            let mut bytes = [0; 32];
+            hasher.update(MASTER_KEY_DOMAIN);
+            hasher.update(&seed);
+            hasher.update(&[counter]);
+            hasher.finalize(&mut bytes);
+            if let Ok(sk) = BlsSecretKey::from_bytes(bytes) {
+                return Ok(sk);
+            }
+        }
+
+        Err(Error::InvalidInput(
+            "Failed to derive a valid master key from the seed phrase".to_string(),
+        ))
+    }
+
+    /// Derive the child key with the given index, for the given purpose, from a master key.
+    ///
+    /// The derivation is hardened, thus neither the master key nor any other child key
+    /// can be obtained from a child key, and the master secret key is needed to derive
+    /// the public key of a child key.
+    pub fn derive_child_key(
+        master_key: &BlsSecretKey,
+        purpose: KeyPurpose,
+        index: u32,
+    ) -> BlsSecretKey {
+        derive_hardened_key(
+            master_key,
+            &[purpose.derivation_index(), &index.to_be_bytes()],
+        )
+    }
+
     /// Serializes a `SecretKey` to hex in a file at a given path.
     ///
     /// If the path already exists it will be overwritten.
@@ -70,15 +183,20 @@ impl Safe {
 
 #[cfg(test)]
 mod tests {
-    use super::{Safe, SafeUrl};
+    use super::{KeyPurpose, Safe, SafeUrl};
     use sn_interface::types::Keypair;
 
     use assert_fs::prelude::*;
-    use bls::SecretKey as BlsSecretKey;
+    use bls::{group::ff::Field, Fr, SecretKey as BlsSecretKey};
     use color_eyre::{eyre::eyre, Result};
     use predicates::prelude::*;
     use xor_name::XorName;
 
+    // Returns the scalar a secret key is made of.
+    fn scalar(sk: &BlsSecretKey) -> Result<Fr> {
+        Option::from(Fr::from_bytes_be(&sk.to_bytes())).ok_or_else(|| eyre!("invalid scalar"))
+    }
+
     #[test]
     fn new_keypair_should_generate_bls_keypair() -> Result<()> {
         let safe = Safe::dry_runner(None);
@@ -97,6 +215,63 @@ mod tests {
         }
     }
 
+    #[test]
+    fn derived_keys_should_be_restored_from_seed_phrase() -> Result<()> {
+        let seed_phrase = Safe::new_seed_phrase()?;
+        let master_key = Safe::master_key_from_seed_phrase(&seed_phrase, "")?;
+        let restored_master_key = Safe::master_key_from_seed_phrase(&seed_phrase, "")?;
+        assert_eq!(master_key, restored_master_key);
+
+        // the passphrase is part of the derivation
+        let other_master_key = Safe::master_key_from_seed_phrase(&seed_phrase, "passphrase")?;
+        assert_ne!(master_key, other_master_key);
+
+        let signing_key = Safe::derive_child_key(&master_key, KeyPurpose::Signing, 0);
+        let dbc_key = Safe::derive_child_key(&master_key, KeyPurpose::DbcOwnership, 0);
+        let next_signing_key = Safe::derive_child_key(&master_key, KeyPurpose::Signing, 1);
+        assert_ne!(signing_key, dbc_key);
+        assert_ne!(signing_key, next_signing_key);
+        assert_eq!(
+            signing_key,
+            Safe::derive_child_key(&restored_master_key, KeyPurpose::Signing, 0)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn master_key_should_not_be_recoverable_from_child_key() -> Result<()> {
+        let master_key = BlsSecretKey::random();
+        let index = 7u32;
+        let path_key = |sk: &BlsSecretKey| {
+            sk.derive_child(b"signing")
+                .derive_child(&index.to_be_bytes())
+        };
+
+        // Multiplying the master key by scalars obtained from the path, as non-hardened
+        // derivation does, lets anyone divide a child key by them to get the master key back.
+        let recover_master_key = |child_key: &BlsSecretKey| -> Result<BlsSecretKey> {
+            let one = BlsSecretKey::from_mut(&mut Fr::one());
+            let path_scalar = scalar(&path_key(&one))?;
+            let inverse = Option::<Fr>::from(path_scalar.invert())
+                .ok_or_else(|| eyre!("zero path scalar"))?;
+            let mut master_scalar = scalar(child_key)?;
+            master_scalar *= inverse;
+            Ok(BlsSecretKey::from_mut(&mut master_scalar))
+        };
+        assert_eq!(recover_master_key(&path_key(&master_key))?, master_key);
+
+        let child_key = Safe::derive_child_key(&master_key, KeyPurpose::Signing, index);
+        assert_ne!(recover_master_key(&child_key)?, master_key);
+
+        Ok(())
+    }
+
+    #[test]
+    fn master_key_from_invalid_seed_phrase_should_fail() {
+        assert!(Safe::master_key_from_seed_phrase("not a valid seed phrase", "").is_err());
+    }
+
     #[test]
     fn serialize_keypair_should_serialize_a_bls_keypair_to_file() -> Result<()> {
         let tmp_dir = assert_fs::TempDir::new()?;
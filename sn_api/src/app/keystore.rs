@@ -0,0 +1,325 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::{
+    crypto::{Encrypted, PasswordKeys},
+    Error, Result,
+};
+
+use bls::{PublicKey as BlsPublicKey, SecretKey as BlsSecretKey};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+const SALT_LEN: usize = 32;
+const SECRET_KEY_LEN: usize = 32;
+
+/// A keystore holding named BLS secret keys, encrypted with a key derived from a password,
+/// and persisted as a JSON file.
+///
+/// All the keys in a keystore are encrypted with the same password. One of them can be
+/// set as the key in use, e.g. the one an application signs its requests with.
+#[derive(Debug)]
+pub struct Keystore {
+    path: PathBuf,
+    content: KeystoreContent,
+}
+
+/// Name and public key of a key held in a [`Keystore`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct KeystoreEntry {
+    /// Name of the key.
+    pub name: String,
+    /// Public key of the key, which is not encrypted in the keystore.
+    pub public_key: BlsPublicKey,
+    /// Whether it's the key in use.
+    pub in_use: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct KeystoreContent {
+    // hex encoded salt for the password key derivation, set when the first key is inserted
+    salt: Option<String>,
+    in_use: Option<String>,
+    keys: BTreeMap<String, EncryptedKey>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptedKey {
+    public_key: String,
+    iv: String,
+    ciphertext: String,
+    mac: String,
+}
+
+impl Keystore {
+    /// Load the keystore from the file at the given path,
+    /// or create an empty one if the file doesn't exist yet.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let content = if path.exists() {
+            let serialised = fs::read_to_string(&path)?;
+            serde_json::from_str(&serialised).map_err(|err| {
+                Error::KeystoreError(format!(
+                    "Failed to parse keystore at '{}': {}",
+                    path.display(),
+                    err
+                ))
+            })?
+        } else {
+            KeystoreContent::default()
+        };
+
+        Ok(Self { path, content })
+    }
+
+    /// Write the keystore to its file, creating its parent folder if it doesn't exist.
+    pub fn save(&self) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let serialised = serde_json::to_string_pretty(&self.content)
+            .map_err(|err| Error::Serialisation(err.to_string()))?;
+
+        // write it to a temporary file which is only readable by the owner from its creation,
+        // and then move it into place so the keystore is never exposed, nor left half-written
+        let tmp_path = self.path.with_extension("tmp");
+        if tmp_path.exists() {
+            fs::remove_file(&tmp_path)?;
+        }
+        let mut options = fs::OpenOptions::new();
+        let _ = options.write(true).create_new(true);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::OpenOptionsExt;
+            let _ = options.mode(0o600);
+        }
+        let mut file = options.open(&tmp_path)?;
+        file.write_all(serialised.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, &self.path)?;
+
+        Ok(())
+    }
+
+    /// Path of the file the keystore is persisted at.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns true if the keystore holds no keys.
+    pub fn is_empty(&self) -> bool {
+        self.content.keys.is_empty()
+    }
+
+    /// Name of the key in use, if any.
+    pub fn in_use(&self) -> Option<&str> {
+        self.content.in_use.as_deref()
+    }
+
+    /// Set the key with the given name as the key in use.
+    pub fn set_in_use(&mut self, name: &str) -> Result<()> {
+        if !self.content.keys.contains_key(name) {
+            return Err(self.key_not_found(name));
+        }
+        self.content.in_use = Some(name.to_string());
+        Ok(())
+    }
+
+    /// List the keys held in the keystore, sorted by name.
+    pub fn keys(&self) -> Result<Vec<KeystoreEntry>> {
+        self.content
+            .keys
+            .iter()
+            .map(|(name, key)| {
+                Ok(KeystoreEntry {
+                    name: name.clone(),
+                    public_key: BlsPublicKey::from_hex(&key.public_key)?,
+                    in_use: self.in_use() == Some(name.as_str()),
+                })
+            })
+            .collect()
+    }
+
+    /// Encrypt the secret key with the password and insert it in the keystore with the given name.
+    ///
+    /// If the keystore already holds keys, the password has to be the one they are encrypted with.
+    pub fn insert(&mut self, name: &str, secret_key: &BlsSecretKey, password: &str) -> Result<()> {
+        if name.is_empty() {
+            return Err(Error::InvalidInput(
+                "The name of a key cannot be empty".to_string(),
+            ));
+        }
+        if self.content.keys.contains_key(name) {
+            return Err(Error::KeystoreError(format!(
+                "A key named '{}' already exists in the keystore",
+                name
+            )));
+        }
+
+        // make sure all the keys are encrypted with the same password
+        if let Some(existing) = self.content.keys.keys().next().cloned() {
+            let _ = self.secret_key(&existing, password)?;
+        }
+
+        let salt = match &self.content.salt {
+            Some(salt) => decode_hex(salt)?,
+            None => {
+                let mut salt = vec![0; SALT_LEN];
+                rand::thread_rng().fill_bytes(&mut salt);
+                self.content.salt = Some(hex::encode(&salt));
+                salt
+            }
+        };
+        let encrypted =
+            PasswordKeys::derive(password.as_bytes(), &salt).encrypt(&secret_key.to_bytes());
+
+        let _ = self.content.keys.insert(
+            name.to_string(),
+            EncryptedKey {
+                public_key: secret_key.public_key().to_hex(),
+                iv: hex::encode(encrypted.iv),
+                ciphertext: hex::encode(encrypted.ciphertext),
+                mac: hex::encode(encrypted.mac),
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Decrypt the secret key with the given name using the password.
+    pub fn secret_key(&self, name: &str, password: &str) -> Result<BlsSecretKey> {
+        let key = self
+            .content
+            .keys
+            .get(name)
+            .ok_or_else(|| self.key_not_found(name))?;
+        let salt = self.content.salt.as_deref().ok_or_else(|| {
+            Error::KeystoreError("The keystore is missing its key derivation salt".to_string())
+        })?;
+        let encrypted = Encrypted {
+            iv: decode_hex(&key.iv)?,
+            ciphertext: decode_hex(&key.ciphertext)?,
+            mac: decode_hex(&key.mac)?,
+        };
+        let bytes = PasswordKeys::derive(password.as_bytes(), &decode_hex(salt)?)
+            .decrypt(&encrypted)
+            .ok_or_else(|| {
+                Error::KeystoreError(format!(
+                    "Failed to decrypt key '{}', the password is incorrect",
+                    name
+                ))
+            })?;
+        let bytes: [u8; SECRET_KEY_LEN] = bytes.try_into().map_err(|_| {
+            Error::KeystoreError(format!("Key '{}' is not a valid BLS secret key", name))
+        })?;
+
+        Ok(BlsSecretKey::from_bytes(bytes)?)
+    }
+
+    fn key_not_found(&self, name: &str) -> Error {
+        Error::KeystoreError(format!(
+            "No key named '{}' found in the keystore at '{}'",
+            name,
+            self.path.display()
+        ))
+    }
+}
+
+fn decode_hex(value: &str) -> Result<Vec<u8>> {
+    hex::decode(value)
+        .map_err(|err| Error::KeystoreError(format!("Invalid hex data in keystore: {}", err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Keystore;
+    use crate::Error;
+
+    use anyhow::{anyhow, Result};
+    use assert_fs::prelude::*;
+    use bls::SecretKey as BlsSecretKey;
+
+    #[test]
+    fn keystore_should_persist_encrypted_keys() -> Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let keystore_file = tmp_dir.child("keystore");
+
+        let sk = BlsSecretKey::random();
+        let mut keystore = Keystore::load(keystore_file.path())?;
+        assert!(keystore.is_empty());
+        keystore.insert("alice", &sk, "password")?;
+        keystore.set_in_use("alice")?;
+        keystore.save()?;
+
+        // the secret key is not stored in plain text
+        let content = std::fs::read_to_string(keystore_file.path())?;
+        assert!(!content.contains(&sk.to_hex()));
+
+        let keystore = Keystore::load(keystore_file.path())?;
+        assert_eq!(keystore.in_use(), Some("alice"));
+        let keys = keystore.keys()?;
+        assert_eq!(keys.len(), 1);
+        assert_eq!(keys[0].public_key, sk.public_key());
+        assert!(keys[0].in_use);
+        assert_eq!(keystore.secret_key("alice", "password")?, sk);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keystore_should_only_be_readable_by_its_owner() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let keystore_file = tmp_dir.child("keystore");
+        // a keystore file created beforehand with the default permissions
+        keystore_file.write_str("{\"keys\":{}}")?;
+        std::fs::set_permissions(keystore_file.path(), std::fs::Permissions::from_mode(0o644))?;
+
+        let mut keystore = Keystore::load(keystore_file.path())?;
+        keystore.insert("alice", &BlsSecretKey::random(), "password")?;
+        keystore.save()?;
+
+        let mode = std::fs::metadata(keystore_file.path())?
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+        assert!(!keystore_file.path().with_extension("tmp").exists());
+
+        Ok(())
+    }
+
+    #[test]
+    fn keystore_should_fail_with_wrong_password() -> Result<()> {
+        let tmp_dir = assert_fs::TempDir::new()?;
+        let mut keystore = Keystore::load(tmp_dir.child("keystore").path())?;
+        keystore.insert("alice", &BlsSecretKey::random(), "password")?;
+
+        match keystore.secret_key("alice", "wrong") {
+            Err(Error::KeystoreError(_)) => {}
+            other => return Err(anyhow!("Unexpected result: {:?}", other)),
+        }
+
+        // keys cannot be added with a different password
+        match keystore.insert("bob", &BlsSecretKey::random(), "wrong") {
+            Err(Error::KeystoreError(_)) => {}
+            other => return Err(anyhow!("Unexpected result: {:?}", other)),
+        }
+        keystore.insert("bob", &BlsSecretKey::random(), "password")?;
+        assert_eq!(keystore.keys()?.len(), 2);
+
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@
 
 pub mod files;
 pub mod keys;
+pub mod keystore;
 pub mod multimap;
 pub mod nrs;
 pub mod register;
@@ -36,7 +37,7 @@ use super::{common, constants, Error, Result};
 
 use sn_client::Client;
 use sn_dbc::Owner;
-use sn_interface::types::Keypair;
+use sn_interface::types::{Keypair, PublicKey};
 
 use std::time::Duration;
 use tracing::debug;
@@ -48,6 +49,22 @@ pub struct Safe {
     client: Option<Client>,
     pub xorurl_base: XorUrlBase,
     pub dry_run_mode: bool,
+    /// When set, files and chunks are recorded in this journal as they get uploaded,
+    /// and those already found in it are not uploaded again.
+    pub upload_journal: Option<files::UploadJournal>,
+    /// Maximum size in bytes of the client's persistent chunk cache when connecting, which
+    /// keeps the chunks retrieved from the network on disk to be reused by subsequent runs.
+    /// It's disabled if not set, unless it's set with the `SN_CHUNK_CACHE_SIZE` env var.
+    pub chunk_cache_size: Option<u64>,
+    /// When set, Registers created are owned by this key rather than the client's one, and
+    /// grant write to the client's key. This is how an application authorised by a Safe creates
+    /// Registers the user keeps control of, as per the `owner` it was granted.
+    pub register_owner: Option<PublicKey>,
+    /// Whether the client keeps the Register ops in a write-ahead log on disk when connecting,
+    /// so those which cannot be published are kept pending until they can be, see
+    /// [`Safe::register_pending`]. It's disabled if not set, unless set with the
+    /// `SN_REGISTER_WAL` env var.
+    pub register_wal: bool,
 }
 
 impl Safe {
@@ -57,6 +74,10 @@ impl Safe {
             client: None,
             xorurl_base: xorurl_base.unwrap_or(DEFAULT_XORURL_BASE),
             dry_run_mode: true,
+            upload_journal: None,
+            chunk_cache_size: None,
+            register_owner: None,
+            register_wal: false,
         }
     }
 
@@ -71,6 +92,10 @@ impl Safe {
             client: None,
             xorurl_base: xorurl_base.unwrap_or(DEFAULT_XORURL_BASE),
             dry_run_mode: false,
+            upload_journal: None,
+            chunk_cache_size: None,
+            register_owner: None,
+            register_wal: false,
         };
 
         safe.connect(keypair, timeout, dbc_owner).await?;
@@ -89,6 +114,8 @@ impl Safe {
         debug!("Client to be instantiated with specific pk?: {:?}", keypair);
 
         let mut b = Client::builder()
+            .chunk_cache_size(self.chunk_cache_size)
+            .register_wal(self.register_wal)
             .from_env() // Load parameters from environment variables.
             .keypair(keypair)
             .dbc_owner(dbc_owner);
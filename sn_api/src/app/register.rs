@@ -11,21 +11,32 @@ pub use sn_interface::types::register::{Entry, EntryHash};
 use crate::safeurl::{ContentType, SafeUrl, XorUrl};
 use crate::{Error, Result, Safe};
 
-use sn_client::Error as ClientError;
+use sn_client::{Client, Error as ClientError};
 use sn_interface::{
-    messaging::data::Error as ErrorMsg,
+    messaging::data::{DataCmd, Error as ErrorMsg, RegisterCmd, SignedRegisterEdit},
     types::{
         register::{Permissions, Policy, User},
-        DataAddress, Error as SafeNdError, RegisterAddress,
+        DataAddress, Error as SafeNdError, PublicKey, RegisterAddress,
     },
 };
 
+use futures::{future, Stream, StreamExt};
 use log::debug;
 use rand::Rng;
 use std::collections::{BTreeMap, BTreeSet};
 use tracing::info;
 use xor_name::XorName;
 
+/// A Register operation kept in the client's write-ahead log, yet to be published,
+/// see [`Safe::register_pending`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingRegisterOp {
+    /// XOR-URL of the Register the operation applies to
+    pub xorurl: XorUrl,
+    /// Kind of operation, i.e. "create", "edit", "delete" or "update-policy"
+    pub kind: String,
+}
+
 impl Safe {
     // === Register data operations ===
     /// Create a Register on the network
@@ -50,13 +61,15 @@ impl Safe {
             return Ok(xorurl);
         }
 
-        // The Register's owner will be the client's public key
+        // The Register's owner will be the client's public key, unless another owner was set,
+        // in which case the client is only granted write permission.
         let client = self.get_safe_client()?;
-        let owner = User::Key(client.public_key());
+        let writer = User::Key(client.public_key());
+        let owner = self.register_owner.map(User::Key).unwrap_or(writer);
 
         // Store the Register on the network
         let (_, op_batch) = client
-            .create_register(xorname, tag, policy(owner))
+            .create_register(xorname, tag, policy(owner, writer))
             .await
             .map_err(|e| {
                 Error::NetDataError(format!(
@@ -217,6 +230,200 @@ impl Safe {
         Ok(entry_hash)
     }
 
+    /// Delete a Register from the network
+    ///
+    /// Only the owner of the Register can delete it.
+    pub async fn register_delete(&self, url: &str) -> Result<()> {
+        let reg_url = self.parse_and_resolve_url(url).await?;
+        let address = self.get_register_address(&reg_url)?;
+        if self.dry_run_mode {
+            return Ok(());
+        }
+
+        let client = self.get_safe_client()?;
+        let op_batch = match client.delete_register(address).await {
+            Ok(data) => data,
+            Err(
+                ClientError::NetworkDataError(SafeNdError::AccessDenied(_))
+                | ClientError::ErrorMsg {
+                    source: ErrorMsg::AccessDenied(_),
+                    ..
+                },
+            ) => {
+                return Err(Error::AccessDenied(format!(
+                    "Couldn't delete Register found at \"{}\"",
+                    url
+                )));
+            }
+            Err(err) => {
+                return Err(Error::NetDataError(format!(
+                    "Failed to delete Register: {:?}",
+                    err
+                )));
+            }
+        };
+
+        client.publish_register_ops(op_batch).await?;
+
+        Ok(())
+    }
+
+    /// Watch a Register, or a FilesContainer, on the network rather than polling it for changes.
+    ///
+    /// Returns a stream of the entries written to the Register from now on, each verified to be
+    /// signed by its author and to target the watched Register. The stream ends once the Register
+    /// is deleted, or the network cannot notify this client anymore.
+    pub async fn register_watch(
+        &self,
+        url: &str,
+    ) -> Result<impl Stream<Item = (EntryHash, Entry)> + Unpin> {
+        let reg_url = self.parse_and_resolve_url(url).await?;
+        let address = self.get_register_address(&reg_url)?;
+
+        let client = self.get_safe_client()?;
+        let watch = client
+            .watch_register(address)
+            .await
+            .map_err(|err| Error::NetDataError(format!("Failed to watch Register: {:?}", err)))?;
+
+        Ok(watch.filter_map(|cmd| {
+            let entry = match cmd {
+                RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => {
+                    Some((EntryHash(op.edit.crdt_op.hash()), op.edit.crdt_op.value))
+                }
+                _ => None,
+            };
+            future::ready(entry)
+        }))
+    }
+
+    /// Register operations which couldn't be published to the network yet, e.g. as it was
+    /// unreachable, kept in the client's write-ahead log in the order they were recorded.
+    /// It's always empty unless [`Safe::register_wal`] is set. The log is read from disk
+    /// without connecting to the network, if not connected already.
+    pub fn register_pending(&self) -> Result<Vec<PendingRegisterOp>> {
+        let pending_ops = match &self.client {
+            Some(client) => client.pending_register_ops()?,
+            None => Client::builder()
+                .register_wal(self.register_wal)
+                .from_env()
+                .pending_register_ops()?,
+        };
+        pending_ops
+            .into_iter()
+            .filter_map(|cmd| match cmd {
+                DataCmd::Register(cmd) => Some(cmd),
+                _ => None,
+            })
+            .map(|cmd| {
+                let kind = match cmd {
+                    RegisterCmd::Create { .. } => "create",
+                    RegisterCmd::Edit(_) => "edit",
+                    RegisterCmd::Delete(_) => "delete",
+                    RegisterCmd::UpdatePolicy(_) => "update-policy",
+                };
+                let address = cmd.dst_address();
+                // the content type of the Register is not known until it's fetched
+                let xorurl = SafeUrl::from_register(address.name, address.tag, ContentType::Raw)?
+                    .encode(self.xorurl_base);
+                Ok(PendingRegisterOp {
+                    xorurl,
+                    kind: kind.to_string(),
+                })
+            })
+            .collect()
+    }
+
+    /// Publish the Register operations kept pending in the client's write-ahead log, in the
+    /// order they were recorded, returning how many were published.
+    pub async fn register_flush(&self) -> Result<usize> {
+        let client = self.get_safe_client()?;
+        Ok(client.flush_register_ops().await?)
+    }
+
+    /// Grant permission to write to a Register, or a FilesContainer, to the given key.
+    ///
+    /// Only the owner of the Register, or users with admin permissions, can grant permissions.
+    pub async fn register_grant_write(&self, url: &str, key: PublicKey) -> Result<()> {
+        self.register_set_write_permission(url, key, true).await
+    }
+
+    /// Revoke the permission to write to a Register, or a FilesContainer, from the given key.
+    ///
+    /// The key is denied write access explicitly, even if anyone else is allowed to write.
+    /// Entries it already wrote are kept. Only the owner of the Register, or users with
+    /// admin permissions, can revoke permissions, and those of the owner cannot be revoked.
+    pub async fn register_revoke_write(&self, url: &str, key: PublicKey) -> Result<()> {
+        self.register_set_write_permission(url, key, false).await
+    }
+
+    // Update the policy of the Register to allow, or deny, the key to write to it
+    async fn register_set_write_permission(
+        &self,
+        url: &str,
+        key: PublicKey,
+        allowed: bool,
+    ) -> Result<()> {
+        let reg_url = self.parse_and_resolve_url(url).await?;
+        let address = self.get_register_address(&reg_url)?;
+        if self.dry_run_mode {
+            return Ok(());
+        }
+
+        let client = self.get_safe_client()?;
+        let mut policy = client.get_register_policy(address).await.map_err(|err| {
+            Error::NetDataError(format!("Failed to get policy of Register: {:?}", err))
+        })?;
+
+        let user = User::Key(key);
+        if !allowed && policy.owner == user {
+            return Err(Error::InvalidInput(
+                "The permissions of the owner of a Register cannot be revoked".to_string(),
+            ));
+        }
+        let permissions = policy
+            .permissions
+            .entry(user)
+            .or_insert_with(|| Permissions::new(None));
+        permissions.set_perms(allowed);
+        if !allowed {
+            // permission to write implies permission to append, which is thus revoked as well
+            permissions.set_append(false);
+        }
+        debug!(
+            "{} write permission to {:?} on Register at {:?}",
+            if allowed { "Granting" } else { "Revoking" },
+            key,
+            address
+        );
+
+        let op_batch = match client.update_register_policy(address, policy).await {
+            Ok(data) => data,
+            Err(
+                ClientError::NetworkDataError(SafeNdError::AccessDenied(_))
+                | ClientError::ErrorMsg {
+                    source: ErrorMsg::AccessDenied(_),
+                    ..
+                },
+            ) => {
+                return Err(Error::AccessDenied(format!(
+                    "Couldn't update the policy of Register found at \"{}\"",
+                    url
+                )));
+            }
+            Err(err) => {
+                return Err(Error::NetDataError(format!(
+                    "Failed to update policy of Register: {:?}",
+                    err
+                )));
+            }
+        };
+
+        client.publish_register_ops(op_batch).await?;
+
+        Ok(())
+    }
+
     pub(crate) fn get_register_address(&self, url: &SafeUrl) -> Result<RegisterAddress> {
         let address = match url.address() {
             DataAddress::Register(reg_address) => reg_address,
@@ -232,16 +439,19 @@ impl Safe {
     }
 }
 
-fn policy(owner: User) -> Policy {
+fn policy(owner: User, writer: User) -> Policy {
     let mut permissions = BTreeMap::new();
     let _ = permissions.insert(owner, Permissions::new(true));
+    let _ = permissions.insert(writer, Permissions::new(true));
     Policy { owner, permissions }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::{app::test_helpers::new_safe_instance, ContentType, Error};
-    use anyhow::{bail, Result};
+    use anyhow::{anyhow, bail, Result};
+    use futures::StreamExt;
+    use std::time::Duration;
 
     #[tokio::test]
     async fn test_register_create() -> Result<()> {
@@ -300,4 +510,78 @@ mod tests {
         let _ = safe.register_read(&xorurl).await?;
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_register_create_with_register_owner() -> Result<()> {
+        let mut safe = new_safe_instance().await?;
+        let owner = new_safe_instance().await?;
+        safe.register_owner = Some(owner.get_safe_client()?.public_key());
+
+        let xorurl = safe.register_create(None, 25_000, ContentType::Raw).await?;
+
+        // the creator has been granted write permission
+        let _ = safe
+            .register_write(&xorurl, b"dummy-pub-data".to_vec(), Default::default())
+            .await?;
+
+        // but only the owner can delete it
+        match safe.register_delete(&xorurl).await {
+            Err(Error::AccessDenied(_)) => {}
+            Err(err) => bail!("Error returned is not the expected: {:?}", err),
+            Ok(_) => bail!("Deletion of Register succeeded unexpectedly".to_string()),
+        }
+        owner.register_delete(&xorurl).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_grant_and_revoke_write() -> Result<()> {
+        let safe = new_safe_instance().await?;
+        let writer = new_safe_instance().await?;
+        let writer_pk = writer.get_safe_client()?.public_key();
+
+        let xorurl = safe.register_create(None, 25_000, ContentType::Raw).await?;
+
+        safe.register_grant_write(&xorurl, writer_pk).await?;
+        let _ = writer
+            .register_write(&xorurl, b"dummy-pub-data".to_vec(), Default::default())
+            .await?;
+
+        // only the owner can update the policy
+        match writer.register_revoke_write(&xorurl, writer_pk).await {
+            Err(Error::AccessDenied(_)) => {}
+            Err(err) => bail!("Error returned is not the expected: {:?}", err),
+            Ok(_) => bail!("Update of the policy succeeded unexpectedly".to_string()),
+        }
+
+        safe.register_revoke_write(&xorurl, writer_pk).await?;
+        match writer
+            .register_write(&xorurl, b"dummy-pub-data".to_vec(), Default::default())
+            .await
+        {
+            Err(Error::AccessDenied(_)) => {}
+            Err(err) => bail!("Error returned is not the expected: {:?}", err),
+            Ok(_) => bail!("Write to Register succeeded unexpectedly".to_string()),
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_watch() -> Result<()> {
+        let safe = new_safe_instance().await?;
+        let xorurl = safe.register_create(None, 25_000, ContentType::Raw).await?;
+
+        let mut entries = safe.register_watch(&xorurl).await?;
+
+        let data = b"watched data".to_vec();
+        let hash = safe
+            .register_write(&xorurl, data.clone(), Default::default())
+            .await?;
+
+        let entry = tokio::time::timeout(Duration::from_secs(30), entries.next())
+            .await?
+            .ok_or_else(|| anyhow!("Register watch ended unexpectedly"))?;
+        assert_eq!(entry, (hash, data));
+        Ok(())
+    }
 }
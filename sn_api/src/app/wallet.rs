@@ -7,20 +7,30 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 pub use sn_dbc::{self as dbc, Dbc, Token};
+pub use sn_interface::messaging::data::SpentKeyImage;
 
-use super::{helpers::parse_tokens_amount, register::EntryHash};
+use super::{
+    helpers::{parse_tokens_amount, systemtime_to_rfc3339},
+    register::EntryHash,
+};
 use crate::{
     safeurl::{ContentType, SafeUrl, XorUrl},
-    Error, Result, Safe,
+    Error, Result, Safe, SectionTree,
 };
 use bytes::Bytes;
 use log::{debug, warn};
+use serde::{Deserialize, Serialize};
 use sn_client::Client;
 use sn_dbc::{
     rng, AmountSecrets, Error as DbcError, Hash, KeyImage, Owner, OwnerOnce, PublicKey,
-    RingCtTransaction, SpentProof, SpentProofShare, TransactionBuilder,
+    RingCtTransaction, SpentProof, SpentProofShare, TransactionBuilder, TransactionVerifier,
+};
+use sn_interface::dbcs::GENESIS_DBC_AMOUNT;
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    time::SystemTime,
 };
-use std::collections::{BTreeMap, BTreeSet, HashSet};
+use xor_name::Prefix;
 
 /// Type tag to use for the Wallet stored on Register
 pub const WALLET_TYPE_TAG: u64 = 1_000;
@@ -29,6 +39,64 @@ pub const WALLET_TYPE_TAG: u64 = 1_000;
 /// depositing DBCs into a wallet.
 pub type WalletSpendableDbcs = BTreeMap<String, (Dbc, EntryHash)>;
 
+/// Key of the wallet entries which hold the records of its history,
+/// it cannot be used as the name of a spendable DBC.
+pub const WALLET_HISTORY_KEY: &str = "__wallet_history__";
+
+/// Record of an operation made on a wallet, as kept in the wallet's append-only history.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletHistoryEntry {
+    /// Time the operation was made at, in RFC 3339 format.
+    pub timestamp: String,
+    /// The operation made on the wallet.
+    pub event: WalletHistoryEvent,
+}
+
+/// Operations recorded in a wallet's history.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WalletHistoryEvent {
+    /// A DBC was deposited in the wallet.
+    Deposit {
+        /// Name given to the spendable DBC.
+        name: String,
+        /// Amount deposited.
+        amount: Token,
+    },
+    /// DBCs from the wallet were reissued.
+    Reissue {
+        /// Amount and owner of each of the output DBCs,
+        /// the owner being `None` when the output DBC is a bearer.
+        outputs: Vec<(Token, Option<bls::PublicKey>)>,
+        /// Name and amount of the change DBC deposited back in the wallet, if there was change.
+        change: Option<(String, Token)>,
+        /// Key images of the DBCs from the wallet which were spent.
+        spent_key_images: Vec<KeyImage>,
+    },
+}
+
+/// Outcome of auditing the network's spentbook against the genesis DBC.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupplyAudit {
+    /// Amount held by the genesis DBC, i.e. the total supply of the network.
+    pub genesis_amount: Token,
+    /// Whether the genesis DBC amount matches the expected total supply.
+    pub genesis_amount_valid: bool,
+    /// Whether the genesis DBC has already been spent.
+    pub genesis_spent: bool,
+    /// Number of key images found spent in the spentbook.
+    pub spent_key_images: usize,
+    /// Key images found spent in more than one transaction.
+    pub double_spends: Vec<SpentKeyImage>,
+}
+
+impl SupplyAudit {
+    /// Returns true if the genesis DBC holds the expected total supply
+    /// and no key image was found spent in more than one transaction.
+    pub fn is_sound(&self) -> bool {
+        self.genesis_amount_valid && self.double_spends.is_empty()
+    }
+}
+
 // Number of attempts to make trying to spend inputs when reissuing DBCs
 // As the spend and query cmds are cascaded closely, there is high chance
 // that the first two query attempts could both be failed.
@@ -58,6 +126,97 @@ impl sn_dbc::SpentProofKeyVerifier for SpentProofKeyVerifier<'_> {
     }
 }
 
+/// Verifier required by sn_dbc API to check a SpentProof is signed by
+/// known sections keys, without connecting to the network.
+struct SectionTreeKeyVerifier<'a> {
+    section_tree: &'a SectionTree,
+}
+
+impl sn_dbc::SpentProofKeyVerifier for SectionTreeKeyVerifier<'_> {
+    type Error = crate::Error;
+
+    // Called by sn_dbc API when it needs to verify a SpentProof is signed by a known key,
+    // we check if the key is any of the sections keys found in the provided SectionTree
+    fn verify_known_key(&self, key: &PublicKey) -> Result<()> {
+        if !self.section_tree.get_sections_dag().has_key(key) {
+            Err(Error::DbcVerificationFailed(format!(
+                "SpentProof key is an unknown section key: {}",
+                key.to_hex()
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Verify a DBC offline, checking its transaction and that its spent proofs are signed by
+/// section keys found in the provided `SectionTree`, e.g. one cached from a previous connection.
+///
+/// The secret key is required to verify the DBC belongs to its owner and that its amount matches
+/// the transaction's commitment, it's taken from the DBC itself if it's a bearer DBC. When it's
+/// not available, only the transaction and the spent proofs are verified.
+///
+/// Note this cannot check if the DBC has been already spent, the `is_dbc_spent`
+/// API needs to be used for that purpose while connected to the network.
+pub fn dbc_verify_offline(
+    dbc: &Dbc,
+    secret_key: Option<&bls::SecretKey>,
+    section_tree: &SectionTree,
+) -> Result<()> {
+    let proof_key_verifier = SectionTreeKeyVerifier { section_tree };
+
+    let base_sk = match secret_key {
+        Some(sk) => Some(sk.clone()),
+        None if dbc.is_bearer() => Some(dbc.owner_base().secret_key()?),
+        None => None,
+    };
+
+    if let Some(base_sk) = base_sk {
+        dbc.verify(&base_sk, &proof_key_verifier)?;
+    } else {
+        TransactionVerifier::verify(&proof_key_verifier, &dbc.transaction, &dbc.spent_proofs)?;
+
+        // verify there is a maching transaction for each spent proof
+        if !dbc.spent_proofs.iter().all(|proof| {
+            dbc.spent_transactions
+                .iter()
+                .any(|tx| Hash::from(tx.hash()) == proof.transaction_hash())
+        }) {
+            return Err(Error::DbcVerificationFailed(
+                "Missing the spent transaction of a spent proof".to_string(),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Audit the total supply against the genesis DBC, given the key images spent on the network.
+///
+/// As amounts are hidden in DBC transactions, the supply cannot be summed up from the spentbook.
+/// Instead the genesis DBC amount is checked against the expected total supply, and every key
+/// image is checked to have been spent in a single transaction, as a double spend is the only
+/// way for the supply to be inflated by transactions the spentbook has verified.
+pub fn audit_supply(genesis_dbc: &Dbc, spent: Vec<SpentKeyImage>) -> Result<SupplyAudit> {
+    let genesis_amount = genesis_dbc.amount_secrets_bearer()?.amount();
+    let genesis_key_image = genesis_dbc.key_image_bearer()?;
+
+    let genesis_spent = spent.iter().any(|item| item.key_image == genesis_key_image);
+    let spent_key_images = spent.len();
+    let double_spends = spent
+        .into_iter()
+        .filter(|item| item.transaction_hashes.len() > 1)
+        .collect();
+
+    Ok(SupplyAudit {
+        genesis_amount,
+        genesis_amount_valid: genesis_amount.as_nano() == GENESIS_DBC_AMOUNT,
+        genesis_spent,
+        spent_key_images,
+        double_spends,
+    })
+}
+
 impl Safe {
     /// Create an empty wallet and return its XOR-URL.
     ///
@@ -120,6 +279,12 @@ impl Safe {
         )?;
 
         let spendable_name = match spendable_name {
+            Some(WALLET_HISTORY_KEY) => {
+                return Err(Error::InvalidInput(format!(
+                    "The name '{}' is reserved for the wallet history",
+                    WALLET_HISTORY_KEY
+                )))
+            }
             Some(name) => name.to_string(),
             None => format!("dbc-{}", &hex::encode(dbc_to_deposit.hash())[0..8]),
         };
@@ -137,9 +302,30 @@ impl Safe {
             amount, safeurl, spendable_name
         );
 
+        self.record_wallet_history(
+            &safeurl,
+            WalletHistoryEvent::Deposit {
+                name: spendable_name.clone(),
+                amount,
+            },
+        )
+        .await;
+
         Ok((spendable_name, amount))
     }
 
+    /// Audit the total supply against the genesis DBC, walking through all the key images
+    /// logged as spent in the network's spentbook. See [`audit_supply`] for the checks made.
+    ///
+    /// The spentbook is listed from all the adults of each section, an error being returned
+    /// if any part of it cannot be listed, so the audit is never made on partial data.
+    pub async fn spentbook_audit(&self, genesis_dbc: &Dbc) -> Result<SupplyAudit> {
+        let client = self.get_safe_client()?;
+        let spent = client.spent_key_images(Prefix::default()).await?;
+        debug!("Auditing supply against {} spent key images", spent.len());
+        audit_supply(genesis_dbc, spent)
+    }
+
     /// Verify if the provided DBC's key_image has been already spent on the network.
     pub async fn is_dbc_spent(&self, key_image: KeyImage) -> Result<bool> {
         let client = self.get_safe_client()?;
@@ -208,6 +394,9 @@ impl Safe {
 
         let mut balances = WalletSpendableDbcs::default();
         for (entry_hash, (key, value)) in &entries {
+            if key == WALLET_HISTORY_KEY.as_bytes() {
+                continue;
+            }
             let xorurl_str = std::str::from_utf8(value)?;
             let dbc_xorurl = SafeUrl::from_xorurl(xorurl_str)?;
             let dbc_bytes = self.fetch_data(&dbc_xorurl, None).await?;
@@ -227,6 +416,39 @@ impl Safe {
         Ok(balances)
     }
 
+    /// Fetch the history of a wallet from a Url performing all type of URL resolution required.
+    /// Return the records of the operations made on the wallet, from the oldest to the newest.
+    pub async fn wallet_history(&self, wallet_url: &str) -> Result<Vec<WalletHistoryEntry>> {
+        let safeurl = self.parse_and_resolve_url(wallet_url).await?;
+        debug!("Fetching history of wallet at: {}", safeurl);
+
+        let entries = self
+            .fetch_multimap_values_by_key(&safeurl, WALLET_HISTORY_KEY.as_bytes())
+            .await?;
+
+        let mut history = Vec::new();
+        for (_, (_, value)) in &entries {
+            let xorurl_str = std::str::from_utf8(value)?;
+            let record_xorurl = SafeUrl::from_xorurl(xorurl_str)?;
+            let record_bytes = self.fetch_data(&record_xorurl, None).await?;
+
+            match rmp_serde::from_slice::<WalletHistoryEntry>(&record_bytes) {
+                Ok(record) => history.push(record),
+                Err(err) => {
+                    warn!(
+                        "Ignoring wallet history record since it cannot be deserialised: {:?}",
+                        err
+                    );
+                }
+            }
+        }
+
+        // Timestamps are all in UTC, thus they sort chronologically
+        history.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
+
+        Ok(history)
+    }
+
     /// Check the total balance of a wallet found at a given XOR-URL
     pub async fn wallet_balance(&self, wallet_url: &str) -> Result<Token> {
         debug!("Finding total wallet balance for: {}", wallet_url);
@@ -284,7 +506,7 @@ impl Safe {
             "Reissuing DBC from wallet at {} for an amount of {} tokens",
             wallet_url, amount
         );
-        let dbcs = self
+        let (dbcs, _) = self
             .wallet_reissue_many(
                 wallet_url,
                 [(amount.to_string(), owner_public_key)]
@@ -306,13 +528,17 @@ impl Safe {
     /// this function allows to reissue from a single wallet several output DBCs instead
     /// of a single one. If there is change from the transaction, the change DBC will be
     /// deposited in the source wallet.
+    ///
+    /// Returns the output DBCs in the same order as the outputs were provided, along with
+    /// the name and amount of the change DBC deposited in the source wallet, if there was change.
     pub async fn wallet_reissue_many(
         &self,
         wallet_url: &str,
         outputs: Vec<(String, Option<bls::PublicKey>)>,
-    ) -> Result<Vec<Dbc>> {
+    ) -> Result<(Vec<Dbc>, Option<(String, Token)>)> {
         let mut total_output_amount = Token::zero();
         let mut outputs_owners = Vec::<(Token, OwnerOnce)>::new();
+        let mut history_outputs = Vec::new();
         for (amount, owner_pk) in outputs {
             let output_amount = parse_tokens_amount(&amount)?;
             if output_amount.as_nano() == 0 {
@@ -339,6 +565,7 @@ impl Safe {
             };
 
             outputs_owners.push((output_amount, output_owner));
+            history_outputs.push((output_amount, owner_pk));
         }
 
         let safeurl = self.parse_and_resolve_url(wallet_url).await?;
@@ -349,6 +576,7 @@ impl Safe {
         // - and a second DBC for the change, which will be stored in the source wallet.
         let mut input_dbcs_to_spend = Vec::<Dbc>::new();
         let mut input_dbcs_entries_hash = BTreeSet::<EntryHash>::new();
+        let mut spent_key_images = Vec::<KeyImage>::new();
         let mut total_input_amount = 0;
         let mut change_amount = total_output_amount;
         for (name, (dbc, entry_hash)) in spendable_dbcs {
@@ -361,6 +589,13 @@ impl Safe {
             };
 
             // Add this DBC as input to be spent
+            match dbc.key_image_bearer() {
+                Ok(key_image) => spent_key_images.push(key_image),
+                Err(err) => warn!(
+                    "Failed to obtain key image of input DBC (entry: {}) for the wallet history: {:?}",
+                    name, err
+                ),
+            }
             input_dbcs_to_spend.push(dbc);
             input_dbcs_entries_hash.insert(entry_hash);
             total_input_amount += dbc_balance.as_nano();
@@ -389,30 +624,49 @@ impl Safe {
         }
 
         // We can now reissue the output DBCs
-        let (output_dbcs, change_dbc) = self
-            .reissue_dbcs(input_dbcs_to_spend, outputs_owners, change_amount)
+        let (mut output_dbcs, change_dbc) = self
+            .reissue_dbcs(input_dbcs_to_spend, outputs_owners.clone(), change_amount)
             .await?;
 
+        // Output DBCs are not necessarily in the same order as the outputs of the transaction
+        output_dbcs.sort_by_key(|(_, owneronce, _)| {
+            outputs_owners
+                .iter()
+                .position(|(_, output_owner)| output_owner == owneronce)
+        });
+
         if output_dbcs.is_empty() {
             return Err(Error::DbcReissueError(
                 "Unexpectedly failed to generate output DBC. No balance were removed from the wallet.".to_string(),
             ));
         }
 
-        if let Some(change_dbc) = change_dbc {
-            self.insert_dbc_into_wallet(
-                &safeurl,
-                &change_dbc,
-                format!("change-dbc-{}", &hex::encode(change_dbc.hash())[0..8]),
-            )
-            .await?;
-        }
+        let change = if let Some(change_dbc) = change_dbc {
+            let change_name = format!("change-dbc-{}", &hex::encode(change_dbc.hash())[0..8]);
+            self.insert_dbc_into_wallet(&safeurl, &change_dbc, change_name.clone())
+                .await?;
+            Some((change_name, change_amount))
+        } else {
+            None
+        };
 
         // (virtually) remove input DBCs in the source wallet
         self.multimap_remove(&safeurl.to_string(), input_dbcs_entries_hash)
             .await?;
 
-        Ok(output_dbcs.into_iter().map(|(dbc, _, _)| dbc).collect())
+        self.record_wallet_history(
+            &safeurl,
+            WalletHistoryEvent::Reissue {
+                outputs: history_outputs,
+                change: change.clone(),
+                spent_key_images,
+            },
+        )
+        .await;
+
+        let output_dbcs = output_dbcs.into_iter().map(|(dbc, _, _)| dbc).collect();
+
+        Ok((output_dbcs, change))
     }
 
     ///
@@ -447,6 +701,42 @@ impl Safe {
         Ok(())
     }
 
+    /// Append a record of an operation made on a wallet to its history.
+    ///
+    /// The operation has already taken place by the time it's recorded, thus failing
+    /// to record it is logged but not reported as an error of the operation itself.
+    async fn record_wallet_history(&self, safeurl: &SafeUrl, event: WalletHistoryEvent) {
+        let record = WalletHistoryEntry {
+            timestamp: systemtime_to_rfc3339(SystemTime::now()),
+            event,
+        };
+
+        let result: Result<EntryHash> = async {
+            let record_bytes = Bytes::from(rmp_serde::to_vec_named(&record).map_err(|err| {
+                Error::Serialisation(format!(
+                    "Failed to serialise wallet history record: {:?}",
+                    err
+                ))
+            })?);
+            let record_xorurl = self.store_bytes(record_bytes, None).await?;
+
+            let entry = (
+                WALLET_HISTORY_KEY.as_bytes().to_vec(),
+                record_xorurl.into_bytes(),
+            );
+            self.multimap_insert(&safeurl.to_string(), entry, BTreeSet::default())
+                .await
+        }
+        .await;
+
+        if let Err(err) = result {
+            warn!(
+                "Failed to record operation in history of wallet at {}: {:?}",
+                safeurl, err
+            );
+        }
+    }
+
     /// Reissue DBCs and log the spent input DBCs on the network. Return the output DBC and the
     /// change DBC if there is one.
     pub(super) async fn reissue_dbcs(
@@ -576,6 +866,71 @@ mod tests {
     use anyhow::{anyhow, Result};
     use sn_client::{Error as ClientError, ErrorMsg};
     use sn_dbc::{Error as DbcError, Owner};
+    use sn_interface::{
+        dbcs::gen_genesis_dbc,
+        test_utils::{TestKeys, TestSapBuilder, TestSectionTree},
+    };
+    use xor_name::Prefix;
+
+    #[test]
+    fn test_dbc_verify_offline() -> Result<()> {
+        let (genesis_sap, genesis_sk_set, ..) = TestSapBuilder::new(Prefix::default())
+            .sk_threshold_size(0)
+            .build();
+        let genesis_sap = TestKeys::get_section_signed(&genesis_sk_set.secret_key(), genesis_sap);
+        let section_tree = SectionTree::new(genesis_sap)?;
+        let dbc = gen_genesis_dbc(&genesis_sk_set, &bls::SecretKey::random())?;
+
+        dbc_verify_offline(&dbc, None, &section_tree)?;
+
+        // the spent proofs are signed by a section key unknown to any other network
+        let (other_section_tree, _) = TestSectionTree::random_tree();
+        match dbc_verify_offline(&dbc, None, &other_section_tree) {
+            Err(Error::DbcError(DbcError::FailedKnownKeyCheck(_))) => Ok(()),
+            Err(err) => Err(anyhow!("Error returned is not the expected: {:?}", err)),
+            Ok(()) => Err(anyhow!("DBC verified unexpectedly".to_string())),
+        }
+    }
+
+    #[test]
+    fn test_audit_supply() -> Result<()> {
+        let (_, genesis_sk_set, ..) = TestSapBuilder::new(Prefix::default())
+            .sk_threshold_size(0)
+            .build();
+        let dbc = gen_genesis_dbc(&genesis_sk_set, &bls::SecretKey::random())?;
+
+        let audit = audit_supply(&dbc, vec![])?;
+        assert!(audit.is_sound());
+        assert_eq!(audit.genesis_amount, Token::from_nano(GENESIS_DBC_AMOUNT));
+        assert!(!audit.genesis_spent);
+        assert_eq!(audit.spent_key_images, 0);
+
+        // the input of the genesis transaction is logged as spent twice
+        let genesis_input = dbc
+            .spent_proofs
+            .iter()
+            .next()
+            .ok_or_else(|| anyhow!("Genesis DBC has no spent proofs"))?;
+        let spent = vec![
+            SpentKeyImage {
+                key_image: dbc.key_image_bearer()?,
+                transaction_hashes: BTreeSet::from([Hash::from([0; 32])]),
+            },
+            SpentKeyImage {
+                key_image: *genesis_input.key_image(),
+                transaction_hashes: BTreeSet::from([Hash::from([1; 32]), Hash::from([2; 32])]),
+            },
+        ];
+
+        let audit = audit_supply(&dbc, spent)?;
+        assert!(!audit.is_sound());
+        assert!(audit.genesis_spent);
+        assert_eq!(audit.spent_key_images, 2);
+        assert_eq!(audit.double_spends.len(), 1);
+        assert_eq!(&audit.double_spends[0].key_image, genesis_input.key_image());
+
+        Ok(())
+    }
 
     #[tokio::test]
     async fn test_wallet_create() -> Result<()> {
@@ -962,6 +1317,73 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_wallet_history() -> Result<()> {
+        let (safe, dbc, dbc_balance) = new_safe_instance_with_dbc().await?;
+        let wallet_xorurl = safe.wallet_create().await?;
+
+        safe.wallet_deposit(&wallet_xorurl, Some("deposited-dbc"), &dbc, None)
+            .await?;
+        let pk = bls::SecretKey::random().public_key();
+        let _ = safe.wallet_reissue(&wallet_xorurl, "1", Some(pk)).await?;
+
+        let history = safe.wallet_history(&wallet_xorurl).await?;
+        assert_eq!(history.len(), 2);
+        assert_eq!(
+            history[0].event,
+            WalletHistoryEvent::Deposit {
+                name: "deposited-dbc".to_string(),
+                amount: dbc_balance
+            }
+        );
+        match &history[1].event {
+            WalletHistoryEvent::Reissue {
+                outputs,
+                change,
+                spent_key_images,
+            } => {
+                assert_eq!(outputs, &vec![(Token::from_nano(1_000_000_000), Some(pk))]);
+                let (change_name, change_amount) = change
+                    .as_ref()
+                    .ok_or_else(|| anyhow!("Change DBC not recorded in wallet history"))?;
+                assert_eq!(
+                    *change_amount,
+                    Token::from_nano(dbc_balance.as_nano() - 1_000_000_000)
+                );
+                assert!(safe
+                    .wallet_get(&wallet_xorurl)
+                    .await?
+                    .contains_key(change_name));
+                assert_eq!(spent_key_images, &vec![dbc.key_image_bearer()?]);
+            }
+            other => return Err(anyhow!("Unexpected wallet history record: {:?}", other)),
+        }
+
+        // history records are not spendable balances
+        let current_balance = safe.wallet_balance(&wallet_xorurl).await?;
+        assert_eq!(
+            current_balance,
+            Token::from_nano(dbc_balance.as_nano() - 1_000_000_000)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_wallet_deposit_with_reserved_name() -> Result<()> {
+        let (safe, dbc, _) = new_safe_instance_with_dbc().await?;
+        let wallet_xorurl = safe.wallet_create().await?;
+
+        match safe
+            .wallet_deposit(&wallet_xorurl, Some(WALLET_HISTORY_KEY), &dbc, None)
+            .await
+        {
+            Err(Error::InvalidInput(_)) => Ok(()),
+            Err(err) => Err(anyhow!("Error returned is not the expected: {:?}", err)),
+            Ok(_) => Err(anyhow!("Deposit succeeded unexpectedly".to_string())),
+        }
+    }
+
     #[tokio::test]
     async fn test_wallet_reissue_with_single_input_dbc() -> Result<()> {
         let (safe, dbc, dbc_balance) = new_safe_instance_with_dbc().await?;
@@ -1276,19 +1698,24 @@ mod tests {
             .map(|amount| (Token::from_nano(*amount).to_string(), None))
             .collect();
 
-        let output_dbcs = safe
+        let (output_dbcs, change) = safe
             .wallet_reissue_many(&wallet_xorurl, outputs_owners)
             .await?;
 
         assert_eq!(output_dbcs.len(), output_amounts.len());
 
-        for dbc in output_dbcs {
+        // output DBCs are returned in the same order as the outputs were provided
+        for (dbc, amount) in output_dbcs.iter().zip(output_amounts.iter()) {
             let balance = dbc
                 .amount_secrets_bearer()
                 .map_err(|err| anyhow!("Couldn't read balance from output DBC: {:?}", err))?;
-            assert!(output_amounts.contains(&balance.amount().as_nano()));
+            assert_eq!(balance.amount().as_nano(), *amount);
         }
 
+        let (_, reported_change) =
+            change.ok_or_else(|| anyhow!("Change DBC not reported by reissue"))?;
+        assert_eq!(reported_change, change_amount);
+
         let current_balance = safe.wallet_balance(&wallet_xorurl).await?;
         assert_eq!(current_balance, change_amount);
 
@@ -7,19 +7,34 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::{
-    ipc::{req::AuthReq, resp::AuthGranted},
-    Result, SafeAuthReq,
+    common::auth_types::{AuthedApp, AuthedAppsList},
+    crypto::{derive_hardened_key, Encrypted, PasswordKeys},
+    ipc::{
+        req::{AuthReq, IpcReq},
+        resp::{AuthGranted, IpcResp},
+        IpcMsg,
+    },
+    Error, Result, SafeAuthReq,
 };
 
-use sn_client::api::Client;
-use sn_interface::types::{Keypair, RegisterAddress};
+use sn_client::{Client, DEFAULT_NETWORK_CONTACTS_FILE_NAME};
+use sn_interface::{
+    network_knowledge::SectionTree,
+    types::{
+        register::{Action, EntryHash, Permissions, Policy, User},
+        Keypair, RegisterAddress,
+    },
+};
 
+use bls::{serde_impl::SerdeSecret, SecretKey as BlsSecretKey};
+use bytes::Bytes;
 use hmac::Hmac;
+use log::{debug, info, trace, warn};
 use rand_07::{rngs::StdRng, SeedableRng};
+use serde::{Deserialize, Serialize};
 use sha3::Sha3_256;
 use std::{
-    collections::HashSet,
-    net::SocketAddr,
+    collections::{BTreeMap, BTreeSet},
     path::{Path, PathBuf},
     sync::Arc,
 };
@@ -28,10 +43,12 @@ use xor_name::{XorName, XOR_NAME_LEN};
 
 const SHA3_512_HASH_LEN: usize = 64;
 
-// Type tag value used for the Map which holds the Safe's content on the network.
-#[allow(dead_code)]
+// Type tag value used for the Register which holds the Safe's content on the network.
 const SAFE_TYPE_TAG: u64 = 1_300;
 
+// Path segment the keypairs of the apps are derived under from the master key.
+const APP_KEY_DOMAIN: &[u8] = b"app";
+
 /// Derive Passphrase, Password and Salt (in order).
 pub fn derive_secrets(acc_passphrase: &[u8], acc_password: &[u8]) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
     let mut passphrase_hasher = Sha3::v512();
@@ -95,29 +112,160 @@ pub fn generate_network_address(passphrase: &[u8], salt: &[u8]) -> Result<XorNam
 // Authenticator API
 #[derive(Default)]
 pub struct SafeAuthenticator {
-    // We keep the client instantiated with the derived keypair, along
-    // with the address of the Map which holds its Safe on the network.
-    #[allow(dead_code)]
-    safe: Option<(Client, RegisterAddress)>,
-    #[allow(dead_code)]
+    // The Safe currently unlocked, if any.
+    safe: Option<UnlockedSafe>,
     config_path: Option<PathBuf>,
-    #[allow(dead_code)]
-    bootstrap_contacts: Option<HashSet<SocketAddr>>,
 }
 
-impl SafeAuthenticator {
-    pub fn new(
-        _config_dir_path: Option<&Path>,
-        _bootstrap_contacts: Option<HashSet<SocketAddr>>,
-    ) -> Self {
-        unimplemented!("Authenticator hasn't yet been updated to work with the new Safe Network");
-        // let config_path = config_dir_path.map(|p| p.to_path_buf());
+// We keep the client instantiated with the derived keypair, along with the address
+// of the Register which holds its Safe on the network, and the keys to decrypt it.
+struct UnlockedSafe {
+    client: Client,
+    address: RegisterAddress,
+    keys: PasswordKeys,
+}
+
+// Content of a Safe, stored encrypted on the network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Account {
+    // Master key the keypairs of the applications are derived from.
+    master_key: SerdeSecret<BlsSecretKey>,
+    // Credentials issued to each of the applications authorised, by app id.
+    apps: BTreeMap<String, AppCredentials>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct AppCredentials {
+    name: String,
+    vendor: String,
+    // Incremented every time the app is authorised again after being revoked,
+    // so it's then granted a keypair different from the revoked one.
+    generation: u32,
+    revoked: bool,
+    // Registers the app's keypair was granted write to, from which it's removed upon revocation.
+    registers: BTreeSet<RegisterAddress>,
+    // Registers the keypairs of earlier, revoked, generations of the app are yet to be
+    // removed from, by generation, as that failed when they were revoked.
+    pending_revocations: BTreeMap<u32, BTreeSet<RegisterAddress>>,
+}
+
+impl AppCredentials {
+    fn new(name: String, vendor: String, generation: u32) -> Self {
+        Self {
+            name,
+            vendor,
+            generation,
+            revoked: false,
+            registers: BTreeSet::new(),
+            pending_revocations: BTreeMap::new(),
+        }
+    }
+
+    // Keep track of the Registers an earlier generation of the app is yet to be removed from.
+    fn carry_over(&mut self, earlier: AppCredentials) {
+        self.merge_pending(earlier.pending_revocations);
+        if !earlier.registers.is_empty() {
+            self.merge_pending(BTreeMap::from([(earlier.generation, earlier.registers)]));
+        }
+    }
+
+    fn merge_pending(&mut self, pending: BTreeMap<u32, BTreeSet<RegisterAddress>>) {
+        for (generation, registers) in pending {
+            self.pending_revocations
+                .entry(generation)
+                .or_default()
+                .extend(registers);
+        }
+    }
+
+    // Registers each revoked generation of the app is yet to be removed from.
+    fn revocations(&self) -> BTreeMap<u32, BTreeSet<RegisterAddress>> {
+        let mut revocations = self.pending_revocations.clone();
+        if self.revoked && !self.registers.is_empty() {
+            let _ = revocations.insert(self.generation, self.registers.clone());
+        }
+        revocations
+    }
+
+    fn revocation_done(&mut self, generation: u32, address: &RegisterAddress) {
+        if generation == self.generation {
+            let _ = self.registers.remove(address);
+        } else if let Some(registers) = self.pending_revocations.get_mut(&generation) {
+            let _ = registers.remove(address);
+            if registers.is_empty() {
+                let _ = self.pending_revocations.remove(&generation);
+            }
+        }
+    }
+}
+
+impl Account {
+    fn new() -> Self {
+        Self {
+            master_key: SerdeSecret(BlsSecretKey::random()),
+            apps: BTreeMap::new(),
+        }
+    }
+
+    // Derive the keypair of the given generation of an app from the master key.
+    // The derivation is hardened, so an app can't obtain the master key, nor the
+    // keypairs of other apps or of its own other generations, from its keypair.
+    fn app_keypair(&self, app_id: &str, generation: u32) -> Result<Keypair> {
+        let sk = derive_hardened_key(
+            &self.master_key,
+            &[APP_KEY_DOMAIN, app_id.as_bytes(), &generation.to_be_bytes()],
+        );
+        Ok(Keypair::bls_from_hex(&sk.to_hex())?)
+    }
+
+    // Record a new generation of an app which is authorised again after being revoked,
+    // keeping track of the Registers the earlier generations are yet to be removed from.
+    fn reauthorise_app(&mut self, app_id: &str, name: String, vendor: String) -> u32 {
+        let generation = self
+            .apps
+            .get(app_id)
+            .map(|app| app.generation + 1)
+            .unwrap_or(0);
+        let mut app = AppCredentials::new(name, vendor, generation);
+        if let Some(earlier) = self.apps.remove(app_id) {
+            app.carry_over(earlier);
+        }
+        let _ = self.apps.insert(app_id.to_string(), app);
+        generation
+    }
+
+    // Merge the content of a concurrent version of the Safe into this one.
+    // Revocations take precedence, so a revoked app is never granted its keypair again,
+    // and the Registers any revoked generation is yet to be removed from are kept.
+    fn merge(&mut self, other: Account) {
+        for (app_id, app) in other.apps {
+            match self.apps.get_mut(&app_id) {
+                Some(existing) if existing.generation == app.generation => {
+                    existing.revoked |= app.revoked;
+                    existing.registers.extend(app.registers);
+                    existing.merge_pending(app.pending_revocations);
+                }
+                Some(existing) if existing.generation < app.generation => {
+                    let earlier = std::mem::replace(existing, app);
+                    existing.carry_over(earlier);
+                }
+                Some(existing) => existing.carry_over(app),
+                None => {
+                    let _ = self.apps.insert(app_id, app);
+                }
+            }
+        }
+    }
+}
 
-        // Self {
-        //     safe: None,
-        //     config_path,
-        //     bootstrap_contacts,
-        // }
+impl SafeAuthenticator {
+    /// Create an authenticator. If a config dir path is provided, the network contacts
+    /// are read from the file found in it, otherwise from the default location.
+    pub fn new(config_dir_path: Option<&Path>) -> Self {
+        Self {
+            safe: None,
+            config_path: config_dir_path.map(|p| p.to_path_buf()),
+        }
     }
 
     /// # Create Safe
@@ -138,9 +286,8 @@ impl SafeAuthenticator {
     /// let my_password = "mypassword";
     /// # let my_secret = &(random_str());
     /// # let my_password = &(random_str());
-    /// # let sk = "83c055c5efdc483bd967adba5c1769daee0a17bc5fa2b6e129cd6b596c217617";
     /// # async_std::task::block_on(async {
-    /// let acc_created = safe_auth.create(sk, my_secret, my_password).await;
+    /// let acc_created = safe_auth.create(my_secret, my_password).await;
     /// match acc_created {
     ///    Ok(()) => assert!(true), // This should pass
     ///    Err(_) => assert!(false)
@@ -160,10 +307,9 @@ impl SafeAuthenticator {
     /// let my_password = "mypassword";
     /// # let my_secret = &(random_str());
     /// # let my_password = &(random_str());
-    /// # let sk = "83c055c5efdc483bd967adba5c1769daee0a17bc5fa2b6e129cd6b596c217617";
     /// # async_std::task::block_on(async {
-    /// # safe_auth.create(sk, my_secret, my_password).await.unwrap();
-    /// let acc_not_created = safe_auth.create(sk, my_secret, my_password).await;
+    /// # safe_auth.create(my_secret, my_password).await.unwrap();
+    /// let acc_not_created = safe_auth.create(my_secret, my_password).await;
     /// match acc_not_created {
     ///    Ok(_) => assert!(false), // This should not pass
     ///    Err(Error::AuthError(message)) => {
@@ -173,53 +319,52 @@ impl SafeAuthenticator {
     /// }
     /// # });
     ///```
-    pub async fn create(&mut self, _passphrase: &str, _password: &str) -> Result<()> {
-        unimplemented!("Authenticator hasn't yet been updated to work with the new Safe Network");
-        // debug!("Attempting to create a Safe from provided passphrase and password.");
-
-        // let (location, keypair) = derive_location_and_keypair(passphrase, password)?;
-        // let data_owner = keypair.public_key();
-
-        // debug!("Creating Safe to be owned by PublicKey: {:?}", data_owner);
-
-        // let client = Client::new(
-        //     Some(keypair),
-        //     self.config_path.as_deref(),
-        //     self.bootstrap_contacts.clone(),
-        //     DEFAULT_OPERATION_TIMEOUT,
-        // )
-        // .await?;
-        // trace!("Client instantiated properly!");
-
-        // // Create Map data to store the list of keypairs generated for
-        // // each of the user's applications.
-        // let permission_set = MapPermissionSet::new()
-        //     .allow(MapAction::Read)
-        //     .allow(MapAction::Insert)
-        //     .allow(MapAction::Update)
-        //     .allow(MapAction::Delete)
-        //     .allow(MapAction::ManagePermissions);
-
-        // let mut permission_map = BTreeMap::new();
-        // permission_map.insert(data_owner, permission_set);
-
-        // // TODO: encrypt content
-        // let map_address = client
-        //     .store_seq_map(
-        //         location,
-        //         SAFE_TYPE_TAG,
-        //         data_owner,
-        //         None,
-        //         Some(permission_map),
-        //     )
-        //     .await
-        //     .map_err(|err| {
-        //         Error::AuthenticatorError(format!("Failed to store Safe on a Map: {}", err))
-        //     })?;
-        // debug!("Map stored successfully for new Safe!");
-
-        // self.safe = Some((client, map_address));
-        // Ok(())
+    pub async fn create(&mut self, passphrase: &str, password: &str) -> Result<()> {
+        debug!("Attempting to create a Safe from provided passphrase and password.");
+
+        let (location, keypair) = derive_location_and_keypair(passphrase, password)?;
+        let data_owner = keypair.public_key();
+
+        debug!("Creating Safe to be owned by PublicKey: {:?}", data_owner);
+
+        let client = self.connect(keypair).await?;
+        trace!("Client instantiated properly!");
+
+        let address = RegisterAddress {
+            name: location,
+            tag: SAFE_TYPE_TAG,
+        };
+        if client.get_register(address).await.is_ok() {
+            return Err(Error::AuthError(
+                "Failed to create a Safe: a Safe already exists with the provided passphrase"
+                    .to_string(),
+            ));
+        }
+
+        // Create the Register to store the list of keypairs generated
+        // for each of the user's applications, only writable by its owner.
+        let owner = User::Key(data_owner);
+        let policy = Policy {
+            owner,
+            permissions: BTreeMap::from([(owner, Permissions::new(true))]),
+        };
+        let (_, op_batch) = client
+            .create_register(location, SAFE_TYPE_TAG, policy)
+            .await?;
+        client.publish_register_ops(op_batch).await.map_err(|err| {
+            Error::AuthError(format!("Failed to create a Safe on a Register: {}", err))
+        })?;
+        debug!("Register stored successfully for new Safe!");
+
+        let safe = UnlockedSafe {
+            client,
+            address,
+            keys: derive_account_keys(passphrase, password),
+        };
+        store_account(&safe, &Account::new(), BTreeSet::new()).await?;
+
+        self.safe = Some(safe);
+        Ok(())
     }
 
     /// # Unlock
@@ -236,9 +381,8 @@ impl SafeAuthenticator {
     /// let my_password = "mypassword";
     /// # let my_secret = &(random_str());
     /// # let my_password = &(random_str());
-    /// # let sk = "83c055c5efdc483bd967adba5c1769daee0a17bc5fa2b6e129cd6b596c217617";
     /// # async_std::task::block_on(async {
-    /// # safe_auth.create(sk, my_secret, my_password).await.unwrap();
+    /// # safe_auth.create(my_secret, my_password).await.unwrap();
     /// let logged_in = safe_auth.unlock(my_secret, my_password).await;
     /// match logged_in {
     ///    Ok(()) => assert!(true), // This should pass
@@ -263,251 +407,442 @@ impl SafeAuthenticator {
     /// }
     /// # });
     ///```
-    pub async fn unlock(&mut self, _passphrase: &str, _password: &str) -> Result<()> {
-        unimplemented!("Authenticator hasn't yet been updated to work with the new Safe Network");
-
-        // debug!("Attempting to unlock a Safe...");
-
-        // let (location, keypair) = derive_location_and_keypair(passphrase, password)?;
-
-        // debug!(
-        //     "Unlocking Safe owned by PublicKey: {:?}",
-        //     keypair.public_key()
-        // );
-
-        // let client = Client::new(
-        //     Some(keypair),
-        //     self.config_path.as_deref(),
-        //     self.bootstrap_contacts.clone(),
-        //     DEFAULT_OPERATION_TIMEOUT,
-        // )
-        // .await?;
-        // trace!("Client instantiated properly!");
-
-        // let map_address = MapAddress::Seq {
-        //     name: location,
-        //     tag: SAFE_TYPE_TAG,
-        // };
-
-        // // Attempt to retrieve Map to make sure it actually exists
-        // let _ = client.get_map(map_address).await?;
-        // debug!("Safe unlocked successfully!");
-
-        // self.safe = Some((client, map_address));
-        // Ok(())
+    pub async fn unlock(&mut self, passphrase: &str, password: &str) -> Result<()> {
+        debug!("Attempting to unlock a Safe...");
+
+        let (location, keypair) = derive_location_and_keypair(passphrase, password)?;
+
+        debug!(
+            "Unlocking Safe owned by PublicKey: {:?}",
+            keypair.public_key()
+        );
+
+        let client = self.connect(keypair).await?;
+        trace!("Client instantiated properly!");
+
+        let safe = UnlockedSafe {
+            client,
+            address: RegisterAddress {
+                name: location,
+                tag: SAFE_TYPE_TAG,
+            },
+            keys: derive_account_keys(passphrase, password),
+        };
+
+        // Attempt to retrieve and decrypt the Safe to make sure it actually exists
+        let _ = fetch_account(&safe)
+            .await
+            .map_err(|err| Error::AuthError(format!("Failed to log in: {}", err)))?;
+        debug!("Safe unlocked successfully!");
+
+        self.safe = Some(safe);
+        Ok(())
     }
 
     pub fn lock(&mut self) -> Result<()> {
-        unimplemented!("Authenticator hasn't yet been updated to work with the new Safe Network");
-
-        // debug!("Locking Safe...");
-        // self.safe = None;
-        // Ok(())
+        debug!("Locking Safe...");
+        self.safe = None;
+        Ok(())
     }
 
     pub fn is_a_safe_unlocked(&self) -> bool {
-        unimplemented!("Authenticator hasn't yet been updated to work with the new Safe Network");
-
-        // let is_a_safe_unlocked = self.safe.is_some();
-        // debug!(
-        //     "Is there a Safe currently unlocked?: {}",
-        //     is_a_safe_unlocked
-        // );
-        // is_a_safe_unlocked
+        let is_a_safe_unlocked = self.safe.is_some();
+        debug!(
+            "Is there a Safe currently unlocked?: {}",
+            is_a_safe_unlocked
+        );
+        is_a_safe_unlocked
     }
 
-    pub async fn decode_req(&self, _req: &str) -> Result<SafeAuthReq> {
-        unimplemented!("Authenticator hasn't yet been updated to work with the new Safe Network");
+    pub async fn decode_req(&self, req: &str) -> Result<SafeAuthReq> {
+        match IpcMsg::from_string(req) {
+            Ok(IpcMsg::Req(IpcReq::Auth(app_auth_req))) => {
+                debug!("Auth request string decoded: {:?}", app_auth_req);
+                Ok(SafeAuthReq::Auth(app_auth_req))
+            }
+            Ok(other) => Err(Error::AuthError(format!(
+                "Failed to decode string as an authorisation request, it's a: '{:?}'",
+                other
+            ))),
+            Err(error) => Err(Error::AuthenticatorError(format!(
+                "Failed to decode request: {:?}",
+                error
+            ))),
+        }
+    }
 
-        // match IpcMsg::from_string(req) {
-        //     Ok(IpcMsg::Req(IpcReq::Auth(app_auth_req))) => {
-        //         debug!("Auth request string decoded: {:?}", app_auth_req);
-        //         Ok(SafeAuthReq::Auth(app_auth_req))
-        //     }
-        //     Ok(other) => Err(Error::AuthError(format!(
-        //         "Failed to decode string as an authorisation request, it's a: '{:?}'",
-        //         other
-        //     ))),
-        //     Err(error) => Err(Error::AuthenticatorError(format!(
-        //         "Failed to decode request: {:?}",
-        //         error
-        //     ))),
-        // }
+    /// List the applications currently authorised in the unlocked Safe.
+    pub async fn authed_apps(&self) -> Result<AuthedAppsList> {
+        let safe = self.unlocked_safe()?;
+        let account = fetch_account(safe).await?;
+        let apps = account
+            .apps
+            .into_iter()
+            .filter(|(_, app)| !app.revoked)
+            .map(|(id, app)| AuthedApp {
+                id,
+                name: app.name,
+                vendor: app.vendor,
+            })
+            .collect();
+
+        Ok(apps)
     }
 
-    // TODO: update terminology around apps auth here
-    pub async fn revoke_app(&self, _y: &str) -> Result<()> {
-        unimplemented!()
+    /// Grant an authorised application write to a Register owned by the unlocked Safe.
+    ///
+    /// The app's key is added to the Register's policy, unless it was already granted write
+    /// as the Register was created by the app, and the Register is recorded in the Safe so
+    /// the app's key is removed from its policy when the app is revoked.
+    pub async fn grant_app_register_write(
+        &self,
+        app_id: &str,
+        address: RegisterAddress,
+    ) -> Result<()> {
+        debug!(
+            "Granting application {} write to Register {:?}",
+            app_id, address
+        );
+        let safe = self.unlocked_safe()?;
+        let (mut account, entries) = fetch_account_entries(safe).await?;
+        let generation = match account.apps.get(app_id) {
+            Some(app) if !app.revoked => app.generation,
+            _ => {
+                return Err(Error::AuthError(format!(
+                    "No application with id '{}' is currently authorised",
+                    app_id
+                )))
+            }
+        };
+        let app_user = User::Key(account.app_keypair(app_id, generation)?.public_key());
+
+        let register = safe.client.get_register(address).await?;
+        let mut policy = register.policy().clone();
+        let can_write = policy
+            .permissions
+            .get(&app_user)
+            .copied()
+            .and_then(|permissions| permissions.is_allowed(Action::Write))
+            .unwrap_or(false);
+        if !can_write {
+            let _ = policy.permissions.insert(app_user, Permissions::new(true));
+            let op_batch = safe.client.update_register_policy(address, policy).await?;
+            safe.client.publish_register_ops(op_batch).await?;
+        }
+
+        if let Some(app) = account.apps.get_mut(app_id) {
+            let _ = app.registers.insert(address);
+        }
+        store_account(safe, &account, entries).await?;
+        info!(
+            "Application '{}' granted write to Register {:?}",
+            app_id, address
+        );
+        Ok(())
     }
 
-    /// Decode requests and trigger application authorisation against the current client
-    pub async fn authorise_app(&self, _req: &str) -> Result<String> {
-        unimplemented!("Authenticator hasn't yet been updated to work with the new Safe Network");
+    /// Revoke an application, its keypair is then never granted again. If the application
+    /// is authorised again later on, a new keypair is derived for it.
+    ///
+    /// The app's key is removed from the policy of each of the Registers it was granted write to
+    /// with [`Self::grant_app_register_write`]. If that fails for some of them, the app is still
+    /// revoked, and revoking it again, or authorising it again, retries removing its key from
+    /// the remaining Registers.
+    pub async fn revoke_app(&self, app_id: &str) -> Result<()> {
+        debug!("Revoking application: {}", app_id);
+        let safe = self.unlocked_safe()?;
+        let (mut account, entries) = fetch_account_entries(safe).await?;
+        match account.apps.get_mut(app_id) {
+            Some(app)
+                if !app.revoked
+                    || !app.registers.is_empty()
+                    || !app.pending_revocations.is_empty() =>
+            {
+                app.revoked = true;
+            }
+            _ => {
+                return Err(Error::AuthError(format!(
+                    "No application with id '{}' is currently authorised",
+                    app_id
+                )))
+            }
+        }
 
-        // let ipc_req = IpcMsg::from_string(req).map_err(|err| {
-        //     Error::AuthenticatorError(format!("Failed to decode authorisation request: {:?}", err))
-        // })?;
+        let failed = revoke_app_registers(safe, &mut account, app_id).await?;
 
-        // debug!("Auth request string decoded: {:?}", ipc_req);
+        store_account(safe, &account, entries).await?;
+        if !failed.is_empty() {
+            return Err(Error::AuthError(format!(
+                "Application '{}' was revoked, but its key could not be removed from the \
+                policy of Registers {:?}, revoke it again to retry",
+                app_id, failed
+            )));
+        }
 
-        // match ipc_req {
-        //     IpcMsg::Req(IpcReq::Auth(app_auth_req)) => {
-        //         info!("Request was recognised as an application auth request");
-        //         debug!("Decoded request: {:?}", app_auth_req);
-        //         self.gen_auth_response(app_auth_req).await
-        //     }
-        //     IpcMsg::Req(IpcReq::Unregistered(user_data)) => {
-        //         info!("Request was recognised as an unregistered auth request");
-        //         debug!("Decoded request: {:?}", user_data);
+        info!("Application '{}' revoked", app_id);
+        Ok(())
+    }
 
-        //         self.gen_unreg_auth_response()
-        //     }
-        //     IpcMsg::Resp { .. } | IpcMsg::Err(..) => Err(Error::AuthError(
-        //         "The request was not recognised as a valid auth request".to_string(),
-        //     )),
-        // }
+    /// Decode requests and trigger application authorisation against the current client
+    pub async fn authorise_app(&self, req: &str) -> Result<String> {
+        let ipc_req = IpcMsg::from_string(req).map_err(|err| {
+            Error::AuthenticatorError(format!("Failed to decode authorisation request: {:?}", err))
+        })?;
+
+        debug!("Auth request string decoded: {:?}", ipc_req);
+
+        match ipc_req {
+            IpcMsg::Req(IpcReq::Auth(app_auth_req)) => {
+                info!("Request was recognised as an application auth request");
+                debug!("Decoded request: {:?}", app_auth_req);
+                self.gen_auth_response(app_auth_req).await
+            }
+            IpcMsg::Req(IpcReq::Unregistered(_)) => Err(Error::AuthError(
+                "Unregistered authorisation requests are not supported, the network contacts \
+                are to be obtained from the network's contacts file"
+                    .to_string(),
+            )),
+            IpcMsg::Resp { .. } | IpcMsg::Err(..) => Err(Error::AuthError(
+                "The request was not recognised as a valid auth request".to_string(),
+            )),
+        }
     }
 
     /// Authenticate an app request.
     ///
     /// First, this function searches for an app info in the Safe.
     /// If the app is found, then the `AuthGranted` struct is returned based on that information.
-    /// If the app is not found in the Safe, then it will be authenticated.
-    pub async fn authenticate(&self, _auth_req: AuthReq) -> Result<AuthGranted> {
-        unimplemented!("Authenticator hasn't yet been updated to work with the new Safe Network");
-
-        // debug!(
-        //     "Retrieving/generating keypair for an application: {:?}",
-        //     auth_req
-        // );
-        // if let Some((client, map_address)) = &self.safe {
-        //     let app_id = auth_req.app_id.as_bytes().to_vec();
-        //     let keypair = match client.get_map_value(*map_address, app_id.clone()).await {
-        //         Ok(value) => {
-        //             // This app already has its own keypair
-        //             trace!(
-        //                 "The app ('{}') already has a Keypair in the Safe",
-        //                 auth_req.app_id
-        //             );
-
-        //             // TODO: support for scenario when app was previously revoked,
-        //             // in which case we should generate a new keypair
-
-        //             let keypair_bytes = match value {
-        //                 MapValue::Seq(seq_value) => seq_value.data,
-        //                 MapValue::Unseq(data) => data,
-        //             };
-        //             let keypair_str = String::from_utf8(keypair_bytes).map_err(|_err| {
-        //                 Error::AuthError(
-        //                     "The Safe contains an invalid keypair associated to this app"
-        //                         .to_string(),
-        //                 )
-        //             })?;
-        //             let keypair: Keypair = serde_json::from_str(&keypair_str).map_err(|_err| {
-        //                 Error::AuthError(
-        //                     "The Safe contains an invalid keypair associated to this app"
-        //                         .to_string(),
-        //                 )
-        //             })?;
-
-        //             debug!(
-        //                 "Keypair for the app being authorised ('{}') retrieved from the Safe: {}",
-        //                 auth_req.app_id,
-        //                 keypair.public_key()
-        //             );
-
-        //             keypair
-        //         }
-        //         Err(ClientError::ErrorMsg {
-        //             source: NoSuchEntry,
-        //             ..
-        //         }) => {
-        //             // This is the first time this app is being authorised,
-        //             // thus let's generate a keypair for it
-        //             trace!(
-        //                 "The app ('{}') was not assigned a Keypair yet in the Safe. Generating one for it...",
-        //                 auth_req.app_id
-        //             );
-        //             let mut rng = OsRng;
-        //             let keypair = Keypair::new_ed25519(&mut rng);
-
-        //             let keypair_str = serde_json::to_string(&keypair).map_err(|err| {
-        //                 Error::AuthError(format!(
-        //                     "Failed to serialised keypair to store it in the Safe: {}",
-        //                     err
-        //                 ))
-        //             })?;
-
-        //             debug!(
-        //                 "New keypair generated for app ('{}') being authorised: {}",
-        //                 auth_req.app_id,
-        //                 keypair.public_key()
-        //             );
-
-        //             // Store the keypair in the Safe, mapped to the app id
-        //             let map_actions =
-        //                 MapSeqEntryActions::new().ins(app_id, keypair_str.as_bytes().to_vec(), 0);
-
-        //             client
-        //                 .edit_map_entries(*map_address, MapEntryActions::Seq(map_actions))
-        //                 .await?;
-
-        //             keypair
-        //         }
-        //         Err(err) => {
-        //             return Err(Error::AuthError(format!(
-        //                 "Failed to retrieve keypair from the Safe: {}",
-        //                 err
-        //             )))
-        //         }
-        //     };
-
-        //     Ok(AuthGranted {
-        //         app_keypair: keypair,
-        //         bootstrap_config: self.bootstrap_contacts.clone(),
-        //     })
-        // } else {
-        //     Err(Error::AuthenticatorError(
-        //         "No Safe is currently unlocked".to_string(),
-        //     ))
-        // }
+    /// If the app is not found in the Safe, or it was revoked, then it will be authenticated
+    /// with a newly derived keypair. In the latter case, removing the revoked keypairs from the
+    /// Registers they could not be removed from upon revocation is retried.
+    ///
+    /// The app is also given the public key of the Safe, which the Registers it creates are to
+    /// be owned by, while granting write to the app's keypair, so the user keeps control of them.
+    pub async fn authenticate(&self, auth_req: AuthReq) -> Result<AuthGranted> {
+        debug!(
+            "Retrieving/generating keypair for an application: {:?}",
+            auth_req
+        );
+        let safe = self.unlocked_safe()?;
+        let (mut account, entries) = fetch_account_entries(safe).await?;
+        let owner = safe.client.public_key();
+
+        if let Some(app) = account.apps.get(&auth_req.app_id) {
+            if !app.revoked {
+                // This app was already authorised, thus it gets the same keypair
+                let app_keypair = account.app_keypair(&auth_req.app_id, app.generation)?;
+                debug!(
+                    "Keypair for the app being authorised ('{}') derived from the Safe: {}",
+                    auth_req.app_id,
+                    app_keypair.public_key()
+                );
+                return Ok(AuthGranted { app_keypair, owner });
+            }
+        }
+
+        // This is the first time this app is being authorised, or it was revoked,
+        // thus let's record it in the Safe, mapped to the app id, and derive a new keypair for it
+        let generation =
+            account.reauthorise_app(&auth_req.app_id, auth_req.app_name, auth_req.app_vendor);
+        let app_keypair = account.app_keypair(&auth_req.app_id, generation)?;
+        debug!(
+            "New keypair derived for app ('{}') being authorised: {}",
+            auth_req.app_id,
+            app_keypair.public_key()
+        );
+
+        // The Registers the earlier generations failed to be removed from are kept
+        // in the Safe if it fails again, so it's retried upon the next revocation
+        let failed = revoke_app_registers(safe, &mut account, &auth_req.app_id).await?;
+        if !failed.is_empty() {
+            warn!(
+                "The revoked keys of application '{}' could still not be removed from the \
+                policy of Registers {:?}",
+                auth_req.app_id, failed
+            );
+        }
+        store_account(safe, &account, entries).await?;
+
+        Ok(AuthGranted { app_keypair, owner })
     }
 
     // Helper function to generate an app authorisation response
-    // async fn gen_auth_response(&self, auth_req: AuthReq) -> Result<String> {
-    //     let auth_granted = self.authenticate(auth_req).await.map_err(|err| {
-    //         Error::AuthenticatorError(format!(
-    //             "Failed to authorise application on the network: {}",
-    //             err
-    //         ))
-    //     })?;
-
-    //     debug!("Encoding response with auth credentials auth granted...");
-    //     let resp = serde_json::to_string(&IpcMsg::Resp(IpcResp::Auth(Ok(auth_granted)))).map_err(
-    //         |err| Error::AuthenticatorError(format!("Failed to encode response: {:?}", err)),
-    //     )?;
-
-    //     debug!("Returning auth response generated");
-
-    //     Ok(resp)
-    // }
-
-    // Helper function to generate an unregistered authorisation response
-    // fn gen_unreg_auth_response(&self) -> Result<String> {
-    //     let bootstrap_contacts = self.bootstrap_contacts.clone().ok_or_else(|| {
-    //         Error::AuthenticatorError("Bootstrap contacts information not available".to_string())
-    //     })?;
-
-    //     debug!("Encoding response... {:?}", bootstrap_contacts);
-    //     let resp =
-    //         serde_json::to_string(&IpcMsg::Resp(IpcResp::Unregistered(Ok(bootstrap_contacts))))
-    //             .map_err(|err| {
-    //                 Error::AuthenticatorError(format!("Failed to encode response: {:?}", err))
-    //             })?;
-
-    //     debug!("Returning unregistered auth response generated: {:?}", resp);
-    //     Ok(resp)
-    // }
+    async fn gen_auth_response(&self, auth_req: AuthReq) -> Result<String> {
+        let auth_granted = self.authenticate(auth_req).await.map_err(|err| {
+            Error::AuthenticatorError(format!(
+                "Failed to authorise application on the network: {}",
+                err
+            ))
+        })?;
+
+        debug!("Encoding response with auth credentials auth granted...");
+        let resp = IpcMsg::Resp(IpcResp::Auth(Ok(auth_granted)))
+            .to_string()
+            .map_err(|err| {
+                Error::AuthenticatorError(format!("Failed to encode response: {:?}", err))
+            })?;
+
+        debug!("Returning auth response generated");
+
+        Ok(resp)
+    }
+
+    // Helper function to instantiate a client with the given keypair
+    async fn connect(&self, keypair: Keypair) -> Result<Client> {
+        let mut builder = Client::builder().from_env().keypair(keypair);
+        if let Some(config_path) = &self.config_path {
+            let network_contacts =
+                SectionTree::from_disk(&config_path.join(DEFAULT_NETWORK_CONTACTS_FILE_NAME))
+                    .await
+                    .map_err(|err| {
+                        Error::ConnectionError(format!(
+                            "Failed to read network contacts: {:?}",
+                            err
+                        ))
+                    })?;
+            builder = builder.network_contacts(network_contacts);
+        }
+
+        builder.build().await.map_err(|err| {
+            Error::ConnectionError(format!("Failed to connect to the SAFE Network: {:?}", err))
+        })
+    }
+
+    fn unlocked_safe(&self) -> Result<&UnlockedSafe> {
+        self.safe
+            .as_ref()
+            .ok_or_else(|| Error::AuthenticatorError("No Safe is currently unlocked".to_string()))
+    }
+}
+
+// Remove the keys of the revoked generations of an app from the policy of the Registers they
+// were granted write to, returning the Registers it failed for, which are kept in the Safe.
+async fn revoke_app_registers(
+    safe: &UnlockedSafe,
+    account: &mut Account,
+    app_id: &str,
+) -> Result<Vec<RegisterAddress>> {
+    let revocations = match account.apps.get(app_id) {
+        Some(app) => app.revocations(),
+        None => return Ok(vec![]),
+    };
+
+    let mut failed = vec![];
+    for (generation, registers) in revocations {
+        let app_user = User::Key(account.app_keypair(app_id, generation)?.public_key());
+        for address in registers {
+            match revoke_register_write(safe, address, app_user).await {
+                Ok(()) => {
+                    if let Some(app) = account.apps.get_mut(app_id) {
+                        app.revocation_done(generation, &address);
+                    }
+                }
+                Err(err) => {
+                    warn!(
+                        "Failed to remove app '{}' from the policy of Register {:?}: {}",
+                        app_id, address, err
+                    );
+                    failed.push(address);
+                }
+            }
+        }
+    }
+
+    Ok(failed)
+}
+
+// Remove the given user from the policy of a Register, if it's in it.
+async fn revoke_register_write(
+    safe: &UnlockedSafe,
+    address: RegisterAddress,
+    user: User,
+) -> Result<()> {
+    let register = safe.client.get_register(address).await?;
+    let mut policy = register.policy().clone();
+    if policy.permissions.remove(&user).is_some() {
+        let op_batch = safe.client.update_register_policy(address, policy).await?;
+        safe.client.publish_register_ops(op_batch).await?;
+    }
+    Ok(())
+}
+
+// Derive the keys the Safe's content is encrypted with
+fn derive_account_keys(passphrase: &str, password: &str) -> PasswordKeys {
+    let (_, password, salt) = derive_secrets(passphrase.as_bytes(), password.as_bytes());
+    PasswordKeys::derive(&password, &salt)
+}
+
+// Fetch the Safe's content, along with the hashes of the Register entries it was read from
+async fn fetch_account_entries(safe: &UnlockedSafe) -> Result<(Account, BTreeSet<EntryHash>)> {
+    let entries = safe.client.read_register(safe.address).await?;
+
+    // There can be more than one latest entry if the Safe was updated concurrently,
+    // in which case we merge them.
+    let mut account: Option<Account> = None;
+    let mut hashes = BTreeSet::new();
+    for (hash, entry) in entries {
+        let name = bincode::deserialize(&entry).map_err(|err| {
+            Error::AuthError(format!("The Safe contains an invalid entry: {}", err))
+        })?;
+        let bytes = safe.client.read_bytes(name).await?;
+        let other = decrypt_account(&safe.keys, &bytes)?;
+        match &mut account {
+            Some(account) => account.merge(other),
+            None => account = Some(other),
+        }
+        let _ = hashes.insert(hash);
+    }
+
+    let account = account.ok_or_else(|| Error::AuthError("The Safe has no content".to_string()))?;
+    Ok((account, hashes))
+}
+
+async fn fetch_account(safe: &UnlockedSafe) -> Result<Account> {
+    fetch_account_entries(safe)
+        .await
+        .map(|(account, _)| account)
+}
+
+// Encrypt and upload the Safe's content, and write its address to the Register,
+// on top of the entries the content was read from.
+async fn store_account(
+    safe: &UnlockedSafe,
+    account: &Account,
+    children: BTreeSet<EntryHash>,
+) -> Result<()> {
+    let bytes = encrypt_account(&safe.keys, account)?;
+    let name = safe.client.upload(bytes).await?;
+    let entry = bincode::serialize(&name)
+        .map_err(|err| Error::Serialisation(format!("Failed to serialise entry: {}", err)))?;
+
+    let (_, op_batch) = safe
+        .client
+        .write_to_local_register(safe.address, entry, children)
+        .await?;
+    safe.client
+        .publish_register_ops(op_batch)
+        .await
+        .map_err(|err| Error::AuthError(format!("Failed to store the Safe: {}", err)))?;
+
+    Ok(())
+}
+
+fn encrypt_account(keys: &PasswordKeys, account: &Account) -> Result<Bytes> {
+    let serialised = bincode::serialize(account)
+        .map_err(|err| Error::Serialisation(format!("Failed to serialise Safe: {}", err)))?;
+    let encrypted = bincode::serialize(&keys.encrypt(&serialised))
+        .map_err(|err| Error::Serialisation(format!("Failed to serialise Safe: {}", err)))?;
+    Ok(Bytes::from(encrypted))
+}
+
+fn decrypt_account(keys: &PasswordKeys, bytes: &[u8]) -> Result<Account> {
+    let encrypted: Encrypted = bincode::deserialize(bytes)
+        .map_err(|err| Error::AuthError(format!("The Safe content is invalid: {}", err)))?;
+    let serialised = keys.decrypt(&encrypted).ok_or_else(|| {
+        Error::AuthError("Failed to decrypt the Safe, the password is incorrect".to_string())
+    })?;
+    bincode::deserialize(&serialised)
+        .map_err(|err| Error::AuthError(format!("The Safe content is invalid: {}", err)))
 }
 
 #[cfg(test)]
@@ -537,6 +872,125 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn account_should_only_be_decrypted_with_the_right_password() -> Result<()> {
+        let mut account = Account::new();
+        let _ = account.apps.insert(
+            "app-id".to_string(),
+            AppCredentials::new("app".to_string(), "vendor".to_string(), 0),
+        );
+
+        let keys = derive_account_keys("passphrase", "password");
+        let bytes = encrypt_account(&keys, &account)?;
+        assert_eq!(decrypt_account(&keys, &bytes)?, account);
+
+        let wrong_keys = derive_account_keys("passphrase", "wrong");
+        assert!(decrypt_account(&wrong_keys, &bytes).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn account_merge_should_keep_revocations() {
+        let app = AppCredentials::new("app".to_string(), "vendor".to_string(), 0);
+        let mut account = Account::new();
+        let _ = account.apps.insert("app-id".to_string(), app.clone());
+
+        let address = RegisterAddress {
+            name: XorName::random(&mut rand::thread_rng()),
+            tag: SAFE_TYPE_TAG,
+        };
+        let mut revoked = account.clone();
+        let _ = revoked.apps.insert(
+            "app-id".to_string(),
+            AppCredentials {
+                revoked: true,
+                registers: BTreeSet::from([address]),
+                ..app
+            },
+        );
+
+        account.merge(revoked);
+        assert!(account.apps.values().all(|app| app.revoked));
+        // the Registers granted to the app are kept, so its key is removed from them
+        assert!(account
+            .apps
+            .values()
+            .all(|app| app.registers.contains(&address)));
+    }
+
+    #[test]
+    fn reauthorised_app_should_keep_failed_revocations() {
+        let address = RegisterAddress {
+            name: XorName::random(&mut rand::thread_rng()),
+            tag: SAFE_TYPE_TAG,
+        };
+        let mut account = Account::new();
+        // the app was revoked, but its key failed to be removed from the Register
+        let _ = account.apps.insert(
+            "app-id".to_string(),
+            AppCredentials {
+                revoked: true,
+                registers: BTreeSet::from([address]),
+                ..AppCredentials::new("app".to_string(), "vendor".to_string(), 0)
+            },
+        );
+        let revoked = account.clone();
+
+        let generation = account.reauthorise_app("app-id", "app".to_string(), "vendor".to_string());
+        assert_eq!(generation, 1);
+        let app = &account.apps["app-id"];
+        assert!(!app.revoked);
+        assert!(app.registers.is_empty());
+        // removing the key of the revoked generation is still to be retried
+        let expected = BTreeMap::from([(0, BTreeSet::from([address]))]);
+        assert_eq!(app.revocations(), expected);
+
+        // merging with the revoked version, in either direction, keeps it pending
+        let mut merged = account.clone();
+        merged.merge(revoked.clone());
+        assert_eq!(merged.apps["app-id"].generation, 1);
+        assert_eq!(merged.apps["app-id"].revocations(), expected);
+        let mut merged = revoked;
+        merged.merge(account.clone());
+        assert_eq!(merged.apps["app-id"].generation, 1);
+        assert_eq!(merged.apps["app-id"].revocations(), expected);
+
+        // once it's removed, it's no longer pending
+        let mut app = account.apps["app-id"].clone();
+        app.revocation_done(0, &address);
+        assert!(app.revocations().is_empty());
+
+        // revoking the new generation then covers both generations
+        let mut app = account.apps["app-id"].clone();
+        app.revoked = true;
+        let _ = app.registers.insert(address);
+        assert_eq!(
+            app.revocations(),
+            BTreeMap::from([
+                (0, BTreeSet::from([address])),
+                (1, BTreeSet::from([address]))
+            ])
+        );
+    }
+
+    #[test]
+    fn app_keypairs_should_be_derived_per_app_and_generation() -> Result<()> {
+        let account = Account::new();
+        let keypair = account.app_keypair("app-id", 0)?;
+        assert_eq!(keypair, account.app_keypair("app-id", 0)?);
+        assert_ne!(keypair, account.app_keypair("app-id", 1)?);
+        assert_ne!(keypair, account.app_keypair("other-app-id", 0)?);
+
+        // app keypairs are not obtained by multiplying the master key with the path
+        let linear_sk = account
+            .master_key
+            .derive_child(b"app-id")
+            .derive_child(&0u32.to_be_bytes());
+        assert_ne!(keypair, Keypair::bls_from_hex(&linear_sk.to_hex())?);
+        Ok(())
+    }
+
     proptest! {
         #[test]
         fn proptest_always_get_same_info_from_from_phrase_and_pw(s in "\\PC*", p in "\\PC*") {
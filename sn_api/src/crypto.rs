@@ -0,0 +1,147 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Password based encryption of the secrets stored by the keystore and the authenticator,
+//! and hardened derivation of the keys derived from a master key.
+
+use aes::{
+    cipher::{block_padding::Pkcs7, BlockDecryptMut, BlockEncryptMut, KeyIvInit},
+    Aes256,
+};
+use bls::SecretKey as BlsSecretKey;
+use hmac::{Hmac, Mac, NewMac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha3::Sha3_256;
+use tiny_keccak::{Hasher, Sha3};
+
+type Aes256CbcEnc = cbc::Encryptor<Aes256>;
+type Aes256CbcDec = cbc::Decryptor<Aes256>;
+
+const SHA3_512_HASH_LEN: usize = 64;
+const KEY_LEN: usize = 32;
+const IV_LEN: usize = 16;
+const KDF_ITERATIONS: u32 = 10_000;
+
+// Domain separator used when deriving a child key from a parent key.
+const CHILD_KEY_DOMAIN: &[u8] = b"safe-network-bls-child-key";
+
+/// Data encrypted with [`PasswordKeys`], along with the IV and the MAC needed to decrypt it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Encrypted {
+    pub(crate) iv: Vec<u8>,
+    pub(crate) ciphertext: Vec<u8>,
+    pub(crate) mac: Vec<u8>,
+}
+
+/// Encryption and MAC keys derived from a password.
+pub(crate) struct PasswordKeys {
+    enc_key: [u8; KEY_LEN],
+    mac_key: [u8; KEY_LEN],
+}
+
+impl PasswordKeys {
+    /// Derive the keys from the password. The password is hashed as the authenticator does to
+    /// derive its secrets, then stretched with PBKDF2 using the given salt.
+    pub(crate) fn derive(password: &[u8], salt: &[u8]) -> Self {
+        let mut hasher = Sha3::v512();
+        let mut password_hash = [0; SHA3_512_HASH_LEN];
+        hasher.update(password);
+        hasher.finalize(&mut password_hash);
+
+        let mut derived = [0; 2 * KEY_LEN];
+        pbkdf2::pbkdf2::<Hmac<Sha3_256>>(&password_hash, salt, KDF_ITERATIONS, &mut derived);
+
+        let mut enc_key = [0; KEY_LEN];
+        let mut mac_key = [0; KEY_LEN];
+        enc_key.copy_from_slice(&derived[..KEY_LEN]);
+        mac_key.copy_from_slice(&derived[KEY_LEN..]);
+        Self { enc_key, mac_key }
+    }
+
+    /// Encrypt the data with AES-256-CBC using a random IV, and authenticate it with HMAC-SHA3.
+    pub(crate) fn encrypt(&self, plaintext: &[u8]) -> Encrypted {
+        let mut iv = [0; IV_LEN];
+        rand::thread_rng().fill_bytes(&mut iv);
+        let ciphertext = Aes256CbcEnc::new(&self.enc_key.into(), &iv.into())
+            .encrypt_padded_vec_mut::<Pkcs7>(plaintext);
+        let mac = self.mac(&iv, &ciphertext).finalize().into_bytes().to_vec();
+
+        Encrypted {
+            iv: iv.to_vec(),
+            ciphertext,
+            mac,
+        }
+    }
+
+    /// Decrypt the data, returning `None` if it wasn't encrypted with these keys,
+    /// i.e. they were derived from a wrong password, or if it was tampered with.
+    pub(crate) fn decrypt(&self, encrypted: &Encrypted) -> Option<Vec<u8>> {
+        self.mac(&encrypted.iv, &encrypted.ciphertext)
+            .verify(&encrypted.mac)
+            .ok()?;
+
+        let iv: [u8; IV_LEN] = encrypted.iv.as_slice().try_into().ok()?;
+        Aes256CbcDec::new(&self.enc_key.into(), &iv.into())
+            .decrypt_padded_vec_mut::<Pkcs7>(&encrypted.ciphertext)
+            .ok()
+    }
+
+    // MAC over the IV and ciphertext of encrypted data
+    fn mac(&self, iv: &[u8], ciphertext: &[u8]) -> Hmac<Sha3_256> {
+        let mut mac =
+            Hmac::<Sha3_256>::new_varkey(&self.mac_key).expect("HMAC can take a key of any size");
+        mac.update(iv);
+        mac.update(ciphertext);
+        mac
+    }
+}
+
+/// Derive the child key found at the given path from a parent key.
+///
+/// The child key is obtained from an HMAC of the path keyed with the parent secret key,
+/// rather than from the parent key multiplied by the path as `derive_child` does, which
+/// would let anyone holding a child key and its path compute the parent key.
+pub(crate) fn derive_hardened_key(parent_key: &BlsSecretKey, path: &[&[u8]]) -> BlsSecretKey {
+    // Not every 32 bytes value is a valid BLS secret key, thus we
+    // compute the HMAC with a counter until we obtain a valid one.
+    let mut counter: u64 = 0;
+    loop {
+        let mut mac = Hmac::<Sha3_256>::new_varkey(&parent_key.to_bytes())
+            .expect("HMAC can take a key of any size");
+        mac.update(CHILD_KEY_DOMAIN);
+        for segment in path {
+            // length-prefixed so distinct paths never feed the same bytes to the HMAC
+            mac.update(&(segment.len() as u64).to_be_bytes());
+            mac.update(segment);
+        }
+        mac.update(&counter.to_be_bytes());
+
+        let mut bytes = [0; 32];
+        bytes.copy_from_slice(&mac.finalize().into_bytes());
+        if let Ok(sk) = BlsSecretKey::from_bytes(bytes) {
+            return sk;
+        }
+        counter += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PasswordKeys;
+
+    #[test]
+    fn decrypt_should_fail_with_keys_from_wrong_password() {
+        let keys = PasswordKeys::derive(b"password", b"salt");
+        let encrypted = keys.encrypt(b"secret");
+        assert_eq!(keys.decrypt(&encrypted), Some(b"secret".to_vec()));
+
+        let wrong_keys = PasswordKeys::derive(b"wrong", b"salt");
+        assert_eq!(wrong_keys.decrypt(&encrypted), None);
+    }
+}
@@ -140,6 +140,9 @@ pub enum Error {
     /// DbcDepositError
     #[error("The secret key does not match the public key for this owned DBC")]
     DbcDepositInvalidSecretKey,
+    /// KeystoreError
+    #[error("KeystoreError: {0}")]
+    KeystoreError(String),
     /// NotImplementedError
     #[error("NotImplementedError: {0}")]
     NotImplementedError(String),
@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::IpcError;
-use sn_interface::types::Keypair;
+use sn_interface::types::{Keypair, PublicKey};
 
 use serde::{Deserialize, Serialize};
 
@@ -24,4 +24,7 @@ pub enum IpcResp {
 pub struct AuthGranted {
     /// The access keys.
     pub app_keypair: Keypair,
+    /// The key of the Safe which authorised the application. Registers created by the
+    /// application are to be owned by it, and grant write to the application's keypair.
+    pub owner: PublicKey,
 }
@@ -19,6 +19,8 @@ mod authd_client;
 mod authenticator;
 mod common;
 mod constants;
+#[cfg(any(feature = "app", feature = "authenticator"))]
+mod crypto;
 mod errors;
 mod safeurl;
 
@@ -14,13 +14,16 @@ use crate::{
         config::config_commander,
         dog::dog_commander,
         files::files_commander,
+        gateway::gateway_commander,
         keys::key_commander,
         networks::networks_commander,
         node::node_commander,
         nrs::nrs_commander,
+        register::{register_commander, RegisterSubCommands},
         setup::setup_commander,
         update::update_commander,
-        wallet::wallet_commander,
+        wallet::{wallet_commander, WalletSubCommands},
+        watch::watch_commander,
         xorurl::{xorurl_commander, xorurl_of_files},
         OutputFmt, SubCommands,
     },
@@ -31,6 +34,10 @@ use sn_api::{Safe, XorUrlBase};
 use std::path::PathBuf;
 use tracing::{debug, warn};
 
+// Maximum size of the chunks kept on disk by the client, shared by all CLI runs.
+// It can be overridden with the SN_CHUNK_CACHE_SIZE env var, or disabled by setting it to 0.
+const CHUNK_CACHE_SIZE: u64 = 512 * 1024 * 1024;
+
 #[derive(clap::StructOpt, Debug)]
 /// Interact with the Safe Network
 #[clap(global_settings(&[ColoredHelp]), version)]
@@ -65,6 +72,10 @@ pub async fn run() -> Result<()> {
     if let Some(base) = args.xorurl_base {
         safe.xorurl_base = base;
     }
+    // Chunks fetched are kept on disk to be reused by subsequent runs
+    safe.chunk_cache_size = Some(CHUNK_CACHE_SIZE);
+    // Register ops which cannot be published are kept on disk until they can be
+    safe.register_wal = true;
 
     let mut config = get_config(args.config_dir_path.clone()).await?;
 
@@ -137,6 +148,14 @@ async fn process_commands(mut safe: &mut Safe, args: CmdArgs, config: &mut Confi
             node_commander(cmd, config, &mut launcher).await
         }
         SubCommands::Keys(cmd) => key_commander(cmd, output_fmt, config),
+        // DBCs are inspected offline, thus we don't connect to the network
+        SubCommands::Wallet(cmd @ WalletSubCommands::Inspect { .. }) => {
+            wallet_commander(cmd, output_fmt, safe, config).await
+        }
+        // the pending Register ops are read from disk, thus we don't connect to the network
+        SubCommands::Register(cmd @ RegisterSubCommands::Pending) => {
+            register_commander(cmd, output_fmt, safe).await
+        }
         SubCommands::Xorurl {
             cmd,
             location,
@@ -170,9 +189,12 @@ async fn process_commands(mut safe: &mut Safe, args: CmdArgs, config: &mut Confi
             match other {
                 SubCommands::Cat(cmd) => cat_commander(cmd, output_fmt, safe).await,
                 SubCommands::Dog(cmd) => dog_commander(cmd, output_fmt, safe).await,
-                SubCommands::Files(cmd) => files_commander(cmd, output_fmt, safe).await,
+                SubCommands::Files(cmd) => files_commander(cmd, output_fmt, safe, config).await,
+                SubCommands::Gateway(cmd) => gateway_commander(cmd, safe).await,
                 SubCommands::Nrs(cmd) => nrs_commander(cmd, output_fmt, safe).await,
+                SubCommands::Register(cmd) => register_commander(cmd, output_fmt, safe).await,
                 SubCommands::Wallet(cmd) => wallet_commander(cmd, output_fmt, safe, config).await,
+                SubCommands::Watch(cmd) => watch_commander(cmd, output_fmt, safe).await,
                 _ => Err(eyre!("Unknown safe subcommand")),
             }
         }
@@ -8,9 +8,13 @@
 
 use super::config::Config;
 use crate::{APP_ID, APP_NAME, APP_VENDOR};
-use color_eyre::{eyre::eyre, eyre::WrapErr, Result};
-use sn_api::{Keypair, Safe};
+use bls::SecretKey;
+use color_eyre::{eyre::eyre, eyre::WrapErr, Help, Result};
+use console::Term;
+use sn_api::{keystore::Keystore, Keypair, PublicKey, Safe};
+use sn_dbc::Owner;
 use std::{
+    env,
     fs::{create_dir_all, File},
     io::Write,
     path::{Path, PathBuf},
@@ -18,6 +22,12 @@ use std::{
 use tracing::{debug, info, warn};
 
 const AUTH_CREDENTIALS_FILENAME: &str = "credentials";
+const AUTH_OWNER_FILENAME: &str = "credentials_owner";
+const KEYSTORE_FILENAME: &str = "keystore";
+
+/// Environment variable which can be set with the password to unlock the keystore,
+/// otherwise the user is prompted for it.
+pub const KEYSTORE_PASSWORD_ENV_VAR: &str = "SAFE_KEYSTORE_PASSWORD";
 
 #[allow(dead_code)]
 pub async fn authorise_cli(
@@ -32,7 +42,7 @@ pub async fn authorise_cli(
         println!("Note you can use this CLI from another console to authorise it with 'auth allow' command. Alternativelly, you can also use '--self-auth' flag with 'auth unlock' command to automatically self authorise the CLI app.");
     }
     println!("Waiting for authorising response from authd...");
-    let app_keypair = Safe::auth_app(
+    let auth_granted = Safe::auth_app(
         APP_ID,
         APP_NAME,
         APP_VENDOR,
@@ -42,12 +52,23 @@ pub async fn authorise_cli(
     .await
     .wrap_err("Application authorisation failed")?;
 
-    let serialised_keypair = serde_json::to_string(&app_keypair)
+    let serialised_keypair = serde_json::to_string(&auth_granted.app_keypair)
         .wrap_err("Unable to serialise the credentials obtained")?;
 
     file.write_all(serialised_keypair.as_bytes())
         .wrap_err_with(|| format!("Unable to write credentials in {}", file_path.display(),))?;
 
+    // the Registers created with these credentials are to be owned by the Safe which granted them
+    let owner_file_path = get_owner_file_path(config)?;
+    let serialised_owner = serde_json::to_string(&auth_granted.owner)
+        .wrap_err("Unable to serialise the owner of the credentials obtained")?;
+    std::fs::write(&owner_file_path, serialised_owner).wrap_err_with(|| {
+        format!(
+            "Unable to write the owner of the credentials in {}",
+            owner_file_path.display()
+        )
+    })?;
+
     println!("Safe CLI app was successfully authorised");
     println!("Credentials were stored in {}", file_path.display());
     Ok(())
@@ -58,11 +79,21 @@ pub async fn authorise_cli(
 pub async fn connect(safe: &mut Safe, config: &Config) -> Result<()> {
     debug!("Connecting...");
 
-    let app_keypair = if let Ok((_, keypair)) = read_credentials(config) {
-        keypair
-    } else {
-        None
+    let cli_key = read_cli_secret_key(config)?;
+    // the owner granted by a Safe along with the credentials only applies to them,
+    // and not to a key in use from the keystore
+    let (_, credentials_path) = get_credentials_file_path(config)?;
+    safe.register_owner = match &cli_key {
+        Some((path, _)) if *path == credentials_path => read_credentials_owner(config)?,
+        _ => None,
     };
+    let cli_sk = cli_key.map(|(_, sk)| sk);
+    let app_keypair = cli_sk
+        .as_ref()
+        .map(|sk| Keypair::bls_from_hex(&sk.to_hex()))
+        .transpose()?;
+    // the key in use from the keystore takes precedence over the legacy credentials file
+    let dbc_owner = cli_sk.map(Owner::from).or_else(|| config.dbc_owner.clone());
 
     let found_app_keypair = app_keypair.is_some();
     if !found_app_keypair {
@@ -70,13 +101,13 @@ pub async fn connect(safe: &mut Safe, config: &Config) -> Result<()> {
     }
 
     match safe
-        .connect(app_keypair.clone(), None, config.dbc_owner.clone())
+        .connect(app_keypair.clone(), None, dbc_owner.clone())
         .await
     {
         Ok(()) => Ok(()),
         Err(_) if found_app_keypair => {
             warn!("Credentials found for CLI are invalid, connecting with read-only access...");
-            safe.connect(None, None, config.dbc_owner.clone())
+            safe.connect(None, None, dbc_owner)
                 .await
                 .wrap_err("Failed to connect with read-only access")
         }
@@ -98,20 +129,126 @@ pub fn create_credentials_file(config: &Config) -> Result<(File, PathBuf)> {
 }
 
 pub fn read_credentials(config: &Config) -> Result<(PathBuf, Option<Keypair>)> {
+    match read_cli_secret_key(config)? {
+        Some((path, sk)) => Ok((path, Some(Keypair::bls_from_hex(&sk.to_hex())?))),
+        None => Ok((get_keystore_file_path(config), None)),
+    }
+}
+
+/// Read the secret key the CLI is configured to use, along with the path of the file it was read
+/// from. The key in use from the keystore is unlocked with the password, which is read from the
+/// `SAFE_KEYSTORE_PASSWORD` env var or prompted for. If no key is in use in the keystore, it falls
+/// back to the legacy, unencrypted, credentials file.
+pub fn read_cli_secret_key(config: &Config) -> Result<Option<(PathBuf, SecretKey)>> {
+    let keystore = read_keystore(config)?;
+    if let Some(name) = keystore.in_use() {
+        let password = read_keystore_password(false)?;
+        let sk = keystore.secret_key(name, &password).map_err(|err| {
+            eyre!(err).suggestion(format!(
+                "Make sure the password provided, or set in the {} env var, is the one \
+                the keystore was created with.",
+                KEYSTORE_PASSWORD_ENV_VAR
+            ))
+        })?;
+        return Ok(Some((keystore.path().to_path_buf(), sk)));
+    }
+
     let (_, path) = get_credentials_file_path(config)?;
-    let keypair = match Safe::deserialize_bls_key(&path) {
-        Ok(sk) => Some(Keypair::bls_from_hex(&sk.to_hex())?),
+    match Safe::deserialize_bls_key(&path) {
+        Ok(sk) => {
+            warn!(
+                "Using unencrypted credentials from {}, consider moving them to the keystore \
+                with the 'keys import' command",
+                path.display()
+            );
+            Ok(Some((path, sk)))
+        }
         Err(e) => {
             debug!("Unable to read credentials from {}: {}", path.display(), e);
-            None
+            Ok(None)
         }
-    };
-    Ok((path, keypair))
+    }
+}
+
+/// Read the key of the Safe which granted the credentials stored in the credentials file, if any,
+/// which the Registers created with those credentials are to be owned by.
+pub fn read_credentials_owner(config: &Config) -> Result<Option<PublicKey>> {
+    let path = get_owner_file_path(config)?;
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let serialised_owner = std::fs::read_to_string(&path).wrap_err_with(|| {
+        format!(
+            "Unable to read the owner of the credentials from {}",
+            path.display()
+        )
+    })?;
+    let owner = serde_json::from_str(&serialised_owner).wrap_err_with(|| {
+        format!(
+            "Unable to deserialise the owner of the credentials from {}",
+            path.display()
+        )
+    })?;
+    Ok(Some(owner))
+}
+
+pub fn get_keystore_file_path(config: &Config) -> PathBuf {
+    let mut path = config.cli_config_path.clone();
+    path.pop();
+    path.join(KEYSTORE_FILENAME)
+}
+
+pub fn read_keystore(config: &Config) -> Result<Keystore> {
+    let path = get_keystore_file_path(config);
+    Keystore::load(&path).wrap_err_with(|| format!("Unable to read keystore at {}", path.display()))
+}
+
+/// Read the password to unlock the keystore from the `SAFE_KEYSTORE_PASSWORD` env var,
+/// or prompt the user for it. When `confirm` is set the user is asked to enter it twice,
+/// which is meant for when the password is being set for a new keystore.
+pub fn read_keystore_password(confirm: bool) -> Result<String> {
+    if let Ok(password) = env::var(KEYSTORE_PASSWORD_ENV_VAR) {
+        return Ok(password);
+    }
+
+    let term = Term::stderr();
+    if !term.features().is_attended() {
+        return Err(
+            eyre!("A password is required to unlock the keystore.").suggestion(format!(
+                "Set the {} env var with the keystore password.",
+                KEYSTORE_PASSWORD_ENV_VAR
+            )),
+        );
+    }
+
+    term.write_str("Enter the keystore password: ")?;
+    let password = term.read_secure_line()?;
+    if confirm {
+        if password.is_empty() {
+            return Err(eyre!("The keystore password cannot be empty."));
+        }
+        term.write_str("Confirm the keystore password: ")?;
+        if term.read_secure_line()? != password {
+            return Err(eyre!("The passwords entered do not match."));
+        }
+    }
+
+    Ok(password)
 }
 
 #[allow(dead_code)]
 pub fn clear_credentials(config: &Config) -> Result<()> {
     let (_, file_path) = create_credentials_file(config).context("Failed to clear credentials")?;
+    let owner_file_path = get_owner_file_path(config)?;
+    if owner_file_path.exists() {
+        std::fs::remove_file(&owner_file_path).with_context(|| {
+            format!(
+                "Failed to clear the owner of the credentials from {}",
+                owner_file_path.display()
+            )
+        })?;
+    }
 
     println!(
         "Credentials were succesfully cleared from {}",
@@ -127,3 +264,8 @@ pub fn get_credentials_file_path(config: &Config) -> Result<(PathBuf, PathBuf)>
     let file_path = credentials_folder.join(AUTH_CREDENTIALS_FILENAME);
     Ok((credentials_folder, file_path))
 }
+
+fn get_owner_file_path(config: &Config) -> Result<PathBuf> {
+    let (credentials_folder, _) = get_credentials_file_path(config)?;
+    Ok(credentials_folder.join(AUTH_OWNER_FILENAME))
+}
@@ -6,6 +6,8 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+#[cfg(target_os = "linux")]
+use super::files_mount::process_mount_command;
 use super::{
     files_get::{process_get_command, FileExistsAction, ProgressIndicator},
     helpers::{
@@ -14,6 +16,7 @@ use super::{
     },
     OutputFmt,
 };
+use crate::operations::config::Config;
 use ansi_term::Colour;
 use bytes::Bytes;
 use clap::Subcommand;
@@ -21,7 +24,7 @@ use color_eyre::{eyre::bail, eyre::eyre, Result};
 use comfy_table::Table;
 use serde::Serialize;
 use sn_api::{
-    files::{FilesMap, ProcessedFiles},
+    files::{FilesContainerDiffTarget, FilesMap, ProcessedFiles, UploadJournal},
     nrs::VersionHash,
     resolver::SafeData,
     Safe, SafeUrl, XorUrl,
@@ -31,11 +34,15 @@ use std::{
     path::{Component, Path, PathBuf},
 };
 use tracing::debug;
+use xor_name::XorName;
 
 type FileDetails = BTreeMap<String, String>;
 
 const UNKNOWN_FILE_NAME: &str = "<unknown>";
 
+// Name of the folder, within the CLI config dir, where upload journals are kept
+const UPLOAD_JOURNALS_DIR: &str = "upload_journals";
+
 // Differentiates between nodes in a file system.
 #[derive(Debug, Serialize, PartialEq)]
 enum FileTreeNodeType {
@@ -105,6 +112,9 @@ pub enum FilesSubCommands {
         /// Follow symlinks
         #[clap(short = 'l', long = "follow-links")]
         follow_links: bool,
+        /// Resume a previously interrupted upload of the same location, only uploading the files and chunks not yet stored
+        #[clap(long = "resume")]
+        resume: bool,
     },
     /// Get a file or folder from the SAFE Network
     Get {
@@ -141,6 +151,29 @@ pub enum FilesSubCommands {
         /// Automatically update the NRS name to link to the new version of the FilesContainer. This is only allowed if an NRS URL was provided, and if the NRS name is currently linked to a specific version of the FilesContainer
         #[clap(short = 'u', long = "update-nrs")]
         update_nrs: bool,
+        /// Resume a previously interrupted sync of the same location and target, only uploading the files and chunks not yet stored
+        #[clap(long = "resume")]
+        resume: bool,
+    },
+    #[clap(name = "diff")]
+    /// Show the changes between two versions of a FilesContainer, or between a FilesContainer and a local folder, without making any change on the network
+    Diff {
+        /// The FilesContainer to compare, optionally including a version (default is latest)
+        source: String,
+        /// The FilesContainer, optionally including a version, or the local folder to compare against. When comparing against a local folder, the changes shown are those a 'sync --recursive --delete' would make (default is the latest version of the source)
+        target: Option<String>,
+        /// Follow symlinks found in the local folder
+        #[clap(short = 'l', long = "follow-links")]
+        follow_links: bool,
+    },
+    #[cfg(target_os = "linux")]
+    #[clap(name = "mount")]
+    /// Mount a FilesContainer, or a folder within it, as a read-only local filesystem until Ctrl-C is pressed. Files' content is only fetched from the network when read
+    Mount {
+        /// The FilesContainer to mount, optionally including a version and the path to a folder within it
+        target: String,
+        /// The local empty directory to mount it onto
+        mountpoint: PathBuf,
     },
     #[clap(name = "add")]
     /// Add a file to an existing FilesContainer on the network
@@ -198,6 +231,7 @@ pub async fn files_commander(
     cmd: FilesSubCommands,
     output_fmt: OutputFmt,
     safe: &Safe,
+    config: &Config,
 ) -> Result<()> {
     match cmd {
         FilesSubCommands::Put {
@@ -205,14 +239,22 @@ pub async fn files_commander(
             dst,
             recursive,
             follow_links,
+            resume,
         } => {
             // create FilesContainer from a given path to local files/folders
             if safe.dry_run_mode && OutputFmt::Pretty == output_fmt {
                 notice_dry_run();
             }
+            let dst_str = dst
+                .as_ref()
+                .map(|dst| dst.display().to_string())
+                .unwrap_or_default();
+            let safe = with_upload_journal(safe, config, &location, &dst_str, resume)?;
             let (files_container_xorurl, processed_files, _) = safe
                 .files_container_create_from(&location, dst.as_deref(), recursive, follow_links)
-                .await?;
+                .await
+                .map_err(|err| upload_interrupted(&safe, err))?;
+            complete_upload_journal(&safe)?;
 
             // Now let's just print out a list of the files uploaded/processed
             if OutputFmt::Pretty == output_fmt {
@@ -237,12 +279,15 @@ pub async fn files_commander(
             follow_links,
             delete,
             update_nrs,
+            resume,
         } => {
             let target = get_from_arg_or_stdin(target, None)?;
             let mut target_url = get_target_url(&target)?;
             if safe.dry_run_mode && OutputFmt::Pretty == output_fmt {
                 notice_dry_run();
             }
+            let safe =
+                with_upload_journal(safe, config, &location, &target_url.to_string(), resume)?;
             // Update the FilesContainer on the Network
             let (content, processed_files) = safe
                 .files_container_sync(
@@ -253,7 +298,9 @@ pub async fn files_commander(
                     delete,
                     update_nrs,
                 )
-                .await?;
+                .await
+                .map_err(|err| upload_interrupted(&safe, err))?;
+            complete_upload_journal(&safe)?;
             let version = content.map(|(version, _)| version);
 
             // Now let's just print out a list of the files synced/processed
@@ -286,6 +333,54 @@ pub async fn files_commander(
             }
             Ok(())
         }
+        FilesSubCommands::Diff {
+            source,
+            target,
+            follow_links,
+        } => {
+            let target = match target {
+                Some(target) => target,
+                None => {
+                    // compare against the latest version of the source
+                    let mut safe_url = SafeUrl::from_url(&source)?;
+                    safe_url.set_content_version(None);
+                    safe_url.to_string()
+                }
+            };
+            let diff_target = if target.starts_with("safe://") {
+                FilesContainerDiffTarget::Url(&target)
+            } else {
+                FilesContainerDiffTarget::LocalPath(Path::new(&target))
+            };
+
+            let changes = safe
+                .files_container_diff(&source, diff_target, follow_links)
+                .await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                if changes.is_empty() {
+                    println!("No differences found between \"{source}\" and \"{target}\"");
+                } else {
+                    let (table, success_count) = gen_processed_files_table(&changes, true);
+                    println!(
+                        "{} {} from \"{source}\" to \"{target}\":",
+                        success_count,
+                        pluralize("change", "changes", success_count)
+                    );
+                    println!("{table}");
+                }
+            } else {
+                println!(
+                    "{}",
+                    serialise_output(&(source, target, changes), output_fmt)
+                );
+            }
+            Ok(())
+        }
+        #[cfg(target_os = "linux")]
+        FilesSubCommands::Mount { target, mountpoint } => {
+            process_mount_command(safe, &target, &mountpoint).await
+        }
         FilesSubCommands::Add {
             location,
             target,
@@ -409,6 +504,70 @@ pub async fn files_commander(
     }
 }
 
+// Returns a Safe instance which records the progress of the upload of `location` onto `target`
+// in a journal kept in the config dir. Unless resuming, any previous journal is discarded.
+fn with_upload_journal(
+    safe: &Safe,
+    config: &Config,
+    location: &str,
+    target: &str,
+    resume: bool,
+) -> Result<Safe> {
+    let mut safe = safe.clone();
+    if safe.dry_run_mode {
+        if resume {
+            bail!("The 'resume' flag cannot be used in dry-run mode");
+        }
+        return Ok(safe);
+    }
+
+    // The journal is identified by the absolute source location and the target
+    let location = Path::new(location)
+        .canonicalize()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| location.to_string());
+    let journal_id = XorName::from_content_parts(&[location.as_bytes(), target.as_bytes()]);
+
+    let mut journal_path = config.cli_config_path.clone();
+    journal_path.pop();
+    journal_path.push(UPLOAD_JOURNALS_DIR);
+    journal_path.push(hex::encode(journal_id));
+
+    let journal = if resume {
+        let journal = UploadJournal::open(&journal_path)?;
+        debug!(
+            "Resuming upload with journal at {}: {} files and {} chunks already uploaded",
+            journal_path.display(),
+            journal.files_count(),
+            journal.chunks_count()
+        );
+        journal
+    } else {
+        UploadJournal::create(&journal_path)?
+    };
+
+    safe.upload_journal = Some(journal);
+    Ok(safe)
+}
+
+// The upload completed, thus the journal is no longer needed
+fn complete_upload_journal(safe: &Safe) -> Result<()> {
+    if let Some(journal) = safe.upload_journal.clone() {
+        journal.remove()?;
+    }
+    Ok(())
+}
+
+fn upload_interrupted(safe: &Safe, err: sn_api::Error) -> color_eyre::Report {
+    match &safe.upload_journal {
+        Some(journal) if journal.chunks_count() > 0 => eyre!(err).wrap_err(
+            "The upload did not complete. Run the same command with the '--resume' flag \
+            to upload only the content which is still missing",
+        ),
+        _ => eyre!(err),
+    }
+}
+
 // processes the `safe files tree` command.
 async fn process_tree_command(
     safe: &Safe,
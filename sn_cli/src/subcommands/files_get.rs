@@ -10,12 +10,11 @@ use super::{
     helpers::{div_or, pluralize, processed_files_err_report, prompt_user},
     OutputFmt,
 };
-use bytes::Buf;
 use color_eyre::{eyre::bail, eyre::eyre, eyre::WrapErr, Result};
 use console::Term;
+use futures::TryStreamExt;
 use sn_api::{
-    files::{FilesMap, GetAttr},
-    resolver::Range,
+    files::{FileReader, FilesMap, GetAttr},
     resolver::SafeData,
     DataType, Result as ApiResult, Safe, SafeUrl, XorUrl,
 };
@@ -619,21 +618,20 @@ fn denormalize_slashes(p: &str) -> String {
 async fn download_file_from_net(safe: &Safe, xorurl: &str, path: &Path, size: u64) -> Result<u64> {
     debug!("downloading file {} to {}", xorurl, path.display());
 
-    // TODO: download the file by concurrently (spawning tasks/threads) pulling chunks.
-    // The chunk_size can be based on https://stackoverflow.com/questions/8803515/optimal-buffer-size-for-write2
-    // which can be 4096 to match common disk block size, but that seems a bit small for the
-    // network, so I multiplied by 16. Perhaps should make it a param so caller can decide.
     let mut rcvd: u64 = 0;
     let mut bytes_written: u64 = 0;
 
     let fh = file_create(path)?;
     let mut stream = BufWriter::new(fh);
 
-    // gets public or private, based on xorurl type
-    let filedata = files_get(safe, xorurl, None).await?;
-    bytes_written += stream_write(&mut stream, &filedata, path)? as u64;
-    rcvd += filedata.len() as u64;
-    trace!("received {} bytes of {}", rcvd, size,);
+    // The file's chunks are fetched lazily (and concurrently, up to the reader's read-ahead),
+    // and written to disk as they arrive, so we never hold the whole file in memory.
+    let mut reader = files_get_stream(safe, xorurl).await?;
+    while let Some(filedata) = reader.try_next().await? {
+        bytes_written += stream_write(&mut stream, &filedata, path)? as u64;
+        rcvd += filedata.len() as u64;
+        trace!("received {} bytes of {}", rcvd, size,);
+    }
 
     // Close may generate an error, so we do a flush/sync first to detect such.
     // see https://github.com/rust-lang/rust/pull/63410#issuecomment-519965351
@@ -681,14 +679,11 @@ fn create_dir_all(dir_path: &Path) -> Result<()> {
         .with_context(|| format!("Couldn't create path: \"{}\"", dir_path.display(),))
 }
 
-/// # Get Public or Private file
-/// Get immutable files from the network.
-pub async fn files_get(safe: &Safe, url: &str, range: Range) -> Result<Vec<u8>> {
+/// # Get a reader of a Public or Private file
+/// Get immutable files from the network, lazily fetching their content.
+pub async fn files_get_stream(safe: &Safe, url: &str) -> Result<FileReader> {
     match SafeUrl::from_url(url)?.data_type() {
-        DataType::File => {
-            let bytes = safe.files_get(url, range).await?;
-            Ok(bytes.chunk().to_vec())
-        }
+        DataType::File => Ok(safe.files_get_stream(url).await?),
         _ => Err(eyre!("URL target is not immutable data")),
     }
 }
@@ -0,0 +1,483 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use color_eyre::{eyre::bail, eyre::eyre, eyre::WrapErr, Result};
+use fuser::{
+    consts::FOPEN_KEEP_CACHE, FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData,
+    ReplyDirectory, ReplyEntry, ReplyOpen, Request, FUSE_ROOT_ID,
+};
+use libc::{EIO, ENOENT, ENOTDIR, EROFS, O_ACCMODE, O_RDONLY};
+use sn_api::{
+    files::{FileInfo, FilesMap},
+    resolver::SafeData,
+    Safe,
+};
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    fs,
+    os::unix::fs::MetadataExt,
+    path::{Component, Path},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use tokio::runtime::Handle;
+use tracing::{debug, info, warn};
+
+// A mounted version of a FilesContainer never changes, so the kernel can cache attributes for long
+const ATTR_TTL: Duration = Duration::from_secs(3600);
+const BLOCK_SIZE: u32 = 512;
+// How often to check if the filesystem was unmounted by other means than Ctrl-C, e.g. 'fusermount -u'
+const UNMOUNT_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+enum NodeKind {
+    Dir(BTreeMap<String, u64>),
+    File { link: Option<String> },
+    Symlink(String),
+}
+
+#[derive(Debug)]
+struct Node {
+    parent: u64,
+    attr: FileAttr,
+    kind: NodeKind,
+}
+
+/// Read-only view of a FilesContainer's FilesMap as a FUSE filesystem.
+///
+/// The directory tree and files' attributes are all built from the FilesMap when mounting,
+/// while files' content is fetched from the network only when read, and only the chunks
+/// which hold the range being read.
+struct FilesMapFs {
+    safe: Safe,
+    runtime: Handle,
+    // a node's inode is its index in the list plus one, the root being FUSE_ROOT_ID
+    nodes: Vec<Node>,
+}
+
+impl FilesMapFs {
+    fn new(safe: Safe, runtime: Handle, files_map: &FilesMap, uid: u32, gid: u32) -> Result<Self> {
+        Ok(Self {
+            safe,
+            runtime,
+            nodes: build_nodes(files_map, uid, gid, SystemTime::now())?,
+        })
+    }
+
+    fn node(&self, ino: u64) -> Option<&Node> {
+        ino.checked_sub(1)
+            .and_then(|index| self.nodes.get(index as usize))
+    }
+}
+
+impl Filesystem for FilesMapFs {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let child = match self.node(parent).map(|node| &node.kind) {
+            Some(NodeKind::Dir(children)) => name
+                .to_str()
+                .and_then(|name| children.get(name))
+                .and_then(|ino| self.node(*ino)),
+            Some(_) => return reply.error(ENOTDIR),
+            None => None,
+        };
+
+        match child {
+            Some(node) => reply.entry(&ATTR_TTL, &node.attr, 0),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyAttr) {
+        match self.node(ino) {
+            Some(node) => reply.attr(&ATTR_TTL, &node.attr),
+            None => reply.error(ENOENT),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyData) {
+        match self.node(ino).map(|node| &node.kind) {
+            Some(NodeKind::Symlink(target)) => reply.data(target.as_bytes()),
+            _ => reply.error(ENOENT),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        if flags & O_ACCMODE != O_RDONLY {
+            return reply.error(EROFS);
+        }
+        match self.node(ino).map(|node| &node.kind) {
+            // the content of a file never changes, thus whatever the kernel cached is still valid
+            Some(NodeKind::File { .. }) => reply.opened(0, FOPEN_KEEP_CACHE),
+            _ => reply.error(ENOENT),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let (link, file_size) = match self.node(ino) {
+            Some(Node {
+                kind: NodeKind::File { link },
+                attr,
+                ..
+            }) => (link.clone(), attr.size),
+            _ => return reply.error(ENOENT),
+        };
+
+        let start = (offset.max(0) as u64).min(file_size);
+        let end = start.saturating_add(size as u64).min(file_size);
+        let link = match link {
+            Some(link) if start < end => link,
+            // empty files are not stored on the network
+            _ => return reply.data(&[]),
+        };
+
+        // fetch the content without blocking other requests to the filesystem
+        let safe = self.safe.clone();
+        drop(self.runtime.spawn(async move {
+            match safe.files_get(&link, Some((Some(start), Some(end)))).await {
+                Ok(data) => reply.data(&data),
+                Err(err) => {
+                    warn!("Failed to read range {start}..{end} of {link}: {err}");
+                    reply.error(EIO);
+                }
+            }
+        }));
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let (parent, children) = match self.node(ino) {
+            Some(Node {
+                kind: NodeKind::Dir(children),
+                parent,
+                ..
+            }) => (*parent, children),
+            Some(_) => return reply.error(ENOTDIR),
+            None => return reply.error(ENOENT),
+        };
+
+        let entries = [(ino, "."), (parent, "..")]
+            .into_iter()
+            .chain(children.iter().map(|(name, ino)| (*ino, name.as_str())));
+        for (index, (entry_ino, name)) in entries.enumerate().skip(offset.max(0) as usize) {
+            let kind = match self.node(entry_ino) {
+                Some(node) => node.attr.kind,
+                None => continue,
+            };
+            // the offset passed is the one of the next entry to be returned
+            if reply.add(entry_ino, index as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// Mounts the FilesContainer, or the folder within it, found at the given URL onto the
+/// local mountpoint, serving it until Ctrl-C is pressed or it's unmounted by other means.
+pub async fn process_mount_command(safe: &Safe, target: &str, mountpoint: &Path) -> Result<()> {
+    let files_map = match safe.fetch(target, None).await? {
+        // the FilesMap of a FilesContainer fetched with a path is already relative to that path
+        SafeData::FilesContainer { files_map, .. } => files_map,
+        _ => bail!("Make sure the URL targets a FilesContainer, or a folder within it"),
+    };
+
+    // files are shown as owned by the owner of the mountpoint
+    let metadata = fs::metadata(mountpoint)
+        .wrap_err_with(|| format!("Failed to access mountpoint {}", mountpoint.display()))?;
+    if !metadata.is_dir() {
+        bail!("The mountpoint {} is not a directory", mountpoint.display());
+    }
+
+    let fs = FilesMapFs::new(
+        safe.clone(),
+        Handle::current(),
+        &files_map,
+        metadata.uid(),
+        metadata.gid(),
+    )?;
+    debug!(
+        "Mounting {target} onto {} with {} nodes",
+        mountpoint.display(),
+        fs.nodes.len()
+    );
+
+    let options = [
+        MountOption::RO,
+        MountOption::FSName("safe".to_string()),
+        MountOption::Subtype("safe".to_string()),
+        MountOption::DefaultPermissions,
+    ];
+    let session = fuser::spawn_mount2(fs, mountpoint, &options)
+        .wrap_err_with(|| format!("Failed to mount onto {}", mountpoint.display()))?;
+
+    println!(
+        "\"{target}\" mounted read-only at {}, press Ctrl-C to unmount it",
+        mountpoint.display()
+    );
+
+    loop {
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => {
+                result.wrap_err("Failed to listen for Ctrl-C")?;
+                break;
+            }
+            _ = tokio::time::sleep(UNMOUNT_CHECK_INTERVAL) => {
+                if session.guard.is_finished() {
+                    info!("{} was unmounted", mountpoint.display());
+                    break;
+                }
+            }
+        }
+    }
+
+    // dropping the session unmounts the filesystem
+    drop(session);
+    println!("{} unmounted", mountpoint.display());
+
+    Ok(())
+}
+
+// Builds the tree of nodes out of a FilesMap, creating any parent folder
+// which doesn't have an entry of its own, with the root at index 0.
+fn build_nodes(files_map: &FilesMap, uid: u32, gid: u32, now: SystemTime) -> Result<Vec<Node>> {
+    let dir_attr = |ino| FileAttr {
+        ino,
+        size: 0,
+        blocks: 0,
+        atime: now,
+        mtime: now,
+        ctime: now,
+        crtime: now,
+        kind: FileType::Directory,
+        perm: 0o555,
+        nlink: 2,
+        uid,
+        gid,
+        rdev: 0,
+        blksize: BLOCK_SIZE,
+        flags: 0,
+    };
+
+    let mut nodes = vec![Node {
+        parent: FUSE_ROOT_ID,
+        attr: dir_attr(FUSE_ROOT_ID),
+        kind: NodeKind::Dir(BTreeMap::new()),
+    }];
+
+    for (path, file_item) in files_map.iter() {
+        let components = Path::new(path)
+            .components()
+            .filter_map(|component| match component {
+                Component::Normal(name) => Some(
+                    name.to_str()
+                        .ok_or_else(|| eyre!("Encountered invalid unicode sequence in path")),
+                ),
+                _ => None,
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // walk down the tree, creating any folder not found
+        let mut ino = FUSE_ROOT_ID;
+        for name in &components {
+            let next_ino = nodes.len() as u64 + 1;
+            let children = match &mut nodes[ino as usize - 1].kind {
+                NodeKind::Dir(children) => children,
+                _ => bail!("Path {path} is found within a file or symlink in the FilesMap"),
+            };
+            ino = match children.get(*name) {
+                Some(child) => *child,
+                None => {
+                    let _ = children.insert(name.to_string(), next_ino);
+                    nodes.push(Node {
+                        parent: ino,
+                        attr: dir_attr(next_ino),
+                        kind: NodeKind::Dir(BTreeMap::new()),
+                    });
+                    next_ino
+                }
+            };
+        }
+
+        // now set the entry's own details onto the node created for it
+        let node = &mut nodes[ino as usize - 1];
+        match file_item.get("type").map(String::as_str) {
+            Some("inode/directory") => {}
+            Some("inode/symlink") => {
+                let target = file_item
+                    .get("symlink_target")
+                    .ok_or_else(|| eyre!("Symlink {path} has no target in the FilesMap"))?;
+                node.attr.kind = FileType::Symlink;
+                node.attr.size = target.len() as u64;
+                node.attr.nlink = 1;
+                node.kind = NodeKind::Symlink(target.clone());
+            }
+            _ => {
+                node.attr.kind = FileType::RegularFile;
+                node.attr.size = file_item
+                    .get("size")
+                    .and_then(|size| size.parse().ok())
+                    .unwrap_or(0);
+                node.attr.nlink = 1;
+                node.kind = NodeKind::File {
+                    link: file_item.get("link").cloned(),
+                };
+            }
+        }
+        set_file_item_attrs(&mut node.attr, file_item);
+    }
+
+    Ok(nodes)
+}
+
+// Sets the times and permissions of a node from those recorded in its FilesMap entry.
+fn set_file_item_attrs(attr: &mut FileAttr, file_item: &FileInfo) {
+    let time_attr = |original: &str, uploaded: &str| {
+        file_item
+            .get(original)
+            .and_then(|time| OffsetDateTime::parse(time, &Rfc3339).ok())
+            .map(SystemTime::from)
+            .or_else(|| {
+                file_item
+                    .get(uploaded)
+                    .and_then(|secs| secs.parse().ok())
+                    .map(|secs| UNIX_EPOCH + Duration::from_secs(secs))
+            })
+    };
+
+    if let Some(mtime) = time_attr("o_modified", "modified") {
+        attr.mtime = mtime;
+        attr.atime = mtime;
+        attr.ctime = mtime;
+    }
+    if let Some(crtime) = time_attr("o_created", "created") {
+        attr.crtime = crtime;
+    }
+
+    attr.blocks = attr.size.div_ceil(BLOCK_SIZE as u64);
+
+    // symlinks' permissions are never used
+    if attr.kind != FileType::Symlink {
+        let default_perm = if attr.kind == FileType::Directory {
+            0o555
+        } else {
+            0o444
+        };
+        // drop write permissions as the filesystem is read-only
+        attr.perm = file_item
+            .get("mode_bits")
+            .and_then(|mode| mode.parse::<u32>().ok())
+            .map(|mode| (mode & 0o555) as u16)
+            .unwrap_or(default_perm);
+    } else {
+        attr.perm = 0o777;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{build_nodes, NodeKind};
+    use color_eyre::Result;
+    use fuser::{FileType, FUSE_ROOT_ID};
+    use sn_api::files::{FileInfo, FilesMap};
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn file_item(details: &[(&str, &str)]) -> FileInfo {
+        details
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn build_nodes_should_create_tree_from_files_map() -> Result<()> {
+        let mut files_map = FilesMap::new();
+        let _ = files_map.insert(
+            "/dir".to_string(),
+            file_item(&[("type", "inode/directory"), ("mode_bits", "16877")]),
+        );
+        let _ = files_map.insert(
+            "/dir/file.txt".to_string(),
+            file_item(&[
+                ("type", "text/plain"),
+                ("size", "1500"),
+                ("link", "safe://file"),
+                ("mode_bits", "33188"),
+                ("modified", "1000"),
+                ("o_modified", "2022-01-02T03:04:05Z"),
+            ]),
+        );
+        let _ = files_map.insert(
+            "/other/link".to_string(),
+            file_item(&[("type", "inode/symlink"), ("symlink_target", "../dir")]),
+        );
+
+        let now = SystemTime::now();
+        let nodes = build_nodes(&files_map, 1000, 1000, now)?;
+        // the root, the three entries, and the implicit "/other" folder
+        assert_eq!(nodes.len(), 5);
+
+        let root = &nodes[FUSE_ROOT_ID as usize - 1];
+        let root_children = match &root.kind {
+            NodeKind::Dir(children) => children,
+            other => panic!("root is not a folder: {other:?}"),
+        };
+        assert_eq!(
+            root_children.keys().collect::<Vec<_>>(),
+            vec!["dir", "other"]
+        );
+
+        let dir = &nodes[root_children["dir"] as usize - 1];
+        assert_eq!(dir.attr.kind, FileType::Directory);
+        assert_eq!(dir.attr.perm, 0o755 & 0o555);
+        let file_ino = match &dir.kind {
+            NodeKind::Dir(children) => children["file.txt"],
+            other => panic!("dir is not a folder: {other:?}"),
+        };
+
+        let file = &nodes[file_ino as usize - 1];
+        assert_eq!(file.parent, root_children["dir"]);
+        assert_eq!(file.attr.kind, FileType::RegularFile);
+        assert_eq!(file.attr.size, 1500);
+        assert_eq!(file.attr.blocks, 3);
+        assert_eq!(file.attr.perm, 0o444);
+        assert_eq!(
+            file.attr.mtime,
+            UNIX_EPOCH + Duration::from_secs(1_641_092_645)
+        );
+        assert!(matches!(&file.kind, NodeKind::File { link: Some(link) } if link == "safe://file"));
+
+        let other = &nodes[root_children["other"] as usize - 1];
+        assert_eq!(other.attr.mtime, now);
+        let link_ino = match &other.kind {
+            NodeKind::Dir(children) => children["link"],
+            other => panic!("other is not a folder: {other:?}"),
+        };
+        let link = &nodes[link_ino as usize - 1];
+        assert_eq!(link.attr.kind, FileType::Symlink);
+        assert!(matches!(&link.kind, NodeKind::Symlink(target) if target == "../dir"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,397 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use clap::Args;
+use color_eyre::{eyre::WrapErr, Result};
+use hyper::{
+    header::{ACCEPT_RANGES, ALLOW, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, LOCATION, RANGE},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use sn_api::{files::FilesMap, resolver::SafeData, Error, Result as ApiResult, Safe};
+use std::{
+    collections::BTreeSet,
+    convert::Infallible,
+    net::{IpAddr, SocketAddr},
+    path::{Component, Path},
+};
+use tracing::{debug, warn};
+
+// Media type used when the one of a file is unknown
+const DEFAULT_MEDIA_TYPE: &str = "application/octet-stream";
+// File served when a folder is requested, if it contains one
+const INDEX_FILE_NAME: &str = "index.html";
+// Characters to escape in the links of a folder listing
+const PATH_SEGMENT: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+#[derive(Args, Debug)]
+pub struct GatewayCommands {
+    /// The port to listen on for HTTP requests
+    #[clap(short = 'p', long = "port", default_value = "8080")]
+    port: u16,
+    /// The local address to listen on, use 0.0.0.0 to also serve requests from other hosts
+    #[clap(long = "address", default_value = "127.0.0.1")]
+    address: IpAddr,
+}
+
+// Outcome of parsing the Range header of a request against the size of a file
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    // No range, or one we don't support (e.g. multiple ranges), thus the whole content is served
+    Full,
+    // Inclusive range of bytes to be served
+    Partial(u64, u64),
+    // A range which doesn't overlap the content
+    Unsatisfiable,
+}
+
+/// Serves the content published on the network over HTTP, mapping requests for
+/// `http://<address>:<port>/<name>/<path>` onto `safe://<name>/<path>`, until Ctrl-C is pressed.
+pub async fn gateway_commander(cmd: GatewayCommands, safe: &Safe) -> Result<()> {
+    let address = SocketAddr::new(cmd.address, cmd.port);
+
+    let safe = safe.clone();
+    let make_service = make_service_fn(move |_conn| {
+        let safe = safe.clone();
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let safe = safe.clone();
+                async move { Ok::<_, Infallible>(handle_request(&safe, req).await) }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&address)
+        .wrap_err_with(|| format!("Failed to listen on {address}"))?
+        .serve(make_service);
+
+    println!("Serving safe:// content at http://{address}/<name>/<path>, press Ctrl-C to stop");
+
+    server
+        .with_graceful_shutdown(async {
+            if let Err(err) = tokio::signal::ctrl_c().await {
+                warn!("Failed to listen for Ctrl-C: {err}");
+            }
+        })
+        .await
+        .wrap_err("The gateway failed to serve requests")
+}
+
+async fn handle_request(safe: &Safe, req: Request<Body>) -> Response<Body> {
+    if req.method() != Method::GET && req.method() != Method::HEAD {
+        let mut response = text_response(
+            StatusCode::METHOD_NOT_ALLOWED,
+            "Only GET and HEAD requests are supported".to_string(),
+        );
+        let _ = response
+            .headers_mut()
+            .insert(ALLOW, "GET, HEAD".parse().expect("valid header value"));
+        return response;
+    }
+
+    let path = req.uri().path();
+    let query = req.uri().query();
+    let url = match safe_url_from_path(path, query) {
+        Some(url) => url,
+        None => {
+            return text_response(
+                StatusCode::BAD_REQUEST,
+                "Request the content at safe://<name>/<path> with /<name>/<path>".to_string(),
+            )
+        }
+    };
+
+    debug!("Gateway request for {path} mapped onto {url}");
+    match serve_content(safe, &req, &url).await {
+        Ok(response) => response,
+        Err(err) => {
+            warn!("Gateway failed to serve {url}: {err}");
+            error_response(err)
+        }
+    }
+}
+
+async fn serve_content(safe: &Safe, req: &Request<Body>, url: &str) -> ApiResult<Response<Body>> {
+    let path = req.uri().path();
+    let query = req.uri().query();
+
+    match resolve(safe, url).await? {
+        SafeData::FilesContainer { files_map, .. } => {
+            // relative links within the folder are only correct with a trailing slash
+            if !path.ends_with('/') {
+                let location = match query {
+                    Some(query) => format!("{path}/?{query}"),
+                    None => format!("{path}/"),
+                };
+                return Ok(Response::builder()
+                    .status(StatusCode::MOVED_PERMANENTLY)
+                    .header(LOCATION, location)
+                    .body(Body::empty())
+                    .expect("valid response"));
+            }
+
+            if !files_map.contains_key(&format!("/{INDEX_FILE_NAME}")) {
+                return Ok(folder_listing(path, &files_map));
+            }
+
+            let index_path = format!("{path}{INDEX_FILE_NAME}");
+            let index_url = safe_url_from_path(&index_path, query).unwrap_or_default();
+            match resolve(safe, &index_url).await? {
+                SafeData::PublicFile {
+                    xorurl, media_type, ..
+                } => serve_file(safe, req, &xorurl, media_type).await,
+                _ => Ok(folder_listing(path, &files_map)),
+            }
+        }
+        SafeData::PublicFile {
+            xorurl, media_type, ..
+        } => serve_file(safe, req, &xorurl, media_type).await,
+        _ => Ok(text_response(
+            StatusCode::NOT_IMPLEMENTED,
+            "Only files and FilesContainers can be served by the gateway".to_string(),
+        )),
+    }
+}
+
+// Resolves the URL down to the content it targets, without fetching files' content
+async fn resolve(safe: &Safe, url: &str) -> ApiResult<SafeData> {
+    safe.inspect(url)
+        .await?
+        .pop()
+        .ok_or_else(|| Error::ContentNotFound(format!("No content found at {url}")))
+}
+
+async fn serve_file(
+    safe: &Safe,
+    req: &Request<Body>,
+    xorurl: &str,
+    media_type: Option<String>,
+) -> ApiResult<Response<Body>> {
+    let reader = safe.files_get_stream(xorurl).await?;
+    let size = reader.size();
+    let is_head = req.method() == Method::HEAD;
+
+    let byte_range = req
+        .headers()
+        .get(RANGE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(ByteRange::Full, |value| parse_range(value, size));
+
+    let response = Response::builder()
+        .header(
+            CONTENT_TYPE,
+            media_type.unwrap_or_else(|| DEFAULT_MEDIA_TYPE.to_string()),
+        )
+        .header(ACCEPT_RANGES, "bytes");
+
+    let response = match byte_range {
+        ByteRange::Full => {
+            // the content is streamed as it's fetched, rather than fetching it all up front
+            let body = if is_head {
+                Body::empty()
+            } else {
+                Body::wrap_stream(reader)
+            };
+            response
+                .status(StatusCode::OK)
+                .header(CONTENT_LENGTH, size)
+                .body(body)
+        }
+        ByteRange::Partial(start, end) => {
+            let body = if is_head {
+                Body::empty()
+            } else {
+                let data = safe
+                    .files_get(xorurl, Some((Some(start), Some(end + 1))))
+                    .await?;
+                Body::from(data)
+            };
+            response
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(CONTENT_RANGE, format!("bytes {start}-{end}/{size}"))
+                .header(CONTENT_LENGTH, end - start + 1)
+                .body(body)
+        }
+        ByteRange::Unsatisfiable => response
+            .status(StatusCode::RANGE_NOT_SATISFIABLE)
+            .header(CONTENT_RANGE, format!("bytes */{size}"))
+            .body(Body::empty()),
+    };
+
+    Ok(response.expect("valid response"))
+}
+
+// Maps the path of a request, i.e. /<name>/<path>, onto the safe://<name>/<path> URL
+fn safe_url_from_path(path: &str, query: Option<&str>) -> Option<String> {
+    let path = path.trim_start_matches('/');
+    let (name, path) = path.split_once('/').unwrap_or((path, ""));
+    if name.is_empty() {
+        return None;
+    }
+
+    let url = match query {
+        Some(query) => format!("safe://{name}/{path}?{query}"),
+        None => format!("safe://{name}/{path}"),
+    };
+    Some(url)
+}
+
+// Parses a Range header, only supporting a single range of bytes
+fn parse_range(header: &str, size: u64) -> ByteRange {
+    let range = match header.trim().strip_prefix("bytes=") {
+        Some(range) if !range.contains(',') => range,
+        _ => return ByteRange::Full,
+    };
+    let (start, end) = match range.split_once('-') {
+        Some(bounds) => bounds,
+        None => return ByteRange::Full,
+    };
+
+    if start.is_empty() {
+        // a suffix range, i.e. the last bytes of the content
+        return match end.parse::<u64>() {
+            Ok(0) => ByteRange::Unsatisfiable,
+            Ok(_) if size == 0 => ByteRange::Unsatisfiable,
+            Ok(len) => ByteRange::Partial(size.saturating_sub(len), size - 1),
+            Err(_) => ByteRange::Full,
+        };
+    }
+
+    let start = match start.parse::<u64>() {
+        Ok(start) => start,
+        Err(_) => return ByteRange::Full,
+    };
+    let end = if end.is_empty() {
+        size.saturating_sub(1)
+    } else {
+        match end.parse::<u64>() {
+            Ok(end) if end >= start => end.min(size.saturating_sub(1)),
+            _ => return ByteRange::Full,
+        }
+    };
+
+    if start >= size {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial(start, end)
+    }
+}
+
+// Renders an HTML page listing the files and folders found at the root of the FilesMap
+fn folder_listing(path: &str, files_map: &FilesMap) -> Response<Body> {
+    let mut entries = BTreeSet::new();
+    for (file_path, file_item) in files_map.iter() {
+        let mut components =
+            Path::new(file_path)
+                .components()
+                .filter_map(|component| match component {
+                    Component::Normal(name) => name.to_str(),
+                    _ => None,
+                });
+        if let Some(name) = components.next() {
+            let is_folder = components.next().is_some()
+                || file_item.get("type").map(String::as_str) == Some("inode/directory");
+            let _ = entries.insert((name.to_string(), is_folder));
+        }
+    }
+
+    let title = html_escape(path);
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{title}</title></head>\n<body>\n<h1>{title}</h1>\n<ul>\n<li><a href=\"../\">../</a></li>\n"
+    );
+    for (name, is_folder) in entries {
+        let slash = if is_folder { "/" } else { "" };
+        html.push_str(&format!(
+            "<li><a href=\"{}{slash}\">{}{slash}</a></li>\n",
+            utf8_percent_encode(&name, PATH_SEGMENT),
+            html_escape(&name)
+        ));
+    }
+    html.push_str("</ul>\n</body>\n</html>\n");
+
+    Response::builder()
+        .header(CONTENT_TYPE, "text/html; charset=utf-8")
+        .body(Body::from(html))
+        .expect("valid response")
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn error_response(err: Error) -> Response<Body> {
+    let status = match err {
+        Error::ContentNotFound(_)
+        | Error::ContentError(_)
+        | Error::VersionNotFound(_)
+        | Error::HashNotFound(_)
+        | Error::EntryNotFound(_) => StatusCode::NOT_FOUND,
+        Error::InvalidXorUrl(_) | Error::InvalidInput(_) | Error::UrlError(_) => {
+            StatusCode::BAD_REQUEST
+        }
+        _ => StatusCode::BAD_GATEWAY,
+    };
+    text_response(status, err.to_string())
+}
+
+fn text_response(status: StatusCode, text: String) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+        .body(Body::from(text))
+        .expect("valid response")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_range, safe_url_from_path, ByteRange};
+
+    #[test]
+    fn safe_url_from_path_should_map_name_and_path() {
+        assert_eq!(
+            safe_url_from_path("/mysite/docs/index.html", None),
+            Some("safe://mysite/docs/index.html".to_string())
+        );
+        assert_eq!(
+            safe_url_from_path("/mysite", Some("v=hbyyyyd")),
+            Some("safe://mysite/?v=hbyyyyd".to_string())
+        );
+        assert_eq!(safe_url_from_path("/", None), None);
+    }
+
+    #[test]
+    fn parse_range_should_support_single_byte_ranges() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial(0, 99));
+        assert_eq!(
+            parse_range("bytes=900-", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(
+            parse_range("bytes=900-2000", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(
+            parse_range("bytes=-100", 1000),
+            ByteRange::Partial(900, 999)
+        );
+        assert_eq!(parse_range("bytes=-2000", 1000), ByteRange::Partial(0, 999));
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=-0", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-10,20-30", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=50-10", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-10", 1000), ByteRange::Full);
+    }
+}
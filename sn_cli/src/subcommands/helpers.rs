@@ -16,7 +16,7 @@ use sn_api::{
     files::{FilesMapChange, ProcessedFiles},
     multimap::Multimap,
     nrs::NrsMap,
-    wallet::Dbc,
+    wallet::{Dbc, WALLET_HISTORY_KEY},
     Safe, SafeUrl,
 };
 use std::io::{stdin, stdout, Read, Write};
@@ -161,6 +161,9 @@ pub async fn gen_wallet_table(safe: &Safe, multimap: &Multimap) -> Result<Table>
     ]);
 
     for (_, (key, value)) in multimap.iter() {
+        if key == WALLET_HISTORY_KEY.as_bytes() {
+            continue;
+        }
         let xorurl_str = std::str::from_utf8(value)?;
         let dbc_bytes = safe.files_get(xorurl_str, None).await?;
 
@@ -6,13 +6,23 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{helpers::serialise_output, OutputFmt};
-use crate::operations::auth_and_connect::{get_credentials_file_path, read_credentials};
+use super::{
+    helpers::{get_from_arg_or_stdin, serialise_output},
+    OutputFmt,
+};
+use crate::operations::auth_and_connect::{
+    read_credentials, read_keystore, read_keystore_password, KEYSTORE_PASSWORD_ENV_VAR,
+};
 use crate::operations::config::Config;
 use bls::SecretKey;
 use clap::Subcommand;
-use color_eyre::{eyre::WrapErr, Result};
-use sn_api::Safe;
+use color_eyre::{eyre::eyre, eyre::WrapErr, Help, Result};
+use comfy_table::Table;
+use sn_api::{keys::KeyPurpose, Safe};
+use std::path::PathBuf;
+
+// Name given to the key created with `keys create --for-cli` if none is provided.
+const DEFAULT_KEY_NAME: &str = "default";
 
 #[derive(Subcommand, Debug)]
 pub enum KeysSubCommands {
@@ -25,10 +35,68 @@ pub enum KeysSubCommands {
     #[clap(name = "create")]
     /// Create a new SafeKey in BLS format.
     Create {
-        /// Set this flag to output the generated keypair to file at ~/.safe/cli/credentials. The
-        /// CLI will then sign all commands using this keypair.
+        /// Set this flag to store the generated key, encrypted with a password, in the keystore
+        /// at ~/.safe/cli/keystore, and set it as the key in use. The CLI will then sign all
+        /// commands using this key.
         #[clap(long = "for-cli")]
         for_cli: bool,
+        /// The name to store the key with in the keystore. Defaults to 'default'.
+        #[clap(long = "name", requires = "for_cli")]
+        name: Option<String>,
+    },
+    #[clap(name = "list")]
+    /// List the keys stored in the keystore.
+    List {},
+    #[clap(name = "use")]
+    /// Set the key from the keystore the CLI signs all commands with.
+    Use {
+        /// The name of the key in the keystore.
+        name: String,
+    },
+    #[clap(name = "export")]
+    /// Decrypt a key from the keystore and output it, including its secret key.
+    Export {
+        /// The name of the key in the keystore. Defaults to the key in use.
+        name: Option<String>,
+    },
+    #[clap(name = "import")]
+    /// Import a BLS secret key into the keystore, encrypting it with the keystore password.
+    Import {
+        /// The name to store the key with in the keystore.
+        name: String,
+        /// The hex-encoded BLS secret key to import. It's read from STDIN if not provided.
+        secret_key: Option<String>,
+        /// Set this flag to set the imported key as the key in use. The imported key is always
+        /// set as the key in use if there was none.
+        #[clap(long = "use")]
+        use_key: bool,
+    },
+    #[clap(name = "derive")]
+    /// Derive a BLS key from a seed phrase. All the keys derived from the same seed phrase can
+    /// be restored from it, for signing, DBC ownership and register authorship.
+    Derive {
+        /// The BIP39 seed phrase to derive the key from. It's read from STDIN if not provided.
+        seed_phrase: Option<String>,
+        /// Set this flag to generate a new seed phrase, and derive the key from it. The seed
+        /// phrase is output, make sure to write it down and keep it safe.
+        #[clap(long = "new", conflicts_with = "seed_phrase")]
+        new: bool,
+        /// Optional passphrase the key is derived with, in addition to the seed phrase.
+        #[clap(long = "passphrase", default_value = "")]
+        passphrase: String,
+        /// The purpose to derive the key for: 'signing', 'dbc' or 'register'.
+        #[clap(long = "purpose", default_value = "signing")]
+        purpose: KeyPurpose,
+        /// The index of the key to derive, amongst the keys derived for the same purpose.
+        #[clap(long = "index", default_value = "0")]
+        index: u32,
+        /// Set this flag to store the derived key, encrypted with a password, in the keystore
+        /// at ~/.safe/cli/keystore, and set it as the key in use.
+        #[clap(long = "for-cli")]
+        for_cli: bool,
+        /// The name to store the key with in the keystore. Defaults to 'default'.
+        #[clap(long = "name", requires = "for_cli")]
+        name: Option<String>,
     },
 }
 
@@ -53,21 +121,164 @@ pub fn key_commander(cmd: KeysSubCommands, output_fmt: OutputFmt, config: &Confi
 
             Ok(())
         }
-        KeysSubCommands::Create { for_cli } => {
+        KeysSubCommands::Create { for_cli, name } => {
             let sk = SecretKey::random();
             print_new_key_output(output_fmt, &sk);
             if for_cli {
-                let (_, path) = get_credentials_file_path(config)?;
-                Safe::serialize_bls_key(&sk, &path)
-                    .wrap_err("Unable to serialize keypair to file")?;
-                println!("Keypair saved at {}", path.display());
+                let name = name.unwrap_or_else(|| DEFAULT_KEY_NAME.to_string());
+                let path = store_key(config, &name, &sk, true)
+                    .wrap_err("Unable to store the key in the keystore")?;
+                println!("Key '{}' saved in keystore at {}", name, path.display());
                 println!("Safe CLI now has write access to the network");
             }
             Ok(())
         }
+        KeysSubCommands::List {} => {
+            let keystore = read_keystore(config)?;
+            let keys = keystore.keys()?;
+            if OutputFmt::Pretty == output_fmt {
+                if keys.is_empty() {
+                    println!("No keys found in keystore at {}", keystore.path().display());
+                    return Ok(());
+                }
+                let mut table = Table::new();
+                table.add_row(vec!["", "Name", "Public Key"]);
+                for key in &keys {
+                    table.add_row(vec![
+                        if key.in_use { "*" } else { "" }.to_string(),
+                        key.name.clone(),
+                        key.public_key.to_hex(),
+                    ]);
+                }
+                println!("Keys stored in keystore at {}:", keystore.path().display());
+                println!("{table}");
+            } else {
+                let keys: Vec<_> = keys
+                    .into_iter()
+                    .map(|key| (key.name, key.public_key.to_hex(), key.in_use))
+                    .collect();
+                println!("{}", serialise_output(&keys, output_fmt));
+            }
+            Ok(())
+        }
+        KeysSubCommands::Use { name } => {
+            let mut keystore = read_keystore(config)?;
+            keystore.set_in_use(&name).map_err(|err| {
+                eyre!(err).suggestion("Run the 'keys list' command to see the keys available.")
+            })?;
+            keystore.save()?;
+            println!("Safe CLI now signs commands with key '{}'", name);
+            Ok(())
+        }
+        KeysSubCommands::Export { name } => {
+            let keystore = read_keystore(config)?;
+            let name = match name.as_deref().or_else(|| keystore.in_use()) {
+                Some(name) => name.to_string(),
+                None => {
+                    return Err(eyre!("There is no key in use in the keystore.")
+                        .suggestion("Provide the name of the key to export."))
+                }
+            };
+            let password = read_keystore_password(false)?;
+            let sk = keystore.secret_key(&name, &password).map_err(|err| {
+                eyre!(err).suggestion(format!(
+                    "Make sure the password provided, or set in the {} env var, is the one \
+                    the keystore was created with.",
+                    KEYSTORE_PASSWORD_ENV_VAR
+                ))
+            })?;
+            print_new_key_output(output_fmt, &sk);
+            Ok(())
+        }
+        KeysSubCommands::Import {
+            name,
+            secret_key,
+            use_key,
+        } => {
+            let sk_hex = get_from_arg_or_stdin(
+                secret_key,
+                Some("...awaiting secret key from STDIN stream..."),
+            )?;
+            let sk = SecretKey::from_hex(sk_hex.trim()).map_err(|err| {
+                eyre!("Invalid secret key: {}", err)
+                    .suggestion("The secret key must be a hex-encoded BLS key.")
+            })?;
+            let path = store_key(config, &name, &sk, use_key)
+                .wrap_err("Unable to import the key into the keystore")?;
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Key '{}' imported into keystore at {}",
+                    name,
+                    path.display()
+                );
+            } else {
+                println!(
+                    "{}",
+                    serialise_output(&(name, sk.public_key().to_hex()), output_fmt)
+                );
+            }
+            Ok(())
+        }
+        KeysSubCommands::Derive {
+            seed_phrase,
+            new,
+            passphrase,
+            purpose,
+            index,
+            for_cli,
+            name,
+        } => {
+            let seed_phrase = if new {
+                let seed_phrase = Safe::new_seed_phrase()?;
+                if OutputFmt::Pretty == output_fmt {
+                    println!("Seed Phrase: {}", seed_phrase);
+                    println!("Write down the seed phrase and keep it safe, it's the only way to restore the keys derived from it");
+                } else {
+                    println!("{}", serialise_output(&seed_phrase, output_fmt));
+                }
+                seed_phrase
+            } else {
+                get_from_arg_or_stdin(
+                    seed_phrase,
+                    Some("...awaiting seed phrase from STDIN stream..."),
+                )?
+            };
+
+            let master_key = Safe::master_key_from_seed_phrase(seed_phrase.trim(), &passphrase)
+                .map_err(|err| {
+                    eyre!(err).suggestion("The seed phrase must be a valid BIP39 mnemonic.")
+                })?;
+            let sk = Safe::derive_child_key(&master_key, purpose, index);
+            print_new_key_output(output_fmt, &sk);
+            if for_cli {
+                let name = name.unwrap_or_else(|| DEFAULT_KEY_NAME.to_string());
+                let path = store_key(config, &name, &sk, true)
+                    .wrap_err("Unable to store the key in the keystore")?;
+                println!("Key '{}' saved in keystore at {}", name, path.display());
+            }
+            Ok(())
+        }
     }
 }
 
+/// Store the key in the keystore with the given name, setting it as the key in use if requested
+/// or if there was none. Returns the path of the keystore.
+fn store_key(
+    config: &Config,
+    name: &str,
+    secret_key: &SecretKey,
+    set_in_use: bool,
+) -> Result<PathBuf> {
+    let mut keystore = read_keystore(config)?;
+    let password = read_keystore_password(keystore.is_empty())?;
+    keystore.insert(name, secret_key, &password)?;
+    if set_in_use || keystore.in_use().is_none() {
+        keystore.set_in_use(name)?;
+    }
+    keystore.save()?;
+    Ok(keystore.path().to_path_buf())
+}
+
 pub fn print_new_key_output(output_fmt: OutputFmt, secret_key: &SecretKey) {
     let sk_hex = secret_key.to_hex();
     let pk_hex = secret_key.public_key().to_hex();
@@ -82,7 +293,7 @@ pub fn print_new_key_output(output_fmt: OutputFmt, secret_key: &SecretKey) {
 #[cfg(test)]
 mod create_command {
     use super::{key_commander, KeysSubCommands};
-    use crate::operations::auth_and_connect::read_credentials;
+    use crate::operations::auth_and_connect::{read_credentials, KEYSTORE_PASSWORD_ENV_VAR};
     use crate::operations::config::Config;
     use crate::subcommands::OutputFmt;
     use assert_fs::prelude::*;
@@ -93,34 +304,41 @@ mod create_command {
     #[tokio::test]
     async fn should_create_bls_keypair() -> Result<()> {
         let config_dir = assert_fs::TempDir::new()?;
-        let credentials_file = config_dir.child(".safe/cli/credentials");
+        let keystore_file = config_dir.child(".safe/cli/keystore");
         let config = Config::create_config(&config_dir, None).await?;
 
         let result = key_commander(
-            KeysSubCommands::Create { for_cli: false },
+            KeysSubCommands::Create {
+                for_cli: false,
+                name: None,
+            },
             OutputFmt::Pretty,
             &config,
         );
 
         assert!(result.is_ok());
-        credentials_file.assert(predicate::path::missing());
+        keystore_file.assert(predicate::path::missing());
         Ok(())
     }
 
     #[tokio::test]
-    async fn should_create_bls_keypair_saved_to_credentials_file() -> Result<()> {
+    async fn should_create_bls_keypair_saved_to_keystore() -> Result<()> {
+        std::env::set_var(KEYSTORE_PASSWORD_ENV_VAR, "test-password");
         let config_dir = assert_fs::TempDir::new()?;
-        let credentials_file = config_dir.child(".safe/cli/credentials");
+        let keystore_file = config_dir.child(".safe/cli/keystore");
         let config = Config::create_config(&config_dir, None).await?;
 
         let result = key_commander(
-            KeysSubCommands::Create { for_cli: true },
+            KeysSubCommands::Create {
+                for_cli: true,
+                name: None,
+            },
             OutputFmt::Pretty,
             &config,
         );
 
         assert!(result.is_ok());
-        credentials_file.assert(predicate::path::is_file());
+        keystore_file.assert(predicate::path::is_file());
 
         let (_, keypair) = read_credentials(&config)?;
         let keypair =
@@ -131,3 +349,131 @@ mod create_command {
         }
     }
 }
+
+#[cfg(test)]
+mod import_command {
+    use super::{key_commander, KeysSubCommands};
+    use crate::operations::auth_and_connect::{
+        read_cli_secret_key, read_keystore, KEYSTORE_PASSWORD_ENV_VAR,
+    };
+    use crate::operations::config::Config;
+    use crate::subcommands::OutputFmt;
+    use bls::SecretKey;
+    use color_eyre::{eyre::eyre, Result};
+
+    #[tokio::test]
+    async fn should_import_keys_and_switch_the_key_in_use() -> Result<()> {
+        std::env::set_var(KEYSTORE_PASSWORD_ENV_VAR, "test-password");
+        let config_dir = assert_fs::TempDir::new()?;
+        let config = Config::create_config(&config_dir, None).await?;
+
+        let alice_sk = SecretKey::random();
+        let bob_sk = SecretKey::random();
+        for (name, sk) in [("alice", &alice_sk), ("bob", &bob_sk)] {
+            key_commander(
+                KeysSubCommands::Import {
+                    name: name.to_string(),
+                    secret_key: Some(sk.to_hex()),
+                    use_key: false,
+                },
+                OutputFmt::Pretty,
+                &config,
+            )?;
+        }
+
+        // the first key imported is set as the key in use
+        let keys = read_keystore(&config)?.keys()?;
+        assert_eq!(keys.len(), 2);
+        let (_, sk) =
+            read_cli_secret_key(&config)?.ok_or_else(|| eyre!("A key should be in use"))?;
+        assert_eq!(sk, alice_sk);
+
+        key_commander(
+            KeysSubCommands::Use {
+                name: "bob".to_string(),
+            },
+            OutputFmt::Pretty,
+            &config,
+        )?;
+        let (_, sk) =
+            read_cli_secret_key(&config)?.ok_or_else(|| eyre!("A key should be in use"))?;
+        assert_eq!(sk, bob_sk);
+
+        // a key cannot be imported twice with the same name
+        let result = key_commander(
+            KeysSubCommands::Import {
+                name: "bob".to_string(),
+                secret_key: Some(SecretKey::random().to_hex()),
+                use_key: false,
+            },
+            OutputFmt::Pretty,
+            &config,
+        );
+        assert!(result.is_err());
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod derive_command {
+    use super::{key_commander, KeysSubCommands};
+    use crate::operations::auth_and_connect::{read_cli_secret_key, KEYSTORE_PASSWORD_ENV_VAR};
+    use crate::operations::config::Config;
+    use crate::subcommands::OutputFmt;
+    use color_eyre::{eyre::eyre, Result};
+    use sn_api::{keys::KeyPurpose, Safe};
+
+    #[tokio::test]
+    async fn should_derive_key_from_seed_phrase_and_save_it_to_keystore() -> Result<()> {
+        std::env::set_var(KEYSTORE_PASSWORD_ENV_VAR, "test-password");
+        let config_dir = assert_fs::TempDir::new()?;
+        let config = Config::create_config(&config_dir, None).await?;
+
+        let seed_phrase = Safe::new_seed_phrase()?;
+        key_commander(
+            KeysSubCommands::Derive {
+                seed_phrase: Some(seed_phrase.clone()),
+                new: false,
+                passphrase: String::new(),
+                purpose: KeyPurpose::Signing,
+                index: 1,
+                for_cli: true,
+                name: None,
+            },
+            OutputFmt::Pretty,
+            &config,
+        )?;
+
+        let master_key = Safe::master_key_from_seed_phrase(&seed_phrase, "")?;
+        let (_, sk) =
+            read_cli_secret_key(&config)?.ok_or_else(|| eyre!("A key should be in use"))?;
+        assert_eq!(
+            sk,
+            Safe::derive_child_key(&master_key, KeyPurpose::Signing, 1)
+        );
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn should_fail_with_invalid_seed_phrase() -> Result<()> {
+        let config_dir = assert_fs::TempDir::new()?;
+        let config = Config::create_config(&config_dir, None).await?;
+
+        let result = key_commander(
+            KeysSubCommands::Derive {
+                seed_phrase: Some("not a seed phrase".to_string()),
+                new: false,
+                passphrase: String::new(),
+                purpose: KeyPurpose::DbcOwnership,
+                index: 0,
+                for_cli: false,
+                name: None,
+            },
+            OutputFmt::Pretty,
+            &config,
+        );
+        assert!(result.is_err());
+        Ok(())
+    }
+}
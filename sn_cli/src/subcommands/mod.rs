@@ -13,15 +13,20 @@ pub mod config;
 pub mod dog;
 pub mod files;
 mod files_get;
+#[cfg(target_os = "linux")]
+mod files_mount;
+pub mod gateway;
 mod helpers;
 pub mod keys;
 pub mod networks;
 pub mod node;
 pub mod nrs;
+pub mod register;
 pub mod safe_id;
 pub mod setup;
 pub mod update;
 pub mod wallet;
+pub mod watch;
 pub mod xorurl;
 
 #[derive(PartialEq, Eq, Clone, Copy, Debug)]
@@ -83,6 +88,12 @@ pub enum SubCommands {
     )]
     /// Inspect data on the SAFE Network providing only metadata information about the content
     Dog(dog::DogCommands),
+    #[clap(
+        name = "gateway",
+        global_settings(&[AppSettings::DisableVersion]),
+    )]
+    /// Serve content from the SAFE Network over HTTP, so it can be viewed with a regular web browser
+    Gateway(gateway::GatewayCommands),
     #[clap(name = "files", subcommand, global_settings(&[AppSettings::DisableVersion]))]
     /// Manage files on the SAFE Network
     Files(files::FilesSubCommands),
@@ -92,6 +103,15 @@ pub enum SubCommands {
     #[clap(name = "nrs", subcommand, global_settings(&[AppSettings::DisableVersion]))]
     /// Manage public names on the SAFE Network
     Nrs(nrs::NrsSubCommands),
+    #[clap(name = "register", subcommand, global_settings(&[AppSettings::DisableVersion]))]
+    /// Manage the permissions of Registers on the SAFE Network
+    Register(register::RegisterSubCommands),
+    #[clap(
+        name = "watch",
+        global_settings(&[AppSettings::DisableVersion]),
+    )]
+    /// Watch a Register, or FilesContainer, on the SAFE Network, printing the new entries written to it
+    Watch(watch::WatchCommands),
     #[clap(name = "keys", subcommand, global_settings(&[AppSettings::DisableVersion]))]
     /// Manage keys on the SAFE Network
     Keys(keys::KeysSubCommands),
@@ -0,0 +1,141 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{helpers::serialise_output, OutputFmt};
+use clap::Subcommand;
+use color_eyre::{eyre::eyre, Help, Result};
+use sn_api::{Error::AccessDenied, PublicKey, Safe};
+
+#[derive(Subcommand, Debug)]
+pub enum RegisterSubCommands {
+    #[clap(name = "grant")]
+    /// Grant permission to write to a Register, or a FilesContainer, to a key
+    Grant {
+        /// The safe:// URL of the Register or FilesContainer
+        url: String,
+        /// The (Ed25519/BLS) public key, hex encoded, to grant write permission to
+        pk: String,
+    },
+    #[clap(name = "revoke")]
+    /// Revoke the permission to write to a Register, or a FilesContainer, from a key
+    Revoke {
+        /// The safe:// URL of the Register or FilesContainer
+        url: String,
+        /// The (Ed25519/BLS) public key, hex encoded, to revoke write permission from
+        pk: String,
+    },
+    #[clap(name = "pending")]
+    /// List the Register operations which couldn't be published to the network yet
+    Pending,
+    #[clap(name = "flush")]
+    /// Publish the Register operations which couldn't be published to the network yet
+    Flush,
+}
+
+pub async fn register_commander(
+    cmd: RegisterSubCommands,
+    output_fmt: OutputFmt,
+    safe: &Safe,
+) -> Result<()> {
+    match cmd {
+        RegisterSubCommands::Grant { url, pk } => {
+            set_write_permission(url, pk, true, output_fmt, safe).await
+        }
+        RegisterSubCommands::Revoke { url, pk } => {
+            set_write_permission(url, pk, false, output_fmt, safe).await
+        }
+        RegisterSubCommands::Pending => {
+            let pending = safe.register_pending()?;
+            if OutputFmt::Pretty == output_fmt {
+                if pending.is_empty() {
+                    println!("No Register operations pending");
+                }
+                for op in &pending {
+                    println!("{:<14} {}", op.kind, op.xorurl);
+                }
+            } else {
+                let pending = pending
+                    .into_iter()
+                    .map(|op| (op.xorurl, op.kind))
+                    .collect::<Vec<_>>();
+                println!("{}", serialise_output(&pending, output_fmt));
+            }
+            Ok(())
+        }
+        RegisterSubCommands::Flush => {
+            let published = safe.register_flush().await?;
+            if OutputFmt::Pretty == output_fmt {
+                println!("{} pending Register operations published", published);
+            } else {
+                println!("{}", serialise_output(&published, output_fmt));
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn set_write_permission(
+    url: String,
+    pk: String,
+    grant: bool,
+    output_fmt: OutputFmt,
+    safe: &Safe,
+) -> Result<()> {
+    let public_key = parse_public_key(&pk)?;
+
+    let result = if grant {
+        safe.register_grant_write(&url, public_key).await
+    } else {
+        safe.register_revoke_write(&url, public_key).await
+    };
+    if let Err(err @ AccessDenied(_)) = result {
+        return Err(eyre!(err).suggestion(
+            "Only the owner of the Register, or keys with admin permissions, can update its policy.",
+        ));
+    }
+    result?;
+
+    let action = if grant { "granted to" } else { "revoked from" };
+    if OutputFmt::Pretty == output_fmt {
+        println!("Write permission {} key {} on {}", action, pk, url);
+    } else {
+        println!("{}", serialise_output(&(url, pk, grant), output_fmt));
+    }
+
+    Ok(())
+}
+
+fn parse_public_key(pk: &str) -> Result<PublicKey> {
+    PublicKey::ed25519_from_hex(pk)
+        .or_else(|_| PublicKey::bls_from_hex(pk))
+        .map_err(|_| eyre!("Invalid (Ed25519/BLS) public key bytes: {}", pk))
+}
+
+#[cfg(test)]
+mod register_command {
+    use super::parse_public_key;
+    use color_eyre::{eyre::eyre, Result};
+    use sn_api::PublicKey;
+
+    #[test]
+    fn parse_public_key_should_accept_bls_and_ed25519_keys() -> Result<()> {
+        let bls_pk = bls::SecretKey::random().public_key();
+        assert_eq!(parse_public_key(&bls_pk.to_hex())?, PublicKey::Bls(bls_pk));
+
+        let ed25519_pk = sn_api::Keypair::new_ed25519().public_key();
+        assert_eq!(
+            parse_public_key(&hex::encode(ed25519_pk.to_bytes()))?,
+            ed25519_pk
+        );
+
+        match parse_public_key("not-a-key") {
+            Err(_) => Ok(()),
+            Ok(pk) => Err(eyre!("Unexpectedly parsed public key: {:?}", pk)),
+        }
+    }
+}
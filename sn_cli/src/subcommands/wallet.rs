@@ -10,13 +10,18 @@ use super::{
     helpers::{get_from_arg_or_stdin, serialise_output},
     OutputFmt,
 };
-use crate::operations::config::Config;
+use crate::operations::{auth_and_connect::read_cli_secret_key, config::Config};
 use bls::{PublicKey, SecretKey};
 use clap::Subcommand;
 use color_eyre::{eyre::eyre, eyre::Error, Help, Result};
-use sn_api::{Error as ApiError, Safe};
-use sn_dbc::{Dbc, Error as DbcError};
-use std::path::{Path, PathBuf};
+use comfy_table::Table;
+use serde::Serialize;
+use sn_api::{
+    wallet::{dbc_verify_offline, WalletHistoryEvent},
+    Error as ApiError, Safe,
+};
+use sn_dbc::{Dbc, Error as DbcError, Token};
+use std::path::{Component, Path, PathBuf};
 use tokio::fs;
 
 #[derive(Subcommand, Debug)]
@@ -30,6 +35,12 @@ pub enum WalletSubCommands {
         /// The URL of wallet to query
         target: Option<String>,
     },
+    #[clap(name = "history")]
+    /// List the deposits and reissues made on a wallet, from the oldest to the newest
+    History {
+        /// The URL of wallet to query
+        target: Option<String>,
+    },
     #[clap(name = "deposit")]
     /// Deposit a spendable DBC in a wallet. If the DBC is not bearer, we will try to deposit using
     /// the secret key configured for use with safe. If you wish to use a different key, use the
@@ -54,6 +65,50 @@ pub enum WalletSubCommands {
         #[clap(long = "force")]
         force: bool,
     },
+    #[clap(name = "pay")]
+    /// Pay several recipients from a wallet with a single reissue, writing the DBC of each
+    /// payment to a file. If there is change, the change DBC is deposited back in the wallet.
+    Pay {
+        /// The URL of wallet to pay from
+        #[clap(long = "from")]
+        from: String,
+        /// A path to a CSV file listing the payments to make, one per line, with the hex-encoded
+        /// BLS public key of the recipient, the amount to pay, and optionally the name of the file
+        /// to write the payment's DBC to, e.g. '<public key>,1.5,alice.dbc'. File names must be
+        /// unique, and without any directory. A header line is ignored, as well as empty lines
+        /// and lines starting with '#'.
+        #[clap(long = "batch")]
+        batch: PathBuf,
+        /// The directory the DBCs are written to. Defaults to the current directory.
+        #[clap(long = "output-dir")]
+        output_dir: Option<PathBuf>,
+    },
+    #[clap(name = "inspect")]
+    /// Decode a DBC and verify it offline, against the network contacts cached for the default
+    /// network. This doesn't check if the DBC has been already spent.
+    Inspect {
+        /// A path to a file containing hex encoded DBC data, or you can supply the data directly.
+        /// Depending on the shell or OS in use, due to the length of the data string, supplying
+        /// directly may not work.
+        dbc: Option<String>,
+        #[clap(long = "secret-key")]
+        /// Use this argument to specify the secret key of an owned DBC, to reveal its amount and
+        /// key image. It should be a hex-encoded BLS key.
+        secret_key_hex: Option<String>,
+        /// A path to a network contacts file to verify the DBC against, instead of the network
+        /// contacts of the default network.
+        #[clap(long = "network-contacts")]
+        network_contacts: Option<PathBuf>,
+    },
+    #[clap(name = "audit")]
+    /// Audit the total supply of the network against the genesis DBC, listing all the key images
+    /// logged as spent in the network's spentbook to check none of them were double spent.
+    Audit {
+        /// A path to a file containing the hex encoded genesis DBC, or you can supply the data
+        /// directly. Depending on the shell or OS in use, due to the length of the data string,
+        /// supplying directly may not work.
+        genesis_dbc: Option<String>,
+    },
     #[clap(name = "reissue")]
     /// Reissue a DBC from a wallet.
     Reissue {
@@ -77,6 +132,41 @@ pub enum WalletSubCommands {
     },
 }
 
+/// A payment listed in the CSV file provided to the `wallet pay` command
+#[derive(Debug, PartialEq)]
+struct Payment {
+    recipient: PublicKey,
+    amount: String,
+    filename: String,
+}
+
+/// Outcome of a payment made by the `wallet pay` command
+#[derive(Serialize)]
+struct PaymentReport {
+    recipient: String,
+    amount: String,
+    dbc_file: Option<PathBuf>,
+}
+
+/// Details of a DBC as shown by the `wallet inspect` command
+#[derive(Serialize)]
+struct DbcInspection {
+    is_bearer: bool,
+    owner: String,
+    amount: Option<String>,
+    key_image: Option<String>,
+    spent_proofs: Vec<SpentProofInspection>,
+    verification_error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct SpentProofInspection {
+    key_image: String,
+    transaction_hash: String,
+    section_key: String,
+    is_known_section_key: bool,
+}
+
 pub async fn wallet_commander(
     cmd: WalletSubCommands,
     output_fmt: OutputFmt,
@@ -114,6 +204,74 @@ pub async fn wallet_commander(
 
             Ok(())
         }
+        WalletSubCommands::History { target } => {
+            let target = get_from_arg_or_stdin(
+                target,
+                Some("...awaiting wallet address/location from STDIN stream..."),
+            )?;
+
+            let history = safe.wallet_history(&target).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Wallet at \"{}\" has {} record/s in its history:",
+                    target,
+                    history.len()
+                );
+                let mut table = Table::new();
+                table.add_row(vec!["Timestamp", "Operation", "Amount", "Details"]);
+                for record in &history {
+                    match &record.event {
+                        WalletHistoryEvent::Deposit { name, amount } => {
+                            table.add_row(vec![
+                                record.timestamp.clone(),
+                                "deposit".to_string(),
+                                amount.to_string(),
+                                format!("name: {}", name),
+                            ]);
+                        }
+                        WalletHistoryEvent::Reissue {
+                            outputs,
+                            change,
+                            spent_key_images,
+                        } => {
+                            for (amount, owner) in outputs {
+                                let recipient = owner
+                                    .map(|pk| pk.to_hex())
+                                    .unwrap_or_else(|| "bearer".to_string());
+                                table.add_row(vec![
+                                    record.timestamp.clone(),
+                                    "reissue".to_string(),
+                                    amount.to_string(),
+                                    format!("to: {}", recipient),
+                                ]);
+                            }
+                            let mut details = spent_key_images
+                                .iter()
+                                .map(|key_image| format!("spent: {}", key_image.to_hex()))
+                                .collect::<Vec<_>>();
+                            if let Some((name, _)) = change {
+                                details.push(format!("change name: {}", name));
+                            }
+                            table.add_row(vec![
+                                record.timestamp.clone(),
+                                "change".to_string(),
+                                change
+                                    .as_ref()
+                                    .map(|(_, amount)| amount.to_string())
+                                    .unwrap_or_else(|| Token::zero().to_string()),
+                                details.join("\n"),
+                            ]);
+                        }
+                    }
+                }
+                println!("{table}");
+            } else {
+                println!("{}", serialise_output(&history, output_fmt));
+            }
+
+            Ok(())
+        }
         WalletSubCommands::Deposit {
             wallet_url,
             name,
@@ -121,27 +279,7 @@ pub async fn wallet_commander(
             secret_key_hex,
             force,
         } => {
-            let dbc = if let Some(dbc) = dbc {
-                let path = Path::new(&dbc);
-                if path.exists() {
-                    if path.is_dir() {
-                        return Err(eyre!("The path supplied refers to a directory.")
-                            .suggestion("A file path must be specified for the DBC data."));
-                    }
-                    let dbc_data = std::fs::read_to_string(path)?;
-                    Dbc::from_hex(dbc_data.trim()).map_err(|e| {
-                        eyre!(e.to_string()).suggestion(
-                            "This file does not appear to have DBC data. \
-                            Please select another file with valid hex-encoded DBC data.",
-                        )
-                    })?
-                } else {
-                    Dbc::from_hex(&dbc)?
-                }
-            } else {
-                let dbc_hex = get_from_arg_or_stdin(dbc, None)?;
-                Dbc::from_hex(dbc_hex.trim())?
-            };
+            let dbc = read_dbc_from_arg_or_stdin(dbc)?;
 
             let (sk, key_image) = if dbc.is_bearer() {
                 (None, dbc.key_image_bearer()?)
@@ -201,6 +339,290 @@ pub async fn wallet_commander(
 
             Ok(())
         }
+        WalletSubCommands::Pay {
+            from,
+            batch,
+            output_dir,
+        } => {
+            let csv = fs::read_to_string(&batch).await.map_err(|err| {
+                eyre!(
+                    "Unable to read payments file '{}': {}",
+                    batch.display(),
+                    err
+                )
+            })?;
+            let payments = parse_payments_csv(&csv)?;
+            if payments.is_empty() {
+                return Err(eyre!("No payments found in '{}'.", batch.display()));
+            }
+
+            // Make sure DBCs can be written before any token is spent
+            let output_dir = output_dir.unwrap_or_else(|| PathBuf::from("."));
+            fs::create_dir_all(&output_dir).await?;
+            let paths: Vec<PathBuf> = payments
+                .iter()
+                .map(|payment| output_dir.join(&payment.filename))
+                .collect();
+            if let Some(path) = paths.iter().find(|path| path.exists()) {
+                return Err(
+                    eyre!("The file '{}' already exists.", path.display()).suggestion(
+                        "Please remove it, or use another --output-dir or file name for the \
+                        payment, and run the command again.",
+                    ),
+                );
+            }
+
+            let outputs = payments
+                .iter()
+                .map(|payment| (payment.amount.clone(), Some(payment.recipient)))
+                .collect();
+            let (dbcs, change) = safe.wallet_reissue_many(&from, outputs).await?;
+
+            let mut reports = vec![];
+            for ((payment, dbc), path) in payments.iter().zip(dbcs.iter()).zip(paths) {
+                let dbc_hex = dbc.to_hex()?;
+                // Fall back to print the DBC to stdout if it cannot be written
+                let dbc_file = match fs::write(&path, &dbc_hex).await {
+                    Ok(()) => Some(path),
+                    Err(err) => {
+                        eprintln!(
+                            "Error: Unable to write DBC at '{}': {}. DBC data:\n{}",
+                            path.display(),
+                            err,
+                            dbc_hex
+                        );
+                        None
+                    }
+                };
+                reports.push(PaymentReport {
+                    recipient: payment.recipient.to_hex(),
+                    amount: payment.amount.clone(),
+                    dbc_file,
+                });
+            }
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Reissued {} DBC/s from wallet at \"{}\":",
+                    reports.len(),
+                    from
+                );
+                let mut table = Table::new();
+                table.add_row(vec!["Recipient", "Amount", "DBC file"]);
+                for report in &reports {
+                    table.add_row(vec![
+                        report.recipient.clone(),
+                        report.amount.clone(),
+                        report
+                            .dbc_file
+                            .as_ref()
+                            .map(|path| path.display().to_string())
+                            .unwrap_or_else(|| "<not written>".to_string()),
+                    ]);
+                }
+                println!("{table}");
+                match &change {
+                    Some((name, amount)) => println!(
+                        "Change of {} safecoins deposited with name '{}' in the wallet.",
+                        amount, name
+                    ),
+                    None => println!("There was no change to deposit in the wallet."),
+                }
+            } else {
+                println!("{}", serialise_output(&(reports, change), output_fmt));
+            }
+
+            Ok(())
+        }
+        WalletSubCommands::Inspect {
+            dbc,
+            secret_key_hex,
+            network_contacts,
+        } => {
+            let dbc = read_dbc_from_arg_or_stdin(dbc)?;
+            let sk = secret_key_hex
+                .map(|sk_hex| SecretKey::from_hex(&sk_hex))
+                .transpose()?;
+
+            let section_tree = match network_contacts {
+                Some(path) => Config::retrieve_local_network_contacts(&path).await?,
+                None => {
+                    config
+                        .read_default_network_contacts()
+                        .await
+                        .map_err(|err| {
+                            err.suggestion(
+                        "Use the --network-contacts argument to provide the network contacts \
+                        to verify the DBC against, or switch to a network which contacts were \
+                        already fetched.",
+                    )
+                        })?
+                        .0
+                }
+            };
+
+            let sk = if dbc.is_bearer() {
+                Some(dbc.owner_base().secret_key()?)
+            } else {
+                sk
+            };
+            let (amount, key_image) = match &sk {
+                Some(sk) => (
+                    Some(
+                        dbc.amount_secrets(sk)
+                            .map_err(|e| map_invalid_sk_error(e.into()))?
+                            .amount()
+                            .to_string(),
+                    ),
+                    Some(dbc.key_image(sk)?.to_hex()),
+                ),
+                None => (None, None),
+            };
+
+            let spent_proofs = dbc
+                .spent_proofs
+                .iter()
+                .map(|proof| SpentProofInspection {
+                    key_image: proof.key_image().to_hex(),
+                    transaction_hash: hex::encode(proof.transaction_hash()),
+                    section_key: proof.spentbook_pub_key.to_hex(),
+                    is_known_section_key: section_tree
+                        .get_sections_dag()
+                        .has_key(&proof.spentbook_pub_key),
+                })
+                .collect();
+
+            let inspection = DbcInspection {
+                is_bearer: dbc.is_bearer(),
+                owner: dbc.owner_base().public_key().to_hex(),
+                amount,
+                key_image,
+                spent_proofs,
+                verification_error: dbc_verify_offline(&dbc, sk.as_ref(), &section_tree)
+                    .err()
+                    .map(|err| err.to_string()),
+            };
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "{} DBC owned by public key {}",
+                    if inspection.is_bearer {
+                        "Bearer"
+                    } else {
+                        "Owned"
+                    },
+                    inspection.owner
+                );
+                println!(
+                    "Amount: {}",
+                    inspection
+                        .amount
+                        .as_deref()
+                        .unwrap_or("unknown (the secret key is required to reveal it)")
+                );
+                println!(
+                    "Key image: {}",
+                    inspection
+                        .key_image
+                        .as_deref()
+                        .unwrap_or("unknown (the secret key is required to reveal it)")
+                );
+
+                let mut table = Table::new();
+                table.add_row(vec![
+                    "Spent key image",
+                    "Transaction hash",
+                    "Signed by section key",
+                ]);
+                for proof in &inspection.spent_proofs {
+                    table.add_row(vec![
+                        proof.key_image.clone(),
+                        proof.transaction_hash.clone(),
+                        format!(
+                            "{}{}",
+                            proof.section_key,
+                            if proof.is_known_section_key {
+                                ""
+                            } else {
+                                " (unknown)"
+                            }
+                        ),
+                    ]);
+                }
+                println!("Spent proofs:");
+                println!("{table}");
+            } else {
+                println!("{}", serialise_output(&inspection, output_fmt));
+            }
+
+            if let Some(err) = inspection.verification_error {
+                return Err(eyre!("The DBC failed the offline verification: {}", err));
+            }
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "The DBC was verified successfully against the network contacts. \
+                    Note this doesn't check if it has been already spent."
+                );
+            }
+
+            Ok(())
+        }
+        WalletSubCommands::Audit { genesis_dbc } => {
+            let genesis_dbc = read_dbc_from_arg_or_stdin(genesis_dbc)?;
+            let audit = safe.spentbook_audit(&genesis_dbc).await?;
+
+            if OutputFmt::Pretty == output_fmt {
+                println!(
+                    "Total supply held by the genesis DBC: {}{}",
+                    audit.genesis_amount,
+                    if audit.genesis_amount_valid {
+                        ""
+                    } else {
+                        " (unexpected)"
+                    }
+                );
+                println!(
+                    "Genesis DBC spent: {}",
+                    if audit.genesis_spent { "yes" } else { "no" }
+                );
+                println!("Spent key images found: {}", audit.spent_key_images);
+
+                if !audit.double_spends.is_empty() {
+                    let mut table = Table::new();
+                    table.add_row(vec!["Double spent key image", "Transaction hashes"]);
+                    for item in &audit.double_spends {
+                        table.add_row(vec![
+                            item.key_image.to_hex(),
+                            item.transaction_hashes
+                                .iter()
+                                .map(hex::encode)
+                                .collect::<Vec<_>>()
+                                .join("\n"),
+                        ]);
+                    }
+                    println!("{table}");
+                }
+            } else {
+                println!("{}", serialise_output(&audit, output_fmt));
+            }
+
+            if !audit.genesis_amount_valid {
+                return Err(eyre!(
+                    "The genesis DBC doesn't hold the expected total supply of the network."
+                ));
+            }
+            if !audit.double_spends.is_empty() {
+                return Err(eyre!(
+                    "{} key image/s were found spent in more than one transaction.",
+                    audit.double_spends.len()
+                ));
+            }
+            if OutputFmt::Pretty == output_fmt {
+                println!("The total supply was audited successfully against the genesis DBC.");
+            }
+
+            Ok(())
+        }
         WalletSubCommands::Reissue {
             amount,
             from,
@@ -276,25 +698,120 @@ pub async fn wallet_commander(
     }
 }
 
+/// Helper to parse the payments listed in a CSV file, one per line with the recipient's public key,
+/// the amount, and optionally the name of the file to write the DBC to.
+fn parse_payments_csv(csv: &str) -> Result<Vec<Payment>> {
+    let mut payments = vec![];
+    for (index, line) in csv.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+        let recipient = match PublicKey::from_hex(fields[0]) {
+            Ok(pk) => pk,
+            // the first line may be a header
+            Err(_) if index == 0 => continue,
+            Err(err) => {
+                return Err(eyre!(
+                    "Invalid public key on line {} of payments file: {}",
+                    index + 1,
+                    err
+                ))
+            }
+        };
+        let (amount, filename) = match fields[1..] {
+            [amount] => (
+                amount,
+                format!("payment-{}-{}.dbc", index + 1, &fields[0][..8]),
+            ),
+            [amount, filename] if !filename.is_empty() => (amount, filename.to_string()),
+            _ => {
+                return Err(eyre!(
+                    "Line {} of payments file should have a public key, an amount, \
+                    and optionally a file name",
+                    index + 1
+                ))
+            }
+        };
+
+        // the DBC is written within the output directory, and not over any other payment's DBC
+        let mut components = Path::new(&filename).components();
+        let is_plain_name = matches!(
+            (components.next(), components.next()),
+            (Some(Component::Normal(name)), None) if name == filename.as_str()
+        );
+        if !is_plain_name {
+            return Err(eyre!(
+                "Invalid file name '{}' on line {} of payments file: it should be a plain \
+                file name, without any directory",
+                filename,
+                index + 1
+            ));
+        }
+        if payments
+            .iter()
+            .any(|payment: &Payment| payment.filename == filename)
+        {
+            return Err(eyre!(
+                "Duplicated file name '{}' on line {} of payments file",
+                filename,
+                index + 1
+            ));
+        }
+
+        payments.push(Payment {
+            recipient,
+            amount: amount.to_string(),
+            filename,
+        });
+    }
+
+    Ok(payments)
+}
+
+/// Helper to read a DBC from a file path or hex encoded data supplied as argument,
+/// or from STDIN if the argument was not supplied.
+fn read_dbc_from_arg_or_stdin(dbc: Option<String>) -> Result<Dbc> {
+    if let Some(dbc) = dbc {
+        let path = Path::new(&dbc);
+        if path.exists() {
+            if path.is_dir() {
+                return Err(eyre!("The path supplied refers to a directory.")
+                    .suggestion("A file path must be specified for the DBC data."));
+            }
+            let dbc_data = std::fs::read_to_string(path)?;
+            Dbc::from_hex(dbc_data.trim()).map_err(|e| {
+                eyre!(e.to_string()).suggestion(
+                    "This file does not appear to have DBC data. \
+                    Please select another file with valid hex-encoded DBC data.",
+                )
+            })
+        } else {
+            Ok(Dbc::from_hex(&dbc)?)
+        }
+    } else {
+        let dbc_hex = get_from_arg_or_stdin(dbc, None)?;
+        Ok(Dbc::from_hex(dbc_hex.trim())?)
+    }
+}
+
 /// Helper to get the secret key from the credentials that are configured for use with safe.
 ///
 /// Different error and suggestion messages need to be provided depending on the context in which
 /// it is used.
 ///
-/// Returns an error if the credentials file is missing.
+/// Returns an error if there is no key in use in the keystore nor a credentials file.
 fn read_key_from_configured_credentials(
     config: &Config,
     error: String,
     suggestion: String,
 ) -> Result<SecretKey> {
-    let mut credentials_path = config.cli_config_path.clone();
-    credentials_path.pop();
-    credentials_path.push("credentials");
-    if !credentials_path.exists() {
-        return Err(eyre!(error).suggestion(suggestion));
+    match read_cli_secret_key(config)? {
+        Some((_, sk)) => Ok(sk),
+        None => Err(eyre!(error).suggestion(suggestion)),
     }
-    let sk_hex = std::fs::read_to_string(credentials_path)?;
-    Ok(SecretKey::from_hex(&sk_hex)?)
 }
 
 fn map_invalid_sk_error(api_error: ApiError) -> Error {
@@ -308,3 +825,60 @@ fn map_invalid_sk_error(api_error: ApiError) -> Error {
         _ => api_error.into(),
     }
 }
+
+#[cfg(test)]
+mod pay_command {
+    use super::{parse_payments_csv, Payment};
+    use bls::SecretKey;
+    use color_eyre::Result;
+
+    #[test]
+    fn should_parse_payments_csv() -> Result<()> {
+        let pk1 = SecretKey::random().public_key();
+        let pk2 = SecretKey::random().public_key();
+        let csv = format!(
+            "owner,amount,file\n\n# a comment\n{},1.5\n{}, 0.25 ,bob.dbc\n",
+            pk1.to_hex(),
+            pk2.to_hex()
+        );
+
+        let payments = parse_payments_csv(&csv)?;
+        assert_eq!(
+            payments,
+            vec![
+                Payment {
+                    recipient: pk1,
+                    amount: "1.5".to_string(),
+                    filename: format!("payment-4-{}.dbc", &pk1.to_hex()[..8]),
+                },
+                Payment {
+                    recipient: pk2,
+                    amount: "0.25".to_string(),
+                    filename: "bob.dbc".to_string(),
+                }
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn should_fail_to_parse_invalid_payments_csv() {
+        let pk = SecretKey::random().public_key().to_hex();
+        assert!(parse_payments_csv(&format!("{},1.5\nnot-a-key,2", pk)).is_err());
+        assert!(parse_payments_csv(&pk).is_err());
+        assert!(parse_payments_csv(&format!("{},1,a.dbc,extra", pk)).is_err());
+    }
+
+    #[test]
+    fn should_reject_unsafe_or_duplicated_file_names() {
+        let pk = SecretKey::random().public_key().to_hex();
+        for filename in ["/tmp/a.dbc", "../a.dbc", "dir/a.dbc", "..", ".", "a.dbc/"] {
+            assert!(
+                parse_payments_csv(&format!("{},1,{}", pk, filename)).is_err(),
+                "{filename} should be rejected"
+            );
+        }
+        assert!(parse_payments_csv(&format!("{pk},1,a.dbc\n{pk},2,a.dbc")).is_err());
+    }
+}
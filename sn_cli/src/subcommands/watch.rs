@@ -0,0 +1,54 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{
+    helpers::{get_from_arg_or_stdin, serialise_output},
+    OutputFmt,
+};
+use clap::Args;
+use color_eyre::Result;
+use futures::StreamExt;
+use sn_api::Safe;
+use tracing::debug;
+
+#[derive(Args, Debug)]
+pub struct WatchCommands {
+    /// The safe:// URL of the Register, or FilesContainer, to watch
+    location: Option<String>,
+}
+
+pub async fn watch_commander(cmd: WatchCommands, output_fmt: OutputFmt, safe: &Safe) -> Result<()> {
+    let url = get_from_arg_or_stdin(cmd.location, None)?;
+    debug!("Running watch for: {:?}", &url);
+
+    let mut entries = safe.register_watch(&url).await?;
+    if OutputFmt::Pretty == output_fmt {
+        println!("Watching {} for new entries, press Ctrl+C to stop...", url);
+    }
+
+    while let Some((hash, entry)) = entries.next().await {
+        let hash = hex::encode(hash.0);
+        if OutputFmt::Pretty == output_fmt {
+            match std::str::from_utf8(&entry) {
+                Ok(text) => println!("New entry {}: {}", hash, text),
+                Err(_) => println!("New entry {}: <{} bytes>", hash, entry.len()),
+            }
+        } else {
+            println!("{}", serialise_output(&(hash, entry), output_fmt));
+        }
+    }
+
+    if OutputFmt::Pretty == output_fmt {
+        println!(
+            "Stopped watching {}, it was deleted or can no longer be watched",
+            url
+        );
+    }
+
+    Ok(())
+}
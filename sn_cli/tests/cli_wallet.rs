@@ -364,10 +364,10 @@ async fn wallet_deposit_owned_dbc_with_no_secret_key_or_credentials_should_fail_
     )?;
 
     // In a real world scenario, there would have been some long period of time between the reissue
-    // and depositing the DBC. In that period of time, the credentials file would have been
+    // and depositing the DBC. In that period of time, the keystore file would have been
     // deleted, most likely by accident, but it could have been intentional.
-    let credentials_file = config_dir.child("cli").child("credentials");
-    std::fs::remove_file(credentials_file.path())?;
+    let keystore_file = config_dir.child("cli").child("keystore");
+    std::fs::remove_file(keystore_file.path())?;
 
     safe_cmd(
         &config_dir,
@@ -663,10 +663,10 @@ async fn wallet_reissue_with_owned_arg_should_fail_if_credentials_are_not_config
     )?;
 
     // In a real world scenario, there would have been some long period of time between the wallet
-    // being created and the reissue. In that period of time, the credentials file would have been
+    // being created and the reissue. In that period of time, the keystore file would have been
     // deleted, most likely by accident, but it could have been intentional.
-    let credentials_file = config_dir.child("cli").child("credentials");
-    std::fs::remove_file(credentials_file.path())?;
+    let keystore_file = config_dir.child("cli").child("keystore");
+    std::fs::remove_file(keystore_file.path())?;
 
     safe_cmd(
         &config_dir,
@@ -1125,3 +1125,154 @@ async fn wallet_deposit_dbc_reissued_to_file() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn wallet_history_should_list_deposits_and_reissues() -> Result<()> {
+    let config_dir = use_isolated_safe_config_dir()?;
+    let json_output = safe_cmd_stdout(&config_dir, ["wallet", "create", "--json"], Some(0))?;
+    let wallet_xorurl = parse_wallet_create_output(&json_output)?;
+    let tmp_data_dir = assert_fs::TempDir::new()?;
+    let (dbc_file_path, _, _) = get_bearer_dbc_on_file(&tmp_data_dir).await?;
+
+    safe_cmd(
+        &config_dir,
+        [
+            "wallet",
+            "deposit",
+            "--name",
+            "my-first-dbc",
+            "--dbc",
+            &dbc_file_path.display().to_string(),
+            &wallet_xorurl,
+        ],
+        Some(0),
+    )?;
+
+    safe_cmd(
+        &config_dir,
+        ["wallet", "reissue", "0.111", "--from", &wallet_xorurl],
+        Some(0),
+    )?;
+
+    safe_cmd(&config_dir, ["wallet", "history", &wallet_xorurl], Some(0))?
+        .assert()
+        .stdout(predicate::str::contains(format!(
+            "Wallet at \"{}\" has 2 record/s in its history:",
+            wallet_xorurl
+        )))
+        .stdout(predicate::str::contains("name: my-first-dbc"))
+        .stdout(predicate::str::contains("0.111000000"))
+        .stdout(predicate::str::contains("to: bearer"))
+        .success();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn wallet_inspect_should_verify_a_bearer_dbc() -> Result<()> {
+    let config_dir = use_isolated_safe_config_dir()?;
+    // connecting to the network caches its contacts, which are used to inspect the DBC
+    safe_cmd(&config_dir, ["wallet", "create"], Some(0))?;
+    let tmp_data_dir = assert_fs::TempDir::new()?;
+    let (dbc_file_path, dbc, balance) = get_bearer_dbc_on_file(&tmp_data_dir).await?;
+
+    safe_cmd(
+        &config_dir,
+        ["wallet", "inspect", &dbc_file_path.display().to_string()],
+        Some(0),
+    )?
+    .assert()
+    .stdout(predicate::str::contains(format!(
+        "Bearer DBC owned by public key {}",
+        dbc.owner_base().public_key().to_hex()
+    )))
+    .stdout(predicate::str::contains(format!("Amount: {}", balance)))
+    .stdout(predicate::str::contains(
+        "The DBC was verified successfully against the network contacts.",
+    ))
+    .success();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn wallet_inspect_should_not_reveal_amount_of_owned_dbc_without_secret_key() -> Result<()> {
+    let config_dir = use_isolated_safe_config_dir()?;
+    safe_cmd(&config_dir, ["wallet", "create"], Some(0))?;
+    let tmp_data_dir = assert_fs::TempDir::new()?;
+    let (dbc_file_path, _, _) = get_owned_dbc_on_file(&tmp_data_dir).await?;
+
+    safe_cmd(
+        &config_dir,
+        ["wallet", "inspect", &dbc_file_path.display().to_string()],
+        Some(0),
+    )?
+    .assert()
+    .stdout(predicate::str::contains(
+        "Amount: unknown (the secret key is required to reveal it)",
+    ))
+    .success();
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn wallet_pay_should_reissue_a_dbc_per_payment() -> Result<()> {
+    let config_dir = use_isolated_safe_config_dir()?;
+    let json_output = safe_cmd_stdout(&config_dir, ["wallet", "create", "--json"], Some(0))?;
+    let wallet_xorurl = parse_wallet_create_output(&json_output)?;
+    let tmp_data_dir = assert_fs::TempDir::new()?;
+    let (dbc_file_path, _, _) = get_bearer_dbc_on_file(&tmp_data_dir).await?;
+
+    safe_cmd(
+        &config_dir,
+        [
+            "wallet",
+            "deposit",
+            "--dbc",
+            &dbc_file_path.display().to_string(),
+            &wallet_xorurl,
+        ],
+        Some(0),
+    )?;
+
+    let alice_pk = bls::SecretKey::random().public_key().to_hex();
+    let bob_pk = bls::SecretKey::random().public_key().to_hex();
+    let payments_file = tmp_data_dir.child("payments.csv");
+    payments_file.write_str(&format!(
+        "owner,amount,file\n{},0.1,alice.dbc\n{},0.2,bob.dbc\n",
+        alice_pk, bob_pk
+    ))?;
+    let output_dir = tmp_data_dir.child("payments");
+
+    safe_cmd(
+        &config_dir,
+        [
+            "wallet",
+            "pay",
+            "--from",
+            &wallet_xorurl,
+            "--batch",
+            &payments_file.display().to_string(),
+            "--output-dir",
+            &output_dir.display().to_string(),
+        ],
+        Some(0),
+    )?
+    .assert()
+    .stdout(predicate::str::contains(format!(
+        "Reissued 2 DBC/s from wallet at \"{}\":",
+        wallet_xorurl
+    )))
+    .stdout(predicate::str::contains("deposited with name 'change-dbc-"))
+    .success();
+
+    output_dir
+        .child("alice.dbc")
+        .assert(predicate::path::exists());
+    output_dir
+        .child("bob.dbc")
+        .assert(predicate::path::exists());
+
+    Ok(())
+}
@@ -14,7 +14,11 @@
 //! # Ok(())
 //! # }
 //! ```
-use crate::{sessions::Session, Client, Error, DEFAULT_NETWORK_CONTACTS_FILE_NAME};
+use super::{DiskChunkCache, DiskRegisterWal, RegisterWriteAheadLog};
+use crate::{
+    sessions::{QueryResponsePolicy, Session},
+    Client, Error, DEFAULT_NETWORK_CONTACTS_FILE_NAME,
+};
 
 use qp2p::Config as Qp2pConfig;
 use sn_dbc::Owner;
@@ -36,6 +40,16 @@ pub const ENV_MAX_BACKOFF_INTERVAL: &str = "SN_MAX_BACKOFF_INTERVAL";
 pub const ENV_CMD_TIMEOUT: &str = "SN_CMD_TIMEOUT";
 /// Environment variable used to convert into [`ClientBuilder::cmd_ack_wait`] (seconds)
 pub const ENV_AE_WAIT: &str = "SN_AE_WAIT";
+/// Environment variable used to convert into [`ClientBuilder::chunk_cache_size`] (bytes)
+pub const ENV_CHUNK_CACHE_SIZE: &str = "SN_CHUNK_CACHE_SIZE";
+/// Environment variable used to convert into [`ClientBuilder::query_response_policy`]
+/// (first-valid, majority or merge-all)
+pub const ENV_QUERY_RESPONSE_POLICY: &str = "SN_QUERY_RESPONSE_POLICY";
+/// Environment variable used to convert into [`ClientBuilder::merge_register_replicas`]
+/// (true or false)
+pub const ENV_MERGE_REGISTER_REPLICAS: &str = "SN_MERGE_REGISTER_REPLICAS";
+/// Environment variable used to convert into [`ClientBuilder::register_wal`] (true or false)
+pub const ENV_REGISTER_WAL: &str = "SN_REGISTER_WAL";
 
 /// Bind by default to all network interfaces on a OS assigned port
 pub const DEFAULT_LOCAL_ADDR: (Ipv4Addr, u16) = (Ipv4Addr::UNSPECIFIED, 0);
@@ -62,6 +76,12 @@ pub struct ClientBuilder {
     cmd_timeout: Option<Duration>,
     cmd_ack_wait: Option<Duration>,
     network_contacts: Option<SectionTree>,
+    chunk_cache_size: Option<u64>,
+    chunk_cache_dir: Option<PathBuf>,
+    query_response_policy: Option<QueryResponsePolicy>,
+    merge_register_replicas: Option<bool>,
+    register_wal: Option<bool>,
+    register_wal_dir: Option<PathBuf>,
 }
 
 impl ClientBuilder {
@@ -127,11 +147,62 @@ impl ClientBuilder {
         self
     }
 
+    /// Maximum size in bytes of the persistent chunk cache, which keeps the chunks retrieved
+    /// from the network on disk so they can be read again by this or any other client using the
+    /// same cache directory. The least recently used chunks are evicted once the size is exceeded.
+    /// The persistent chunk cache is disabled unless a size greater than zero is set.
+    pub fn chunk_cache_size(mut self, max_size: impl Into<Option<u64>>) -> Self {
+        self.chunk_cache_size = max_size.into();
+        self
+    }
+
+    /// Directory where the persistent chunk cache is kept, see [`Self::chunk_cache_size()`]
+    pub fn chunk_cache_dir(mut self, dir: impl Into<Option<PathBuf>>) -> Self {
+        self.chunk_cache_dir = dir.into();
+        self
+    }
+
+    /// How the responses to queries received from several nodes are validated,
+    /// see [`QueryResponsePolicy`]
+    pub fn query_response_policy(mut self, policy: impl Into<Option<QueryResponsePolicy>>) -> Self {
+        self.query_response_policy = policy.into();
+        self
+    }
+
+    /// Whether Registers are retrieved from all the Adults holding a replica, and merged,
+    /// rather than from a single one, see [`Client::get_register_from_all_replicas()`]
+    pub fn merge_register_replicas(mut self, merge: impl Into<Option<bool>>) -> Self {
+        self.merge_register_replicas = merge.into();
+        self
+    }
+
+    /// Whether the Register ops are recorded in a write-ahead log on disk before being published,
+    /// so those which cannot be published, e.g. while the network is unreachable, are kept
+    /// pending, and published once the client connects again, or with
+    /// [`Client::flush_register_ops()`]. The log is kept at a fixed location, see
+    /// [`Self::register_wal_dir()`], so the ops are published by the next client using it
+    /// whatever its keypair, as each of them is signed by its author.
+    pub fn register_wal(mut self, enabled: impl Into<Option<bool>>) -> Self {
+        self.register_wal = enabled.into();
+        self
+    }
+
+    /// Directory where the Register write-ahead log is kept, see [`Self::register_wal()`].
+    /// It's `$HOME/.safe/client/register_wal` if not set.
+    pub fn register_wal_dir(mut self, dir: impl Into<Option<PathBuf>>) -> Self {
+        self.register_wal_dir = dir.into();
+        self
+    }
+
     /// Read options from environment variables:
     /// - [`Self::query_timeout()`] from [`ENV_QUERY_TIMEOUT`]
     /// - [`Self::max_backoff_interval()`] from [`ENV_MAX_BACKOFF_INTERVAL`]
     /// - [`Self::cmd_timeout()`] from [`ENV_CMD_TIMEOUT`]
     /// - [`Self::cmd_ack_wait()`] from [`ENV_AE_WAIT`]
+    /// - [`Self::chunk_cache_size()`] from [`ENV_CHUNK_CACHE_SIZE`]
+    /// - [`Self::query_response_policy()`] from [`ENV_QUERY_RESPONSE_POLICY`]
+    /// - [`Self::merge_register_replicas()`] from [`ENV_MERGE_REGISTER_REPLICAS`]
+    /// - [`Self::register_wal()`] from [`ENV_REGISTER_WAL`]
     pub fn from_env(mut self) -> Self {
         if let Ok(Some(v)) = env_parse(ENV_QUERY_TIMEOUT) {
             self.query_timeout = Some(Duration::from_secs(v));
@@ -145,6 +216,18 @@ impl ClientBuilder {
         if let Ok(Some(v)) = env_parse(ENV_AE_WAIT) {
             self.cmd_ack_wait = Some(Duration::from_secs(v));
         }
+        if let Ok(Some(v)) = env_parse(ENV_CHUNK_CACHE_SIZE) {
+            self.chunk_cache_size = Some(v);
+        }
+        if let Ok(Some(v)) = env_parse(ENV_QUERY_RESPONSE_POLICY) {
+            self.query_response_policy = Some(v);
+        }
+        if let Ok(Some(v)) = env_parse(ENV_MERGE_REGISTER_REPLICAS) {
+            self.merge_register_replicas = Some(v);
+        }
+        if let Ok(Some(v)) = env_parse(ENV_REGISTER_WAL) {
+            self.register_wal = Some(v);
+        }
 
         self
     }
@@ -157,7 +240,13 @@ impl ClientBuilder {
     /// - `[Self::max_backoff_interval`] defaults to [`DEFAULT_MAX_QUERY_CMD_BACKOFF_INTERVAL`]
     /// - [`qp2p::Config`] will default to it's [`Default`] impl
     /// - Network contacts file will be read from a standard location
+    /// - Persistent chunk cache is disabled, its directory defaults to a standard location
+    /// - `[Self::query_response_policy]` defaults to [`QueryResponsePolicy::Majority`]
+    /// - `[Self::merge_register_replicas]` defaults to `false`
+    /// - Register write-ahead log is disabled, its directory defaults to a standard location
     pub async fn build(self) -> Result<Client, Error> {
+        let register_wal = self.open_register_wal()?;
+
         let max_backoff_interval = self
             .max_backoff_interval
             .unwrap_or(DEFAULT_MAX_QUERY_CMD_BACKOFF_INTERVAL);
@@ -189,6 +278,7 @@ impl ClientBuilder {
             self.local_addr
                 .unwrap_or_else(|| SocketAddr::from(DEFAULT_LOCAL_ADDR)),
             network_contacts,
+            self.query_response_policy.unwrap_or_default(),
         )?;
 
         let keypair = self.keypair.unwrap_or_else(Keypair::new_ed25519);
@@ -196,6 +286,28 @@ impl ClientBuilder {
             .dbc_owner
             .unwrap_or_else(|| Owner::from_random_secret_key(&mut rand::thread_rng()));
 
+        let disk_chunk_cache = match self.chunk_cache_size {
+            Some(max_size) if max_size > 0 => {
+                let dir = match self.chunk_cache_dir {
+                    Some(dir) => dir,
+                    None => default_chunk_cache_path()?,
+                };
+                // loading it scans the whole directory, so keep it off the async runtime
+                let cache = tokio::task::spawn_blocking({
+                    let dir = dir.clone();
+                    move || DiskChunkCache::open(&dir, max_size)
+                })
+                .await
+                .map_err(|err| err.to_string())
+                .and_then(|opened| opened.map_err(|err| err.to_string()))
+                .map_err(|err| {
+                    Error::ChunkCache(format!("cannot open {}: {err}", dir.display()))
+                })?;
+                Some(cache)
+            }
+            _ => None,
+        };
+
         let client = Client {
             keypair,
             dbc_owner,
@@ -204,11 +316,46 @@ impl ClientBuilder {
             max_backoff_interval,
             cmd_timeout,
             chunks_cache: Arc::new(RwLock::new(Default::default())),
+            disk_chunk_cache,
+            merge_register_replicas: self.merge_register_replicas.unwrap_or(false),
+            register_wal,
         };
         client.connect().await?;
 
+        // publish the Register ops left pending, e.g. by a previous run which lost connectivity
+        if let Err(err) = client.flush_register_ops().await {
+            warn!("Failed to publish pending Register ops: {err:?}");
+        }
+
         Ok(client)
     }
+
+    /// Register operations pending in the write-ahead log on disk, see [`Self::register_wal()`],
+    /// in the order they were recorded. They are read without connecting to the network.
+    /// It's always empty if the log isn't enabled.
+    pub fn pending_register_ops(&self) -> Result<RegisterWriteAheadLog, Error> {
+        match self.open_register_wal()? {
+            Some(disk_wal) => Ok(disk_wal
+                .pending()?
+                .into_iter()
+                .map(|(_, cmd)| cmd)
+                .collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    fn open_register_wal(&self) -> Result<Option<DiskRegisterWal>, Error> {
+        if !self.register_wal.unwrap_or(false) {
+            return Ok(None);
+        }
+        let dir = match &self.register_wal_dir {
+            Some(dir) => dir.clone(),
+            None => default_register_wal_path()?,
+        };
+        let wal = DiskRegisterWal::open(&dir)
+            .map_err(|err| Error::RegisterWal(format!("cannot open {}: {err}", dir.display())))?;
+        Ok(Some(wal))
+    }
 }
 
 /// Parse environment variable. Returns `Ok(None)` if environment variable isn't set.
@@ -232,5 +379,48 @@ fn default_network_contacts_path() -> Result<PathBuf, Error> {
     Ok(path)
 }
 
+fn default_chunk_cache_path() -> Result<PathBuf, Error> {
+    // Use `$HOME/.safe/client/chunks_cache` directory
+    let path = dirs_next::home_dir()
+        .ok_or_else(|| Error::ChunkCache("Could not read user's home directory".to_string()))?
+        .join(".safe")
+        .join("client")
+        .join("chunks_cache");
+
+    Ok(path)
+}
+
+fn default_register_wal_path() -> Result<PathBuf, Error> {
+    // Use `$HOME/.safe/client/register_wal` directory
+    let path = dirs_next::home_dir()
+        .ok_or_else(|| Error::RegisterWal("Could not read user's home directory".to_string()))?
+        .join(".safe")
+        .join("client")
+        .join("register_wal");
+
+    Ok(path)
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::{ClientBuilder, DiskRegisterWal};
+    use sn_interface::{
+        messaging::data::DataCmd,
+        types::{utils::random_bytes, Chunk},
+    };
+
+    use eyre::Result;
+
+    #[test]
+    fn pending_register_ops_are_read_without_connecting() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cmd = DataCmd::StoreChunk(Chunk::new(random_bytes(100)));
+        DiskRegisterWal::open(dir.path())?.append(std::slice::from_ref(&cmd))?;
+
+        let builder = ClientBuilder::new().register_wal_dir(dir.path().to_path_buf());
+        assert!(builder.pending_register_ops()?.is_empty());
+        let builder = builder.register_wal(true);
+        assert_eq!(builder.pending_register_ops()?, vec![cmd]);
+        Ok(())
+    }
+}
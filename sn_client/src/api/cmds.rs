@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::Client;
-use crate::{Error, Result};
+use crate::{Error, ErrorMsg, Result};
 
 use sn_interface::{
     messaging::{
@@ -18,8 +18,16 @@ use sn_interface::{
 };
 
 use bytes::Bytes;
+use futures::future::{join_all, try_join_all};
+use std::collections::BTreeMap;
 use xor_name::XorName;
 
+pub use sn_interface::messaging::data::CMD_BATCH_MAX_SIZE;
+
+/// Maximum number of times the operations of a batch are sent again, to the section matching
+/// their data, after being sent to a section not handling it, see [`Client::send_cmd_batch`]
+pub const MAX_BATCH_RESENDS: usize = 3;
+
 impl Client {
     /// Sign data using the client keypair
     pub fn sign(&self, data: &[u8]) -> Signature {
@@ -82,4 +90,129 @@ impl Client {
 
         res
     }
+
+    /// Send several `DataCmd`s to the network, returning the result of each of them,
+    /// in the same order.
+    ///
+    /// Rather than sending each cmd in its own message, the chunk and Register cmds for the same
+    /// section are bundled in batches of at most [`CMD_BATCH_MAX_SIZE`] operations, each sent in a
+    /// single message and acknowledged per operation. Batches for different sections are sent
+    /// concurrently, and those for the same section in order, as are the operations they contain.
+    /// Any other cmd is sent on its own.
+    ///
+    /// The operations sent to a section not handling their data, e.g. as it split, are sent
+    /// again to the section matching their data, up to [`MAX_BATCH_RESENDS`] times.
+    ///
+    /// An error is returned if any of the batches cannot be sent at all, e.g. if the Elders
+    /// could not be reached, as the results of its operations are then unknown.
+    #[instrument(skip_all, level = "debug", name = "client-api send cmd batch")]
+    pub async fn send_cmd_batch(&self, cmds: Vec<DataCmd>) -> Result<Vec<Result<()>>> {
+        debug!("Attempting batch of {} cmds", cmds.len());
+
+        let mut results = BTreeMap::new();
+        let mut to_send = (0..cmds.len()).collect::<Vec<_>>();
+        let mut resends = 0;
+        while !to_send.is_empty() {
+            let sending = to_send
+                .drain(..)
+                .map(|index| (index, cmds[index].clone()))
+                .collect();
+            for (index, result) in self.send_cmds_by_section(sending).await? {
+                match result {
+                    Err(Error::CmdError {
+                        source: ErrorMsg::DataNotInSection { .. },
+                        ..
+                    }) if resends < MAX_BATCH_RESENDS => to_send.push(index),
+                    result => {
+                        let _ = results.insert(index, result);
+                    }
+                }
+            }
+            if !to_send.is_empty() {
+                resends += 1;
+                debug!(
+                    "Sending {} cmds again to the section matching their data",
+                    to_send.len()
+                );
+            }
+        }
+
+        Ok(results.into_values().collect())
+    }
+
+    // Send the cmds bundled in batches per section, returning the result of each of them
+    // along with its index
+    async fn send_cmds_by_section(
+        &self,
+        cmds: Vec<(usize, DataCmd)>,
+    ) -> Result<Vec<(usize, Result<()>)>> {
+        let mut cmds_by_section = BTreeMap::<_, Vec<_>>::new();
+        let mut others = vec![];
+        for (index, cmd) in cmds {
+            match cmd {
+                DataCmd::StoreChunk(_) | DataCmd::Register(_) => {
+                    let section_pk = self.session.dst_section_key(cmd.dst_name()).await?;
+                    cmds_by_section
+                        .entry(section_pk)
+                        .or_default()
+                        .push((index, cmd));
+                }
+                other => others.push((index, other)),
+            }
+        }
+
+        let section_tasks = cmds_by_section.into_values().map(|cmds| async move {
+            let mut results = vec![];
+            for batch in cmds.chunks(CMD_BATCH_MAX_SIZE) {
+                let (indexes, batch): (Vec<_>, Vec<_>) = batch.iter().cloned().unzip();
+                let batch_results = self.send_batch(batch).await?;
+                results.extend(indexes.into_iter().zip(batch_results));
+            }
+            Ok::<_, Error>(results)
+        });
+        let other_tasks = others
+            .into_iter()
+            .map(|(index, cmd)| async move { (index, self.send_cmd(cmd).await) });
+
+        let (section_results, other_results) =
+            futures::join!(try_join_all(section_tasks), join_all(other_tasks));
+
+        Ok(section_results?
+            .into_iter()
+            .flatten()
+            .chain(other_results)
+            .collect())
+    }
+
+    // Send the cmds in a single batch cmd, returning the result of each of them
+    async fn send_batch(&self, cmds: Vec<DataCmd>) -> Result<Vec<Result<()>>> {
+        let items = cmds.len();
+        let dst_address = cmds.first().map(DataCmd::dst_name).unwrap_or_default();
+
+        let serialised_cmd = {
+            let msg = ClientMsg::Cmd(DataCmd::Batch(cmds));
+            WireMsg::serialize_msg_payload(&msg)?
+        };
+        let auth = ClientAuth {
+            public_key: self.public_key(),
+            signature: self.sign(&serialised_cmd),
+        };
+
+        let results = tokio::time::timeout(self.cmd_timeout, async {
+            self.session
+                .send_cmd_batch(dst_address, auth, serialised_cmd, items)
+                .await
+        })
+        .await
+        .map_err(|_| Error::CmdAckValidationTimeout {
+            elapsed: self.cmd_timeout,
+            dst_address,
+        })??;
+
+        debug!(
+            "{} of the {items} cmds of batch to {dst_address:?} sent okay",
+            results.iter().filter(|result| result.is_ok()).count()
+        );
+        Ok(results)
+    }
 }
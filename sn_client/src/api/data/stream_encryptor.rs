@@ -0,0 +1,348 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{pac_man::pack, to_chunk, SmallFile};
+use crate::{api::CHUNKS_BATCH_MAX_SIZE, Client, Result};
+use sn_interface::types::Chunk;
+
+use bytes::{Bytes, BytesMut};
+use self_encryption::{ChunkInfo, DataMap, MAX_CHUNK_SIZE, MIN_CHUNK_SIZE, MIN_ENCRYPTABLE_BYTES};
+use std::{collections::VecDeque, fmt, ops::Range};
+use tokio::io::{AsyncRead, AsyncReadExt};
+use xor_name::XorName;
+
+// Any content beyond this many bytes ahead of the current position means the chunk at the
+// current position is neither one of the last two chunks nor part of a file smaller than
+// three chunks, and therefore its size (MAX_CHUNK_SIZE) doesn't depend on the total file size.
+const LOOKAHEAD_SIZE: usize = 3 * MAX_CHUNK_SIZE;
+
+/// Output of a [`StreamEncryptor`].
+#[derive(Debug)]
+pub enum EncryptedBatch {
+    /// A batch of at most [`CHUNKS_BATCH_MAX_SIZE`] chunks ready to be stored on the network.
+    Chunks(Vec<Chunk>),
+    /// All the content was encrypted and every chunk has been output already.
+    /// The address is that of the chunk the content can be retrieved from.
+    Done(XorName),
+}
+
+// Clear text content of a chunk, pending to be encrypted.
+#[derive(Clone)]
+struct RawChunk {
+    index: usize,
+    data: Bytes,
+}
+
+/// Self-encrypts the content read from an [`AsyncRead`] incrementally, outputting
+/// the resulting chunks in batches as soon as they are produced.
+///
+/// Only a few chunks are kept in memory at any time, regardless of the size of the content.
+/// The chunks produced, as well as the resulting address, are the same as those obtained with
+/// [`Client::chunk_bytes`] for the same content.
+///
+/// The `self_encryption` crate can only encrypt the whole content at once, but the key and pad
+/// of a chunk only depend on the content of the two chunks preceding it, wrapping around for the
+/// first two. Thus the chunks are encrypted with it in windows, each made of the two chunks
+/// preceding those to be encrypted followed by them, as long as the sizes of the chunks of a
+/// window are those the whole content is split into, the resulting chunks being the same.
+pub struct StreamEncryptor<R> {
+    reader: R,
+    // content read but not yet split into chunks
+    buffer: BytesMut,
+    // number of bytes already split into chunks
+    consumed: usize,
+    eof: bool,
+    // number of chunks split so far
+    chunks_count: usize,
+    // the first two chunks can only be encrypted once the last two are known
+    head: Vec<RawChunk>,
+    // the two chunks preceding the pending ones, once some chunks were encrypted
+    preceding: Vec<RawChunk>,
+    pending: Vec<RawChunk>,
+    infos: Vec<ChunkInfo>,
+    ready: VecDeque<Chunk>,
+    address: Option<XorName>,
+}
+
+impl<R> fmt::Debug for StreamEncryptor<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StreamEncryptor")
+            .field("consumed", &self.consumed)
+            .field("chunks", &self.chunks_count)
+            .field("address", &self.address)
+            .finish()
+    }
+}
+
+impl<R: AsyncRead + Unpin> StreamEncryptor<R> {
+    /// Creates a new encryptor of the content to be read from the given reader.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buffer: BytesMut::new(),
+            consumed: 0,
+            eof: false,
+            chunks_count: 0,
+            head: vec![],
+            preceding: vec![],
+            pending: vec![],
+            infos: vec![],
+            ready: VecDeque::new(),
+            address: None,
+        }
+    }
+
+    /// Reads and encrypts content until the next batch of chunks is ready.
+    /// Once all the content has been output, [`EncryptedBatch::Done`] is returned.
+    pub async fn next_batch(&mut self) -> Result<EncryptedBatch> {
+        while self.ready.len() < CHUNKS_BATCH_MAX_SIZE && self.address.is_none() {
+            self.fill_buffer().await?;
+            if self.eof {
+                self.finish()?;
+            } else {
+                let data = self.buffer.split_to(MAX_CHUNK_SIZE).freeze();
+                self.split_chunk(data);
+                if self.pending.len() == CHUNKS_BATCH_MAX_SIZE {
+                    self.encrypt_pending()?;
+                }
+            }
+        }
+
+        if self.ready.is_empty() {
+            if let Some(address) = self.address {
+                return Ok(EncryptedBatch::Done(address));
+            }
+        }
+
+        let len = self.ready.len().min(CHUNKS_BATCH_MAX_SIZE);
+        Ok(EncryptedBatch::Chunks(self.ready.drain(..len).collect()))
+    }
+
+    // Reads until there is more than LOOKAHEAD_SIZE bytes in the buffer or the reader is exhausted.
+    async fn fill_buffer(&mut self) -> Result<()> {
+        while !self.eof && self.buffer.len() <= LOOKAHEAD_SIZE {
+            self.buffer.reserve(MAX_CHUNK_SIZE);
+            if self.reader.read_buf(&mut self.buffer).await? == 0 {
+                self.eof = true;
+            }
+
+            #[cfg(feature = "limit-client-upload-size")]
+            {
+                let size = self.consumed + self.buffer.len();
+                if size > super::LargeFile::CLIENT_UPLOAD_SIZE_LIMIT {
+                    return Err(crate::Error::UploadSizeLimitExceeded {
+                        size,
+                        limit: super::LargeFile::CLIENT_UPLOAD_SIZE_LIMIT,
+                    });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn split_chunk(&mut self, data: Bytes) {
+        let index = self.chunks_count;
+        self.chunks_count += 1;
+        self.consumed += data.len();
+
+        let chunk = RawChunk { index, data };
+        if index < 2 {
+            self.head.push(chunk);
+        } else {
+            self.pending.push(chunk);
+        }
+    }
+
+    // Encrypts what is left now that the total size is known, followed by the data map.
+    fn finish(&mut self) -> Result<()> {
+        let total_size = self.consumed + self.buffer.len();
+        if total_size < MIN_ENCRYPTABLE_BYTES {
+            let file = SmallFile::new(self.buffer.split().freeze())?;
+            let chunk = Client::package_small(file)?;
+            self.address = Some(*chunk.name());
+            self.ready.push_back(chunk);
+            return Ok(());
+        }
+
+        if self.infos.is_empty() {
+            // the whole content is still in memory, so it's encrypted at once
+            let mut content = BytesMut::with_capacity(total_size);
+            for chunk in self.head.drain(..).chain(self.pending.drain(..)) {
+                content.extend_from_slice(&chunk.data);
+            }
+            content.extend_from_slice(&self.buffer.split());
+            let (data_map, encrypted) = self_encryption::encrypt(content.freeze())?;
+            self.infos = data_map.infos();
+            self.ready
+                .extend(encrypted.into_iter().map(|chunk| to_chunk(chunk.content)));
+        } else {
+            let mut remaining = self.buffer.split().freeze();
+            for index in self.chunks_count..num_chunks(total_size) {
+                let data = remaining.split_to(chunk_size(total_size, index));
+                self.split_chunk(data);
+            }
+
+            // the first two chunks are encrypted as if they were followed by the last two
+            let mut window = std::mem::take(&mut self.head);
+            window.extend(self.pending[self.pending.len() - 2..].iter().cloned());
+            self.encrypt_pending()?;
+            self.encrypt_window(&window, 0..2)?;
+        }
+
+        let (address, data_map_chunks) =
+            pack(DataMap::new(std::mem::take(&mut self.infos)), vec![])?;
+        self.ready.extend(data_map_chunks);
+        self.address = Some(address);
+        Ok(())
+    }
+
+    // Encrypts the pending chunks, along with the two chunks preceding them,
+    // which are then replaced by the last two pending ones.
+    fn encrypt_pending(&mut self) -> Result<()> {
+        let mut window = if self.preceding.is_empty() {
+            self.head.clone()
+        } else {
+            std::mem::take(&mut self.preceding)
+        };
+        let preceding_count = window.len();
+        window.append(&mut self.pending);
+        self.encrypt_window(&window, preceding_count..window.len())?;
+        self.preceding = window.split_off(window.len() - 2);
+        Ok(())
+    }
+
+    // Encrypts the content of all the chunks of the window at once with `self_encryption`,
+    // keeping the resulting chunks found in the given range of the window.
+    fn encrypt_window(&mut self, window: &[RawChunk], keep: Range<usize>) -> Result<()> {
+        let mut content = BytesMut::new();
+        for chunk in window {
+            content.extend_from_slice(&chunk.data);
+        }
+        let (data_map, encrypted) = self_encryption::encrypt(content.freeze())?;
+
+        let infos = data_map.infos();
+        for encrypted in encrypted
+            .into_iter()
+            .filter(|chunk| keep.contains(&chunk.index))
+        {
+            let info = &infos[encrypted.index];
+            self.infos.push(ChunkInfo {
+                index: window[encrypted.index].index,
+                ..info.clone()
+            });
+            self.ready.push_back(to_chunk(encrypted.content));
+        }
+        Ok(())
+    }
+}
+
+// The following replicate how the `self_encryption` crate splits the content into chunks, to
+// split the last ones as it does once the total size is known.
+
+fn num_chunks(file_size: usize) -> usize {
+    if file_size < 3 * MAX_CHUNK_SIZE {
+        3
+    } else {
+        file_size.div_ceil(MAX_CHUNK_SIZE)
+    }
+}
+
+fn chunk_size(file_size: usize, index: usize) -> usize {
+    if file_size < 3 * MAX_CHUNK_SIZE {
+        return if index < 2 {
+            file_size / 3
+        } else {
+            file_size - (2 * (file_size / 3))
+        };
+    }
+
+    let total_chunks = num_chunks(file_size);
+    let remainder = file_size % MAX_CHUNK_SIZE;
+    let penultimate = total_chunks - 2 == index;
+    if index < total_chunks - 2 || remainder == 0 {
+        MAX_CHUNK_SIZE
+    } else if remainder < MIN_CHUNK_SIZE {
+        // the last chunk takes some of the content of the penultimate one
+        if penultimate {
+            MAX_CHUNK_SIZE - MIN_CHUNK_SIZE
+        } else {
+            MIN_CHUNK_SIZE + remainder
+        }
+    } else if penultimate {
+        MAX_CHUNK_SIZE
+    } else {
+        remainder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EncryptedBatch, StreamEncryptor};
+    use crate::{Client, Error};
+
+    use eyre::{bail, Result};
+    use self_encryption::{MAX_CHUNK_SIZE, MIN_CHUNK_SIZE, MIN_ENCRYPTABLE_BYTES};
+    use sn_interface::types::utils::random_bytes;
+
+    async fn stream_chunk(
+        bytes: &[u8],
+    ) -> Result<(xor_name::XorName, Vec<sn_interface::types::Chunk>)> {
+        let mut encryptor = StreamEncryptor::new(bytes);
+        let mut chunks = vec![];
+        loop {
+            match encryptor.next_batch().await? {
+                EncryptedBatch::Chunks(batch) => {
+                    assert!(!batch.is_empty());
+                    assert!(batch.len() <= super::CHUNKS_BATCH_MAX_SIZE);
+                    chunks.extend(batch);
+                }
+                EncryptedBatch::Done(address) => return Ok((address, chunks)),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn stream_encryption_matches_chunk_bytes() -> Result<()> {
+        for size in [
+            1,
+            MIN_ENCRYPTABLE_BYTES - 1,
+            MIN_ENCRYPTABLE_BYTES,
+            3 * MAX_CHUNK_SIZE - 1,
+            3 * MAX_CHUNK_SIZE,
+            4 * MAX_CHUNK_SIZE + MIN_CHUNK_SIZE / 2,
+            6 * MAX_CHUNK_SIZE + 3 * MIN_CHUNK_SIZE,
+            // large enough for the chunks to be encrypted in several windows
+            12 * MAX_CHUNK_SIZE + MIN_CHUNK_SIZE / 2,
+            13 * MAX_CHUNK_SIZE,
+        ] {
+            let bytes = random_bytes(size);
+            let (expected_address, mut expected_chunks) = Client::chunk_bytes(bytes.clone())?;
+            let (address, mut chunks) = stream_chunk(&bytes).await?;
+
+            assert_eq!(
+                address, expected_address,
+                "address mismatch for size {size}"
+            );
+            expected_chunks.sort_by_key(|chunk| *chunk.name());
+            chunks.sort_by_key(|chunk| *chunk.name());
+            assert_eq!(chunks, expected_chunks, "chunks mismatch for size {size}");
+        }
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn stream_encryption_of_empty_content_fails() -> Result<()> {
+        match stream_chunk(&[]).await {
+            Err(err) => match err.downcast_ref::<Error>() {
+                Some(Error::EmptyFileProvided) => Ok(()),
+                _ => bail!("unexpected error: {err:?}"),
+            },
+            Ok(_) => bail!("empty content shouldn't be encrypted"),
+        }
+    }
+}
@@ -0,0 +1,333 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::Result;
+use sn_interface::types::Chunk;
+
+use bytes::Bytes;
+use filetime::FileTime;
+use std::{
+    collections::HashMap,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::SystemTime,
+};
+use tempfile::NamedTempFile;
+use xor_name::XorName;
+
+#[derive(Debug)]
+struct CacheEntry {
+    size: u64,
+    // the higher the more recently used
+    last_used: u64,
+}
+
+#[derive(Debug, Default)]
+struct CacheState {
+    entries: HashMap<XorName, CacheEntry>,
+    total_size: u64,
+    next_use: u64,
+}
+
+/// Persistent cache of chunks, keeping each chunk in a file named after its address.
+///
+/// Chunks are immutable and their address is derived from their content, thus a cached
+/// chunk never goes stale, and it's verified against its address whenever read back.
+/// When the size of the cached chunks goes over the maximum size set, the least recently
+/// used chunks are evicted. Files' modification time is used to record their last use,
+/// so the same directory can be shared by different processes and runs.
+#[derive(Clone, Debug)]
+pub(crate) struct DiskChunkCache {
+    dir: PathBuf,
+    max_size: u64,
+    state: Arc<Mutex<CacheState>>,
+}
+
+impl DiskChunkCache {
+    /// Open the cache found in the given directory, creating it if it doesn't exist.
+    pub(crate) fn open(dir: &Path, max_size: u64) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+
+        let mut found = vec![];
+        for dir_entry in fs::read_dir(dir)? {
+            let path = dir_entry?.path();
+            // skip anything which is not a chunk, e.g. a chunk still being written
+            let name = match path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .and_then(name_from_file_name)
+            {
+                Some(name) => name,
+                None => continue,
+            };
+            let metadata = fs::metadata(&path)?;
+            let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            found.push((modified, name, metadata.len()));
+        }
+        found.sort();
+
+        let mut state = CacheState::default();
+        for (_, name, size) in found {
+            let _ = state.entries.insert(
+                name,
+                CacheEntry {
+                    size,
+                    last_used: state.next_use,
+                },
+            );
+            state.total_size += size;
+            state.next_use += 1;
+        }
+
+        debug!(
+            "Persistent chunk cache at {} loaded with {} chunks ({} bytes, max {} bytes)",
+            dir.display(),
+            state.entries.len(),
+            state.total_size,
+            max_size
+        );
+
+        let cache = Self {
+            dir: dir.to_path_buf(),
+            max_size,
+            state: Arc::new(Mutex::new(state)),
+        };
+        let evicted = cache.evict(&mut cache.lock());
+        cache.remove_files(&evicted);
+
+        Ok(cache)
+    }
+
+    /// Returns the chunk found at the given address, if it's cached and it's valid.
+    /// The file is read on a blocking thread, so the async runtime is never stalled by disk I/O.
+    pub(crate) async fn get(&self, name: &XorName) -> Option<Chunk> {
+        let cache = self.clone();
+        let name = *name;
+        tokio::task::spawn_blocking(move || cache.get_blocking(&name))
+            .await
+            .unwrap_or_else(|err| {
+                warn!("Failed to read chunk {name:?} from persistent cache: {err:?}");
+                None
+            })
+    }
+
+    /// Adds a chunk to the cache, evicting the least recently used chunks if needed.
+    /// Any error is logged and ignored, as the cache is only an optimisation.
+    pub(crate) async fn insert(&self, chunk: &Chunk) {
+        let cache = self.clone();
+        let chunk = chunk.clone();
+        if let Err(err) = tokio::task::spawn_blocking(move || cache.insert_blocking(&chunk)).await {
+            warn!("Failed to store chunk in persistent cache: {err:?}");
+        }
+    }
+
+    // The state is only locked to update the bookkeeping, never while accessing the disk.
+    fn get_blocking(&self, name: &XorName) -> Option<Chunk> {
+        // the chunk may have been added, or evicted, by another process since we loaded the cache
+        let path = self.chunk_path(name);
+        let chunk = match fs::read(&path) {
+            Ok(content) => Chunk::new(Bytes::from(content)),
+            Err(_) => {
+                let mut state = self.lock();
+                if let Some(entry) = state.entries.remove(name) {
+                    state.total_size -= entry.size;
+                }
+                return None;
+            }
+        };
+
+        if chunk.name() != name {
+            warn!("Corrupted chunk {name:?} found in persistent cache, removing it");
+            self.remove_entry(&mut self.lock(), name);
+            self.remove_files(&[*name]);
+            return None;
+        }
+
+        // record its use for other processes and subsequent runs
+        if let Err(err) = filetime::set_file_mtime(&path, FileTime::now()) {
+            trace!("Failed to update last use of chunk {name:?} in persistent cache: {err:?}");
+        }
+
+        let evicted = {
+            let mut state = self.lock();
+            let last_used = state.next_use;
+            state.next_use += 1;
+            let size = chunk.value().len() as u64;
+            if let Some(previous) = state.entries.insert(*name, CacheEntry { size, last_used }) {
+                state.total_size -= previous.size;
+            }
+            state.total_size += size;
+            self.evict(&mut state)
+        };
+        self.remove_files(&evicted);
+
+        Some(chunk)
+    }
+
+    fn insert_blocking(&self, chunk: &Chunk) {
+        let size = chunk.value().len() as u64;
+        if size > self.max_size || self.lock().entries.contains_key(chunk.name()) {
+            return;
+        }
+
+        if let Err(err) = self.write_chunk(chunk) {
+            warn!(
+                "Failed to store chunk {:?} in persistent cache: {err:?}",
+                chunk.name()
+            );
+            return;
+        }
+
+        let evicted = {
+            let mut state = self.lock();
+            let last_used = state.next_use;
+            state.next_use += 1;
+            if let Some(previous) = state
+                .entries
+                .insert(*chunk.name(), CacheEntry { size, last_used })
+            {
+                // it was inserted concurrently while we were writing it
+                state.total_size -= previous.size;
+            }
+            state.total_size += size;
+            self.evict(&mut state)
+        };
+        self.remove_files(&evicted);
+    }
+
+    fn write_chunk(&self, chunk: &Chunk) -> Result<()> {
+        // write it to a temporary file first so a chunk file is never seen
+        // partially written, even by other processes sharing the cache
+        let mut file = NamedTempFile::new_in(&self.dir)?;
+        file.write_all(chunk.value())?;
+        let _ = file
+            .persist(self.chunk_path(chunk.name()))
+            .map_err(|err| err.error)?;
+
+        Ok(())
+    }
+
+    // Drops the least recently used chunks until the total size is within the limit,
+    // returning their names so their files can be removed once the state is unlocked.
+    fn evict(&self, state: &mut CacheState) -> Vec<XorName> {
+        let mut evicted = vec![];
+        while state.total_size > self.max_size {
+            let oldest = state
+                .entries
+                .iter()
+                .min_by_key(|(_, entry)| entry.last_used)
+                .map(|(name, _)| *name);
+
+            match oldest {
+                Some(name) => {
+                    trace!("Evicting chunk {name:?} from persistent cache");
+                    self.remove_entry(state, &name);
+                    evicted.push(name);
+                }
+                None => break,
+            }
+        }
+        evicted
+    }
+
+    fn remove_entry(&self, state: &mut CacheState, name: &XorName) {
+        if let Some(entry) = state.entries.remove(name) {
+            state.total_size -= entry.size;
+        }
+    }
+
+    fn remove_files(&self, names: &[XorName]) {
+        for name in names {
+            if let Err(err) = fs::remove_file(self.chunk_path(name)) {
+                trace!("Failed to remove chunk {name:?} from persistent cache: {err:?}");
+            }
+        }
+    }
+
+    fn chunk_path(&self, name: &XorName) -> PathBuf {
+        self.dir.join(hex::encode(name))
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CacheState> {
+        // the state is never left inconsistent by a panicking holder, so it's safe to recover it
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+fn name_from_file_name(file_name: &str) -> Option<XorName> {
+    let bytes = hex::decode(file_name).ok()?;
+    Some(XorName(bytes.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiskChunkCache;
+    use sn_interface::types::{utils::random_bytes, Chunk};
+
+    use eyre::Result;
+    use std::fs;
+
+    #[tokio::test]
+    async fn disk_chunk_cache_should_survive_reopening() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let chunk = Chunk::new(random_bytes(100));
+
+        let cache = DiskChunkCache::open(dir.path(), 1024)?;
+        assert!(cache.get(chunk.name()).await.is_none());
+        cache.insert(&chunk).await;
+        assert_eq!(cache.get(chunk.name()).await, Some(chunk.clone()));
+
+        let reopened = DiskChunkCache::open(dir.path(), 1024)?;
+        assert_eq!(reopened.get(chunk.name()).await, Some(chunk));
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disk_chunk_cache_should_evict_least_recently_used() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let chunks = (0..3)
+            .map(|_| Chunk::new(random_bytes(400)))
+            .collect::<Vec<_>>();
+
+        let cache = DiskChunkCache::open(dir.path(), 1000)?;
+        cache.insert(&chunks[0]).await;
+        cache.insert(&chunks[1]).await;
+        // using the first chunk makes the second one the least recently used
+        assert!(cache.get(chunks[0].name()).await.is_some());
+        cache.insert(&chunks[2]).await;
+
+        assert!(cache.get(chunks[0].name()).await.is_some());
+        assert!(cache.get(chunks[1].name()).await.is_none());
+        assert!(cache.get(chunks[2].name()).await.is_some());
+        assert_eq!(fs::read_dir(dir.path())?.count(), 2);
+
+        // a smaller budget when reopening evicts chunks straight away
+        let reopened = DiskChunkCache::open(dir.path(), 500)?;
+        assert_eq!(fs::read_dir(dir.path())?.count(), 1);
+        assert_eq!(reopened.lock().entries.len(), 1);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn disk_chunk_cache_should_discard_corrupted_chunks() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let chunk = Chunk::new(random_bytes(100));
+
+        let cache = DiskChunkCache::open(dir.path(), 1024)?;
+        cache.insert(&chunk).await;
+        fs::write(cache.chunk_path(chunk.name()), b"not the original content")?;
+
+        assert!(cache.get(chunk.name()).await.is_none());
+        assert!(!cache.chunk_path(chunk.name()).exists());
+        Ok(())
+    }
+}
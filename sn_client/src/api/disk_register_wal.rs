@@ -0,0 +1,200 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::Result;
+use sn_interface::messaging::data::DataCmd;
+
+use rand::Rng;
+use std::{
+    fs, io,
+    io::Write,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tempfile::NamedTempFile;
+
+// Extension of the files holding a pending operation
+const OP_FILE_EXTENSION: &str = "op";
+
+/// Write-ahead log of Register operations kept on disk until they are published to the network,
+/// so they are not lost if the client can't reach the network, or the process is restarted.
+///
+/// Each operation is kept in its own file, named after the time it was recorded, so the
+/// operations are published in the order they were recorded, even by different processes
+/// and runs. Publishing an operation more than once is harmless, as Registers are CRDTs.
+#[derive(Clone, Debug)]
+pub(crate) struct DiskRegisterWal {
+    dir: PathBuf,
+}
+
+impl DiskRegisterWal {
+    /// Open the log found in the given directory, creating it if it doesn't exist.
+    pub(crate) fn open(dir: &Path) -> Result<Self> {
+        fs::create_dir_all(dir)?;
+        let wal = Self {
+            dir: dir.to_path_buf(),
+        };
+        debug!(
+            "Register WAL at {} loaded with {} pending ops",
+            dir.display(),
+            wal.op_files()?.len()
+        );
+
+        Ok(wal)
+    }
+
+    /// Records the operations, in order, after any already pending.
+    pub(crate) fn append(&self, cmds: &[DataCmd]) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default();
+        // never record them before the last pending op, e.g. on a coarse or adjusted clock
+        let last = self
+            .op_files()?
+            .last()
+            .and_then(|(id, _)| id.split('-').next()?.parse::<u128>().ok());
+        let nanos = match last {
+            Some(last) if last >= now => last + 1,
+            _ => now,
+        };
+        // the random suffix keeps ops recorded at the same time by other processes apart
+        let suffix: u32 = rand::thread_rng().gen();
+
+        for (index, cmd) in cmds.iter().enumerate() {
+            let file_name = format!("{nanos:020}-{index:06}-{suffix:08x}.{OP_FILE_EXTENSION}");
+            // write it to a temporary file first so an op file is never seen partially written
+            let mut file = NamedTempFile::new_in(&self.dir)?;
+            file.write_all(&bincode::serialize(cmd)?)?;
+            file.as_file().sync_all()?;
+            let _ = file
+                .persist(self.dir.join(file_name))
+                .map_err(|err| err.error)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the pending operations, in the order they were recorded, along with their ids.
+    pub(crate) fn pending(&self) -> Result<Vec<(String, DataCmd)>> {
+        let mut pending = vec![];
+        for (id, path) in self.op_files()? {
+            let bytes = match fs::read(&path) {
+                Ok(bytes) => bytes,
+                // it was published, and removed, by another process in the meantime
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err.into()),
+            };
+            match bincode::deserialize(&bytes) {
+                Ok(cmd) => pending.push((id, cmd)),
+                Err(err) => {
+                    warn!("Discarding corrupted op {id} found in Register WAL: {err:?}");
+                    self.remove(&id)?;
+                }
+            }
+        }
+
+        Ok(pending)
+    }
+
+    /// Removes the operation with the given id, once published or rejected by the network.
+    pub(crate) fn remove(&self, id: &str) -> Result<()> {
+        match fs::remove_file(self.dir.join(id)) {
+            Err(err) if err.kind() != io::ErrorKind::NotFound => Err(err.into()),
+            _ => Ok(()),
+        }
+    }
+
+    // Files of the pending ops, sorted by the time they were recorded
+    fn op_files(&self) -> Result<Vec<(String, PathBuf)>> {
+        let mut files = vec![];
+        for dir_entry in fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            // skip anything which is not an op, e.g. an op still being written
+            if path.extension().and_then(|ext| ext.to_str()) != Some(OP_FILE_EXTENSION) {
+                continue;
+            }
+            if let Some(id) = path.file_name().and_then(|file_name| file_name.to_str()) {
+                files.push((id.to_string(), path.clone()));
+            }
+        }
+        files.sort();
+
+        Ok(files)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DiskRegisterWal;
+    use sn_interface::{
+        messaging::data::DataCmd,
+        types::{utils::random_bytes, Chunk},
+    };
+
+    use eyre::Result;
+    use std::fs;
+
+    fn random_cmd() -> DataCmd {
+        DataCmd::StoreChunk(Chunk::new(random_bytes(100)))
+    }
+
+    #[test]
+    fn disk_register_wal_should_keep_ops_in_order_across_reopening() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cmds = (0..3).map(|_| random_cmd()).collect::<Vec<_>>();
+
+        let wal = DiskRegisterWal::open(dir.path())?;
+        assert!(wal.pending()?.is_empty());
+        wal.append(&cmds[..2])?;
+        wal.append(&cmds[2..])?;
+
+        let reopened = DiskRegisterWal::open(dir.path())?;
+        let pending = reopened.pending()?;
+        assert_eq!(
+            pending
+                .iter()
+                .map(|(_, cmd)| cmd.clone())
+                .collect::<Vec<_>>(),
+            cmds
+        );
+
+        reopened.remove(&pending[0].0)?;
+        // removing it again, e.g. by another process, is not an error
+        reopened.remove(&pending[0].0)?;
+        assert_eq!(
+            reopened
+                .pending()?
+                .into_iter()
+                .map(|(_, cmd)| cmd)
+                .collect::<Vec<_>>(),
+            cmds[1..]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn disk_register_wal_should_discard_corrupted_ops() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let cmd = random_cmd();
+
+        let wal = DiskRegisterWal::open(dir.path())?;
+        wal.append(std::slice::from_ref(&cmd))?;
+        wal.append(&[random_cmd()])?;
+        let (corrupted, _) = wal.pending()?.remove(1);
+        fs::write(dir.path().join(&corrupted), b"not an op")?;
+        // files other than ops, e.g. ops still being written, are ignored
+        fs::write(dir.path().join(".tmp-op"), b"not an op")?;
+
+        let pending = wal.pending()?;
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].1, cmd);
+        assert!(!dir.path().join(corrupted).exists());
+        Ok(())
+    }
+}
@@ -7,13 +7,13 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    data::{encrypt_large, to_chunk, LargeFile, SmallFile},
+    data::{encrypt_large, to_chunk, EncryptedBatch, LargeFile, SmallFile, StreamEncryptor},
     Client,
 };
 use crate::{api::data::DataMapLevel, Error, Result};
 
 use sn_interface::{
-    messaging::data::{DataCmd, DataQueryVariant, QueryResponse},
+    messaging::data::{DataCmd, DataQueryVariant, QueryResponse, CMD_BATCH_MAX_SIZE},
     types::{Chunk, ChunkAddress},
 };
 
@@ -22,12 +22,13 @@ use bytes::Bytes;
 use futures::future::join_all;
 use itertools::Itertools;
 use self_encryption::{self, ChunkInfo, DataMap, EncryptedChunk};
-use tokio::{task, time::sleep};
+use std::path::Path;
+use tokio::{fs::File, io::AsyncRead, task, time::sleep};
 use tracing::trace;
 use xor_name::XorName;
 
-// Maximum number of concurrent chunks to be uploaded/retrieved for a file
-const CHUNKS_BATCH_MAX_SIZE: usize = 5;
+/// Maximum number of concurrent chunks to be uploaded/retrieved for a file
+pub const CHUNKS_BATCH_MAX_SIZE: usize = 5;
 
 impl Client {
     #[instrument(skip(self), level = "debug")]
@@ -90,6 +91,15 @@ impl Client {
             return Ok(chunk.clone());
         }
 
+        // then check if we have it in our persistent cache, if enabled
+        if let Some(cache) = &self.disk_chunk_cache {
+            if let Some(chunk) = cache.get(name).await {
+                trace!("Chunk retrieved from persistent cache: {name:?}");
+                let _ = self.chunks_cache.write().await.insert(chunk.clone());
+                return Ok(chunk);
+            }
+        }
+
         let query = DataQueryVariant::GetChunk(ChunkAddress(*name));
         let res = self.send_query(query.clone()).await?;
 
@@ -100,6 +110,9 @@ impl Client {
             response => return Err(Error::UnexpectedQueryResponse { query, response }),
         }?;
 
+        if let Some(cache) = &self.disk_chunk_cache {
+            cache.insert(&chunk).await;
+        }
         let _ = self.chunks_cache.write().await.insert(chunk.clone());
 
         Ok(chunk)
@@ -128,7 +141,7 @@ impl Client {
 
     /// Packages a [`SmallFile`] and returns the resulting address and the chunk.
     /// Does not store anything to the network.
-    fn package_small(file: SmallFile) -> Result<Chunk> {
+    pub(crate) fn package_small(file: SmallFile) -> Result<Chunk> {
         let chunk = to_chunk(file.bytes());
         if chunk.value().len() >= self_encryption::MIN_ENCRYPTABLE_BYTES {
             return Err(Error::SmallFilePaddingNeeded(chunk.value().len()));
@@ -152,6 +165,35 @@ impl Client {
         self.upload_bytes(bytes, true).await
     }
 
+    /// Writes the content read from the given reader to the network in the form of immutable
+    /// chunks, optionally verifying each of them was stored.
+    ///
+    /// Unlike [`Client::upload`], the content doesn't need to be held in memory, as it is
+    /// self-encrypted incrementally while being read, with the resulting chunks being
+    /// uploaded in batches of at most [`CHUNKS_BATCH_MAX_SIZE`] as soon as they are produced.
+    #[instrument(skip_all, level = "debug")]
+    pub async fn upload_from_reader<R: AsyncRead + Unpin>(
+        &self,
+        reader: R,
+        verify: bool,
+    ) -> Result<XorName> {
+        let mut encryptor = StreamEncryptor::new(reader);
+        loop {
+            match encryptor.next_batch().await? {
+                EncryptedBatch::Chunks(chunks) => self.upload_chunks(&chunks, verify).await?,
+                EncryptedBatch::Done(address) => return Ok(address),
+            }
+        }
+    }
+
+    /// Writes the content of a local file to the network in the form of immutable chunks,
+    /// optionally verifying each of them was stored. See [`Client::upload_from_reader`].
+    #[instrument(skip(self), level = "debug")]
+    pub async fn upload_from_path(&self, path: &Path, verify: bool) -> Result<XorName> {
+        let file = File::open(path).await?;
+        self.upload_from_reader(file, verify).await
+    }
+
     /// Calculates a LargeFile's/SmallFile's address from self encrypted chunks,
     /// without storing them onto the network.
     #[instrument(skip(bytes), level = "debug")]
@@ -180,22 +222,42 @@ impl Client {
     #[instrument(skip_all, level = "trace")]
     async fn upload_large(&self, large: LargeFile, verify: bool) -> Result<XorName> {
         let (head_address, all_chunks) = Self::encrypt_large(large)?;
-        for next_batch in all_chunks.chunks(CHUNKS_BATCH_MAX_SIZE) {
+        self.upload_chunks(&all_chunks, verify).await?;
+
+        Ok(head_address)
+    }
+
+    /// Directly writes the given [`Chunk`]s to the network, in batches of at most
+    /// [`CMD_BATCH_MAX_SIZE`] concurrent uploads, optionally verifying each of them was stored.
+    /// The chunks of a batch which belong to the same section are sent in a single message,
+    /// see [`Client::send_cmd_batch`].
+    ///
+    /// This is useful together with [`Client::chunk_bytes`] when the caller needs to keep track of
+    /// which chunks have already been uploaded, e.g. to resume an interrupted upload.
+    #[instrument(skip_all, level = "trace")]
+    pub async fn upload_chunks(&self, chunks: &[Chunk], verify: bool) -> Result<()> {
+        for next_batch in chunks.chunks(CMD_BATCH_MAX_SIZE) {
             // Connect to all relevant elders before we fire off all msgs...
             self.session
                 .setup_connections_to_relevant_nodes(next_batch.iter().map(|c| *c.name()).collect())
                 .await?;
 
-            let tasks = next_batch.iter().cloned().map(|chunk| {
+            let cmds = next_batch
+                .iter()
+                .cloned()
+                .map(DataCmd::StoreChunk)
+                .collect();
+            for result in self.send_cmd_batch(cmds).await? {
+                result?;
+            }
+            if !verify {
+                continue;
+            }
+
+            let tasks = next_batch.iter().map(|chunk| {
                 let client_clone = self.clone();
-                task::spawn(async move {
-                    let chunk_addr = *chunk.address().name();
-                    client_clone.send_cmd(DataCmd::StoreChunk(chunk)).await?;
-                    if verify {
-                        client_clone.verify_chunk_is_stored(chunk_addr).await?;
-                    }
-                    Ok::<(), Error>(())
-                })
+                let chunk_addr = *chunk.address().name();
+                task::spawn(async move { client_clone.verify_chunk_is_stored(chunk_addr).await })
             });
 
             let respones = join_all(tasks)
@@ -210,7 +272,7 @@ impl Client {
             }
         }
 
-        Ok(head_address)
+        Ok(())
     }
 
     /// Directly writes a [`SmallFile`] to the network in the
@@ -332,7 +394,7 @@ impl Client {
     /// If the DataMapLevel is not the first level mapping directly to the user's contents,
     /// the process repeats itself until it obtains the first level DataMapLevel.
     #[instrument(skip_all, level = "trace")]
-    async fn unpack_chunk(&self, mut chunk: Chunk) -> Result<DataMap> {
+    pub(crate) async fn unpack_chunk(&self, mut chunk: Chunk) -> Result<DataMap> {
         loop {
             match deserialize(chunk.value())? {
                 DataMapLevel::First(data_map) => {
@@ -0,0 +1,341 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{Client, CHUNKS_BATCH_MAX_SIZE};
+use crate::Result;
+
+use bytes::Bytes;
+use futures::{
+    stream::{self, BoxStream},
+    Stream, StreamExt,
+};
+use self_encryption::{ChunkInfo, DataMap, EncryptedChunk};
+use std::{
+    fmt, io,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, AsyncSeek, ReadBuf};
+use xor_name::XorName;
+
+/// Default number of chunks a [`FileReader`] fetches ahead of the one currently being read.
+pub const DEFAULT_READ_AHEAD: usize = CHUNKS_BATCH_MAX_SIZE;
+
+// Where the content read by a FileReader comes from.
+#[derive(Clone)]
+enum Source {
+    // Content small enough to have been stored in a single chunk
+    Small(Bytes),
+    // Self-encrypted content, with the offset in the file where each chunk begins
+    Large {
+        data_map: DataMap,
+        infos: Vec<(u64, ChunkInfo)>,
+    },
+}
+
+/// Lazy reader of the content stored at a network address.
+///
+/// Chunks are only fetched and decrypted as the content is consumed, with at most
+/// `read_ahead` chunks being retrieved concurrently, so reading a file only requires
+/// memory for those chunks regardless of the file size.
+///
+/// The content can be consumed either as a [`Stream`] of [`Bytes`], or through the
+/// [`AsyncRead`]/[`AsyncSeek`] traits, e.g. to copy it into a local file with `tokio::io::copy`.
+pub struct FileReader {
+    client: Client,
+    source: Source,
+    size: u64,
+    position: u64,
+    read_ahead: usize,
+    stream: BoxStream<'static, Result<Bytes>>,
+    // bytes yielded by the stream but not yet read through AsyncRead
+    buffered: Bytes,
+    pending_seek: Option<u64>,
+}
+
+impl fmt::Debug for FileReader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FileReader")
+            .field("size", &self.size)
+            .field("position", &self.position)
+            .field("read_ahead", &self.read_ahead)
+            .finish()
+    }
+}
+
+impl Client {
+    /// Returns a [`FileReader`] for the content stored at the given address, which
+    /// fetches the chunks from the network lazily as the content is read.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn read_stream(&self, address: XorName) -> Result<FileReader> {
+        let chunk = self.get_chunk(&address).await?;
+
+        // first try to deserialize a LargeFile, if it fails we assume it's a SmallFile
+        let source = if let Ok(data_map) = self.unpack_chunk(chunk.clone()).await {
+            let mut offset = 0;
+            let infos = data_map
+                .infos()
+                .into_iter()
+                .map(|info| {
+                    let chunk_offset = offset;
+                    offset += info.src_size as u64;
+                    (chunk_offset, info)
+                })
+                .collect();
+            Source::Large { data_map, infos }
+        } else {
+            Source::Small(chunk.value().clone())
+        };
+
+        Ok(FileReader::new(self.clone(), source))
+    }
+}
+
+impl FileReader {
+    fn new(client: Client, source: Source) -> Self {
+        let size = match &source {
+            Source::Small(bytes) => bytes.len() as u64,
+            Source::Large { data_map, .. } => data_map.file_size() as u64,
+        };
+
+        let mut reader = Self {
+            client,
+            source,
+            size,
+            position: 0,
+            read_ahead: DEFAULT_READ_AHEAD,
+            stream: stream::empty().boxed(),
+            buffered: Bytes::new(),
+            pending_seek: None,
+        };
+        reader.reset_stream();
+        reader
+    }
+
+    /// Sets the maximum number of chunks to be fetched ahead of the one currently being read.
+    pub fn with_read_ahead(mut self, read_ahead: usize) -> Self {
+        self.read_ahead = read_ahead.max(1);
+        self.reset_stream();
+        self
+    }
+
+    /// Total size of the content in bytes.
+    pub fn size(&self) -> u64 {
+        self.size
+    }
+
+    /// Current position in the content, i.e. the offset of the next byte to be read.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Moves the reader to the given position, any chunk previously
+    /// fetched ahead is discarded. Positions past the end yield no content.
+    pub fn seek_to(&mut self, position: u64) {
+        self.position = position.min(self.size);
+        self.reset_stream();
+    }
+
+    // Rebuilds the stream of content pieces starting from the current position.
+    fn reset_stream(&mut self) {
+        self.buffered = Bytes::new();
+        let position = self.position;
+
+        self.stream = match &self.source {
+            Source::Small(bytes) => {
+                let bytes = bytes.slice(position as usize..);
+                stream::iter((!bytes.is_empty()).then_some(Ok(bytes))).boxed()
+            }
+            Source::Large { data_map, infos } => {
+                let pending = infos
+                    .iter()
+                    .filter(|(offset, info)| offset + info.src_size as u64 > position)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                let client = self.client.clone();
+                let data_map = data_map.clone();
+
+                stream::iter(pending)
+                    .map(move |(offset, info)| {
+                        let client = client.clone();
+                        let data_map = data_map.clone();
+                        async move {
+                            let skip = position.saturating_sub(offset) as usize;
+                            fetch_and_decrypt(&client, &data_map, info, skip).await
+                        }
+                    })
+                    .buffered(self.read_ahead)
+                    .boxed()
+            }
+        };
+    }
+}
+
+// Fetches a chunk from the network and decrypts it, skipping the first `skip` bytes of its content.
+async fn fetch_and_decrypt(
+    client: &Client,
+    data_map: &DataMap,
+    info: ChunkInfo,
+    skip: usize,
+) -> Result<Bytes> {
+    let chunk = client.get_chunk(&info.dst_hash).await.map_err(|err| {
+        warn!(
+            "Reading chunk {} from network, resulted in error {:?}.",
+            info.dst_hash, err
+        );
+        err
+    })?;
+
+    let encrypted_chunk = EncryptedChunk {
+        index: info.index,
+        content: chunk.value().clone(),
+    };
+    let bytes = self_encryption::decrypt_range(data_map, &[encrypted_chunk], skip, usize::MAX)?;
+    Ok(bytes)
+}
+
+impl Stream for FileReader {
+    type Item = Result<Bytes>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        // first yield anything left over from a partial AsyncRead
+        if !self.buffered.is_empty() {
+            let bytes = std::mem::take(&mut self.buffered);
+            self.position += bytes.len() as u64;
+            return Poll::Ready(Some(Ok(bytes)));
+        }
+
+        let next = futures::ready!(self.stream.poll_next_unpin(cx));
+        if let Some(Ok(bytes)) = &next {
+            self.position += bytes.len() as u64;
+        }
+        Poll::Ready(next)
+    }
+}
+
+impl AsyncRead for FileReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        while self.buffered.is_empty() {
+            match futures::ready!(self.stream.poll_next_unpin(cx)) {
+                Some(Ok(bytes)) => self.buffered = bytes,
+                Some(Err(err)) => return Poll::Ready(Err(io::Error::other(err))),
+                // end of the content
+                None => return Poll::Ready(Ok(())),
+            }
+        }
+
+        let len = self.buffered.len().min(buf.remaining());
+        let bytes = self.buffered.split_to(len);
+        buf.put_slice(&bytes);
+        self.position += len as u64;
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncSeek for FileReader {
+    fn start_seek(mut self: Pin<&mut Self>, position: io::SeekFrom) -> io::Result<()> {
+        let new_position = match position {
+            io::SeekFrom::Start(offset) => Some(offset),
+            io::SeekFrom::End(offset) => checked_offset(self.size, offset),
+            io::SeekFrom::Current(offset) => checked_offset(self.position, offset),
+        }
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "invalid seek to a negative or overflowing position",
+            )
+        })?;
+
+        self.pending_seek = Some(new_position);
+        Ok(())
+    }
+
+    fn poll_complete(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<u64>> {
+        if let Some(position) = self.pending_seek.take() {
+            self.seek_to(position);
+        }
+        Poll::Ready(Ok(self.position))
+    }
+}
+
+fn checked_offset(base: u64, offset: i64) -> Option<u64> {
+    if offset >= 0 {
+        base.checked_add(offset as u64)
+    } else {
+        base.checked_sub(offset.unsigned_abs())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checked_offset, FileReader, Source};
+    use crate::utils::test_utils::{create_test_client, init_logger};
+
+    use bytes::Bytes;
+    use eyre::Result;
+    use futures::TryStreamExt;
+    use self_encryption::MIN_ENCRYPTABLE_BYTES;
+    use sn_interface::types::utils::random_bytes;
+    use std::io::SeekFrom;
+    use tokio::io::{AsyncReadExt, AsyncSeekExt};
+
+    #[test]
+    fn checked_offset_rejects_negative_positions() {
+        assert_eq!(checked_offset(10, -3), Some(7));
+        assert_eq!(checked_offset(10, 3), Some(13));
+        assert_eq!(checked_offset(2, -3), None);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn read_stream_of_small_file() -> Result<()> {
+        init_logger();
+        let client = create_test_client().await?;
+        let bytes = Bytes::from_static(b"some small content");
+
+        let mut reader = FileReader::new(client, Source::Small(bytes.clone()));
+        let mut read = vec![];
+        let _ = reader.read_to_end(&mut read).await?;
+        assert_eq!(read, bytes);
+
+        let _ = reader.seek(SeekFrom::Start(5)).await?;
+        let streamed: Vec<Bytes> = reader.try_collect().await?;
+        assert_eq!(streamed.concat(), bytes.slice(5..));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn read_stream_of_large_file() -> Result<()> {
+        init_logger();
+        let _outer_span = tracing::info_span!("read_stream_of_large_file").entered();
+        let client = create_test_client().await?;
+
+        let size = 5 * 1024 * 1024 + MIN_ENCRYPTABLE_BYTES;
+        let bytes = random_bytes(size);
+        let address = client.upload_and_verify(bytes.clone()).await?;
+
+        let reader = client.read_stream(address).await?.with_read_ahead(2);
+        assert_eq!(reader.size(), size as u64);
+        let streamed: Vec<Bytes> = reader.try_collect().await?;
+        assert_eq!(streamed.concat(), bytes);
+
+        let mut reader = client.read_stream(address).await?;
+        let pos = 1024 * 1024 + 512;
+        let _ = reader.seek(SeekFrom::Start(pos as u64)).await?;
+        let mut read = vec![0; 4096];
+        let _ = reader.read_exact(&mut read).await?;
+        assert_eq!(read, bytes.slice(pos..pos + 4096));
+
+        Ok(())
+    }
+}
@@ -10,15 +10,24 @@
 pub mod client_builder;
 mod cmds;
 mod data;
+mod disk_chunk_cache;
+mod disk_register_wal;
 mod file_apis;
+mod file_reader;
 mod queries;
 mod register_apis;
 mod spentbook_apis;
 
 pub use client_builder::ClientBuilder;
-pub use register_apis::RegisterWriteAheadLog;
+pub use cmds::{CMD_BATCH_MAX_SIZE, MAX_BATCH_RESENDS};
+pub use data::{EncryptedBatch, StreamEncryptor};
+pub use file_apis::CHUNKS_BATCH_MAX_SIZE;
+pub use file_reader::{FileReader, DEFAULT_READ_AHEAD};
+pub use register_apis::{RegisterReplicas, RegisterWatch, RegisterWriteAheadLog};
 
 use crate::{errors::Error, sessions::Session};
+use disk_chunk_cache::DiskChunkCache;
+use disk_register_wal::DiskRegisterWal;
 
 use sn_dbc::Owner;
 use sn_interface::{
@@ -57,6 +66,9 @@ pub struct Client {
     pub(crate) max_backoff_interval: Duration,
     pub(crate) cmd_timeout: Duration,
     chunks_cache: Arc<RwLock<ChunksCache>>,
+    disk_chunk_cache: Option<DiskChunkCache>,
+    merge_register_replicas: bool,
+    register_wal: Option<DiskRegisterWal>,
 }
 
 /// Easily manage connections to/from The Safe Network with the client and its APIs.
@@ -8,20 +8,31 @@
 
 use super::Client;
 
-use crate::Error;
+use crate::{Error, ErrorMsg, QueryResponsePolicy};
 
 use sn_interface::{
-    messaging::data::{
-        CreateRegister, DataCmd, DataQueryVariant, EditRegister, QueryResponse, RegisterCmd,
-        RegisterQuery, SignedRegisterCreate, SignedRegisterEdit,
+    messaging::{
+        data::{
+            ClientMsg, CreateRegister, DataCmd, DataQuery, DataQueryVariant, DeleteRegister,
+            EditRegister, QueryResponse, RegisterCmd, RegisterQuery, SignedRegisterCreate,
+            SignedRegisterDelete, SignedRegisterEdit, SignedRegisterPolicyUpdate,
+            UpdateRegisterPolicy,
+        },
+        ClientAuth, WireMsg,
     },
     types::{
-        register::{Action, Entry, EntryHash, Permissions, Policy, Register, User},
-        RegisterAddress as Address,
+        register::{Action, Entry, EntryHash, Permissions, Policy, Register, Stamp, User},
+        DataAddress, Error as DtError, Keypair, RegisterAddress as Address, ReplicatedRegisterLog,
     },
 };
 
-use std::collections::BTreeSet;
+use futures::Stream;
+use std::{
+    collections::{BTreeMap, BTreeSet, VecDeque},
+    pin::Pin,
+    task::{Context, Poll},
+};
+use tokio::sync::mpsc;
 use xor_name::XorName;
 
 /// Register Write Ahead Log
@@ -32,22 +43,234 @@ use xor_name::XorName;
 /// Batches can be republished without duplication risks thanks to the CRDT nature of registers.
 pub type RegisterWriteAheadLog = Vec<DataCmd>;
 
+// Number of operations received last from a watched Register, kept to filter out those
+// received again from other Elders
+const MAX_RECENT_REGISTER_EVENTS: usize = 100;
+
+/// A Register merged from the replicas held by the Adults,
+/// see [`Client::get_register_from_all_replicas`]
+#[derive(Debug)]
+pub struct RegisterReplicas {
+    /// The Register merged from all the replicas retrieved.
+    pub register: Register,
+    /// Index of the Adults whose replica was behind the merged Register,
+    /// i.e. it was missing some of the cmds merged, such as entries or policy updates.
+    pub behind: BTreeSet<usize>,
+    /// Index of the Adults a replica couldn't be retrieved from, along with the error obtained.
+    pub failed: Vec<(usize, Error)>,
+}
+
+/// Stream of the operations applied to a watched Register, see [`Client::watch_register`]
+///
+/// Only the operations signed by their author and targeting the watched Register are yielded,
+/// any other event received is dropped.
+///
+/// The stream ends once the Register is deleted, or none of the Elders watching it
+/// can reach the client anymore, e.g. after they were replaced due to churn.
+#[derive(Debug)]
+pub struct RegisterWatch {
+    address: Address,
+    events: mpsc::UnboundedReceiver<RegisterCmd>,
+    // The operations received last, as each of the Elders watching the Register sends them
+    recent: VecDeque<RegisterCmd>,
+}
+
+impl RegisterWatch {
+    /// Address of the watched Register
+    pub fn address(&self) -> Address {
+        self.address
+    }
+}
+
+impl Stream for RegisterWatch {
+    type Item = RegisterCmd;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        loop {
+            match self.events.poll_recv(cx) {
+                Poll::Ready(Some(cmd)) => {
+                    if self.recent.contains(&cmd) {
+                        continue;
+                    }
+                    if cmd.dst_address() != self.address {
+                        warn!(
+                            "Dropping event of Register {:?} while watching {:?}",
+                            cmd.dst_address(),
+                            self.address
+                        );
+                        continue;
+                    }
+                    if let Err(error) = cmd.verify_signature() {
+                        warn!(
+                            "Dropping event of Register {:?} with invalid signature: {error:?}",
+                            self.address
+                        );
+                        continue;
+                    }
+                    if self.recent.len() >= MAX_RECENT_REGISTER_EVENTS {
+                        let _ = self.recent.pop_front();
+                    }
+                    self.recent.push_back(cmd.clone());
+                    return Poll::Ready(Some(cmd));
+                }
+                other => return other,
+            }
+        }
+    }
+}
+
 impl Client {
     //----------------------
     // Write Operations
     //---------------------
 
     /// Publish all register mutation operations in a WAL to the network
-    /// Incrementing the WAL index as successful writes are sent out. Stops at the first error.
-    /// Starts publishing from the index when called again with the same WAL.
+    /// The operations are sent in batches, in order, see [`Self::send_cmd_batch()`],
+    /// failing with the error of the first one which couldn't be published.
+    ///
+    /// If the client keeps a Register write-ahead log on disk, see
+    /// [`crate::api::ClientBuilder::register_wal()`], the operations are recorded in it first,
+    /// and published after any already pending, see [`Self::flush_register_ops()`].
     #[instrument(skip(self), level = "debug")]
     pub async fn publish_register_ops(&self, wal: RegisterWriteAheadLog) -> Result<(), Error> {
-        for cmd in &wal {
-            self.send_cmd(cmd.clone()).await?;
+        if let Some(disk_wal) = &self.register_wal {
+            disk_wal.append(&wal)?;
+            return self.flush_register_ops().await.map(|_| ());
+        }
+
+        for result in self.send_cmd_batch(wal).await? {
+            result?;
         }
         Ok(())
     }
 
+    /// Register operations recorded in the client's write-ahead log on disk, which
+    /// are yet to be published, in the order they were recorded.
+    /// It's always empty if the client doesn't keep such a log.
+    pub fn pending_register_ops(&self) -> Result<RegisterWriteAheadLog, Error> {
+        match &self.register_wal {
+            Some(disk_wal) => Ok(disk_wal
+                .pending()?
+                .into_iter()
+                .map(|(_, cmd)| cmd)
+                .collect()),
+            None => Ok(vec![]),
+        }
+    }
+
+    /// Publish the Register operations pending in the client's write-ahead log on disk, in the
+    /// order they were recorded, returning how many were published.
+    ///
+    /// The operations signed by the client are stamped and signed again first, as the network
+    /// only accepts those stamped recently, and entries stamped against the current version of
+    /// their Register's policy, see [`sn_interface::types::register::MAX_STAMP_CLOCK_DRIFT`].
+    ///
+    /// The operations are sent in batches, see [`Self::send_cmd_batch()`]. Those which cannot
+    /// be sent are kept pending, see [`Error::RegisterOpsPending`], as are those which could be
+    /// accepted later on, e.g. which were sent to the wrong section, or with an invalid stamp.
+    /// An operation rejected by the network is removed from the log, and the error returned.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn flush_register_ops(&self) -> Result<usize, Error> {
+        let disk_wal = match &self.register_wal {
+            Some(disk_wal) => disk_wal,
+            None => return Ok(0),
+        };
+
+        let (ids, cmds): (Vec<_>, Vec<_>) = disk_wal.pending()?.into_iter().unzip();
+        let total = ids.len();
+        let cmds = self.restamp_register_ops(cmds).await?;
+        let results = self
+            .send_cmd_batch(cmds)
+            .await
+            .map_err(|err| Error::RegisterOpsPending {
+                pending: total,
+                source: Box::new(err),
+            })?;
+
+        let mut published = 0;
+        let mut rejected = None;
+        let mut pending = vec![];
+        for (id, result) in ids.into_iter().zip(results) {
+            match result {
+                Ok(()) => {
+                    disk_wal.remove(&id)?;
+                    published += 1;
+                }
+                // the op could be published later on, e.g. once the section split is known,
+                // or once stamped again, e.g. after our clock was adjusted
+                Err(
+                    err @ Error::CmdError {
+                        source:
+                            ErrorMsg::DataNotInSection { .. }
+                            | ErrorMsg::InsufficientAdults { .. }
+                            | ErrorMsg::InvalidStamp(_),
+                        ..
+                    },
+                ) => pending.push(err),
+                Err(err @ Error::CmdError { .. }) => {
+                    warn!("Register op {id} rejected by the network, dropping it: {err:?}");
+                    disk_wal.remove(&id)?;
+                    let _ = rejected.get_or_insert(err);
+                }
+                Err(err) => pending.push(err),
+            }
+        }
+
+        debug!("Published {published} of {total} pending Register ops");
+        if !pending.is_empty() {
+            return Err(Error::RegisterOpsPending {
+                pending: pending.len(),
+                source: Box::new(pending.remove(0)),
+            });
+        }
+        match rejected {
+            Some(err) => Err(err),
+            None => Ok(published),
+        }
+    }
+
+    // Stamp and sign again the pending Register ops we signed, as they may have been stamped
+    // long ago, e.g. while offline. Entries are stamped against the current state of their
+    // Register, which is kept as it was stamped if it cannot be retrieved.
+    async fn restamp_register_ops(&self, cmds: Vec<DataCmd>) -> Result<Vec<DataCmd>, Error> {
+        let mut registers = BTreeMap::new();
+        let mut restamped = Vec::with_capacity(cmds.len());
+        for cmd in cmds {
+            let register_cmd = match cmd {
+                DataCmd::Register(register_cmd)
+                    if register_cmd.stamp().is_some()
+                        && register_cmd.signer() == self.keypair.public_key() =>
+                {
+                    register_cmd
+                }
+                other => {
+                    restamped.push(other);
+                    continue;
+                }
+            };
+
+            let address = register_cmd.dst_address();
+            if !registers.contains_key(&address) && matches!(register_cmd, RegisterCmd::Edit(_)) {
+                let register = match self.get_register(address).await {
+                    Ok(register) => Some(register),
+                    Err(err) => {
+                        warn!("Failed to retrieve Register {address:?} to stamp ops: {err:?}");
+                        None
+                    }
+                };
+                let _ = registers.insert(address, register);
+            }
+            let register = registers.get(&address).and_then(Option::as_ref);
+            restamped.push(DataCmd::Register(restamp_register_cmd(
+                register_cmd,
+                register,
+                &self.keypair,
+            )?));
+        }
+
+        Ok(restamped)
+    }
+
     /// Creates a Register which can then be written to.
     ///
     /// Returns a write ahead log (WAL) of register operations, note that the changes are not uploaded to the
@@ -70,7 +293,7 @@ impl Client {
         let cmd = DataCmd::Register(RegisterCmd::Create {
             cmd: SignedRegisterCreate {
                 op,
-                auth: sn_interface::messaging::ClientAuth {
+                auth: ClientAuth {
                     public_key: self.keypair.public_key(),
                     signature,
                 },
@@ -102,17 +325,24 @@ impl Client {
         // Let's check the policy/permissions to make sure this operation is allowed,
         // otherwise it will fail when the operation is applied on the network replica.
         let public_key = self.keypair.public_key();
-        register.check_permissions(Action::Write, Some(User::Key(public_key)))?;
-
-        // We can now write the entry to the Register
-        let (hash, op) = register.write(entry, children)?;
-        let op = EditRegister { address, edit: op };
+        let requester = User::Key(public_key);
+        // the op is stamped with our view of the Register, which our permissions are evaluated at
+        let stamp = register.stamp();
+        register.check_write_permissions(requester, &children, stamp)?;
+
+        // We can now write the entry to the Register, as authored by us
+        let (hash, op) = register.write_as(entry, children, requester)?;
+        let op = EditRegister {
+            address,
+            edit: op,
+            stamp,
+        };
 
         let signature = self.keypair.sign(&bincode::serialize(&op)?);
 
         let edit = SignedRegisterEdit {
             op,
-            auth: sn_interface::messaging::ClientAuth {
+            auth: ClientAuth {
                 public_key,
                 signature,
             },
@@ -124,13 +354,127 @@ impl Client {
         Ok((hash, batch))
     }
 
+    /// Delete a Register
+    ///
+    /// Only the owner of the Register can delete it. Once the deletion is published, nodes
+    /// stop serving and replicating the Register's content, and reclaim its space after a
+    /// grace period. The address cannot be reused to create a new Register.
+    ///
+    /// Returns a write ahead log (WAL) of register operations, note that the changes are not uploaded to the
+    /// network until the WAL is published with `publish_register_ops`
+    #[instrument(skip(self), level = "debug")]
+    pub async fn delete_register(&self, address: Address) -> Result<RegisterWriteAheadLog, Error> {
+        debug!("Deleting register at {:?}", address);
+        let register = self.get_register(address).await?;
+
+        // Let's check we are the owner, otherwise it will be rejected by the network's replicas.
+        let public_key = self.keypair.public_key();
+        let requester = User::Key(public_key);
+        if register.owner() != requester {
+            return Err(DtError::AccessDenied(requester).into());
+        }
+
+        let op = DeleteRegister { address };
+        let signature = self.keypair.sign(&bincode::serialize(&op)?);
+
+        let delete = SignedRegisterDelete {
+            op,
+            auth: ClientAuth {
+                public_key,
+                signature,
+            },
+        };
+
+        let cmd = DataCmd::Register(RegisterCmd::Delete(delete));
+        Ok(vec![cmd])
+    }
+
+    /// Replace the policy of a Register
+    ///
+    /// The owner of the Register, and users with admin permissions, can update its policy,
+    /// but only the owner can change the owner. The new policy applies to entries written
+    /// after the update is published, existing entries are kept.
+    ///
+    /// Returns a write ahead log (WAL) of register operations, note that the changes are not uploaded to the
+    /// network until the WAL is published with `publish_register_ops`
+    #[instrument(skip(self), level = "debug")]
+    pub async fn update_register_policy(
+        &self,
+        address: Address,
+        policy: Policy,
+    ) -> Result<RegisterWriteAheadLog, Error> {
+        debug!("Updating policy of register at {:?}", address);
+        let register = self.get_register(address).await?;
+
+        // Let's check we are allowed to, otherwise it will be rejected by the network's replicas.
+        let public_key = self.keypair.public_key();
+        let requester = User::Key(public_key);
+        let stamp = register.stamp();
+        register.check_permissions(Action::Admin, Some(requester), stamp)?;
+        if policy.owner() != register.policy().owner() && register.owner() != requester {
+            return Err(DtError::AccessDenied(requester).into());
+        }
+
+        let op = UpdateRegisterPolicy {
+            address,
+            policy,
+            version: register.policy_version() + 1,
+            stamp,
+        };
+        let signature = self.keypair.sign(&bincode::serialize(&op)?);
+
+        let update = SignedRegisterPolicyUpdate {
+            op,
+            auth: ClientAuth {
+                public_key,
+                signature,
+            },
+        };
+
+        let cmd = DataCmd::Register(RegisterCmd::UpdatePolicy(update));
+        Ok(vec![cmd])
+    }
+
     //----------------------
     // Get Register
     //---------------------
 
     /// Get the entire Register from the Network
+    ///
+    /// If the client was built to merge Register replicas, the Register is retrieved from
+    /// all the Adults holding a replica, see [`Self::get_register_from_all_replicas`].
     #[instrument(skip(self), level = "debug")]
     pub async fn get_register(&self, address: Address) -> Result<Register, Error> {
+        if self.merge_register_replicas {
+            let replicas = self.get_register_from_all_replicas(address).await?;
+            if !replicas.behind.is_empty() {
+                warn!(
+                    "Register {address:?} replicas held by Adults {:?} are behind",
+                    replicas.behind
+                );
+            }
+            return Ok(replicas.register);
+        }
+
+        // The logs of signed cmds returned by the nodes are verified and merged,
+        // and the Register is reconstructed from them
+        if self.session.query_response_policy == QueryResponsePolicy::MergeAll {
+            let query = DataQueryVariant::Register(RegisterQuery::GetLog(address));
+            let query_result = self.send_query(query.clone()).await?;
+            return match query_result.response {
+                QueryResponse::GetRegisterLog(Ok(log)) => Register::from_log(&log.op_log)
+                    .filter(|register| *register.address() == address)
+                    .ok_or(Error::ErrorMsg {
+                        source: ErrorMsg::DataNotFound(DataAddress::Register(address)),
+                    }),
+                QueryResponse::GetRegisterLog(Err(err)) => Err(Error::ErrorMsg { source: err }),
+                other => Err(Error::UnexpectedQueryResponse {
+                    query,
+                    response: other,
+                }),
+            };
+        }
+
         // Let's fetch the Register from the network
         let query = DataQueryVariant::Register(RegisterQuery::Get(address));
         let query_result = self.send_query(query.clone()).await?;
@@ -145,12 +489,85 @@ impl Client {
         }
     }
 
+    /// Get the Register from all the Adults holding a replica of it, and merge the replicas.
+    ///
+    /// As Registers are CRDTs, the merged Register holds all the entries written to any of
+    /// the replicas, which makes reads right after concurrent writes consistent. The Adults
+    /// whose replica was behind the merged Register are reported.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn get_register_from_all_replicas(
+        &self,
+        address: Address,
+    ) -> Result<RegisterReplicas, Error> {
+        let query = DataQueryVariant::Register(RegisterQuery::GetLog(address));
+        let results = self.send_query_to_all_replicas(query.clone()).await?;
+
+        let mut replicas = vec![];
+        let mut failed = vec![];
+        for (result, adult_index) in results {
+            match result.map(|query_result| query_result.response) {
+                Ok(QueryResponse::GetRegisterLog(Ok(log))) => replicas.push((adult_index, log)),
+                Ok(QueryResponse::GetRegisterLog(Err(err))) => {
+                    failed.push((adult_index, Error::ErrorMsg { source: err }))
+                }
+                Ok(other) => failed.push((
+                    adult_index,
+                    Error::UnexpectedQueryResponse {
+                        query: query.clone(),
+                        response: other,
+                    },
+                )),
+                Err(err) => failed.push((adult_index, err)),
+            }
+        }
+
+        merge_register_replicas(address, replicas, failed)
+    }
+
+    /// Watch the Register, i.e. get notified by the Elders of the operations applied to it
+    /// from now on, rather than polling it for changes.
+    ///
+    /// Returns a [`RegisterWatch`] stream of the operations, each yielded once even though
+    /// they are received from several Elders.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn watch_register(&self, address: Address) -> Result<RegisterWatch, Error> {
+        let query = DataQuery {
+            adult_index: 0,
+            variant: DataQueryVariant::Register(RegisterQuery::Watch(address)),
+        };
+        let serialised_query = WireMsg::serialize_msg_payload(&ClientMsg::Query(query.clone()))?;
+        let auth = ClientAuth {
+            public_key: self.public_key(),
+            signature: self.keypair.sign(&serialised_query),
+        };
+
+        let (events_sender, events) = mpsc::unbounded_channel();
+        self.session
+            .watch_register(query, auth, serialised_query, events_sender)
+            .await?;
+
+        Ok(RegisterWatch {
+            address,
+            events,
+            recent: VecDeque::new(),
+        })
+    }
+
     /// Get the latest entry (or entries if branching)
+    ///
+    /// With the [`QueryResponsePolicy::MergeAll`] policy, the logs of the Register are retrieved
+    /// from the nodes queried, verified and merged to read its latest entries.
     #[instrument(skip(self), level = "debug")]
     pub async fn read_register(
         &self,
         address: Address,
     ) -> Result<BTreeSet<(EntryHash, Entry)>, Error> {
+        // The latest entries returned by several nodes cannot be merged, as some of them could
+        // have been superseded by the entries returned by other nodes, so we merge the replicas.
+        if self.session.query_response_policy == QueryResponsePolicy::MergeAll {
+            return Ok(self.get_register(address).await?.read());
+        }
+
         let query = DataQueryVariant::Register(RegisterQuery::Read(address));
         let query_result = self.send_query(query.clone()).await?;
         match query_result.response {
@@ -257,30 +674,131 @@ fn section_sig() -> sn_interface::messaging::SectionSig {
     }
 }
 
+// Verifies and merges the logs of the replicas of the Register, the same way the logs returned
+// by the Elders are with the `QueryResponsePolicy::MergeAll` policy, and reconstructs the
+// Register from the merged log. The replicas missing any of the cmds merged are behind.
+fn merge_register_replicas(
+    address: Address,
+    replicas: Vec<(usize, ReplicatedRegisterLog)>,
+    mut failed: Vec<(usize, Error)>,
+) -> Result<RegisterReplicas, Error> {
+    let mut merged = ReplicatedRegisterLog {
+        address,
+        op_log: vec![],
+    };
+    let mut merged_replicas = vec![];
+    for (adult_index, log) in replicas {
+        match merged.merge(log.clone()) {
+            Ok(()) => merged_replicas.push((adult_index, log)),
+            Err(err) => {
+                warn!("Discarding Register replica from Adult #{adult_index}: {err:?}");
+                failed.push((adult_index, err.into()));
+            }
+        }
+    }
+
+    let register = match Register::from_log(&merged.op_log) {
+        Some(register) => register,
+        None => {
+            // none of the replicas could be retrieved, return the error from the closest Adult
+            return Err(failed.into_iter().next().map_or(
+                Error::ErrorMsg {
+                    source: ErrorMsg::DataNotFound(DataAddress::Register(address)),
+                },
+                |(_, err)| err,
+            ));
+        }
+    };
+
+    let behind = merged_replicas
+        .into_iter()
+        .filter(|(_, log)| merged.op_log.iter().any(|cmd| !log.op_log.contains(cmd)))
+        .map(|(adult_index, _)| adult_index)
+        .collect();
+    debug!("Register {address:?} merged, replicas behind: {behind:?}, failed: {failed:?}");
+
+    Ok(RegisterReplicas {
+        register,
+        behind,
+        failed,
+    })
+}
+
+// Stamp the Register op again, now, and sign it again with the keypair. An edit is stamped against
+// the given state of its Register, if any, while a policy update keeps the version of the policy
+// it updates, so it's still rejected if the policy was updated in the meantime.
+fn restamp_register_cmd(
+    cmd: RegisterCmd,
+    register: Option<&Register>,
+    keypair: &Keypair,
+) -> Result<RegisterCmd, Error> {
+    let auth = |bytes: Vec<u8>| ClientAuth {
+        public_key: keypair.public_key(),
+        signature: keypair.sign(&bytes),
+    };
+    match cmd {
+        RegisterCmd::Edit(SignedRegisterEdit { mut op, .. }) => {
+            // it cannot be stamped before the entries it supersedes
+            let (generation, policy_version) = match register {
+                Some(register) => (
+                    op.stamp.generation.max(register.size()),
+                    register.policy_version(),
+                ),
+                None => (op.stamp.generation, op.stamp.policy_version),
+            };
+            op.stamp = Stamp::now(generation, policy_version);
+            let auth = auth(bincode::serialize(&op)?);
+            Ok(RegisterCmd::Edit(SignedRegisterEdit { op, auth }))
+        }
+        RegisterCmd::UpdatePolicy(SignedRegisterPolicyUpdate { mut op, .. }) => {
+            op.stamp = Stamp::now(op.stamp.generation, op.stamp.policy_version);
+            let auth = auth(bincode::serialize(&op)?);
+            Ok(RegisterCmd::UpdatePolicy(SignedRegisterPolicyUpdate {
+                op,
+                auth,
+            }))
+        }
+        other => Ok(other),
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{merge_register_replicas, restamp_register_cmd, RegisterWatch};
     use crate::{
         utils::test_utils::{create_test_client, init_logger},
-        Error,
+        Client, Error,
     };
 
     use sn_interface::{
-        messaging::data::Error as ErrorMsg,
+        messaging::{
+            data::{DataCmd, Error as ErrorMsg, RegisterCmd, SignedRegisterEdit},
+            ClientAuth,
+        },
+        test_utils::{create_register_cmd, edit_register_cmd, update_register_policy_cmd},
         types::{
             log_markers::LogMarker,
-            register::{Action, EntryHash, Permissions, Policy, User},
-            Keypair,
+            register::{
+                Action, EntryHash, Permissions, Policy, Register, Stamp, User,
+                MAX_STAMP_CLOCK_DRIFT,
+            },
+            Keypair, ReplicatedRegisterLog,
         },
     };
 
     use eyre::{bail, eyre, Context, Result};
+    use futures::StreamExt;
     use rand::Rng;
     use std::{
-        collections::{BTreeMap, BTreeSet},
+        collections::{BTreeMap, BTreeSet, VecDeque},
         time::Instant,
     };
-    use tokio::time::{sleep, Duration};
+    use tokio::{
+        sync::mpsc,
+        time::{sleep, Duration},
+    };
     use tracing::Instrument;
+    use xor_name::XorName;
 
     #[tokio::test(flavor = "multi_thread")]
     async fn test_register_batching() -> Result<()> {
@@ -617,6 +1135,275 @@ mod tests {
         }
     }
 
+    #[test]
+    fn register_replicas_are_merged() -> Result<()> {
+        let keypair = Keypair::new_ed25519();
+        let owner = User::Key(keypair.public_key());
+        let mut register =
+            Register::new_owned(owner, XorName::random(&mut rand::thread_rng()), 15000);
+        let address = *register.address();
+        let create = create_register_cmd(&register, &keypair);
+        let (hash1, edit1) =
+            edit_register_cmd(&mut register, random_register_entry(), &keypair, &keypair)?;
+        let (hash2, edit2) =
+            edit_register_cmd(&mut register, random_register_entry(), &keypair, &keypair)?;
+        let forger = Keypair::new_ed25519();
+        let (_, forged) =
+            edit_register_cmd(&mut register, random_register_entry(), &keypair, &forger)?;
+
+        let log = |op_log| ReplicatedRegisterLog { address, op_log };
+        let replicas = vec![
+            (0, log(vec![create.clone(), edit1.clone(), edit2.clone()])),
+            // lagging behind, missing the latest entry
+            (1, log(vec![create.clone(), edit1.clone()])),
+            // holding an entry which wasn't signed by its author
+            (2, log(vec![create.clone(), edit1, edit2, forged])),
+            // missing the creation of the Register
+            (3, log(vec![])),
+        ];
+        let merged = merge_register_replicas(address, replicas, vec![])?;
+
+        assert_eq!(merged.register.size(), 2);
+        assert_eq!(
+            merged
+                .register
+                .read()
+                .into_iter()
+                .map(|(hash, _)| hash)
+                .collect::<BTreeSet<_>>(),
+            BTreeSet::from([hash1, hash2])
+        );
+        assert_eq!(merged.behind, BTreeSet::from([1, 3]));
+        assert_eq!(
+            merged
+                .failed
+                .iter()
+                .map(|(adult_index, _)| *adult_index)
+                .collect::<Vec<_>>(),
+            vec![2]
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn register_watch_yields_verified_events_only() -> Result<()> {
+        let keypair = Keypair::new_ed25519();
+        let owner = User::Key(keypair.public_key());
+        let mut register =
+            Register::new_owned(owner, XorName::random(&mut rand::thread_rng()), 15000);
+        let mut other_register =
+            Register::new_owned(owner, XorName::random(&mut rand::thread_rng()), 15000);
+        let (_, edit) =
+            edit_register_cmd(&mut register, random_register_entry(), &keypair, &keypair)?;
+        let forger = Keypair::new_ed25519();
+        let (_, forged) =
+            edit_register_cmd(&mut register, random_register_entry(), &keypair, &forger)?;
+        let (_, other_edit) = edit_register_cmd(
+            &mut other_register,
+            random_register_entry(),
+            &keypair,
+            &keypair,
+        )?;
+
+        let (events_sender, events) = mpsc::unbounded_channel();
+        let mut watch = RegisterWatch {
+            address: *register.address(),
+            events,
+            recent: VecDeque::new(),
+        };
+        for cmd in [forged, other_edit, edit.clone(), edit.clone()] {
+            events_sender.send(cmd)?;
+        }
+        drop(events_sender);
+
+        assert_eq!(watch.next().await, Some(edit));
+        assert_eq!(watch.next().await, None);
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn register_ops_are_acked_individually_in_a_batch() -> Result<()> {
+        init_logger();
+        let _outer_span =
+            tracing::info_span!("test__register_ops_are_acked_individually_in_a_batch").entered();
+
+        let client = create_test_client().await?;
+        let name = XorName::random(&mut rand::thread_rng());
+        let tag = 15000;
+        let owner = User::Key(client.public_key());
+
+        let (address, batch) = client.create_register(name, tag, policy(owner)).await?;
+        client.publish_register_ops(batch).await?;
+
+        // the Register is written to several times in a single batch
+        let mut batch = vec![];
+        let mut values = vec![];
+        for _ in 0..3 {
+            let value = random_register_entry();
+            let (_, write_batch) = client
+                .write_to_local_register(address, value.clone(), BTreeSet::new())
+                .await?;
+            batch.extend(write_batch);
+            values.push(value);
+        }
+
+        let results = client.send_cmd_batch(batch).await?;
+        assert_eq!(results.len(), values.len());
+        for result in results {
+            result?;
+        }
+
+        let entries = client.read_register(address).await?;
+        assert_eq!(entries.len(), values.len());
+        assert!(entries.iter().all(|(_, entry)| values.contains(entry)));
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn register_op_failing_in_a_batch_doesnt_fail_the_others() -> Result<()> {
+        init_logger();
+        let _outer_span =
+            tracing::info_span!("test__register_op_failing_in_a_batch_doesnt_fail_the_others")
+                .entered();
+
+        let client = create_test_client().await?;
+        let name = XorName::random(&mut rand::thread_rng());
+        let tag = 15000;
+        let owner = User::Key(client.public_key());
+
+        let (address, batch) = client.create_register(name, tag, policy(owner)).await?;
+        client.publish_register_ops(batch).await?;
+
+        // an entry written to a Register which was never created, in between two valid ones
+        let keypair = Keypair::new_ed25519();
+        let mut missing = Register::new_owned(
+            User::Key(keypair.public_key()),
+            XorName::random(&mut rand::thread_rng()),
+            tag,
+        );
+        let (_, missing_edit) =
+            edit_register_cmd(&mut missing, random_register_entry(), &keypair, &keypair)?;
+
+        let mut values = vec![];
+        let mut batch = vec![];
+        for _ in 0..2 {
+            let value = random_register_entry();
+            let (_, write_batch) = client
+                .write_to_local_register(address, value.clone(), BTreeSet::new())
+                .await?;
+            batch.extend(write_batch);
+            values.push(value);
+        }
+        batch.insert(1, DataCmd::Register(missing_edit));
+
+        let results = client.send_cmd_batch(batch).await?;
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+
+        let entries = client.read_register(address).await?;
+        assert_eq!(entries.len(), values.len());
+        assert!(entries.iter().all(|(_, entry)| values.contains(entry)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_ops_are_stamped_again() -> Result<()> {
+        let keypair = Keypair::new_ed25519();
+        let owner = User::Key(keypair.public_key());
+        let mut register =
+            Register::new_owned(owner, XorName::random(&mut rand::thread_rng()), 15000);
+        let (_, edit) =
+            edit_register_cmd(&mut register, random_register_entry(), &keypair, &keypair)?;
+        let policy_update = update_register_policy_cmd(&mut register, policy(owner), &keypair)?;
+        // both ops were stamped, e.g. while offline, longer ago than the network accepts
+        let (edit, policy_update) = (
+            stale_register_cmd(edit, &keypair)?,
+            stale_register_cmd(policy_update, &keypair)?,
+        );
+
+        let now = Stamp::now(0, 0).time;
+        let recent = |cmd: &RegisterCmd| {
+            cmd.stamp()
+                .map(|stamp| stamp.time.abs_diff(now) <= MAX_STAMP_CLOCK_DRIFT.as_secs())
+                .unwrap_or(false)
+        };
+
+        // the edit is stamped against the current version of the policy
+        let restamped = restamp_register_cmd(edit, Some(&register), &keypair)?;
+        assert!(recent(&restamped));
+        assert_eq!(
+            restamped.stamp().map(|stamp| stamp.policy_version),
+            Some(register.policy_version())
+        );
+        restamped.verify_signature()?;
+        register.check_cmd_permissions(&restamped)?;
+
+        // the policy update keeps the version it updates
+        let restamped = restamp_register_cmd(policy_update.clone(), None, &keypair)?;
+        assert!(recent(&restamped));
+        assert_eq!(
+            restamped.stamp().map(|stamp| stamp.policy_version),
+            policy_update.stamp().map(|stamp| stamp.policy_version)
+        );
+        restamped.verify_signature()?;
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn register_op_stamped_long_ago_is_flushed() -> Result<()> {
+        init_logger();
+        let _outer_span =
+            tracing::info_span!("test__register_op_stamped_long_ago_is_flushed").entered();
+
+        let wal_dir = tempfile::tempdir()?;
+        let client = Client::builder()
+            .register_wal(true)
+            .register_wal_dir(wal_dir.path().to_path_buf())
+            .from_env()
+            .build()
+            .await?;
+        let name = XorName::random(&mut rand::thread_rng());
+        let tag = 15000;
+        let owner = User::Key(client.public_key());
+
+        let (address, batch) = client.create_register(name, tag, policy(owner)).await?;
+        client.publish_register_ops(batch).await?;
+
+        // an entry written while offline, longer ago than the network accepts stamps from
+        let value = random_register_entry();
+        let (_, batch) = client
+            .write_to_local_register(address, value.clone(), BTreeSet::new())
+            .await?;
+        let stale = batch
+            .into_iter()
+            .map(|cmd| match cmd {
+                DataCmd::Register(cmd) => {
+                    stale_register_cmd(cmd, &client.keypair).map(DataCmd::Register)
+                }
+                other => Ok(other),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        client
+            .register_wal
+            .as_ref()
+            .ok_or_else(|| eyre!("The client has no Register WAL"))?
+            .append(&stale)?;
+
+        assert_eq!(client.flush_register_ops().await?, 1);
+        assert!(client.pending_register_ops()?.is_empty());
+        let entries = client.read_register(address).await?;
+        assert!(entries.iter().any(|(_, entry)| *entry == value));
+
+        Ok(())
+    }
+
     #[tokio::test(flavor = "multi_thread")]
     async fn register_owner() -> Result<()> {
         init_logger();
@@ -669,6 +1456,28 @@ mod tests {
         Ok(())
     }
 
+    // Stamp the edit further in the past than the network accepts, and sign it again
+    fn stale_register_cmd(cmd: RegisterCmd, keypair: &Keypair) -> Result<RegisterCmd> {
+        let drift = MAX_STAMP_CLOCK_DRIFT.as_secs() + 60;
+        let auth = |bytes: Vec<u8>| ClientAuth {
+            public_key: keypair.public_key(),
+            signature: keypair.sign(&bytes),
+        };
+        match cmd {
+            RegisterCmd::Edit(SignedRegisterEdit { mut op, .. }) => {
+                op.stamp.time -= drift;
+                let auth = auth(bincode::serialize(&op)?);
+                Ok(RegisterCmd::Edit(SignedRegisterEdit { op, auth }))
+            }
+            RegisterCmd::UpdatePolicy(mut cmd) => {
+                cmd.op.stamp.time -= drift;
+                cmd.auth = auth(bincode::serialize(&cmd.op)?);
+                Ok(RegisterCmd::UpdatePolicy(cmd))
+            }
+            other => bail!("Unexpected Register cmd without stamp: {other:?}"),
+        }
+    }
+
     fn random_register_entry() -> Vec<u8> {
         let random_bytes = rand::thread_rng().gen::<[u8; 32]>();
         random_bytes.to_vec()
@@ -13,14 +13,14 @@ use crate::Error;
 use sn_dbc::{KeyImage, RingCtTransaction, SpentProof, SpentProofShare};
 use sn_interface::{
     messaging::data::{
-        DataCmd, DataQueryVariant, Error as NetworkDataError, QueryResponse, SpentbookCmd,
-        SpentbookQuery,
+        DataCmd, DataQueryVariant, Error as NetworkDataError, QueryResponse, SpentKeyImage,
+        SpentKeyImagesPage, SpentbookCmd, SpentbookQuery,
     },
     types::SpentbookAddress,
 };
 
-use std::collections::BTreeSet;
-use xor_name::XorName;
+use std::collections::{BTreeMap, BTreeSet};
+use xor_name::{Prefix, XorName};
 
 // Maximum number of attempts when retrying a spend DBC operation with updated network knowledge.
 const MAX_SPEND_DBC_ATTEMPS: u8 = 5;
@@ -123,6 +123,98 @@ impl Client {
             }),
         }
     }
+
+    /// Return a page of the key images spent within the given address range, together with the
+    /// hashes of the transactions they were spent in, starting after the `start_after` cursor.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn spent_key_images_page(
+        &self,
+        prefix: Prefix,
+        start_after: Option<XorName>,
+    ) -> Result<SpentKeyImagesPage, Error> {
+        let query = DataQueryVariant::Spentbook(SpentbookQuery::SpentKeyImages {
+            prefix,
+            start_after,
+        });
+        let query_result = self.send_query(query.clone()).await?;
+        match query_result.response {
+            QueryResponse::SpentKeyImages(res) => {
+                res.map_err(|err| Error::ErrorMsg { source: err })
+            }
+            other => Err(Error::UnexpectedQueryResponse {
+                query,
+                response: other,
+            }),
+        }
+    }
+
+    /// Return all the key images spent within the given address range, walking through the
+    /// pages of the spentbook of each section holding a part of the range. Transaction hashes
+    /// reported for the same key image are merged.
+    ///
+    /// An error is returned if any part of the range cannot be listed, so a partial list is
+    /// never reported as the complete one.
+    #[instrument(skip(self), level = "debug")]
+    pub async fn spent_key_images(&self, prefix: Prefix) -> Result<Vec<SpentKeyImage>, Error> {
+        // each section lists its own part of the range, so let's walk them one by one
+        let mut ranges: Vec<(Prefix, Option<XorName>)> = self
+            .session
+            .network
+            .read()
+            .await
+            .all()
+            .map(|sap| sap.prefix())
+            .filter(|section| section.is_extension_of(&prefix))
+            .map(|section| (section, None))
+            .collect();
+        if ranges.is_empty() {
+            ranges.push((prefix, None));
+        }
+
+        let mut spent = BTreeMap::new();
+        while let Some((range, start_after)) = ranges.pop() {
+            let page = self.spent_key_images_page(range, start_after).await?;
+            if page.prefix != range {
+                if !page.prefix.is_extension_of(&range) {
+                    return Err(Error::IncompleteSpentKeyImages(format!(
+                        "page listed from {:?} when {range:?} was queried",
+                        page.prefix
+                    )));
+                }
+                // the range spans more than one section, e.g. after a split, so let's walk
+                // each half of it separately
+                ranges.push((range.pushed(true), start_after));
+                ranges.push((range.pushed(false), start_after));
+                continue;
+            }
+
+            for item in page.spent {
+                spent
+                    .entry(item.name())
+                    .or_insert_with(|| SpentKeyImage {
+                        key_image: item.key_image,
+                        transaction_hashes: BTreeSet::new(),
+                    })
+                    .transaction_hashes
+                    .extend(item.transaction_hashes);
+            }
+
+            match page.next {
+                Some(next) if start_after.is_none_or(|after| next > after) => {
+                    ranges.push((range, Some(next)))
+                }
+                // a cursor not moving forward would loop forever
+                Some(next) => {
+                    return Err(Error::IncompleteSpentKeyImages(format!(
+                        "cursor {next:?} of range {range:?} didn't move forward"
+                    )))
+                }
+                None => {}
+            }
+        }
+
+        Ok(spent.into_values().collect())
+    }
 }
 
 #[cfg(test)]
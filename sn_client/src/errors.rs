@@ -52,6 +52,23 @@ pub enum Error {
     /// Failed to obtain network contacts to bootstrap to
     #[error("Failed to obtain network contacts to bootstrap to: {0}")]
     NetworkContacts(String),
+    /// Failed to set up the persistent chunk cache
+    #[error("Failed to set up the persistent chunk cache: {0}")]
+    ChunkCache(String),
+    /// Failed to set up, or use, the on-disk Register write-ahead log
+    #[error("Failed to use the Register write-ahead log: {0}")]
+    RegisterWal(String),
+    /// Register ops could not be published, they are kept in the write-ahead log until they are
+    #[error("Failed to publish Register ops, {pending} of them are kept pending: {source}")]
+    RegisterOpsPending {
+        /// Number of ops pending in the write-ahead log
+        pending: usize,
+        /// The error which prevented the ops from being published
+        source: Box<Error>,
+    },
+    /// The spent key images could not be fully listed
+    #[error("Failed to list all the spent key images: {0}")]
+    IncompleteSpentKeyImages(String),
     /// InsufficientAcksReceived
     #[error(
         "Did not receive sufficient ACK messages from Elders to be sure this cmd ({msg_id:?}) \
@@ -165,6 +182,14 @@ pub enum Error {
         /// Peers the msg was sent to
         peers: Vec<Peer>,
     },
+    /// The responses obtained from Elders don't agree on the result.
+    #[error("No response for {msg_id:?} was returned by a majority of: {peers:?}")]
+    NoMajorityResponse {
+        /// MsgId of the msg sent
+        msg_id: MsgId,
+        /// Peers the msg was sent to
+        peers: Vec<Peer>,
+    },
     /// Timeout when awaiting command ACK from Elders.
     #[error("Timeout after {elapsed:?} when awaiting command ACK from Elders for data address {dst_address}")]
     CmdAckValidationTimeout {
@@ -58,10 +58,14 @@ mod connections;
 mod errors;
 
 // Export public API.
-pub use api::{Client, RegisterWriteAheadLog, DEFAULT_NETWORK_CONTACTS_FILE_NAME};
+pub use api::{
+    Client, RegisterReplicas, RegisterWatch, RegisterWriteAheadLog,
+    DEFAULT_NETWORK_CONTACTS_FILE_NAME,
+};
 pub use connections::LinkError;
 pub use errors::{Error, Result};
 pub use qp2p::Config as QuicP2pConfig;
+pub use sessions::QueryResponsePolicy;
 pub use sn_interface::messaging::data::Error as ErrorMsg;
 
 /// Client trait and related constants.
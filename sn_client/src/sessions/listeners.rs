@@ -13,14 +13,15 @@ use crate::{Error, Result};
 use qp2p::{RecvStream, UsrMsgBytes};
 use sn_interface::{
     messaging::{
-        data::{ClientDataResponse, ClientMsg},
+        data::{ClientDataResponse, ClientMsg, QueryResponse},
         AuthorityProof, ClientAuth, Dst, MsgId, MsgKind, MsgType, WireMsg,
     },
     network_knowledge::SectionTreeUpdate,
-    types::{log_markers::LogMarker, Peer},
+    types::{log_markers::LogMarker, Peer, RegisterCmd},
 };
 
 use itertools::Itertools;
+use tokio::sync::mpsc;
 
 // Maximum number of times we'll re-send a msg upon receiving an AE response for it
 const MAX_AE_RETRIES_TO_ATTEMPT: u8 = 5;
@@ -97,6 +98,17 @@ impl Session {
                 };
 
             match resp_msg {
+                ClientDataResponse::QueryResponse {
+                    response: QueryResponse::WatchRegister(Ok(())),
+                    correlation_id,
+                } => {
+                    trace!(
+                        "Register watched with id {msg_id:?} regarding correlation_id \
+                        {correlation_id:?} by {peer:?}, on {stream_id}"
+                    );
+                    // the events are to be received on this same stream
+                    return MsgResponse::RegisterWatch(peer, recv_stream);
+                }
                 ClientDataResponse::QueryResponse {
                     response,
                     correlation_id,
@@ -117,6 +129,13 @@ impl Session {
                     );
                     break MsgResponse::CmdResponse(addr, Box::new(response));
                 }
+                ClientDataResponse::RegisterEvent { .. } => {
+                    warn!(
+                        "Unexpected Register event with id {msg_id:?} received for \
+                        {correlation_id:?} from {peer:?}, before the response to it"
+                    );
+                    continue;
+                }
                 ClientDataResponse::AntiEntropy {
                     section_tree_update,
                     bounced_msg,
@@ -166,6 +185,41 @@ impl Session {
         result
     }
 
+    // Forward the events of a watched Register received on the stream,
+    // until the stream is closed or the watch is dropped.
+    #[instrument(skip_all, level = "debug")]
+    pub(super) async fn register_events_listener(
+        correlation_id: MsgId,
+        peer: Peer,
+        mut recv_stream: RecvStream,
+        events_sender: mpsc::UnboundedSender<RegisterCmd>,
+    ) {
+        loop {
+            match Self::read_resp_from_recvstream(&mut recv_stream, peer, correlation_id).await {
+                Ok((_, ClientDataResponse::RegisterEvent { cmd, .. })) => {
+                    trace!("Register event for {correlation_id:?} from {peer:?}: {cmd:?}");
+                    if events_sender.send(cmd).is_err() {
+                        debug!("Register watch {correlation_id:?} was dropped");
+                        break;
+                    }
+                }
+                Ok((msg_id, msg)) => {
+                    warn!(
+                        "Unexpected msg {msg_id:?} received from {peer:?} while watching a \
+                        Register with {correlation_id:?}: {msg}"
+                    );
+                }
+                Err(error) => {
+                    debug!(
+                        "Stopped receiving Register events for {correlation_id:?} \
+                        from {peer:?}: {error:?}"
+                    );
+                    break;
+                }
+            }
+        }
+    }
+
     // Handle Anti-Entropy Redirect or Retry msgs
     #[instrument(skip_all, level = "debug")]
     async fn handle_ae_msg(
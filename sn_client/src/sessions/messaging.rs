@@ -6,21 +6,25 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{MsgResponse, QueryResult, Session};
+use super::{MsgResponse, QueryResponsePolicy, QueryResult, Session};
 use crate::{Error, Result};
 use sn_interface::{
     messaging::{
-        data::{DataQuery, DataQueryVariant, QueryResponse},
+        data::{
+            CmdResponse, DataQuery, DataQueryVariant, Error as ErrorMsg, QueryResponse,
+            SpentKeyImagesPage,
+        },
         ClientAuth, Dst, MsgId, MsgKind, WireMsg,
     },
     network_knowledge::supermajority,
-    types::{ChunkAddress, Peer},
+    types::{ChunkAddress, Peer, RegisterCmd},
 };
 
 use bytes::Bytes;
 use rand::{rngs::OsRng, seq::SliceRandom};
+use sn_dbc::SpentProofShare;
 use std::collections::BTreeSet;
-use tokio::task::JoinSet;
+use tokio::{sync::mpsc, task::JoinSet};
 use tracing::{debug, error, trace, warn};
 use xor_name::XorName;
 
@@ -71,6 +75,50 @@ impl Session {
         auth: ClientAuth,
         payload: Bytes,
     ) -> Result<()> {
+        let (msg_id, elders, send_cmd_tasks) =
+            self.send_cmd_to_elders(dst_address, auth, payload).await?;
+
+        // We wait for ALL the Acks get received.
+        // The AE messages are handled by the tasks, hence no extra wait is required.
+        self.we_have_sufficient_acks_for_cmd(msg_id, elders, send_cmd_tasks)
+            .await
+    }
+
+    #[instrument(
+        skip(self, auth, payload),
+        level = "debug",
+        name = "session send cmd batch"
+    )]
+    /// Send a batch of cmds, i.e. a `DataCmd::Batch` with `items` operations, to the Elders of
+    /// the section the `dst_address` belongs to, returning the result of each of the operations,
+    /// in the same order.
+    pub(crate) async fn send_cmd_batch(
+        &self,
+        dst_address: XorName,
+        auth: ClientAuth,
+        payload: Bytes,
+        items: usize,
+    ) -> Result<Vec<Result<()>>> {
+        let (msg_id, elders, send_cmd_tasks) =
+            self.send_cmd_to_elders(dst_address, auth, payload).await?;
+
+        self.we_have_sufficient_acks_for_batch(msg_id, elders, items, send_cmd_tasks)
+            .await
+    }
+
+    /// Key of the section the `dst_address` belongs to, as far as we know
+    pub(crate) async fn dst_section_key(&self, dst_address: XorName) -> Result<bls::PublicKey> {
+        let (section_pk, _) = self.get_cmd_elders(dst_address).await?;
+        Ok(section_pk)
+    }
+
+    // Send the cmd to the Elders of the section the `dst_address` belongs to
+    async fn send_cmd_to_elders(
+        &self,
+        dst_address: XorName,
+        auth: ClientAuth,
+        payload: Bytes,
+    ) -> Result<(MsgId, Vec<Peer>, JoinSet<MsgResponse>)> {
         let endpoint = self.endpoint.clone();
         // TODO: Consider other approach: Keep a session per section!
         let (section_pk, elders) = self.get_cmd_elders(dst_address).await?;
@@ -93,10 +141,7 @@ impl Session {
         let send_cmd_tasks = self.send_msg(elders.clone(), wire_msg).await?;
         trace!("Cmd msg {:?} sent", msg_id);
 
-        // We wait for ALL the Acks get received.
-        // The AE messages are handled by the tasks, hence no extra wait is required.
-        self.we_have_sufficient_acks_for_cmd(msg_id, elders.clone(), send_cmd_tasks)
-            .await
+        Ok((msg_id, elders, send_cmd_tasks))
     }
 
     /// Checks for acks for a given msg.
@@ -122,6 +167,11 @@ impl Session {
                     let _ = received_errors.insert(src);
                     continue;
                 }
+                Ok(MsgResponse::RegisterWatch(peer, _)) => {
+                    debug!("Unexpected Register watch response received from {peer:?} for {msg_id:?} when awaiting a CmdAck");
+                    let _ = received_errors.insert(peer.addr());
+                    continue;
+                }
                 Ok(MsgResponse::Failure(src, error)) => {
                     debug!("Failure occurred with msg {msg_id:?} from {src:?}: {error:?}");
                     let _ = failures.insert(src);
@@ -194,6 +244,93 @@ impl Session {
         })
     }
 
+    /// Checks for the acks of each of the operations of a batch.
+    /// An operation is a success if acked by all the Elders, and an error
+    /// if they all failed to ack it, at least one of them with an error.
+    async fn we_have_sufficient_acks_for_batch(
+        &self,
+        msg_id: MsgId,
+        elders: Vec<Peer>,
+        items: usize,
+        mut send_cmd_tasks: JoinSet<MsgResponse>,
+    ) -> Result<Vec<Result<()>>> {
+        debug!("----> init of check for acks for batch {:?}", msg_id);
+        let expected_acks = elders.len();
+        let mut received_acks = vec![0; items];
+        let mut received_errors: Vec<Vec<ErrorMsg>> = vec![vec![]; items];
+        let mut failures = 0;
+
+        while let Some(msg_resp) = send_cmd_tasks.join_next().await {
+            debug!("Handling msg_resp sent to ack wait channel: {msg_resp:?}");
+            let results = match msg_resp {
+                Ok(MsgResponse::CmdResponse(src, response)) => match *response {
+                    CmdResponse::Batch(responses) if responses.len() == items => responses
+                        .iter()
+                        .map(|response| response.result().clone())
+                        .collect::<Vec<_>>(),
+                    // a response to the batch as a whole applies to each of its operations
+                    response => {
+                        debug!("Response to the whole batch {msg_id:?} received from {src:?}: {response:?}");
+                        vec![response.result().clone(); items]
+                    }
+                },
+                Ok(MsgResponse::QueryResponse(src, resp)) => {
+                    debug!("Unexpected query response received from {src:?} for {msg_id:?} when awaiting a CmdAck: {resp:?}");
+                    failures += 1;
+                    continue;
+                }
+                Ok(MsgResponse::RegisterWatch(peer, _)) => {
+                    debug!("Unexpected Register watch response received from {peer:?} for {msg_id:?} when awaiting a CmdAck");
+                    failures += 1;
+                    continue;
+                }
+                Ok(MsgResponse::Failure(src, error)) => {
+                    debug!("Failure occurred with msg {msg_id:?} from {src:?}: {error:?}");
+                    failures += 1;
+                    continue;
+                }
+                Err(join_err) => {
+                    warn!("Join failure occurred with msg {msg_id:?}: {join_err:?}");
+                    continue;
+                }
+            };
+
+            for (index, result) in results.into_iter().enumerate() {
+                match result {
+                    Ok(()) => received_acks[index] += 1,
+                    Err(error) => received_errors[index].push(error),
+                }
+            }
+        }
+
+        let results = received_acks
+            .into_iter()
+            .zip(received_errors)
+            .map(|(acks, mut errors)| {
+                if acks >= expected_acks {
+                    Ok(())
+                } else if !errors.is_empty() && errors.len() + failures >= expected_acks {
+                    Err(Error::CmdError {
+                        source: errors.remove(0),
+                        msg_id,
+                    })
+                } else {
+                    Err(Error::InsufficientAcksReceived {
+                        msg_id,
+                        expected: expected_acks,
+                        received: acks,
+                    })
+                }
+            })
+            .collect::<Vec<_>>();
+
+        debug!(
+            "{} of the {items} ops of batch {msg_id:?} acked",
+            results.iter().filter(|result| result.is_ok()).count()
+        );
+        Ok(results)
+    }
+
     #[instrument(
         skip(self, auth, payload),
         level = "debug",
@@ -245,16 +382,10 @@ impl Session {
 
         let send_query_tasks = self.send_msg(elders.clone(), wire_msg).await?;
 
-        // TODO:
-        // We are now simply accepting the very first valid response we receive,
-        // but we may want to revisit this to compare multiple responses and validate them,
-        // similar to what we used to do up to the following commit:
-        // https://github.com/maidsafe/sn_client/blob/9091a4f1f20565f25d3a8b00571cc80751918928/src/connection_manager.rs#L328
-        //
-        // For Chunk responses we already validate its hash matches the xorname requested from,
+        // For Chunk responses we validate its hash matches the xorname requested from,
         // so we don't need more than one valid response to prevent from accepting invalid responses
-        // from byzantine nodes, however for mutable data (non-Chunk responses) we will
-        // have to review the approach.
+        // from byzantine nodes, however mutable data (non-Chunk responses) are compared or merged
+        // as per the query response policy.
         self.check_query_responses(msg_id, elders.clone(), chunk_addr, send_query_tasks)
             .await
     }
@@ -266,9 +397,13 @@ impl Session {
         chunk_addr: Option<ChunkAddress>,
         mut send_query_tasks: JoinSet<MsgResponse>,
     ) -> Result<QueryResult> {
+        let policy = self.query_response_policy;
         let mut discarded_responses: usize = 0;
         let mut error_response = None;
+        // a response accepted straight away, or merged from all the responses
         let mut valid_response = None;
+        // distinct responses to be compared, along with the number of nodes which returned them
+        let mut compared_responses: Vec<(QueryResponse, usize)> = Vec::new();
         let elders_len = elders.len();
 
         while let Some(msg_resp) = send_query_tasks.join_next().await {
@@ -279,6 +414,11 @@ impl Session {
                     discarded_responses += 1;
                     continue;
                 }
+                Ok(MsgResponse::RegisterWatch(peer, _)) => {
+                    debug!("Unexpected Register watch response received from {peer:?} for {msg_id:?} when awaiting a QueryResponse");
+                    discarded_responses += 1;
+                    continue;
+                }
                 Ok(MsgResponse::Failure(src, error)) => {
                     debug!("Failure occurred with msg {msg_id:?} from {src:?}: {error:?}");
                     discarded_responses += 1;
@@ -313,6 +453,7 @@ impl Session {
                     }
                 }
                 QueryResponse::GetRegister(Err(_))
+                | QueryResponse::GetRegisterLog(Err(_))
                 | QueryResponse::ReadRegister(Err(_))
                 | QueryResponse::GetRegisterPolicy(Err(_))
                 | QueryResponse::GetRegisterOwner(Err(_))
@@ -327,66 +468,208 @@ impl Session {
                     error_response = Some(*response);
                     discarded_responses += 1;
                 }
-                QueryResponse::GetRegister(Ok(ref register)) => {
-                    debug!("okay got register from {peer_address:?}");
-                    // TODO: properly merge all registers
-                    if let Some(QueryResponse::GetRegister(Ok(prior_response))) = &valid_response {
-                        if register.size() > prior_response.size() {
-                            debug!("longer register");
-                            // keep this new register
-                            valid_response = Some(*response);
-                        }
-                    } else {
-                        valid_response = Some(*response);
-                    }
+                response if policy == QueryResponsePolicy::FirstValid => {
+                    debug!("okay got first valid response from {peer_address:?}");
+                    valid_response = Some(response);
+                    break;
                 }
-                QueryResponse::ReadRegister(Ok(_)) => {
-                    debug!("okay _read_ register from {peer_address:?}");
-                    if valid_response.is_none() {
-                        valid_response = Some(*response);
+                QueryResponse::GetRegisterLog(Ok(log))
+                    if policy == QueryResponsePolicy::MergeAll =>
+                {
+                    debug!("okay got register log from {peer_address:?}");
+                    // the cmds are all verified before being merged, the policy updates are then
+                    // checked against the policy when the Register is reconstructed from them
+                    let result = if let Some(QueryResponse::GetRegisterLog(Ok(merged))) =
+                        &mut valid_response
+                    {
+                        merged.merge(log)
+                    } else {
+                        log.verify().map(|()| {
+                            valid_response = Some(QueryResponse::GetRegisterLog(Ok(log)));
+                        })
+                    };
+                    if let Err(err) = result {
+                        warn!("Discarding register log received from {peer_address:?} for {msg_id:?}: {err:?}");
+                        discarded_responses += 1;
                     }
                 }
-                QueryResponse::SpentProofShares(Ok(ref spentproof_set)) => {
+                QueryResponse::SpentProofShares(Ok(spentproof_set))
+                    if policy == QueryResponsePolicy::MergeAll =>
+                {
                     debug!("okay _read_ spentproofs from {peer_address:?}");
-                    // TODO: properly merge all registers
-                    if let Some(QueryResponse::SpentProofShares(Ok(prior_response))) =
-                        &valid_response
+                    if !self.verify_spent_proof_shares(&spentproof_set).await {
+                        warn!("Discarding spent proof shares received from {peer_address:?} for {msg_id:?}, holding an invalid share");
+                        discarded_responses += 1;
+                    } else if let Some(QueryResponse::SpentProofShares(Ok(merged))) =
+                        &mut valid_response
                     {
-                        if spentproof_set.len() > prior_response.len() {
-                            debug!("longer spentproof response retrieved");
-                            // keep this new register
-                            valid_response = Some(*response);
+                        for share in spentproof_set {
+                            if !merged.contains(&share) {
+                                merged.push(share);
+                            }
+                        }
+                    } else {
+                        valid_response = Some(QueryResponse::SpentProofShares(Ok(spentproof_set)));
+                    }
+                }
+                QueryResponse::SpentKeyImages(Ok(page))
+                    if policy == QueryResponsePolicy::MergeAll =>
+                {
+                    debug!("okay _read_ spent key images from {peer_address:?}");
+                    if let Some(QueryResponse::SpentKeyImages(Ok(merged))) = &mut valid_response {
+                        match SpentKeyImagesPage::merge([merged.clone(), page]) {
+                            Some(page) => *merged = page,
+                            None => {
+                                warn!("Discarding spent key images page received from {peer_address:?} for {msg_id:?}, listed from another range");
+                                discarded_responses += 1;
+                            }
                         }
                     } else {
-                        valid_response = Some(*response);
+                        valid_response = Some(QueryResponse::SpentKeyImages(Ok(page)));
                     }
                 }
                 response => {
-                    // we got a valid response
-                    valid_response = Some(response)
+                    // to be compared with the responses from the other nodes
+                    debug!("okay got response to be compared from {peer_address:?}");
+                    if let Some((_, count)) = compared_responses
+                        .iter_mut()
+                        .find(|(compared, _)| *compared == response)
+                    {
+                        *count += 1;
+                    } else {
+                        compared_responses.push((response, 1));
+                    }
                 }
             }
         }
 
         // we've looped over all responses...
-        // if any are valid, lets return it
+        // if any was accepted, or merged from all of them, lets return it
         if let Some(response) = valid_response {
             debug!("valid response innnn!!! : {:?}", response);
             return Ok(QueryResult { response });
-            // otherwise, if we've got an error in
-            // we can return that too
-        } else if let Some(response) = error_response {
+        }
+
+        // otherwise, the response returned by a majority of the nodes queried is accepted
+        let responses_disagree = !compared_responses.is_empty();
+        if let Some((response, count)) = compared_responses
+            .into_iter()
+            .max_by_key(|(_, count)| *count)
+        {
+            if count > elders_len / 2 {
+                debug!(
+                    "response returned by {count}/{elders_len} nodes: {:?}",
+                    response
+                );
+                return Ok(QueryResult { response });
+            }
+        }
+
+        // or, if we've got an error in, we can return that too
+        if let Some(response) = error_response {
             if discarded_responses > elders_len / 2 {
                 return Ok(QueryResult { response });
             }
         }
 
-        Err(Error::NoResponse {
-            msg_id,
-            peers: elders,
+        if responses_disagree {
+            Err(Error::NoMajorityResponse {
+                msg_id,
+                peers: elders,
+            })
+        } else {
+            Err(Error::NoResponse {
+                msg_id,
+                peers: elders,
+            })
+        }
+    }
+
+    // Verify each spent proof share is signed by a section we know of.
+    async fn verify_spent_proof_shares(&self, shares: &[SpentProofShare]) -> bool {
+        let network = self.network.read().await;
+        let sections_dag = network.get_sections_dag();
+        shares.iter().all(|share| {
+            let (index, sig_share) = share.spentbook_sig_share().threshold_crypto();
+            sections_dag.has_key(&share.spentbook_pks().public_key())
+                && share
+                    .spentbook_pks()
+                    .public_key_share(index)
+                    .verify(sig_share, share.content.hash())
         })
     }
 
+    #[instrument(skip(self, auth, payload, events_sender), level = "debug")]
+    /// Send a Register watch query to the network, and forward the events of the Register
+    /// received from each of the Elders watching it.
+    pub(crate) async fn watch_register(
+        &self,
+        query: DataQuery,
+        auth: ClientAuth,
+        payload: Bytes,
+        events_sender: mpsc::UnboundedSender<RegisterCmd>,
+    ) -> Result<()> {
+        let dst = query.variant.dst_name();
+        let (section_pk, elders) = self.get_query_elders(dst).await?;
+        let msg_id = MsgId::new();
+
+        debug!(
+            "Sending Register watch query {msg_id:?}, {query:?} to the {} Elders closest to data name: {elders:?}",
+            elders.len()
+        );
+
+        let dst = Dst {
+            name: dst,
+            section_key: section_pk,
+        };
+        let kind = MsgKind::Client(auth);
+        let wire_msg = WireMsg::new_msg(msg_id, payload, kind, dst);
+
+        let mut send_query_tasks = self.send_msg(elders.clone(), wire_msg).await?;
+
+        let mut watching = 0;
+        let mut error_response = None;
+        while let Some(msg_resp) = send_query_tasks.join_next().await {
+            match msg_resp {
+                Ok(MsgResponse::RegisterWatch(peer, recv_stream)) => {
+                    watching += 1;
+                    let _handle = tokio::spawn(Self::register_events_listener(
+                        msg_id,
+                        peer,
+                        recv_stream,
+                        events_sender.clone(),
+                    ));
+                }
+                Ok(MsgResponse::QueryResponse(src, response)) => {
+                    debug!("Register watch {msg_id:?} rejected by {src:?}: {response:?}");
+                    error_response = Some(*response);
+                }
+                Ok(MsgResponse::CmdResponse(src, response)) => {
+                    debug!("Unexpected cmd response received from {src:?} for {msg_id:?} when awaiting a QueryResponse: {response:?}");
+                }
+                Ok(MsgResponse::Failure(src, error)) => {
+                    debug!("Failure occurred with msg {msg_id:?} from {src:?}: {error:?}");
+                }
+                Err(join_err) => {
+                    warn!("Join failure occurred with msg {msg_id:?}: {join_err:?}");
+                }
+            }
+        }
+
+        if watching > 0 {
+            debug!("Register watched by {watching}/{} Elders", elders.len());
+            return Ok(());
+        }
+
+        match error_response {
+            Some(QueryResponse::WatchRegister(Err(source))) => Err(Error::ErrorMsg { source }),
+            _ => Err(Error::NoResponse {
+                msg_id,
+                peers: elders,
+            }),
+        }
+    }
+
     /// Get DataSection elders details. Resort to own section if DataSection is not available.
     /// Takes a random subset (NUM_OF_ELDERS_SUBSET_FOR_QUERIES) of the avialable elders as targets
     pub(crate) async fn get_query_elders(
@@ -518,12 +801,19 @@ mod tests {
     use super::*;
     use sn_interface::{
         network_knowledge::SectionTree,
-        test_utils::{prefix, TestKeys, TestSapBuilder},
+        test_utils::{create_register_cmd, edit_register_cmd, prefix, TestKeys, TestSapBuilder},
+        types::{
+            register::{Register, User},
+            Keypair, ReplicatedRegisterLog,
+        },
     };
 
-    use eyre::Result;
+    use eyre::{eyre, Result};
     use qp2p::Config;
+    use sn_dbc::{Hash, IndexedSignatureShare, SpentProofContent};
     use std::net::{Ipv4Addr, SocketAddr};
+
+    use eyre::bail;
     use xor_name::Prefix;
 
     fn new_network_network_contacts() -> (SectionTree, bls::SecretKey, bls::PublicKey) {
@@ -551,6 +841,7 @@ mod tests {
             Config::default(),
             SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
             network_contacts,
+            QueryResponsePolicy::default(),
         )?;
 
         let mut rng = rand::thread_rng();
@@ -560,4 +851,164 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn query_responses_register_logs_are_verified_and_merged() -> Result<()> {
+        let session = new_session(QueryResponsePolicy::MergeAll)?;
+
+        let keypair = Keypair::new_ed25519();
+        let owner = User::Key(keypair.public_key());
+        let mut rng = rand::thread_rng();
+        let mut replica1 = Register::new_owned(owner, XorName::random(&mut rng), 1);
+        let mut replica2 = replica1.clone();
+        let create = create_register_cmd(&replica1, &keypair);
+        let (hash1, edit1) =
+            edit_register_cmd(&mut replica1, b"entry1".to_vec(), &keypair, &keypair)?;
+        let (hash2, edit2) =
+            edit_register_cmd(&mut replica2, b"entry2".to_vec(), &keypair, &keypair)?;
+        // an entry forged by a faulty node, which cannot sign it as the owner
+        let forger = Keypair::new_ed25519();
+        let (forged_hash, forged) =
+            edit_register_cmd(&mut replica1.clone(), b"forged".to_vec(), &keypair, &forger)?;
+
+        // a node missing the latest entries cannot hide them, nor add entries to the Register
+        let address = *replica1.address();
+        let responses = vec![
+            QueryResponse::GetRegisterLog(Ok(ReplicatedRegisterLog {
+                address,
+                op_log: vec![create.clone(), edit1.clone()],
+            })),
+            QueryResponse::GetRegisterLog(Ok(ReplicatedRegisterLog {
+                address,
+                op_log: vec![create.clone(), edit2],
+            })),
+            QueryResponse::GetRegisterLog(Ok(ReplicatedRegisterLog {
+                address,
+                op_log: vec![create, edit1, forged],
+            })),
+        ];
+        let (elders, tasks) = query_response_tasks(responses);
+        let result = session
+            .check_query_responses(MsgId::new(), elders, None, tasks)
+            .await?;
+        match result.response {
+            QueryResponse::GetRegisterLog(Ok(log)) => {
+                let register =
+                    Register::from_log(&log.op_log).ok_or_else(|| eyre!("Register not found"))?;
+                let hashes: BTreeSet<_> = register.read().into_iter().map(|(h, _)| h).collect();
+                assert_eq!(hashes, BTreeSet::from([hash1, hash2]));
+                assert!(!hashes.contains(&forged_hash));
+            }
+            other => bail!("Unexpected response: {other:?}"),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn query_responses_spent_proof_shares_are_verified() -> Result<()> {
+        let (genesis_sap, genesis_sk_set, ..) = TestSapBuilder::new(Prefix::default()).build();
+        let genesis_sap = TestKeys::get_section_signed(&genesis_sk_set.secret_key(), genesis_sap);
+        let session = Session::new(
+            Config::default(),
+            SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            SectionTree::new(genesis_sap)?,
+            QueryResponsePolicy::MergeAll,
+        )?;
+
+        let valid = spent_proof_share(&genesis_sk_set);
+        // a share signed by a section we don't know of
+        let unknown = spent_proof_share(&bls::SecretKeySet::random(0, &mut rand::thread_rng()));
+        // a share whose signature is not over its content
+        let mut forged = spent_proof_share(&genesis_sk_set);
+        forged.spentbook_sig_share = spent_proof_share(&genesis_sk_set).spentbook_sig_share;
+
+        let responses = vec![
+            QueryResponse::SpentProofShares(Ok(vec![valid.clone()])),
+            QueryResponse::SpentProofShares(Ok(vec![unknown])),
+            QueryResponse::SpentProofShares(Ok(vec![valid.clone(), forged])),
+        ];
+        let (elders, tasks) = query_response_tasks(responses);
+        let result = session
+            .check_query_responses(MsgId::new(), elders, None, tasks)
+            .await?;
+        assert_eq!(
+            result.response,
+            QueryResponse::SpentProofShares(Ok(vec![valid]))
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn query_responses_are_returned_by_majority() -> Result<()> {
+        let session = new_session(QueryResponsePolicy::Majority)?;
+
+        let owner = User::Key(Keypair::new_ed25519().public_key());
+        let forged = User::Key(Keypair::new_ed25519().public_key());
+        let responses = vec![
+            QueryResponse::GetRegisterOwner(Ok(forged)),
+            QueryResponse::GetRegisterOwner(Ok(owner)),
+            QueryResponse::GetRegisterOwner(Ok(owner)),
+        ];
+        let (elders, tasks) = query_response_tasks(responses);
+        let result = session
+            .check_query_responses(MsgId::new(), elders, None, tasks)
+            .await?;
+        assert_eq!(result.response, QueryResponse::GetRegisterOwner(Ok(owner)));
+
+        // no response is accepted if the nodes don't agree
+        let responses = vec![
+            QueryResponse::GetRegisterOwner(Ok(forged)),
+            QueryResponse::GetRegisterOwner(Ok(owner)),
+            QueryResponse::GetRegisterOwner(Ok(User::Anyone)),
+        ];
+        let (elders, tasks) = query_response_tasks(responses);
+        match session
+            .check_query_responses(MsgId::new(), elders, None, tasks)
+            .await
+        {
+            Err(Error::NoMajorityResponse { .. }) => Ok(()),
+            other => bail!("Unexpected result: {other:?}"),
+        }
+    }
+
+    fn new_session(policy: QueryResponsePolicy) -> Result<Session> {
+        let (network_contacts, ..) = new_network_network_contacts();
+        let session = Session::new(
+            Config::default(),
+            SocketAddr::from((Ipv4Addr::UNSPECIFIED, 0)),
+            network_contacts,
+            policy,
+        )?;
+        Ok(session)
+    }
+
+    fn spent_proof_share(sk_set: &bls::SecretKeySet) -> SpentProofShare {
+        let content = SpentProofContent {
+            key_image: bls::SecretKey::random().public_key(),
+            transaction_hash: Hash::from([0; 32]),
+            public_commitments: vec![],
+        };
+        let signature_share = sk_set.secret_key_share(0).sign(content.hash());
+        SpentProofShare {
+            content,
+            spentbook_pks: sk_set.public_keys(),
+            spentbook_sig_share: IndexedSignatureShare::new(0, signature_share),
+        }
+    }
+
+    // Tasks resolving to the given responses, as if each was sent by a different Elder
+    fn query_response_tasks(responses: Vec<QueryResponse>) -> (Vec<Peer>, JoinSet<MsgResponse>) {
+        let mut rng = rand::thread_rng();
+        let mut elders = Vec::new();
+        let mut tasks = JoinSet::new();
+        for (i, response) in responses.into_iter().enumerate() {
+            let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, 12000 + i as u16));
+            elders.push(Peer::new(XorName::random(&mut rng), addr));
+            let _ =
+                tasks.spawn(async move { MsgResponse::QueryResponse(addr, Box::new(response)) });
+        }
+        (elders, tasks)
+    }
 }
@@ -14,10 +14,11 @@ use crate::{connections::PeerLinks, Error, Result};
 use sn_interface::{
     messaging::data::{CmdResponse, QueryResponse},
     network_knowledge::SectionTree,
+    types::Peer,
 };
 
-use qp2p::{Config as QuicP2pConfig, Endpoint};
-use std::{net::SocketAddr, sync::Arc};
+use qp2p::{Config as QuicP2pConfig, Endpoint, RecvStream};
+use std::{net::SocketAddr, str::FromStr, sync::Arc};
 use tokio::sync::RwLock;
 
 // Use for internal communication between the bi-stream response listener threads
@@ -26,6 +27,8 @@ use tokio::sync::RwLock;
 pub(super) enum MsgResponse {
     CmdResponse(SocketAddr, Box<CmdResponse>),
     QueryResponse(SocketAddr, Box<QueryResponse>),
+    // A Register is being watched, its events are to be received on the stream
+    RegisterWatch(Peer, RecvStream),
     Failure(SocketAddr, Error),
 }
 
@@ -45,6 +48,41 @@ impl QueryResult {
     }
 }
 
+/// How the responses to a query, received from the nodes it was sent to, are validated
+/// before one is returned.
+///
+/// Chunks are self-verifying, their content has to match the address they were requested from,
+/// so the first valid Chunk received is always accepted, whatever the policy.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum QueryResponsePolicy {
+    /// Accept the first valid response received. A single faulty node can thus
+    /// return stale or forged mutable data.
+    FirstValid,
+    /// Accept the response returned by a majority of the nodes queried.
+    #[default]
+    Majority,
+    /// Merge the responses of all the nodes queried when the data can be merged, i.e. Registers
+    /// and spent proof shares, so no entries can be hidden by a faulty node. The signed cmds of
+    /// the Register logs and the spent proof shares are verified before being merged. The other
+    /// responses are accepted if returned by a majority of the nodes queried.
+    MergeAll,
+}
+
+impl FromStr for QueryResponsePolicy {
+    type Err = String;
+    fn from_str(str: &str) -> Result<Self, String> {
+        match str {
+            "first-valid" => Ok(Self::FirstValid),
+            "majority" => Ok(Self::Majority),
+            "merge-all" => Ok(Self::MergeAll),
+            other => Err(format!(
+                "Query response policy '{}' not supported. Supported values are first-valid, majority, and merge-all",
+                other
+            )),
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub(super) struct Session {
     // Session endpoint.
@@ -53,6 +91,8 @@ pub(super) struct Session {
     pub(super) network: Arc<RwLock<SectionTree>>,
     /// Links to nodes
     peer_links: PeerLinks,
+    /// How the responses to queries are validated
+    pub(super) query_response_policy: QueryResponsePolicy,
 }
 
 impl Session {
@@ -62,6 +102,7 @@ impl Session {
         qp2p_config: QuicP2pConfig,
         local_addr: SocketAddr,
         network_contacts: SectionTree,
+        query_response_policy: QueryResponsePolicy,
     ) -> Result<Self> {
         let endpoint = Endpoint::new_client(local_addr, qp2p_config)?;
         let peer_links = PeerLinks::new(endpoint.clone());
@@ -70,6 +111,7 @@ impl Session {
             endpoint,
             network: Arc::new(RwLock::new(network_contacts)),
             peer_links,
+            query_response_policy,
         };
 
         Ok(session)
@@ -44,6 +44,9 @@ pub mod util {
     pub const TEST_SYMLINKS_FOLDER: &str = "../resources/test_symlinks";
     pub const TEST_SYMLINK: &str = "../resources/test_symlinks/file_link";
 
+    // Password the CLI keystore is unlocked with when running the test cases
+    const TEST_KEYSTORE_PASSWORD: &str = "test-keystore-password";
+
     #[ctor::ctor]
     fn init() {
         let _ = color_eyre::install();
@@ -443,6 +446,7 @@ pub mod util {
         let mut cmd = Command::cargo_bin("safe")?;
         Ok(cmd
             .args(args)
+            .env("SAFE_KEYSTORE_PASSWORD", TEST_KEYSTORE_PASSWORD)
             .current_dir(working_directory)
             .assert()
             .code(code)
@@ -504,7 +508,12 @@ pub mod util {
         );
         println!("Executing: safe {}", args.join(" "));
         let mut cmd = Command::cargo_bin("safe")?;
-        let output = cmd.args(args).assert().get_output().clone();
+        let output = cmd
+            .args(args)
+            .env("SAFE_KEYSTORE_PASSWORD", TEST_KEYSTORE_PASSWORD)
+            .assert()
+            .get_output()
+            .clone();
         let stderr = String::from_utf8(output.stderr)
             .wrap_err("Failed to parse the error output as a UTF-8 string".to_string())?;
         println!("Output from stderr:");
@@ -158,6 +158,6 @@ pub mod test_utils {
         network_knowledge::{
             section_authority_provider::test_utils::*, test_utils::*, test_utils_st::*,
         },
-        types::keys::test_utils::*,
+        types::{keys::test_utils::*, register::test_utils::*},
     };
 }
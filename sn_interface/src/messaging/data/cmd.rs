@@ -14,6 +14,10 @@ use crate::{
 use serde::{Deserialize, Serialize};
 use xor_name::XorName;
 
+/// Maximum number of operations bundled in a single [`DataCmd::Batch`], larger batches
+/// are rejected by the Elders.
+pub const CMD_BATCH_MAX_SIZE: usize = 50;
+
 /// Data cmds - creating, updating, or removing data.
 ///
 /// See the [`types`] module documentation for more details of the types supported by the Safe
@@ -36,19 +40,36 @@ pub enum DataCmd {
     #[cfg(feature = "spentbook")]
     /// Spentbook write operation.
     Spentbook(SpentbookCmd),
+    /// Several [`Chunk`] and [`Register`] write operations for the same section, sent in a
+    /// single message and acknowledged individually, in the same order, with a
+    /// [`CmdResponse::Batch`].
+    ///
+    /// Operations on the same data are applied in the order they are found in the batch,
+    /// e.g. a [`Register`] can be created and written to in the same batch. It can hold at most
+    /// [`CMD_BATCH_MAX_SIZE`] operations.
+    ///
+    /// [`Chunk`]: crate::types::Chunk
+    /// [`Register`]: crate::types::register::Register
+    Batch(Vec<DataCmd>),
 }
 
 impl DataCmd {
     /// Returns the address of the corresponding variant.
+    /// For a batch, it's the address of its first operation.
     pub fn address(&self) -> DataAddress {
         match self {
             Self::StoreChunk(chunk) => DataAddress::Bytes(*chunk.address()),
             Self::Register(register_cmd) => DataAddress::Register(register_cmd.dst_address()),
             Self::Spentbook(spentbook_cmd) => DataAddress::Spentbook(spentbook_cmd.dst_address()),
+            Self::Batch(cmds) => cmds
+                .first()
+                .map(Self::address)
+                .unwrap_or_else(|| DataAddress::SafeKey(XorName::default())),
         }
     }
 
     /// Returns the xorname of the data for this cmd.
+    /// For a batch, it's the xorname of the data of its first operation.
     pub fn dst_name(&self) -> XorName {
         use DataCmd::*;
         match self {
@@ -58,6 +79,7 @@ impl DataCmd {
             Register(c) => c.name(), // TODO: c.dst_id(), as to not co-locate private and public and different tags of same name.
             #[cfg(feature = "spentbook")]
             Spentbook(c) => c.name(),
+            Batch(cmds) => cmds.first().map(Self::dst_name).unwrap_or_default(),
         }
     }
 
@@ -72,6 +94,11 @@ impl DataCmd {
             Register(c) => c.to_error_response(error),
             #[cfg(feature = "spentbook")]
             Spentbook(c) => c.to_error_response(error),
+            Batch(cmds) => CmdResponse::Batch(
+                cmds.iter()
+                    .map(|cmd| cmd.to_error_response(error.clone()))
+                    .collect(),
+            ),
         }
     }
 }
@@ -7,13 +7,13 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::types::{
-    register::{EntryHash, User},
+    register::{EntryHash, Stamp, User},
     DataAddress,
 };
 use serde::{Deserialize, Serialize};
 use std::result;
 use thiserror::Error;
-use xor_name::Prefix;
+use xor_name::{Prefix, XorName};
 
 /// A specialised `Result` type.
 pub type Result<T, E = Error> = result::Result<T, E>;
@@ -50,6 +50,19 @@ pub enum Error {
     /// User entry could not be found on the data
     #[error("Requested user not found {0:?}")]
     NoSuchUser(User),
+    /// The data is not handled by the section the cmd was sent to, e.g. as the section split,
+    /// so the cmd shall be sent again to the section matching the data name.
+    #[error("Data {name:?} is not handled by section {prefix:?}")]
+    DataNotInSection {
+        /// The name of the data.
+        name: XorName,
+        /// The prefix of the section.
+        prefix: Prefix,
+    },
+    /// The stamp of a Register op doesn't match the Register as held by the section, e.g. it
+    /// was stamped too long ago, so the op shall be stamped and signed again to be retried.
+    #[error("Invalid stamp of the Register op: {0:?}")]
+    InvalidStamp(Stamp),
     /// Invalid Operation
     #[error("Invalid operation: {0}")]
     InvalidOperation(String),
@@ -16,21 +16,25 @@ mod register;
 mod spentbook;
 
 pub use self::{
-    cmd::DataCmd,
+    cmd::{DataCmd, CMD_BATCH_MAX_SIZE},
     data_exchange::{MetadataExchange, StorageLevel},
     errors::{Error, Result},
     query::{DataQuery, DataQueryVariant},
     register::{
-        CreateRegister, EditRegister, RegisterCmd, RegisterQuery, SignedRegisterCreate,
-        SignedRegisterEdit,
+        CreateRegister, DeleteRegister, EditRegister, RegisterCmd, RegisterQuery,
+        SignedRegisterCreate, SignedRegisterDelete, SignedRegisterEdit, SignedRegisterPolicyUpdate,
+        UpdateRegisterPolicy,
+    },
+    spentbook::{
+        SpentKeyImage, SpentKeyImagesPage, SpentbookCmd, SpentbookQuery,
+        MAX_SPENT_KEY_IMAGES_PER_PAGE,
     },
-    spentbook::{SpentbookCmd, SpentbookQuery},
 };
 
 use crate::network_knowledge::SectionTreeUpdate;
 use crate::types::{
     register::{Entry, EntryHash, Permissions, Policy, Register, User},
-    Chunk,
+    Chunk, ReplicatedRegisterLog,
 };
 use crate::{
     messaging::{
@@ -125,6 +129,14 @@ pub enum ClientDataResponse {
         #[debug(skip)]
         bounced_msg: UsrMsgBytes,
     },
+    /// An operation applied to a Register watched by the client, sent over the
+    /// stream the [`RegisterQuery::Watch`] query was received on.
+    RegisterEvent {
+        /// The operation applied to the Register.
+        cmd: RegisterCmd,
+        /// ID of the [`RegisterQuery::Watch`] query message.
+        correlation_id: MsgId,
+    },
 }
 
 impl ClientDataResponse {
@@ -147,6 +159,13 @@ impl Display for ClientDataResponse {
             Self::AntiEntropy { .. } => {
                 write!(f, "ClientDataResponse::AntiEntropy")
             }
+            Self::RegisterEvent { cmd, .. } => {
+                write!(
+                    f,
+                    "ClientDataResponse::RegisterEvent({:?})",
+                    cmd.dst_address()
+                )
+            }
         }
     }
 }
@@ -167,6 +186,8 @@ pub enum QueryResponse {
     //
     /// Response to [`RegisterQuery::Get`].
     GetRegister(Result<Register>),
+    /// Response to [`RegisterQuery::GetLog`].
+    GetRegisterLog(Result<ReplicatedRegisterLog>),
     /// Response to [`RegisterQuery::GetEntry`].
     GetRegisterEntry(Result<Entry>),
     /// Response to [`RegisterQuery::GetOwner`].
@@ -177,11 +198,15 @@ pub enum QueryResponse {
     GetRegisterPolicy(Result<Policy>),
     /// Response to [`RegisterQuery::GetUserPermissions`].
     GetRegisterUserPermissions(Result<Permissions>),
+    /// Response to [`RegisterQuery::Watch`].
+    WatchRegister(Result<()>),
     //
     // ===== Spentbook Data =====
     //
     /// Response to [`SpentbookQuery::SpentProofShares`].
     SpentProofShares(Result<Vec<SpentProofShare>>),
+    /// Response to [`SpentbookQuery::SpentKeyImages`].
+    SpentKeyImages(Result<SpentKeyImagesPage>),
 }
 
 impl QueryResponse {
@@ -192,12 +217,15 @@ impl QueryResponse {
             self,
             GetChunk(Ok(_))
                 | GetRegister(Ok(_))
+                | GetRegisterLog(Ok(_))
                 | GetRegisterEntry(Ok(_))
                 | GetRegisterOwner(Ok(_))
                 | ReadRegister(Ok(_))
                 | GetRegisterPolicy(Ok(_))
                 | GetRegisterUserPermissions(Ok(_))
+                | WatchRegister(Ok(_))
                 | SpentProofShares(Ok(_))
+                | SpentKeyImages(Ok(_))
         )
     }
 
@@ -208,6 +236,7 @@ impl QueryResponse {
             self,
             GetChunk(Err(Error::DataNotFound(_)))
                 | GetRegister(Err(Error::DataNotFound(_)))
+                | GetRegisterLog(Err(Error::DataNotFound(_)))
                 | GetRegisterEntry(Err(Error::DataNotFound(_)))
                 | GetRegisterEntry(Err(Error::NoSuchEntry(_)))
                 | GetRegisterOwner(Err(Error::DataNotFound(_)))
@@ -218,6 +247,7 @@ impl QueryResponse {
                 | GetRegisterUserPermissions(Err(Error::DataNotFound(_)))
                 | GetRegisterUserPermissions(Err(Error::NoSuchUser(_)))
                 | SpentProofShares(Err(Error::DataNotFound(_)))
+                | SpentKeyImages(Err(Error::DataNotFound(_)))
         )
     }
 }
@@ -238,11 +268,20 @@ pub enum CmdResponse {
     CreateRegister(Result<()>),
     /// Response to RegisterCmd::Edit.
     EditRegister(Result<()>),
+    /// Response to RegisterCmd::Delete.
+    DeleteRegister(Result<()>),
+    /// Response to RegisterCmd::UpdatePolicy.
+    UpdateRegisterPolicy(Result<()>),
     //
     // ===== Spentbook Data =====
     //
     /// Response to SpentbookCmd::Spend.
     SpendKey(Result<()>),
+    //
+    // ===== Batch =====
+    //
+    /// Responses to each of the operations of a DataCmd::Batch, in the same order.
+    Batch(Vec<CmdResponse>),
 }
 
 impl CmdResponse {
@@ -256,6 +295,12 @@ impl CmdResponse {
             ReplicatedData::RegisterWrite(RegisterCmd::Edit { .. }) => {
                 CmdResponse::EditRegister(Ok(()))
             }
+            ReplicatedData::RegisterWrite(RegisterCmd::Delete { .. }) => {
+                CmdResponse::DeleteRegister(Ok(()))
+            }
+            ReplicatedData::RegisterWrite(RegisterCmd::UpdatePolicy { .. }) => {
+                CmdResponse::UpdateRegisterPolicy(Ok(()))
+            }
             ReplicatedData::SpentbookWrite(_) => CmdResponse::SpendKey(Ok(())),
             ReplicatedData::RegisterLog(_) => return Err(Error::NoCorrespondingCmdError), // this should be unreachable, since `RegisterLog` is not resulting from a cmd.
             ReplicatedData::SpentbookLog(_) => return Err(Error::NoCorrespondingCmdError), // this should be unreachable, since `SpentbookLog` is not resulting from a cmd.
@@ -273,6 +318,12 @@ impl CmdResponse {
             ReplicatedData::RegisterWrite(RegisterCmd::Edit { .. }) => {
                 CmdResponse::EditRegister(Err(err))
             }
+            ReplicatedData::RegisterWrite(RegisterCmd::Delete { .. }) => {
+                CmdResponse::DeleteRegister(Err(err))
+            }
+            ReplicatedData::RegisterWrite(RegisterCmd::UpdatePolicy { .. }) => {
+                CmdResponse::UpdateRegisterPolicy(Err(err))
+            }
             ReplicatedData::SpentbookWrite(_) => CmdResponse::SpendKey(Err(err)),
             ReplicatedData::RegisterLog(_) => return Err(Error::NoCorrespondingCmdError), // this should be unreachable, since `RegisterLog` is not resulting from a cmd.
             ReplicatedData::SpentbookLog(_) => return Err(Error::NoCorrespondingCmdError), // this should be unreachable, since `SpentbookLog` is not resulting from a cmd.
@@ -285,14 +336,21 @@ impl CmdResponse {
         self.result().is_ok()
     }
 
-    /// Returns the result
+    /// Returns the result, which for a batch is the first error of its operations, if any
     pub fn result(&self) -> &Result<()> {
         use CmdResponse::*;
         match self {
             StoreChunk(result)
             | CreateRegister(result)
             | EditRegister(result)
+            | DeleteRegister(result)
+            | UpdateRegisterPolicy(result)
             | SpendKey(result) => result,
+            Batch(responses) => responses
+                .iter()
+                .map(CmdResponse::result)
+                .find(|result| result.is_err())
+                .unwrap_or(&Ok(())),
         }
     }
 }
@@ -339,6 +397,7 @@ impl TryFrom<QueryResponse> for Chunk {
 }
 
 try_from!(Register, GetRegister);
+try_from!(ReplicatedRegisterLog, GetRegisterLog);
 try_from!(User, GetRegisterOwner);
 try_from!(BTreeSet<(EntryHash, Entry)>, ReadRegister);
 try_from!(Policy, GetRegisterPolicy);
@@ -431,4 +490,70 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn batch_cmd_responses_are_per_item() -> Result<()> {
+        use crate::messaging::data::DataCmd;
+
+        let cmds = (0..3)
+            .map(|_| DataCmd::StoreChunk(Chunk::new(random_bytes(100))))
+            .collect::<Vec<_>>();
+        let batch = DataCmd::Batch(cmds.clone());
+        assert_eq!(batch.dst_name(), cmds[0].dst_name());
+
+        let error = Error::InvalidOperation("test".to_string());
+        assert_eq!(
+            batch.to_error_response(error.clone()),
+            CmdResponse::Batch(vec![CmdResponse::StoreChunk(Err(error.clone())); 3])
+        );
+
+        let response = CmdResponse::Batch(vec![
+            CmdResponse::StoreChunk(Ok(())),
+            CmdResponse::EditRegister(Err(error.clone())),
+        ]);
+        assert_eq!(response.result(), &Err(error));
+        assert!(CmdResponse::Batch(vec![CmdResponse::StoreChunk(Ok(()))]).is_success());
+
+        Ok(())
+    }
+
+    #[test]
+    fn spent_key_images_pages_are_merged_up_to_the_shortest() -> Result<()> {
+        use sn_dbc::Hash;
+        use xor_name::Prefix;
+
+        let mut spent = (0..4)
+            .map(|i| SpentKeyImage {
+                key_image: bls::SecretKey::random().public_key(),
+                transaction_hashes: [Hash::from([i; 32])].into(),
+            })
+            .collect::<Vec<_>>();
+        spent.sort_by_key(|item| item.name());
+        let page = |items: &[SpentKeyImage], next: Option<usize>| SpentKeyImagesPage {
+            prefix: Prefix::default(),
+            spent: items.to_vec(),
+            next: next.map(|index| spent[index].name()),
+        };
+
+        // a holder which listed the first two only, as it holds more than a page
+        let truncated = page(&spent[..2], Some(1));
+        // another which listed the rest, with a different transaction for the first one
+        let mut other_tx = spent[0].clone();
+        other_tx.transaction_hashes = [Hash::from([9; 32])].into();
+        let complete = page(&[other_tx, spent[2].clone(), spent[3].clone()], None);
+
+        let merged = SpentKeyImagesPage::merge([truncated, complete])
+            .ok_or_else(|| eyre!("pages should be merged"))?;
+        assert_eq!(merged.next, Some(spent[1].name()));
+        assert_eq!(merged.spent.len(), 2);
+        assert_eq!(merged.spent[0].transaction_hashes.len(), 2);
+        assert_eq!(merged.spent[1], spent[1]);
+
+        // pages listed from different ranges can't be merged
+        let mut narrower = page(&spent, None);
+        narrower.prefix = Prefix::default().pushed(true);
+        assert!(SpentKeyImagesPage::merge([page(&spent, None), narrower]).is_none());
+
+        Ok(())
+    }
 }
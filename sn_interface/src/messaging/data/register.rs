@@ -8,14 +8,15 @@
 
 use super::{CmdResponse, Error, QueryResponse};
 
-use crate::messaging::{ClientAuth, SectionSig};
+use crate::messaging::{ClientAuth, Error as MessagingError, SectionSig, VerifyAuthority};
 #[allow(unused_imports)] // needed by rustdocs links
 use crate::types::register::Register;
 use crate::types::{
-    register::{Entry, EntryHash, Policy, RegisterOp, User},
-    RegisterAddress,
+    register::{Entry, EntryHash, Policy, RegisterOp, Stamp, User},
+    PublicKey, RegisterAddress,
 };
 
+use bincode::serialize;
 use serde::{Deserialize, Serialize};
 use xor_name::XorName;
 
@@ -28,6 +29,13 @@ pub enum RegisterQuery {
     ///
     /// [`GetRegister`]: QueryResponse::GetRegister
     Get(RegisterAddress),
+    /// Retrieve the log of signed cmds of the [`Register`] at the given address, for the
+    /// requester to verify them and reconstruct the [`Register`] from them.
+    ///
+    /// This should eventually lead to a [`GetRegisterLog`] response.
+    ///
+    /// [`GetRegisterLog`]: QueryResponse::GetRegisterLog
+    GetLog(RegisterAddress),
     /// Retrieve the current entries from the [`Register`] at the given address.
     ///
     /// Multiple entries occur on concurrent writes. This should eventually lead to a
@@ -69,6 +77,15 @@ pub enum RegisterQuery {
     ///
     /// [`GetRegisterOwner`]: QueryResponse::GetRegisterOwner
     GetOwner(RegisterAddress),
+    /// Watch the [`Register`] at the given address, to be notified of the operations
+    /// applied to it from then on.
+    ///
+    /// This should eventually lead to a [`WatchRegister`] response, followed by a
+    /// [`RegisterEvent`] on the same stream for each operation applied to the Register.
+    ///
+    /// [`WatchRegister`]: QueryResponse::WatchRegister
+    /// [`RegisterEvent`]: super::ClientDataResponse::RegisterEvent
+    Watch(RegisterAddress),
 }
 
 /// A [`Register`] cmd that is stored in a log on Adults.
@@ -85,6 +102,16 @@ pub enum RegisterCmd {
     },
     /// Edit the [`Register`].
     Edit(SignedRegisterEdit),
+    /// Delete the [`Register`], leaving a tombstone at its address.
+    ///
+    /// Only the owner can delete a [`Register`]. Nodes stop serving it straight away,
+    /// and reclaim the space used by its entries after a grace period.
+    Delete(SignedRegisterDelete),
+    /// Replace the policy of the [`Register`].
+    ///
+    /// Only the owner, or users with admin permissions, can update the policy.
+    /// Updates are applied in the order they are found in the register log.
+    UpdatePolicy(SignedRegisterPolicyUpdate),
 }
 
 impl RegisterCmd {
@@ -94,6 +121,8 @@ impl RegisterCmd {
         match self {
             Self::Create { .. } => CmdResponse::CreateRegister(Err(error)),
             Self::Edit(_) => CmdResponse::EditRegister(Err(error)),
+            Self::Delete(_) => CmdResponse::DeleteRegister(Err(error)),
+            Self::UpdatePolicy(_) => CmdResponse::UpdateRegisterPolicy(Err(error)),
         }
     }
 }
@@ -132,6 +161,28 @@ pub struct EditRegister {
     pub address: RegisterAddress,
     /// The operation to perform.
     pub edit: RegisterOp<Entry>,
+    /// When the operation was issued, which the writer's permissions are evaluated at.
+    pub stamp: Stamp,
+}
+
+/// Delete a [`Register`], leaving a tombstone at its address.
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct DeleteRegister {
+    /// The address of the [`Register`] to delete.
+    pub address: RegisterAddress,
+}
+
+/// Replace the policy of a [`Register`].
+#[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
+pub struct UpdateRegisterPolicy {
+    /// The address of the [`Register`] to update.
+    pub address: RegisterAddress,
+    /// The new policy of the [`Register`].
+    pub policy: Policy,
+    /// The version the policy will have once updated, which has to follow the current one.
+    pub version: u64,
+    /// When the update was issued, which the requester's permissions are evaluated at.
+    pub stamp: Stamp,
 }
 
 /// A signed cmd to create a [`Register`].
@@ -156,6 +207,28 @@ pub struct SignedRegisterEdit {
     pub auth: ClientAuth,
 }
 
+/// A signed cmd to delete a [`Register`].
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct SignedRegisterDelete {
+    /// The operation to perform.
+    pub op: DeleteRegister,
+    /// A signature carrying authority to perform the operation.
+    ///
+    /// This will be verified against the register's owner.
+    pub auth: ClientAuth,
+}
+
+/// A signed cmd to update the policy of a [`Register`].
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct SignedRegisterPolicyUpdate {
+    /// The operation to perform.
+    pub op: UpdateRegisterPolicy,
+    /// A signature carrying authority to perform the operation.
+    ///
+    /// This will be verified against the register's owner and admin permissions.
+    pub auth: ClientAuth,
+}
+
 impl SignedRegisterCreate {
     /// Returns the dst address of the register.
     pub fn dst_address(&self) -> RegisterAddress {
@@ -170,12 +243,27 @@ impl SignedRegisterEdit {
     }
 }
 
+impl SignedRegisterDelete {
+    /// Returns the dst address of the register.
+    pub fn dst_address(&self) -> RegisterAddress {
+        self.op.address
+    }
+}
+
+impl SignedRegisterPolicyUpdate {
+    /// Returns the dst address of the register.
+    pub fn dst_address(&self) -> RegisterAddress {
+        self.op.address
+    }
+}
+
 impl RegisterQuery {
     /// Creates a Response containing an error, with the Response variant corresponding to the
     /// Request variant.
     pub fn to_error_response(&self, error: Error) -> QueryResponse {
         match self {
             Self::Get(_) => QueryResponse::GetRegister(Err(error)),
+            Self::GetLog(_) => QueryResponse::GetRegisterLog(Err(error)),
             Self::Read(_) => QueryResponse::ReadRegister(Err(error)),
             Self::GetPolicy(_) => QueryResponse::GetRegisterPolicy(Err(error)),
             Self::GetUserPermissions { .. } => {
@@ -183,6 +271,7 @@ impl RegisterQuery {
             }
             Self::GetEntry { .. } => QueryResponse::GetRegisterEntry(Err(error)),
             Self::GetOwner(_) => QueryResponse::GetRegisterOwner(Err(error)),
+            Self::Watch(_) => QueryResponse::WatchRegister(Err(error)),
         }
     }
 
@@ -190,11 +279,13 @@ impl RegisterQuery {
     pub fn dst_address(&self) -> RegisterAddress {
         match self {
             Self::Get(ref address)
+            | Self::GetLog(ref address)
             | Self::Read(ref address)
             | Self::GetPolicy(ref address)
             | Self::GetUserPermissions { ref address, .. }
             | Self::GetEntry { ref address, .. }
-            | Self::GetOwner(ref address) => *address,
+            | Self::GetOwner(ref address)
+            | Self::Watch(ref address) => *address,
         }
     }
 
@@ -202,11 +293,13 @@ impl RegisterQuery {
     pub fn dst_name(&self) -> XorName {
         match self {
             Self::Get(ref address)
+            | Self::GetLog(ref address)
             | Self::Read(ref address)
             | Self::GetPolicy(ref address)
             | Self::GetUserPermissions { ref address, .. }
             | Self::GetEntry { ref address, .. }
-            | Self::GetOwner(ref address) => *address.name(),
+            | Self::GetOwner(ref address)
+            | Self::Watch(ref address) => *address.name(),
         }
     }
 }
@@ -223,6 +316,44 @@ impl RegisterCmd {
         match self {
             Self::Create { cmd, .. } => cmd.dst_address(),
             Self::Edit(cmd) => cmd.dst_address(),
+            Self::Delete(cmd) => cmd.dst_address(),
+            Self::UpdatePolicy(cmd) => cmd.dst_address(),
+        }
+    }
+
+    /// Returns the stamp the cmd was issued with, if it's an edit or a policy update.
+    pub fn stamp(&self) -> Option<Stamp> {
+        match self {
+            Self::Edit(cmd) => Some(cmd.op.stamp),
+            Self::UpdatePolicy(cmd) => Some(cmd.op.stamp),
+            Self::Create { .. } | Self::Delete(_) => None,
+        }
+    }
+
+    /// Returns the key of the user who signed the cmd.
+    pub fn signer(&self) -> PublicKey {
+        self.auth().public_key
+    }
+
+    /// Verify the signature of the cmd against the key of the user who signed it.
+    pub fn verify_signature(&self) -> crate::messaging::Result<()> {
+        let bytes = match self {
+            Self::Create { cmd, .. } => serialize(&cmd.op),
+            Self::Edit(cmd) => serialize(&cmd.op),
+            Self::Delete(cmd) => serialize(&cmd.op),
+            Self::UpdatePolicy(cmd) => serialize(&cmd.op),
+        }
+        .map_err(|err| MessagingError::Serialisation(err.to_string()))?;
+        let _ = self.auth().clone().verify_authority(bytes)?;
+        Ok(())
+    }
+
+    fn auth(&self) -> &ClientAuth {
+        match self {
+            Self::Create { cmd, .. } => &cmd.auth,
+            Self::Edit(cmd) => &cmd.auth,
+            Self::Delete(cmd) => &cmd.auth,
+            Self::UpdatePolicy(cmd) => &cmd.auth,
         }
     }
 
@@ -13,9 +13,13 @@ use crate::network_knowledge::{SectionAuthorityProvider, SectionsDAG};
 use crate::types::SpentbookAddress;
 
 use serde::{Deserialize, Serialize};
-use sn_dbc::{KeyImage, RingCtTransaction, SpentProof};
-use std::collections::BTreeSet;
-use xor_name::XorName;
+use sn_dbc::{Hash, KeyImage, RingCtTransaction, SpentProof};
+use std::collections::{BTreeMap, BTreeSet};
+use xor_name::{Prefix, XorName};
+
+/// Maximum number of spent key images returned in a single page
+/// of a [`SpentbookQuery::SpentKeyImages`] response.
+pub const MAX_SPENT_KEY_IMAGES_PER_PAGE: usize = 100;
 
 /// Spentbook read operations.
 #[allow(clippy::large_enum_variant)]
@@ -23,6 +27,92 @@ use xor_name::XorName;
 pub enum SpentbookQuery {
     /// Query the set of spent proofs if the provided key image has already been spent with a Tx
     SpentProofShares(SpentbookAddress),
+    /// Query a page of the key images spent within the given address range, together with the
+    /// hashes of the transactions they were spent in.
+    ///
+    /// Key images are listed in ascending order of their spentbook address, starting after
+    /// `start_after` if provided. The query is routed to the section holding the start of the
+    /// page, whose Elders list it from all their Adults, thus a page only covers the part of
+    /// the range within that section, which is reported as the page's `prefix`.
+    SpentKeyImages {
+        /// The address range to list spent key images from.
+        prefix: Prefix,
+        /// Cursor returned as `next` in the previous page, if any.
+        start_after: Option<XorName>,
+    },
+}
+
+/// A page of key images logged as spent in a spentbook address range.
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct SpentKeyImagesPage {
+    /// The address range the page was listed from, narrower than the one queried
+    /// if the latter spans more than one section.
+    pub prefix: Prefix,
+    /// The key images spent in this page, in ascending order of their spentbook address.
+    pub spent: Vec<SpentKeyImage>,
+    /// Cursor to query the next page with, or `None` if this is the last page.
+    pub next: Option<XorName>,
+}
+
+impl SpentKeyImagesPage {
+    /// Merges the pages listed from the same address range, and after the same cursor, by
+    /// different holders of the spentbook. The merged page only goes up to the shortest of the
+    /// pages which are not the last one, as the others may be missing key images beyond that.
+    /// Returns `None` if no page is provided, or they were not listed from the same range.
+    pub fn merge(pages: impl IntoIterator<Item = Self>) -> Option<Self> {
+        let mut pages = pages.into_iter();
+        let first = pages.next()?;
+        let prefix = first.prefix;
+        let mut next = first.next;
+        let mut spent = BTreeMap::new();
+        for page in std::iter::once(first).chain(pages) {
+            if page.prefix != prefix {
+                return None;
+            }
+            next = match (next, page.next) {
+                (Some(current), Some(other)) => Some(current.min(other)),
+                (current, other) => current.or(other),
+            };
+            for item in page.spent {
+                spent
+                    .entry(item.name())
+                    .or_insert_with(|| SpentKeyImage {
+                        key_image: item.key_image,
+                        transaction_hashes: BTreeSet::new(),
+                    })
+                    .transaction_hashes
+                    .extend(item.transaction_hashes);
+            }
+        }
+
+        if let Some(next) = next {
+            spent.retain(|name, _| *name <= next);
+        }
+
+        Some(Self {
+            prefix,
+            spent: spent.into_values().collect(),
+            next,
+        })
+    }
+}
+
+/// A key image logged as spent, along with the hashes of the transactions it was spent in.
+///
+/// More than one transaction hash means the key image was spent in different transactions.
+#[derive(Eq, PartialEq, Clone, Serialize, Deserialize, Debug)]
+pub struct SpentKeyImage {
+    /// The key image of the spent DBC.
+    pub key_image: KeyImage,
+    /// Hashes of the transactions the key image was spent in.
+    pub transaction_hashes: BTreeSet<Hash>,
+}
+
+impl SpentKeyImage {
+    /// Returns the name of the spentbook address the key image is logged at.
+    pub fn name(&self) -> XorName {
+        XorName::from_content(&self.key_image.to_bytes())
+    }
 }
 
 /// A Spentbook cmd.
@@ -65,6 +155,7 @@ impl SpentbookQuery {
     pub fn to_error_response(&self, error: Error) -> QueryResponse {
         match self {
             Self::SpentProofShares(_) => QueryResponse::SpentProofShares(Err(error)),
+            Self::SpentKeyImages { .. } => QueryResponse::SpentKeyImages(Err(error)),
         }
     }
 
@@ -72,6 +163,14 @@ impl SpentbookQuery {
     pub fn dst_address(&self) -> SpentbookAddress {
         match self {
             Self::SpentProofShares(address) => *address,
+            // a cursor outside of the range, e.g. left by a section split, is not routed to
+            Self::SpentKeyImages {
+                prefix,
+                start_after,
+            } => SpentbookAddress::new(match start_after {
+                Some(after) if prefix.matches(after) => *after,
+                _ => prefix.lower_bound(),
+            }),
         }
     }
 
@@ -7,7 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
-    register::{EntryHash, User},
+    register::{EntryHash, Stamp, User},
     RegisterAddress,
 };
 
@@ -60,6 +60,9 @@ pub enum Error {
     /// Number out of expected range.
     #[error("The provided number is out of the expected range")]
     OutOfRange,
+    /// The stamp of an op is invalid, e.g. behind the entries the op supersedes.
+    #[error("Invalid stamp of the op: {0:?}")]
+    InvalidStamp(Stamp),
     /// The CRDT operation cannot be applied as it targets a different content address.
     #[error("The CRDT operation cannot be applied as it targets a different content address.")]
     CrdtWrongAddress(RegisterAddress),
@@ -85,6 +88,7 @@ impl From<Error> for ErrorMsg {
             Error::NoSuchEntry(hash) => ErrorMsg::NoSuchEntry(hash),
             Error::NoSuchUser(user) => ErrorMsg::NoSuchUser(user),
             Error::AccessDenied(pk) => ErrorMsg::AccessDenied(pk),
+            Error::InvalidStamp(stamp) => ErrorMsg::InvalidStamp(stamp),
             other => ErrorMsg::InvalidOperation(format!("DtError: {:?}", other)),
         }
     }
@@ -60,6 +60,37 @@ pub struct ReplicatedRegisterLog {
     pub op_log: Vec<RegisterCmd>,
 }
 
+impl ReplicatedRegisterLog {
+    /// Verify every cmd of the log is signed by the user it's issued by,
+    /// and targets the Register of the log.
+    pub fn verify(&self) -> Result<()> {
+        for cmd in &self.op_log {
+            if cmd.dst_address() != self.address {
+                return Err(Error::CrdtWrongAddress(cmd.dst_address()));
+            }
+            cmd.verify_signature()
+                .map_err(|_| Error::InvalidSignature)?;
+        }
+        Ok(())
+    }
+
+    /// Merge the cmds of another log of the same Register into this one, once they're all
+    /// verified. They're checked against the policy of the Register once it's reconstructed
+    /// from them, see [`Register::from_log`](register::Register::from_log).
+    pub fn merge(&mut self, other: Self) -> Result<()> {
+        if other.address != self.address {
+            return Err(Error::CrdtWrongAddress(other.address));
+        }
+        other.verify()?;
+        for cmd in other.op_log {
+            if !self.op_log.contains(&cmd) {
+                self.op_log.push(cmd);
+            }
+        }
+        Ok(())
+    }
+}
+
 ///
 #[allow(clippy::large_enum_variant)]
 #[derive(Debug, Eq, PartialEq, Clone, Serialize, Deserialize)]
@@ -121,6 +152,12 @@ impl ReplicatedData {
             Self::RegisterWrite(RegisterCmd::Edit { .. }) => {
                 Ok(CmdResponse::EditRegister(Err(error)))
             }
+            Self::RegisterWrite(RegisterCmd::Delete { .. }) => {
+                Ok(CmdResponse::DeleteRegister(Err(error)))
+            }
+            Self::RegisterWrite(RegisterCmd::UpdatePolicy { .. }) => {
+                Ok(CmdResponse::UpdateRegisterPolicy(Err(error)))
+            }
             Self::SpentbookWrite(_) => Ok(CmdResponse::SpendKey(Err(error))),
             Self::SpentbookLog(_) => Err(Error::NoCmdResponseForTheVariant), // should be unreachable, since `SpentbookLog` is not resulting from a cmd.
             Self::RegisterLog(_) => Err(Error::NoCmdResponseForTheVariant), // should be unreachable, since `RegisterLog` is not resulting from a cmd.,
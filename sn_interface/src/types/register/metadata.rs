@@ -11,8 +11,12 @@
 pub enum Action {
     /// Read from the data.
     Read,
-    /// Write to the data.
+    /// Write to the data, including entries merging over other users' entries.
     Write,
+    /// Write entries to the data which only supersede entries written by the same user.
+    Append,
+    /// Edit the policy of the data.
+    Admin,
 }
 
 /// An entry in a Register (note that the `vec<u8>` is size limited: `MAX_REG_ENTRY_SIZE`)
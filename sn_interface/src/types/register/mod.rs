@@ -11,12 +11,16 @@ mod policy;
 mod reg_crdt;
 
 pub use metadata::{Action, Entry};
-pub use policy::{Permissions, Policy, User};
+pub use policy::{Expiry, Permissions, Policy, Stamp, User, MAX_STAMP_CLOCK_DRIFT};
 pub use reg_crdt::EntryHash;
 
 pub(crate) use reg_crdt::{CrdtOperation, RegisterCrdt};
 
 use super::{Error, Result};
+use crate::messaging::data::{
+    RegisterCmd, SignedRegisterCreate, SignedRegisterDelete, SignedRegisterEdit,
+    SignedRegisterPolicyUpdate,
+};
 use crate::types::RegisterAddress;
 use self_encryption::MIN_ENCRYPTABLE_BYTES;
 use serde::{Deserialize, Serialize};
@@ -41,6 +45,13 @@ pub struct Register {
     authority: User,
     pub(super) crdt: RegisterCrdt, // Temporarily exposed to 'super' till spentbook fully implemented.
     policy: Policy,
+    // Number of updates applied to the policy the Register was created with.
+    policy_version: u64,
+    // Policies superseded by the current one, by version, which the ops issued
+    // before they were superseded are checked against.
+    past_policies: BTreeMap<u64, Policy>,
+    // Users who wrote each of the entries.
+    authors: BTreeMap<EntryHash, User>,
 }
 
 impl Register {
@@ -51,6 +62,9 @@ impl Register {
             authority,
             crdt: RegisterCrdt::new(address),
             policy,
+            policy_version: 0,
+            past_policies: BTreeMap::new(),
+            authors: BTreeMap::new(),
         }
     }
 
@@ -121,6 +135,45 @@ impl Register {
         &self.policy
     }
 
+    /// Return the version of the policy, i.e. the number of updates applied to
+    /// the policy the Register was created with.
+    pub fn policy_version(&self) -> u64 {
+        self.policy_version
+    }
+
+    /// Return the policy with the given version, either the current one or a superseded one.
+    pub fn policy_at(&self, version: u64) -> Option<&Policy> {
+        if version == self.policy_version {
+            Some(&self.policy)
+        } else {
+            self.past_policies.get(&version)
+        }
+    }
+
+    /// Return a stamp for an op issued now on this replica.
+    pub fn stamp(&self) -> Stamp {
+        Stamp::now(self.size(), self.policy_version)
+    }
+
+    /// Replace the policy with the given one, which has to be the next version of the current one.
+    /// The current policy is kept, so ops issued before it's superseded can still be checked.
+    ///
+    /// Returns `Err::OutOfRange` if the version doesn't follow the current one.
+    pub fn update_policy(&mut self, policy: Policy, version: u64) -> Result<()> {
+        if version != self.policy_version + 1 {
+            return Err(Error::OutOfRange);
+        }
+        let previous = std::mem::replace(&mut self.policy, policy);
+        let _ = self.past_policies.insert(self.policy_version, previous);
+        self.policy_version = version;
+        Ok(())
+    }
+
+    /// Return the user who wrote the entry with the provided 'hash', if known.
+    pub fn author(&self, hash: EntryHash) -> Option<User> {
+        self.authors.get(&hash).copied()
+    }
+
     /// Write an entry to the Register, returning the generated unsigned
     /// CRDT operation so the caller can sign and broadcast it to other replicas,
     /// along with the hash of the entry just written.
@@ -128,15 +181,32 @@ impl Register {
         &mut self,
         entry: Entry,
         children: BTreeSet<EntryHash>,
+    ) -> Result<(EntryHash, RegisterOp<Entry>)> {
+        self.write_as(entry, children, self.authority)
+    }
+
+    /// Write an entry to the Register as the given user, who is to sign the generated
+    /// CRDT operation, and who is recorded as the author of the entry.
+    pub fn write_as(
+        &mut self,
+        entry: Entry,
+        children: BTreeSet<EntryHash>,
+        author: User,
     ) -> Result<(EntryHash, RegisterOp<Entry>)> {
         self.check_entry_and_reg_sizes(&entry)?;
-        self.crdt.write(entry, children, self.authority)
+        let (hash, op) = self.crdt.write(entry, children, author)?;
+        let _ = self.authors.insert(hash, author);
+        Ok((hash, op))
     }
 
     /// Apply a signed data CRDT operation.
     pub fn apply_op(&mut self, op: RegisterOp<Entry>) -> Result<()> {
         self.check_entry_and_reg_sizes(&op.crdt_op.value)?;
-        self.crdt.apply_op(op)
+        let hash = EntryHash(op.crdt_op.hash());
+        let author = op.source;
+        self.crdt.apply_op(op)?;
+        let _ = self.authors.entry(hash).or_insert(author);
+        Ok(())
     }
 
     // Private helper to check the given Entry's size is within define limit,
@@ -159,24 +229,277 @@ impl Register {
     }
 
     /// Helper to check permissions for given `action`
-    /// for the given requester's public key.
+    /// for the given requester's public key, as of the stamp of the op performing it,
+    /// i.e. against the version of the policy the stamp references.
     ///
     /// Returns:
     /// `Ok(())` if the permissions are valid,
-    /// `Err::AccessDenied` if the action is not allowed.
-    pub fn check_permissions(&self, action: Action, requester: Option<User>) -> Result<()> {
+    /// `Err::AccessDenied` if the action is not allowed,
+    /// `Err::InvalidStamp` if the version of the policy referenced is unknown.
+    pub fn check_permissions(
+        &self,
+        action: Action,
+        requester: Option<User>,
+        stamp: Stamp,
+    ) -> Result<()> {
         let requester = requester.unwrap_or(self.authority);
-        self.policy.is_action_allowed(requester, action)
+        self.policy_at(stamp.policy_version)
+            .ok_or(Error::InvalidStamp(stamp))?
+            .is_action_allowed(requester, action, stamp)
+    }
+
+    /// Helper to check the requester is allowed to write an entry superseding the given
+    /// children entries. Permission to append is enough if all of them were written
+    /// by the requester, otherwise permission to write is required.
+    ///
+    /// The generation of the stamp cannot be lower than the number of entries the
+    /// children entries supersede, including themselves, so the writer cannot pretend
+    /// to write before entries it has seen were written.
+    pub fn check_write_permissions(
+        &self,
+        requester: User,
+        children: &BTreeSet<EntryHash>,
+        stamp: Stamp,
+    ) -> Result<()> {
+        if stamp.generation < self.crdt.num_ancestors(children) {
+            return Err(Error::InvalidStamp(stamp));
+        }
+
+        let merges_over_others = children
+            .iter()
+            .any(|child| self.author(*child) != Some(requester));
+        let action = if merges_over_others {
+            Action::Write
+        } else {
+            Action::Append
+        };
+        self.check_permissions(action, Some(requester), stamp)
+    }
+
+    /// Reconstructs a Register from the signed cmds of its log, which are expected to have been
+    /// verified already. The cmds are applied in the order of the log, see [`in_apply_order`],
+    /// skipping those not allowed by the policy they're checked against, so all the replicas
+    /// holding the same cmds end up with the same state.
+    ///
+    /// Returns `None` if the log doesn't contain the cmd creating the Register.
+    pub fn from_log(op_log: &[RegisterCmd]) -> Option<Self> {
+        let mut cmds = in_apply_order(op_log).into_iter();
+        let mut register = match cmds.next() {
+            Some(RegisterCmd::Create {
+                cmd: SignedRegisterCreate { op, .. },
+                ..
+            }) => Self::new(*op.policy.owner(), op.name, op.tag, op.policy.clone()),
+            _ => return None,
+        };
+
+        for cmd in cmds {
+            if let Err(err) = register.apply_cmd(cmd) {
+                warn!("Skipping Register cmd not allowed by the policy {cmd:?}: {err:?}");
+            }
+        }
+
+        Some(register)
+    }
+
+    /// Check the signed cmd is allowed by the version of the policy it references,
+    /// without verifying its signature.
+    ///
+    /// Returns:
+    /// `Ok(())` if the cmd is allowed,
+    /// `Err::AccessDenied` if it's not signed by a user allowed to perform it,
+    /// `Err::InvalidStamp` if it doesn't reference a known version of the policy.
+    pub fn check_cmd_permissions(&self, cmd: &RegisterCmd) -> Result<()> {
+        if cmd.dst_address() != *self.address() {
+            return Err(Error::CrdtWrongAddress(cmd.dst_address()));
+        }
+
+        match cmd {
+            RegisterCmd::Create { .. } => Ok(()),
+            RegisterCmd::Edit(SignedRegisterEdit { op, auth }) => {
+                // the entry is recorded as written by the source of the op, which must thus be
+                // the one who signed it, as it's checked against the policy
+                let requester = User::Key(auth.public_key);
+                if op.edit.source != requester {
+                    return Err(Error::AccessDenied(requester));
+                }
+                let children = op
+                    .edit
+                    .crdt_op
+                    .children
+                    .iter()
+                    .map(|hash| EntryHash(*hash))
+                    .collect();
+                self.check_write_permissions(requester, &children, op.stamp)
+            }
+            RegisterCmd::Delete(SignedRegisterDelete { auth, .. }) => {
+                // only the owner can delete a Register
+                let requester = User::Key(auth.public_key);
+                if self.owner() != requester {
+                    return Err(Error::AccessDenied(requester));
+                }
+                Ok(())
+            }
+            RegisterCmd::UpdatePolicy(SignedRegisterPolicyUpdate { op, auth }) => {
+                // an update replaces the version of the policy it's checked against
+                if op.stamp.policy_version + 1 != op.version {
+                    return Err(Error::InvalidStamp(op.stamp));
+                }
+                // the owner and admins can update the policy, but only the owner can transfer
+                // the ownership of the Register
+                let requester = User::Key(auth.public_key);
+                self.check_permissions(Action::Admin, Some(requester), op.stamp)?;
+                let previous_owner = self
+                    .policy_at(op.stamp.policy_version)
+                    .map(|policy| *policy.owner())
+                    .ok_or(Error::InvalidStamp(op.stamp))?;
+                if op.policy.owner() != &previous_owner && previous_owner != requester {
+                    return Err(Error::AccessDenied(requester));
+                }
+                Ok(())
+            }
+        }
+    }
+
+    /// Apply the signed cmd, once checked it's allowed by the version of the policy
+    /// it references. Its signature is expected to have been verified already.
+    ///
+    /// A creation cmd has no effect, nor has a policy update superseded by the one applied
+    /// with the same version, which comes first in the order of the log.
+    pub fn apply_cmd(&mut self, cmd: &RegisterCmd) -> Result<()> {
+        self.check_cmd_permissions(cmd)?;
+        match cmd {
+            RegisterCmd::Edit(SignedRegisterEdit { op, .. }) => self.apply_op(op.edit.clone()),
+            RegisterCmd::UpdatePolicy(SignedRegisterPolicyUpdate { op, .. })
+                if op.version > self.policy_version =>
+            {
+                self.update_policy(op.policy.clone(), op.version)
+            }
+            RegisterCmd::Create { .. } | RegisterCmd::Delete(_) | RegisterCmd::UpdatePolicy(_) => {
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Sorts the cmds of a Register log in the order they have to be applied in, as they are not kept
+/// in the order they were written: the creation first, then the policy updates, by version, and
+/// the edits and deletion last, as each of them is checked against the version of the policy it
+/// references. Cmds of the same kind are ordered by the hash of the signed cmd, so all the
+/// replicas keep the same policy update out of those with the same version.
+pub fn in_apply_order(op_log: &[RegisterCmd]) -> Vec<&RegisterCmd> {
+    let mut cmds: Vec<_> = op_log
+        .iter()
+        .map(|cmd| {
+            let hash = bincode::serialize(cmd)
+                .map(|bytes| XorName::from_content(&bytes))
+                .unwrap_or_default();
+            let key = match cmd {
+                RegisterCmd::Create { .. } => (0, 0, hash),
+                RegisterCmd::UpdatePolicy(SignedRegisterPolicyUpdate { op, .. }) => {
+                    (1, op.version, hash)
+                }
+                RegisterCmd::Edit(_) | RegisterCmd::Delete(_) => (2, 0, hash),
+            };
+            (key, cmd)
+        })
+        .collect();
+    cmds.sort_by_key(|(key, _)| *key);
+    cmds.into_iter().map(|(_, cmd)| cmd).collect()
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils {
+    use super::{Entry, EntryHash, Policy, Register, User};
+    use crate::{
+        messaging::{
+            data::{
+                CreateRegister, EditRegister, RegisterCmd, SignedRegisterCreate,
+                SignedRegisterEdit, SignedRegisterPolicyUpdate, UpdateRegisterPolicy,
+            },
+            ClientAuth,
+        },
+        types::{keys::test_utils::TestKeys, Keypair},
+    };
+    use std::collections::BTreeSet;
+
+    /// Create the signed cmd creating the given Register, as paid for to a random section.
+    pub fn create_register_cmd(register: &Register, keypair: &Keypair) -> RegisterCmd {
+        let op = CreateRegister {
+            name: *register.name(),
+            tag: register.tag(),
+            policy: register.policy().clone(),
+        };
+        let section_sig = TestKeys::get_section_sig(&bls::SecretKey::random(), &op);
+        RegisterCmd::Create {
+            cmd: SignedRegisterCreate {
+                auth: client_auth(keypair, &op),
+                op,
+            },
+            section_sig,
+        }
+    }
+
+    /// Write the entry to the given Register as authored by the author, returning the signed
+    /// cmd editing the Register, signed by the signer, along with the hash of the entry.
+    pub fn edit_register_cmd(
+        register: &mut Register,
+        entry: Entry,
+        author: &Keypair,
+        signer: &Keypair,
+    ) -> eyre::Result<(EntryHash, RegisterCmd)> {
+        let stamp = register.stamp();
+        let (hash, edit) =
+            register.write_as(entry, BTreeSet::new(), User::Key(author.public_key()))?;
+        let op = EditRegister {
+            address: *register.address(),
+            edit,
+            stamp,
+        };
+        let auth = ClientAuth {
+            public_key: author.public_key(),
+            signature: client_auth(signer, &op).signature,
+        };
+        Ok((hash, RegisterCmd::Edit(SignedRegisterEdit { op, auth })))
+    }
+
+    /// Replace the policy of the given Register, returning the signed cmd updating it.
+    pub fn update_register_policy_cmd(
+        register: &mut Register,
+        policy: Policy,
+        keypair: &Keypair,
+    ) -> eyre::Result<RegisterCmd> {
+        let op = UpdateRegisterPolicy {
+            address: *register.address(),
+            policy: policy.clone(),
+            version: register.policy_version() + 1,
+            stamp: register.stamp(),
+        };
+        register.update_policy(policy, op.version)?;
+        Ok(RegisterCmd::UpdatePolicy(SignedRegisterPolicyUpdate {
+            auth: client_auth(keypair, &op),
+            op,
+        }))
+    }
+
+    fn client_auth<T: serde::Serialize>(keypair: &Keypair, op: &T) -> ClientAuth {
+        let bytes = bincode::serialize(op).expect("Failed to serialize op");
+        ClientAuth {
+            public_key: keypair.public_key(),
+            signature: keypair.sign(&bytes),
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::super::{
-        register::{Entry, EntryHash, Permissions, Register, RegisterOp, User},
+        register::{
+            Action, Entry, EntryHash, Expiry, Permissions, Register, RegisterOp, Stamp, User,
+        },
         utils, Error, Keypair, Result,
     };
-    use crate::types::register::MAX_REG_NUM_ENTRIES;
+    use super::test_utils::{create_register_cmd, edit_register_cmd, update_register_policy_cmd};
+    use crate::types::{register::MAX_REG_NUM_ENTRIES, ReplicatedRegisterLog};
     use crate::{types::register::Policy, types::RegisterAddress as Address};
     use eyre::Context;
     use proptest::prelude::*;
@@ -415,6 +738,204 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn register_append_only_permissions() -> Result<()> {
+        let owner = User::Key(Keypair::new_ed25519().public_key());
+        let appender = User::Key(Keypair::new_ed25519().public_key());
+        let mut perms = Permissions::new(None);
+        perms.set_append(true);
+        let policy = Policy {
+            owner,
+            permissions: BTreeMap::from([(appender, perms)]),
+        };
+        let mut register = Register::new(
+            owner,
+            XorName::random(&mut rand::thread_rng()),
+            43_000,
+            policy,
+        );
+
+        let (owner_entry, _) = register.write(random_register_entry(), BTreeSet::new())?;
+
+        // the appender can write entries superseding its own entries
+        register.check_write_permissions(appender, &BTreeSet::new(), Stamp::now(1, 0))?;
+        let (appender_entry, _) =
+            register.write_as(random_register_entry(), BTreeSet::new(), appender)?;
+        assert_eq!(register.author(appender_entry), Some(appender));
+        register.check_write_permissions(
+            appender,
+            &BTreeSet::from([appender_entry]),
+            Stamp::now(2, 0),
+        )?;
+
+        // but not merge over others' entries
+        assert_eq!(
+            register.check_write_permissions(
+                appender,
+                &BTreeSet::from([owner_entry, appender_entry]),
+                Stamp::now(2, 0)
+            ),
+            Err(Error::AccessDenied(appender))
+        );
+        // nor edit the policy
+        assert_eq!(
+            register.check_permissions(Action::Admin, Some(appender), Stamp::now(2, 0)),
+            Err(Error::AccessDenied(appender))
+        );
+        register.check_permissions(Action::Admin, Some(owner), Stamp::now(2, 0))?;
+
+        // nor pretend to write before entries it supersedes were written
+        let stale_stamp = Stamp::now(0, 0);
+        assert_eq!(
+            register.check_write_permissions(
+                appender,
+                &BTreeSet::from([appender_entry]),
+                stale_stamp
+            ),
+            Err(Error::InvalidStamp(stale_stamp))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_permissions_expire() -> Result<()> {
+        let owner = User::Key(Keypair::new_ed25519().public_key());
+        let user = User::Key(Keypair::new_ed25519().public_key());
+
+        let mut perms = Permissions::new(true);
+        perms.set_expiry(Expiry::Generation(2));
+        let policy = Policy {
+            owner,
+            permissions: BTreeMap::from([(user, perms)]),
+        };
+        let stamp = |generation, time| Stamp {
+            generation,
+            time,
+            policy_version: 0,
+        };
+        policy.is_action_allowed(user, Action::Write, stamp(1, 0))?;
+        assert_eq!(
+            policy.is_action_allowed(user, Action::Write, stamp(2, 0)),
+            Err(Error::AccessDenied(user))
+        );
+
+        // expired permissions fall back to those of anyone
+        let mut perms = Permissions::new(true);
+        perms.set_expiry(Expiry::Time(100));
+        let policy = Policy {
+            owner,
+            permissions: BTreeMap::from([(user, perms), (User::Anyone, Permissions::new(false))]),
+        };
+        policy.is_action_allowed(user, Action::Write, stamp(0, 99))?;
+        assert_eq!(
+            policy.is_action_allowed(user, Action::Write, stamp(0, 100)),
+            Err(Error::AccessDenied(user))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_policy_updates_follow_versions() -> Result<()> {
+        let owner = User::Key(Keypair::new_ed25519().public_key());
+        let user = User::Key(Keypair::new_ed25519().public_key());
+        let mut register =
+            Register::new_owned(owner, XorName::random(&mut rand::thread_rng()), 43_000);
+        assert_eq!(register.policy_version(), 0);
+
+        let policy = Policy {
+            owner,
+            permissions: BTreeMap::from([(user, Permissions::new(true))]),
+        };
+        // versions cannot be skipped
+        assert_eq!(
+            register.update_policy(policy.clone(), 2),
+            Err(Error::OutOfRange)
+        );
+        register.update_policy(policy.clone(), 1)?;
+        assert_eq!(register.policy_version(), 1);
+        register.check_permissions(Action::Write, Some(user), register.stamp())?;
+        // ops issued before the update are still checked against the previous version
+        assert_eq!(
+            register.check_permissions(Action::Write, Some(user), Stamp::default()),
+            Err(Error::AccessDenied(user))
+        );
+
+        // nor replayed
+        assert_eq!(register.update_policy(policy, 1), Err(Error::OutOfRange));
+
+        Ok(())
+    }
+
+    #[test]
+    fn register_merge_replica_logs() -> eyre::Result<()> {
+        let keypair = Keypair::new_ed25519();
+        let authority = User::Key(keypair.public_key());
+        let name = XorName::random(&mut rand::thread_rng());
+        let mut replica1 = Register::new_owned(authority, name, 43_000);
+        let mut replica2 = replica1.clone();
+        let create = create_register_cmd(&replica1, &keypair);
+
+        let (hash1, edit1) =
+            edit_register_cmd(&mut replica1, random_register_entry(), &keypair, &keypair)?;
+        let (hash2, edit2) =
+            edit_register_cmd(&mut replica2, random_register_entry(), &keypair, &keypair)?;
+        let policy = Policy {
+            owner: authority,
+            permissions: BTreeMap::from([(User::Anyone, Permissions::new(true))]),
+        };
+        let update = update_register_policy_cmd(&mut replica2, policy.clone(), &keypair)?;
+
+        let address = *replica1.address();
+        let mut log = ReplicatedRegisterLog {
+            address,
+            op_log: vec![create.clone(), edit1],
+        };
+        log.merge(ReplicatedRegisterLog {
+            address,
+            op_log: vec![create, edit2, update],
+        })?;
+        let merged = Register::from_log(&log.op_log).ok_or_else(|| eyre::eyre!("No Register"))?;
+        assert_eq!(merged.size(), 2);
+        assert_eq!(
+            merged
+                .read()
+                .into_iter()
+                .map(|(hash, _)| hash)
+                .collect::<BTreeSet<_>>(),
+            BTreeSet::from([hash1, hash2])
+        );
+        assert_eq!(merged.author(hash2), Some(authority));
+        assert_eq!(merged.policy(), &policy);
+        assert_eq!(merged.policy_version(), 1);
+
+        // cmds which are not signed by their author cannot be merged
+        let forger = Keypair::new_ed25519();
+        let (_, forged) =
+            edit_register_cmd(&mut replica1, random_register_entry(), &keypair, &forger)?;
+        assert_eq!(
+            log.merge(ReplicatedRegisterLog {
+                address,
+                op_log: vec![forged],
+            }),
+            Err(Error::InvalidSignature)
+        );
+        assert_eq!(log.op_log.len(), 4);
+
+        // nor logs of other Registers
+        let other = Register::new_owned(authority, name, 43_001);
+        assert_eq!(
+            log.merge(ReplicatedRegisterLog {
+                address: *other.address(),
+                op_log: vec![create_register_cmd(&other, &keypair)],
+            }),
+            Err(Error::CrdtWrongAddress(*other.address()))
+        );
+
+        Ok(())
+    }
+
     #[test]
     fn exceeding_max_reg_entries_errors() -> eyre::Result<()> {
         let name = xor_name::rand::random();
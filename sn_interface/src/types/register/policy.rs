@@ -9,7 +9,62 @@
 use super::super::{Error, PublicKey, Result};
 use super::Action;
 use serde::{Deserialize, Serialize};
-use std::{collections::BTreeMap, hash::Hash};
+use std::{
+    collections::BTreeMap,
+    hash::Hash,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// How far from the clock of the Elders the time of the stamp of an op sent by a client can be.
+///
+/// Ops are only stamped when they are signed, so an op which is sent later on, e.g. as it was
+/// written while offline, or retried after a delay, is rejected with `InvalidStamp`, and is to
+/// be stamped and signed again against the current state of the Register to be sent again.
+pub const MAX_STAMP_CLOCK_DRIFT: Duration = Duration::from_secs(5 * 60);
+
+/// When the permissions granted to a user expire.
+#[derive(Copy, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
+pub enum Expiry {
+    /// At the given time, in seconds since the Unix epoch, as stamped by the writer, which
+    /// Elders only accept within [`MAX_STAMP_CLOCK_DRIFT`] of their clock, so the permissions
+    /// can still be used up to that long after they expired.
+    Time(u64),
+    /// Once the Register holds the given number of entries.
+    Generation(u64),
+}
+
+/// The point in the history of a Register an op was issued at, as declared in the signed op.
+/// Expiring permissions are evaluated against it rather than against the local clock and
+/// replica, so that all the replicas evaluate them alike, whenever they receive the op.
+///
+/// An op's stamp is checked once upon receiving it from a client, see [`MAX_STAMP_CLOCK_DRIFT`].
+#[derive(
+    Copy, Clone, Default, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq, Hash, Debug,
+)]
+pub struct Stamp {
+    /// Number of entries the Register held.
+    pub generation: u64,
+    /// Time, in seconds since the Unix epoch.
+    pub time: u64,
+    /// Version of the policy of the Register, which the op is checked against.
+    pub policy_version: u64,
+}
+
+impl Stamp {
+    /// Stamp of an op issued now, on a replica holding the given number of entries,
+    /// with the given version of the policy.
+    pub fn now(generation: u64, policy_version: u64) -> Self {
+        let time = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or_default();
+        Self {
+            generation,
+            time,
+            policy_version,
+        }
+    }
+}
 
 /// Set of public permissions for a user.
 #[derive(Copy, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord, Eq, Hash, Debug)]
@@ -18,6 +73,13 @@ pub struct Permissions {
     /// `Some(false)` explicitly denies this permission (even if `Anyone` has permissions).
     /// Use permissions for `Anyone` if `None`.
     write: Option<bool>,
+    /// `Some(true)` if the user can write entries only superseding entries of their own,
+    /// i.e. without merging over others' entries. Permission to write implies this one.
+    append: Option<bool>,
+    /// `Some(true)` if the user can edit the policy.
+    admin: Option<bool>,
+    /// When the permissions expire, if ever. Expired permissions are ignored.
+    expiry: Option<Expiry>,
 }
 
 impl Permissions {
@@ -25,6 +87,9 @@ impl Permissions {
     pub fn new(write: impl Into<Option<bool>>) -> Self {
         Self {
             write: write.into(),
+            append: None,
+            admin: None,
+            expiry: None,
         }
     }
 
@@ -33,12 +98,59 @@ impl Permissions {
         self.write = write.into();
     }
 
+    /// Sets the permission to append entries.
+    pub fn set_append(&mut self, append: impl Into<Option<bool>>) {
+        self.append = append.into();
+    }
+
+    /// Sets the permission to edit the policy.
+    pub fn set_admin(&mut self, admin: impl Into<Option<bool>>) {
+        self.admin = admin.into();
+    }
+
+    /// Sets when the permissions expire.
+    pub fn set_expiry(&mut self, expiry: impl Into<Option<Expiry>>) {
+        self.expiry = expiry.into();
+    }
+
+    /// Returns when the permissions expire, if ever.
+    pub fn expiry(&self) -> Option<Expiry> {
+        self.expiry
+    }
+
     /// Returns `Some(true)` if `action` is allowed and `Some(false)` if it's not permitted.
     /// `None` means that default permissions should be applied.
     pub fn is_allowed(self, action: Action) -> Option<bool> {
         match action {
             Action::Read => Some(true), // It's public data, so it's always allowed to read it.
             Action::Write => self.write,
+            Action::Append => {
+                if self.write == Some(true) {
+                    Some(true)
+                } else {
+                    self.append.or(self.write)
+                }
+            }
+            Action::Admin => self.admin,
+        }
+    }
+
+    /// Returns true if the permissions expired as of the given stamp.
+    pub fn is_expired(&self, stamp: Stamp) -> bool {
+        match self.expiry {
+            Some(Expiry::Time(time)) => stamp.time >= time,
+            Some(Expiry::Generation(max)) => stamp.generation >= max,
+            None => false,
+        }
+    }
+
+    // All the permissions, as held by the owner.
+    fn full() -> Self {
+        Self {
+            write: Some(true),
+            append: Some(true),
+            admin: Some(true),
+            expiry: None,
         }
     }
 }
@@ -64,15 +176,16 @@ pub struct Policy {
 
 impl Policy {
     /// Returns `Ok(())` if `action` is allowed for the provided user and `Err(AccessDenied)` if
-    /// this action is not permitted.
-    pub fn is_action_allowed(&self, requester: User, action: Action) -> Result<()> {
+    /// this action is not permitted, as of the stamp of the op performing it, which
+    /// permissions can be set to expire at.
+    pub fn is_action_allowed(&self, requester: User, action: Action, stamp: Stamp) -> Result<()> {
         // First checks if the requester is the owner.
         if action == Action::Read || requester == self.owner {
             Ok(())
         } else {
             match self
-                .is_action_allowed_by_user(&requester, action)
-                .or_else(|| self.is_action_allowed_by_user(&User::Anyone, action))
+                .is_action_allowed_by_user(&requester, action, stamp)
+                .or_else(|| self.is_action_allowed_by_user(&User::Anyone, action, stamp))
             {
                 Some(true) => Ok(()),
                 Some(false) => Err(Error::AccessDenied(requester)),
@@ -82,10 +195,12 @@ impl Policy {
     }
 
     /// Returns `Some(true)` if `action` is allowed for the provided user and `Some(false)` if it's
-    /// not permitted. `None` means that default permissions should be applied.
-    fn is_action_allowed_by_user(&self, user: &User, action: Action) -> Option<bool> {
+    /// not permitted. `None` means that default permissions should be applied, which is also
+    /// the case when the user's permissions expired.
+    fn is_action_allowed_by_user(&self, user: &User, action: Action, stamp: Stamp) -> Option<bool> {
         self.permissions
             .get(user)
+            .filter(|perms| !perms.is_expired(stamp))
             .and_then(|perms| perms.is_allowed(action))
     }
 
@@ -95,7 +210,7 @@ impl Policy {
             // i.e. it won't be possible to circumvent the semantics of `owner`
             // by setting some other permissions for the user.
             // the permissions can still be kept in the state though, so that switching owners gives an immediate permission update as well
-            Some(Permissions::new(true))
+            Some(Permissions::full())
         } else {
             self.permissions.get(&user).copied()
         }
@@ -140,6 +140,21 @@ impl RegisterCrdt {
         Ok(())
     }
 
+    /// Returns the number of entries known to be superseded by the given entries,
+    /// directly or not, including the given entries themselves.
+    pub(crate) fn num_ancestors(&self, entries: &BTreeSet<EntryHash>) -> u64 {
+        let mut visited = BTreeSet::new();
+        let mut to_visit: Vec<_> = entries.iter().map(|hash| hash.0).collect();
+        while let Some(hash) = to_visit.pop() {
+            if let Some(node) = self.data.node(hash) {
+                if visited.insert(hash) {
+                    to_visit.extend(node.children.iter().copied());
+                }
+            }
+        }
+        visited.len() as u64
+    }
+
     /// Get the entry corresponding to the provided `hash` if it exists.
     pub(crate) fn get(&self, hash: EntryHash) -> Option<&Entry> {
         self.data.node(hash.0).map(|node| &node.value)
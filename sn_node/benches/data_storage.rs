@@ -321,6 +321,7 @@ pub fn create_random_register_replicated_data_edit(
     let edit_register_op = EditRegister {
         address: *register.address(),
         edit,
+        stamp: register.stamp(),
     };
     let signature =
         keypair.sign(&bincode::serialize(&edit_register_op).expect("could not serialize op"));
@@ -369,7 +370,7 @@ fn get_new_data_store() -> Result<DataStorage> {
     let max_capacity = config.max_capacity();
 
     let used_space = UsedSpace::new(max_capacity);
-    let store = DataStorage::new(&storage_dir, used_space)?;
+    let store = DataStorage::new(&storage_dir, used_space, config.storage_backend())?;
 
     Ok(store)
 }
@@ -46,7 +46,7 @@ mod comm;
 
 mod storage;
 
-pub use storage::UsedSpace;
+pub use storage::{StorageBackendKind, UsedSpace};
 
 pub mod node;
 
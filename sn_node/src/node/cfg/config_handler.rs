@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use crate::node::{Error, NetworkConfig, Result};
+use crate::storage::StorageBackendKind;
 use clap::Parser;
 use serde::{Deserialize, Serialize};
 use std::{
@@ -131,6 +132,13 @@ pub struct Config {
     /// Duration of a UPnP port mapping.
     #[clap(long)]
     pub upnp_lease_duration: Option<u32>,
+    /// Backend used to persist the data held by the node: [filesystem, key-value].
+    /// If unspecified, data is stored as individual files on the filesystem.
+    ///
+    /// Data stored with one backend is not visible to the other, so the same backend
+    /// shall be used every time the node is started with the same root dir.
+    #[clap(long, arg_enum)]
+    pub storage_backend: Option<StorageBackendKind>,
     #[clap(skip)]
     #[allow(missing_docs)]
     pub network_config: NetworkConfig,
@@ -256,6 +264,10 @@ impl Config {
         if let Some(keep_alive_interval_msec) = config.keep_alive_interval_msec {
             self.keep_alive_interval_msec = Some(keep_alive_interval_msec);
         }
+
+        if let Some(storage_backend) = config.storage_backend {
+            self.storage_backend = Some(storage_backend);
+        }
     }
 
     /// The address to be credited when this node farms `SafeCoin`.
@@ -278,6 +290,11 @@ impl Config {
         DEFAULT_MAX_CAPACITY
     }
 
+    /// Backend used to persist the data held by the node.
+    pub fn storage_backend(&self) -> StorageBackendKind {
+        self.storage_backend.unwrap_or_default()
+    }
+
     /// Root directory for dbs and cached state. If not set, it defaults to
     /// `DEFAULT_ROOT_DIR_NAME` within the project's data directory (see `Config::root_dir` for the
     /// directories on each platform).
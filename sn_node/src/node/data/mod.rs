@@ -8,5 +8,9 @@
 
 mod capacity;
 mod records;
+mod register_watchers;
 
-pub(crate) use self::capacity::{Capacity, MIN_LEVEL_WHEN_FULL};
+pub(crate) use self::{
+    capacity::{Capacity, MIN_LEVEL_WHEN_FULL},
+    register_watchers::RegisterWatchers,
+};
@@ -14,20 +14,33 @@ use sn_dysfunction::IssueType;
 use sn_interface::{
     data_copy_count,
     messaging::{
-        data::{ClientDataResponse, DataCmd, DataQuery, MetadataExchange, StorageLevel},
+        data::{
+            ClientDataResponse, CmdResponse, DataCmd, DataQuery, DataQueryVariant,
+            Error as ErrorMsg, MetadataExchange, QueryResponse, RegisterCmd, RegisterQuery,
+            SpentKeyImagesPage, SpentbookQuery, StorageLevel, CMD_BATCH_MAX_SIZE,
+        },
         system::{NodeDataCmd, NodeDataQuery, NodeDataResponse, NodeMsg, OperationId},
         AuthorityProof, ClientAuth, Dst, MsgId, MsgKind, MsgType, WireMsg,
     },
-    types::{log_markers::LogMarker, Peer, PublicKey, ReplicatedData},
+    types::{
+        log_markers::LogMarker,
+        register::{Stamp, MAX_STAMP_CLOCK_DRIFT},
+        Error as NetworkDataError, Peer, PublicKey, ReplicatedData,
+    },
 };
 
 use qp2p::{SendStream, UsrMsgBytes};
 
 use bytes::Bytes;
-use futures::FutureExt;
+use futures::{stream, FutureExt, StreamExt};
 use itertools::Itertools;
 use lazy_static::lazy_static;
-use std::{collections::BTreeSet, env::var, str::FromStr, sync::Arc};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    env::var,
+    str::FromStr,
+    sync::Arc,
+};
 use tokio::{
     sync::Mutex,
     time::{timeout, Duration},
@@ -43,6 +56,9 @@ const ENV_ADULT_RESPONSE_TIMEOUT: &str = "SN_ADULT_RESPONSE_TIMEOUT";
 // TODO: how to determine this time properly?
 const ADULT_RESPONSE_DEFAULT_TIMEOUT: Duration = Duration::from_secs(70);
 
+// Max number of the data of a batch whose ops are replicated to the Adults concurrently.
+const BATCH_OPS_CONCURRENCY: usize = 10;
+
 lazy_static! {
     static ref ADULT_RESPONSE_TIMEOUT: Duration = match var(ENV_ADULT_RESPONSE_TIMEOUT)
         .map(|v| u64::from_str(&v))
@@ -158,6 +174,11 @@ impl MyNode {
                     client_response_stream.clone(),
                 )
                 .await?;
+
+                // and let the clients watching the Register know about the operation
+                if let DataCmd::Register(cmd) = &cmd {
+                    snapshot.register_watchers.notify(snapshot, cmd);
+                }
             } else {
                 // This should not be possible with above checks
                 error!("No valid response to send from all responses for {msg_id:?}")
@@ -180,6 +201,191 @@ impl MyNode {
         Ok(())
     }
 
+    // Store each of the operations of a batch at their data holders, and ack them all to the
+    // client in a single response. Operations on the same data are applied in order, the
+    // others concurrently, for up to `BATCH_OPS_CONCURRENCY` data at a time.
+    pub(crate) async fn replicate_batch_to_adults_and_ack_to_client(
+        snapshot: &NodeContext,
+        cmds: Vec<DataCmd>,
+        msg_id: MsgId,
+        client_response_stream: Arc<Mutex<SendStream>>,
+    ) -> Result<()> {
+        debug!("{msg_id:?} replicating batch of {} ops", cmds.len());
+
+        if cmds.len() > CMD_BATCH_MAX_SIZE {
+            warn!("{msg_id:?} rejecting batch of {} ops", cmds.len());
+            let error = ErrorMsg::InvalidOperation(format!(
+                "Batch of {} ops, more than the max of {CMD_BATCH_MAX_SIZE}",
+                cmds.len()
+            ));
+            let response = DataCmd::Batch(cmds).to_error_response(error);
+            return MyNode::send_batch_response_on_stream(
+                snapshot,
+                response,
+                msg_id,
+                client_response_stream,
+            )
+            .await;
+        }
+
+        let mut cmds_by_data: BTreeMap<XorName, Vec<(usize, DataCmd)>> = BTreeMap::new();
+        for (index, cmd) in cmds.into_iter().enumerate() {
+            cmds_by_data
+                .entry(cmd.dst_name())
+                .or_default()
+                .push((index, cmd));
+        }
+
+        let tasks = cmds_by_data.into_values().map(|cmds| async move {
+            let mut responses = vec![];
+            for (index, cmd) in cmds {
+                let response = MyNode::replicate_batch_op_to_adults(snapshot, cmd, msg_id).await;
+                responses.push((index, response));
+            }
+            responses
+        });
+        let mut responses = stream::iter(tasks)
+            .buffer_unordered(BATCH_OPS_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await
+            .into_iter()
+            .flatten()
+            .collect_vec();
+        responses.sort_by_key(|(index, _)| *index);
+
+        let response = CmdResponse::Batch(
+            responses
+                .into_iter()
+                .map(|(_, response)| response)
+                .collect(),
+        );
+        MyNode::send_batch_response_on_stream(snapshot, response, msg_id, client_response_stream)
+            .await
+    }
+
+    async fn send_batch_response_on_stream(
+        snapshot: &NodeContext,
+        response: CmdResponse,
+        msg_id: MsgId,
+        client_response_stream: Arc<Mutex<SendStream>>,
+    ) -> Result<()> {
+        let client_msg = ClientDataResponse::CmdResponse {
+            response,
+            correlation_id: msg_id,
+        };
+        let (kind, payload) = MyNode::serialize_client_msg_response(snapshot.name, client_msg)?;
+
+        debug!("{msg_id:?} sending batch cmd response back to client");
+        MyNode::send_msg_on_stream(
+            snapshot.network_knowledge.section_key(),
+            payload,
+            kind,
+            client_response_stream,
+            None,
+            msg_id,
+        )
+        .await
+    }
+
+    // Check the stamp a Register cmd received from a client was issued with is close enough to our
+    // clock. Replicas evaluate expiring permissions against the stamp rather than their clock, so
+    // they all do alike, thus it's checked once here, so that it cannot be set far in the past.
+    // The client is told with an `InvalidStamp` error, upon which it stamps and signs it again.
+    pub(crate) fn check_register_cmd_stamp(cmd: &RegisterCmd) -> Result<()> {
+        let stamp = match cmd.stamp() {
+            Some(stamp) => stamp,
+            None => return Ok(()),
+        };
+        let now = Stamp::now(0, 0).time;
+        if stamp.time.abs_diff(now) > MAX_STAMP_CLOCK_DRIFT.as_secs() {
+            warn!("Register cmd stamped at {stamp:?} too far from our clock at {now}");
+            return Err(Error::NetworkData(NetworkDataError::InvalidStamp(stamp)));
+        }
+        Ok(())
+    }
+
+    // Store one of the operations of a batch at its data holders, returning the response to it
+    async fn replicate_batch_op_to_adults(
+        snapshot: &NodeContext,
+        cmd: DataCmd,
+        batch_msg_id: MsgId,
+    ) -> CmdResponse {
+        let data = match cmd.clone() {
+            DataCmd::StoreChunk(chunk) => ReplicatedData::Chunk(chunk),
+            DataCmd::Register(register_cmd) => {
+                if let Err(error) = MyNode::check_register_cmd_stamp(&register_cmd) {
+                    return cmd.to_error_response(error.into());
+                }
+                ReplicatedData::RegisterWrite(register_cmd)
+            }
+            DataCmd::Spentbook(_) | DataCmd::Batch(_) => {
+                return cmd.to_error_response(ErrorMsg::InvalidOperation(
+                    "Only chunk and Register ops can be batched".to_string(),
+                ))
+            }
+        };
+
+        // the client shall send it again to the right section, e.g. after we split
+        let prefix = snapshot.network_knowledge.prefix();
+        if !prefix.matches(&data.name()) {
+            return cmd.to_error_response(ErrorMsg::DataNotInSection {
+                name: data.name(),
+                prefix,
+            });
+        }
+
+        let targets = MyNode::target_data_holders(snapshot, data.name());
+        if data_copy_count() > targets.len() {
+            error!("InsufficientAdults for storing data reliably");
+            return cmd.to_error_response(ErrorMsg::InsufficientAdults {
+                prefix,
+                expected: data_copy_count() as u8,
+                found: targets.len() as u8,
+            });
+        }
+
+        // each op is replicated with its own msg id, so their responses are kept apart
+        let msg_id = MsgId::new();
+        debug!(
+            "{batch_msg_id:?} replicating batch op {:?} with {msg_id:?}",
+            data.name()
+        );
+        let responses =
+            match MyNode::replicate_data_to_adults(snapshot, data, msg_id, targets).await {
+                Ok(responses) => responses,
+                Err(error) => return cmd.to_error_response(error.into()),
+            };
+
+        let mut ack_response = None;
+        for (peer, the_response) in responses {
+            let response = match the_response.and_then(|response| Ok(response.into_msg()?)) {
+                Ok(MsgType::NodeDataResponse {
+                    msg: NodeDataResponse::CmdResponse { response, .. },
+                    ..
+                }) => response,
+                Ok(other) => {
+                    error!("Unexpected response to data cmd {msg_id:?} from {peer:?}: {other:?}");
+                    return cmd.to_error_response(Error::InvalidMessage.into());
+                }
+                Err(error) => {
+                    error!("{msg_id:?} Error when replicating to adult {peer:?}: {error:?}");
+                    return cmd.to_error_response(error.into());
+                }
+            };
+            if !response.is_success() {
+                return response;
+            }
+            ack_response = Some(response);
+        }
+
+        // let the clients watching the Register know about the operation
+        if let DataCmd::Register(register_cmd) = &cmd {
+            snapshot.register_watchers.notify(snapshot, register_cmd);
+        }
+
+        ack_response.unwrap_or_else(|| cmd.to_error_response(Error::InvalidMessage.into()))
+    }
+
     /// Parses WireMsg and if DataStored Ack, we send a response to the client
     async fn respond_to_client_on_stream(
         snapshot: &NodeContext,
@@ -241,6 +447,30 @@ impl MyNode {
 
         let targets = MyNode::target_data_holders(&snapshot, *address.name());
 
+        // the client will be notified of the operations applied to a watched Register
+        let watched_register = match &query.variant {
+            DataQueryVariant::Register(RegisterQuery::Watch(address)) => Some(*address),
+            _ => None,
+        };
+        if let Some(address) = &watched_register {
+            if let Err(error) = snapshot
+                .register_watchers
+                .check_limits(address, &source_client)
+                .await
+            {
+                MyNode::send_query_error_response_on_stream(
+                    snapshot,
+                    error,
+                    &query.variant,
+                    source_client,
+                    msg_id,
+                    client_response_stream,
+                )
+                .await?;
+                return Ok(vec![]);
+            }
+        }
+
         // Query only the nth adult
         let target = if let Some(peer) = targets.iter().nth(query.adult_index) {
             *peer
@@ -313,6 +543,7 @@ impl MyNode {
             ..
         } = response.into_msg()?
         {
+            let watching = response.is_success() && watched_register.is_some();
             let client_msg = ClientDataResponse::QueryResponse {
                 response,
                 correlation_id: msg_id,
@@ -320,6 +551,24 @@ impl MyNode {
 
             let (kind, payload) = MyNode::serialize_client_msg_response(snapshot.name, client_msg)?;
 
+            if let (true, Some(address)) = (watching, watched_register) {
+                // the stream is kept open to send the Register events on it
+                MyNode::send_msg_on_open_stream(
+                    snapshot.network_knowledge.section_key(),
+                    payload,
+                    kind,
+                    client_response_stream.clone(),
+                    Some(target),
+                    msg_id,
+                )
+                .await?;
+                snapshot
+                    .register_watchers
+                    .add(address, msg_id, source_client, client_response_stream)
+                    .await;
+                return Ok(vec![]);
+            }
+
             MyNode::send_msg_on_stream(
                 snapshot.network_knowledge.section_key(),
                 payload,
@@ -339,6 +588,150 @@ impl MyNode {
         Ok(vec![])
     }
 
+    /// Lists a page of spent key images from all our adults, as each of them only holds the
+    /// spentbooks it's amongst the closest adults to, and sends the merged page to the client.
+    /// The page is restricted to the part of the queried range within our section.
+    /// If any adult fails to list its part of the page, an error is sent instead, so the client
+    /// never takes a partial page as complete.
+    pub(crate) async fn read_spent_key_images_from_adults_and_respond_to_client(
+        snapshot: NodeContext,
+        prefix: Prefix,
+        start_after: Option<XorName>,
+        msg_id: MsgId,
+        auth: AuthorityProof<ClientAuth>,
+        source_client: Peer,
+        client_response_stream: Arc<Mutex<SendStream>>,
+    ) -> Result<Vec<Cmd>> {
+        let our_prefix = snapshot.network_knowledge.prefix();
+        let page_prefix = if our_prefix.is_extension_of(&prefix) {
+            our_prefix
+        } else {
+            prefix
+        };
+        let query = DataQueryVariant::Spentbook(SpentbookQuery::SpentKeyImages {
+            prefix: page_prefix,
+            start_after,
+        });
+        if !our_prefix.is_compatible(&prefix) {
+            MyNode::send_query_error_response_on_stream(
+                snapshot,
+                Error::InvalidXorname(prefix.lower_bound()),
+                &query,
+                source_client,
+                msg_id,
+                client_response_stream,
+            )
+            .await?;
+            return Ok(vec![]);
+        }
+
+        let operation_id = OperationId::from(&Bytes::copy_from_slice(msg_id.as_ref()));
+        let msg = NodeMsg::NodeDataQuery(NodeDataQuery {
+            query: query.clone(),
+            auth: auth.into_inner(),
+            operation_id,
+        });
+        let (kind, payload) = MyNode::serialize_node_msg(snapshot.name, msg)?;
+        let section_key = snapshot.network_knowledge.section_key();
+
+        let adults = snapshot.network_knowledge.adults();
+        debug!(
+            "Querying {} adults for a page of spent key images in {page_prefix:?} for {msg_id:?}",
+            adults.len()
+        );
+        let tasks = adults.iter().map(|adult| {
+            let comm = snapshot.comm.clone();
+            let bytes = MyNode::form_usr_msg_bytes_to_node(
+                section_key,
+                payload.clone(),
+                kind.clone(),
+                Some(*adult),
+                msg_id,
+            );
+            async move {
+                let bytes = bytes?;
+                let response = timeout(*ADULT_RESPONSE_TIMEOUT, async {
+                    comm.send_out_bytes_to_peer_and_return_response(*adult, msg_id, bytes)
+                        .await
+                })
+                .await;
+                Ok::<_, Error>((*adult, response))
+            }
+        });
+
+        let mut cmds = vec![];
+        let mut pages = vec![];
+        for result in futures::future::join_all(tasks).await {
+            let (adult, response) = match result {
+                Ok(result) => result,
+                Err(error) => {
+                    error!("{msg_id:?}: Failed to query an adult for spent key images: {error:?}");
+                    continue;
+                }
+            };
+            let response = match response {
+                Ok(Ok(response)) => response,
+                Ok(Err(error)) => {
+                    error!("{msg_id:?}: Failed to query {adult:?} for spent key images: {error:?}");
+                    continue;
+                }
+                Err(_elapsed) => {
+                    error!(
+                        "{msg_id:?}: No response from {adult:?} after {:?} timeout. \
+                        Marking adult as dysfunctional",
+                        *ADULT_RESPONSE_TIMEOUT
+                    );
+                    cmds.push(Cmd::TrackNodeIssueInDysfunction {
+                        name: adult.name(),
+                        issue: IssueType::RequestOperation(operation_id),
+                    });
+                    continue;
+                }
+            };
+            match response.into_msg() {
+                Ok(MsgType::NodeDataResponse {
+                    msg:
+                        NodeDataResponse::QueryResponse {
+                            response: QueryResponse::SpentKeyImages(Ok(page)),
+                            ..
+                        },
+                    ..
+                }) => pages.push(page),
+                other => {
+                    error!("{msg_id:?}: Unexpected spent key images response from {adult:?}: {other:?}")
+                }
+            }
+        }
+
+        let response = match SpentKeyImagesPage::merge(pages.iter().cloned()) {
+            Some(page) if pages.len() == adults.len() => QueryResponse::SpentKeyImages(Ok(page)),
+            _ => query.to_error_response(
+                Error::InsufficientAdults {
+                    prefix: our_prefix,
+                    expected: adults.len() as u8,
+                    found: pages.len() as u8,
+                }
+                .into(),
+            ),
+        };
+        let client_msg = ClientDataResponse::QueryResponse {
+            response,
+            correlation_id: msg_id,
+        };
+        let (kind, payload) = MyNode::serialize_client_msg_response(snapshot.name, client_msg)?;
+        MyNode::send_msg_on_stream(
+            section_key,
+            payload,
+            kind,
+            client_response_stream,
+            Some(source_client),
+            msg_id,
+        )
+        .await?;
+
+        Ok(cmds)
+    }
+
     /// Send an OutgoingMsg on a given stream
     pub(crate) async fn send_msg_on_stream(
         section_key: bls::PublicKey,
@@ -356,9 +749,54 @@ impl MyNode {
             target_peer,
             original_msg_id,
         )?;
-        let stream_prio = 10;
         let mut send_stream = send_stream.lock_owned().await;
         let stream_id = send_stream.id();
+        MyNode::send_bytes_on_stream(&mut send_stream, bytes, target_peer, original_msg_id).await?;
+
+        // unblock + move finish off thread as it's not strictly related to the sending of the msg.
+        let stream_id_clone = stream_id.clone();
+        let _handle = tokio::spawn(async move {
+            // Attempt to gracefully terminate the stream.
+            // If this errors it does _not_ mean our message has not been sent
+            let result = send_stream.finish().await;
+            trace!("bidi {stream_id_clone} finished for {original_msg_id:?} to {target_peer:?}: {result:?}");
+        });
+
+        debug!("Sent the msg {original_msg_id:?} over {stream_id} to {target_peer:?}");
+
+        Ok(())
+    }
+
+    /// Send an OutgoingMsg on a given stream, without finishing the stream,
+    /// so more msgs can be sent on it afterwards
+    pub(crate) async fn send_msg_on_open_stream(
+        section_key: bls::PublicKey,
+        payload: Bytes,
+        kind: MsgKind,
+        send_stream: Arc<Mutex<SendStream>>,
+        target_peer: Option<Peer>,
+        original_msg_id: MsgId,
+    ) -> Result<()> {
+        let bytes = MyNode::form_usr_msg_bytes_to_node(
+            section_key,
+            payload,
+            kind,
+            target_peer,
+            original_msg_id,
+        )?;
+        let mut send_stream = send_stream.lock().await;
+        MyNode::send_bytes_on_stream(&mut send_stream, bytes, target_peer, original_msg_id).await
+    }
+
+    // Send the msg bytes on the (locked) stream
+    async fn send_bytes_on_stream(
+        send_stream: &mut SendStream,
+        bytes: UsrMsgBytes,
+        target_peer: Option<Peer>,
+        original_msg_id: MsgId,
+    ) -> Result<()> {
+        let stream_prio = 10;
+        let stream_id = send_stream.id();
         trace!("Sending {original_msg_id:?} to recipient over {stream_id}");
 
         trace!("Stream {stream_id} locked for {original_msg_id:?} to {target_peer:?}");
@@ -373,18 +811,6 @@ impl MyNode {
         }
 
         trace!("Msg away for {original_msg_id:?} to {target_peer:?}, over {stream_id}");
-
-        // unblock + move finish off thread as it's not strictly related to the sending of the msg.
-        let stream_id_clone = stream_id.clone();
-        let _handle = tokio::spawn(async move {
-            // Attempt to gracefully terminate the stream.
-            // If this errors it does _not_ mean our message has not been sent
-            let result = send_stream.finish().await;
-            trace!("bidi {stream_id_clone} finished for {original_msg_id:?} to {target_peer:?}: {result:?}");
-        });
-
-        debug!("Sent the msg {original_msg_id:?} over {stream_id} to {target_peer:?}");
-
         Ok(())
     }
 
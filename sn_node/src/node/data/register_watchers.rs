@@ -0,0 +1,176 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::node::{core::NodeContext, Error, MyNode, Result};
+
+use sn_interface::{
+    messaging::{data::ClientDataResponse, MsgId},
+    types::{Peer, RegisterAddress, RegisterCmd},
+};
+
+use futures::future::join_all;
+use qp2p::SendStream;
+use std::{collections::BTreeMap, sync::Arc, time::Duration};
+use tokio::{
+    sync::{Mutex, RwLock},
+    time::timeout,
+};
+
+/// Max number of Registers a single client can be watching on this Elder.
+const MAX_WATCHERS_PER_CLIENT: usize = 32;
+/// Max number of clients which can be watching a single Register on this Elder.
+const MAX_WATCHERS_PER_REGISTER: usize = 256;
+/// Time a watcher is given to take an event, before it is dropped as too slow.
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Clients watching Registers, i.e. notified by the Elders of the operations
+/// applied to them, over the stream they sent their watch query on.
+#[derive(Clone, Default)]
+pub(crate) struct RegisterWatchers {
+    watchers: Arc<RwLock<BTreeMap<RegisterAddress, Vec<RegisterWatcher>>>>,
+}
+
+#[derive(Clone)]
+struct RegisterWatcher {
+    // id of the watch query, which the notifications are correlated to
+    correlation_id: MsgId,
+    client: Peer,
+    send_stream: Arc<Mutex<SendStream>>,
+}
+
+impl RegisterWatchers {
+    /// Check the client can start watching the Register at the given address,
+    /// i.e. neither the client nor the Register reached their limit of watchers.
+    pub(crate) async fn check_limits(
+        &self,
+        address: &RegisterAddress,
+        client: &Peer,
+    ) -> Result<()> {
+        let watchers = self.watchers.read().await;
+        let register_watchers = watchers.get(address).map_or(0, Vec::len);
+        let client_watchers = watchers
+            .values()
+            .flatten()
+            .filter(|watcher| watcher.client == *client)
+            .count();
+
+        if register_watchers >= MAX_WATCHERS_PER_REGISTER
+            || client_watchers >= MAX_WATCHERS_PER_CLIENT
+        {
+            Err(Error::TooManyRegisterWatchers(*address))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Register a client watching the Register at the given address.
+    /// The stream is closed instead if the watchers limits were reached meanwhile.
+    pub(crate) async fn add(
+        &self,
+        address: RegisterAddress,
+        correlation_id: MsgId,
+        client: Peer,
+        send_stream: Arc<Mutex<SendStream>>,
+    ) {
+        if let Err(error) = self.check_limits(&address, &client).await {
+            debug!("Client {client:?} cannot watch Register {address:?}: {error:?}");
+            let _ = send_stream.lock().await.finish().await;
+            return;
+        }
+
+        debug!("Client {client:?} watching Register {address:?} with {correlation_id:?}");
+        self.watchers
+            .write()
+            .await
+            .entry(address)
+            .or_default()
+            .push(RegisterWatcher {
+                correlation_id,
+                client,
+                send_stream,
+            });
+    }
+
+    /// Notify the clients watching the Register of the operation applied to it.
+    /// The events are sent from a spawned task, so the caller is never held up by the watchers.
+    /// Clients which cannot be notified anymore, e.g. they closed the stream, or which don't take
+    /// the event within `NOTIFY_TIMEOUT`, are dropped, as well as all of them once the Register
+    /// is deleted.
+    pub(crate) fn notify(&self, context: &NodeContext, cmd: &RegisterCmd) {
+        let register_watchers = self.clone();
+        let context = context.clone();
+        let cmd = cmd.clone();
+        let _handle = tokio::spawn(async move {
+            register_watchers.send_events(&context, &cmd).await;
+        });
+    }
+
+    async fn send_events(&self, context: &NodeContext, cmd: &RegisterCmd) {
+        let address = cmd.dst_address();
+        let watchers = match self.watchers.read().await.get(&address) {
+            Some(watchers) => watchers.clone(),
+            None => return,
+        };
+
+        let sends = watchers.iter().map(|watcher| async move {
+            let result =
+                match timeout(NOTIFY_TIMEOUT, Self::send_event(context, cmd, watcher)).await {
+                    Ok(result) => result.map_err(|error| format!("{error:?}")),
+                    Err(_elapsed) => Err(format!("no event taken within {NOTIFY_TIMEOUT:?}")),
+                };
+            (watcher, result)
+        });
+
+        let mut unreachable = vec![];
+        for (watcher, result) in join_all(sends).await {
+            if let Err(error) = result {
+                debug!(
+                    "Dropping {:?} watching Register {address:?}: {error}",
+                    watcher.client
+                );
+                unreachable.push(watcher.correlation_id);
+            }
+        }
+
+        let mut watchers = self.watchers.write().await;
+        if matches!(cmd, RegisterCmd::Delete(_)) {
+            if let Some(watchers) = watchers.remove(&address) {
+                for watcher in watchers {
+                    let _ = watcher.send_stream.lock().await.finish().await;
+                }
+            }
+        } else if let Some(address_watchers) = watchers.get_mut(&address) {
+            address_watchers.retain(|watcher| !unreachable.contains(&watcher.correlation_id));
+            if address_watchers.is_empty() {
+                let _ = watchers.remove(&address);
+            }
+        }
+    }
+
+    async fn send_event(
+        context: &NodeContext,
+        cmd: &RegisterCmd,
+        watcher: &RegisterWatcher,
+    ) -> Result<()> {
+        let event = ClientDataResponse::RegisterEvent {
+            cmd: cmd.clone(),
+            correlation_id: watcher.correlation_id,
+        };
+        let (kind, payload) = MyNode::serialize_client_msg_response(context.name, event)?;
+
+        MyNode::send_msg_on_open_stream(
+            context.network_knowledge.section_key(),
+            payload,
+            kind,
+            watcher.send_stream.clone(),
+            Some(watcher.client),
+            watcher.correlation_id,
+        )
+        .await
+    }
+}
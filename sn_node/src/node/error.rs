@@ -15,7 +15,7 @@ use sn_interface::{
     dbcs::Error as GenesisError,
     messaging::data::{DataQuery, Error as ErrorMsg},
     messaging::system::DkgSessionId,
-    types::{DataAddress, Peer},
+    types::{DataAddress, Peer, RegisterAddress},
 };
 
 use ed25519::Signature;
@@ -140,6 +140,9 @@ pub enum Error {
     /// Chunk already exists for this node
     #[error("Data already exists at this node: {0:?}")]
     DataExists(DataAddress),
+    /// The limit of clients watching a Register, or of Registers watched by a client, was reached
+    #[error("Too many watchers of Register {0:?}, or Registers watched by the client")]
+    TooManyRegisterWatchers(RegisterAddress),
     /// I/O error.
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
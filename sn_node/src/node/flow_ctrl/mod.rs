@@ -48,6 +48,8 @@ pub(crate) struct FlowCtrl {
     cmd_sender_channel: mpsc::Sender<(Cmd, Vec<usize>)>,
     dysfunction_channels: DysfunctionChannels,
     timestamps: PeriodicChecksTimestamps,
+    // name of the last chunk verified by the scrubber, to resume walking the store from there
+    last_scrubbed_chunk: Arc<RwLock<Option<XorName>>>,
 }
 
 impl FlowCtrl {
@@ -93,6 +95,7 @@ impl FlowCtrl {
             cmd_sender_channel: cmd_sender_channel.clone(),
             dysfunction_channels,
             timestamps: PeriodicChecksTimestamps::now(),
+            last_scrubbed_chunk: Arc::new(RwLock::new(None)),
         };
 
         let _ =
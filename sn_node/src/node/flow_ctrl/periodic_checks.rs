@@ -27,6 +27,15 @@ const DYSFUNCTION_CHECK_INTERVAL: Duration = Duration::from_secs(5);
 // Which should hopefully trigger dysfunction if we're not getting responses back
 // const ADULT_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(2);
 const ELDER_HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(3);
+const DELETED_REGISTERS_GC_INTERVAL: Duration = Duration::from_secs(60 * 60);
+const CHUNK_SCRUB_INTERVAL: Duration = Duration::from_secs(10);
+// Number of chunks verified on each scrubbing round, so the disk isn't hogged by it
+const CHUNK_SCRUB_BATCH_SIZE: usize = 50;
+// Chunks written more recently than this may still be being written, so are not scrubbed yet
+const CHUNK_SCRUB_MIN_AGE: Duration = Duration::from_secs(60);
+// Time a deleted Register's entries are kept for before reclaiming their space,
+// giving the tombstone a chance to reach all the replicas first
+const DELETED_REGISTERS_GRACE_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
 
 pub(super) struct PeriodicChecksTimestamps {
     last_probe: Instant,
@@ -36,6 +45,8 @@ pub(super) struct PeriodicChecksTimestamps {
     last_vote_check: Instant,
     last_dkg_msg_check: Instant,
     last_dysfunction_check: Instant,
+    last_deleted_registers_gc: Instant,
+    last_chunk_scrub: Instant,
 }
 
 impl PeriodicChecksTimestamps {
@@ -48,6 +59,8 @@ impl PeriodicChecksTimestamps {
             last_vote_check: Instant::now(),
             last_dkg_msg_check: Instant::now(),
             last_dysfunction_check: Instant::now(),
+            last_deleted_registers_gc: Instant::now(),
+            last_chunk_scrub: Instant::now(),
         }
     }
 }
@@ -85,6 +98,26 @@ impl FlowCtrl {
             cmds.push(Self::probe_the_section(context).await);
         }
 
+        if self.timestamps.last_deleted_registers_gc.elapsed() > DELETED_REGISTERS_GC_INTERVAL {
+            self.timestamps.last_deleted_registers_gc = Instant::now();
+            // this walks through all the stored Registers, so let's not hold up the flow
+            let data_storage = context.data_storage.clone();
+            let _handle = tokio::spawn(async move {
+                match data_storage
+                    .reclaim_deleted_registers(DELETED_REGISTERS_GRACE_PERIOD)
+                    .await
+                {
+                    Ok(count) => debug!("Space reclaimed from {count} deleted Register/s"),
+                    Err(error) => error!("Error reclaiming space of deleted Registers: {error:?}"),
+                }
+            });
+        }
+
+        if self.timestamps.last_chunk_scrub.elapsed() > CHUNK_SCRUB_INTERVAL {
+            self.timestamps.last_chunk_scrub = Instant::now();
+            self.scrub_chunks(context);
+        }
+
         for cmd in cmds {
             if let Err(error) = self.cmd_sender_channel.send((cmd, vec![])).await {
                 error!("Error queuing adult periodic check: {error:?}");
@@ -92,6 +125,51 @@ impl FlowCtrl {
         }
     }
 
+    /// Spawns a task which verifies the integrity of the next batch of chunks we hold,
+    /// asking our neighbours for fresh replicas of any chunk found corrupted and removed.
+    fn scrub_chunks(&self, context: &NodeContext) {
+        // don't start another round if the previous one is still running
+        let mut last_scrubbed = match self.last_scrubbed_chunk.clone().try_write_owned() {
+            Ok(last_scrubbed) => last_scrubbed,
+            Err(_) => {
+                debug!("Previous chunk scrubbing round still running, skipping this one");
+                return;
+            }
+        };
+
+        let context = context.clone();
+        let cmd_channel = self.cmd_sender_channel.clone();
+        let _handle = tokio::spawn(async move {
+            let (corrupted, last) = match context
+                .data_storage
+                .scrub_chunks(*last_scrubbed, CHUNK_SCRUB_BATCH_SIZE, CHUNK_SCRUB_MIN_AGE)
+                .await
+            {
+                Ok(result) => result,
+                Err(error) => {
+                    error!("Error scrubbing chunks: {error:?}");
+                    return;
+                }
+            };
+            *last_scrubbed = last;
+
+            if corrupted.is_empty() {
+                return;
+            }
+
+            warn!(
+                "{} corrupted chunk/s removed, asking for fresh replicas: {corrupted:?}",
+                corrupted.len()
+            );
+            // those removed are no longer in the list of data we hold,
+            // thus our neighbours will send them back to us
+            let cmd = MyNode::ask_for_any_new_data(&context).await;
+            if let Err(error) = cmd_channel.send((cmd, vec![])).await {
+                error!("Error queuing request for missing data: {error:?}");
+            }
+        });
+    }
+
     /// Periodic tasks run for elders only
     async fn enqueue_cmds_for_elder_periodic_checks(
         &mut self,
@@ -2,9 +2,9 @@ use crate::{
     comm::{Comm, MsgFromPeer},
     node::{
         cfg::create_test_max_capacity_and_root_storage, core::MyNode,
-        flow_ctrl::dispatcher::Dispatcher,
+        flow_ctrl::dispatcher::Dispatcher, DataStorage,
     },
-    UsedSpace,
+    StorageBackendKind, UsedSpace,
 };
 use sn_interface::{
     elder_count,
@@ -820,7 +820,12 @@ impl TestNetwork {
             info.keypair.clone(),
             network_knowledge.clone(),
             sk_share.clone(),
-            UsedSpace::new(max_capacity),
+            DataStorage::new(
+                &root_storage_dir,
+                UsedSpace::new(max_capacity),
+                StorageBackendKind::Filesystem,
+            )
+            .expect("Failed to create data storage"),
             root_storage_dir,
             mpsc::channel(10).0,
         ))
@@ -19,8 +19,8 @@ use sn_interface::{
     data_copy_count,
     messaging::{
         data::{
-            ClientDataResponse, ClientMsg, DataCmd, DataQueryVariant, EditRegister,
-            SignedRegisterEdit, SpentbookCmd,
+            ClientDataResponse, ClientMsg, DataCmd, DataQuery, DataQueryVariant, EditRegister,
+            SignedRegisterEdit, SpentbookCmd, SpentbookQuery,
         },
         system::{NodeDataResponse, OperationId},
         AuthorityProof, ClientAuth, MsgId,
@@ -28,7 +28,7 @@ use sn_interface::{
     network_knowledge::section_keys::build_spent_proof_share,
     types::{
         log_markers::LogMarker,
-        register::{Permissions, Policy, Register, User},
+        register::{Permissions, Policy, Register, Stamp, User},
         Keypair, Peer, RegisterCmd, ReplicatedData, SPENTBOOK_TYPE_TAG,
     },
 };
@@ -162,6 +162,25 @@ impl MyNode {
 
         let cmd = match msg {
             ClientMsg::Cmd(cmd) => cmd,
+            ClientMsg::Query(DataQuery {
+                variant:
+                    DataQueryVariant::Spentbook(SpentbookQuery::SpentKeyImages {
+                        prefix,
+                        start_after,
+                    }),
+                ..
+            }) => {
+                return MyNode::read_spent_key_images_from_adults_and_respond_to_client(
+                    context,
+                    prefix,
+                    start_after,
+                    msg_id,
+                    auth,
+                    origin,
+                    send_stream,
+                )
+                .await
+            }
             ClientMsg::Query(query) => {
                 return MyNode::read_data_from_adult_and_respond_to_client(
                     context,
@@ -179,7 +198,9 @@ impl MyNode {
         let data_result = match cmd.clone() {
             // These reads/writes are for adult nodes...
             DataCmd::StoreChunk(chunk) => Ok(ReplicatedData::Chunk(chunk)),
-            DataCmd::Register(cmd) => Ok(ReplicatedData::RegisterWrite(cmd)),
+            DataCmd::Register(cmd) => {
+                MyNode::check_register_cmd_stamp(&cmd).map(|()| ReplicatedData::RegisterWrite(cmd))
+            }
             DataCmd::Spentbook(cmd) => {
                 let SpentbookCmd::Spend {
                     network_knowledge,
@@ -215,6 +236,17 @@ impl MyNode {
                 }
                 MyNode::extract_contents_as_replicated_data(&context, cmd)
             }
+            DataCmd::Batch(cmds) => {
+                // each op of the batch is acked individually, in a single response
+                MyNode::replicate_batch_to_adults_and_ack_to_client(
+                    &context,
+                    cmds,
+                    msg_id,
+                    send_stream,
+                )
+                .await?;
+                return Ok(vec![]);
+            }
         };
 
         let data = match data_result {
@@ -409,9 +441,11 @@ impl MyNode {
         })?;
 
         let (_, op) = register.write(entry.into_inner(), BTreeSet::default())?;
+        // the owner's permissions never expire, and a default stamp keeps the cmd deterministic
         let op = EditRegister {
             address: *register.address(),
             edit: op,
+            stamp: Stamp::default(),
         };
 
         let signature = own_keypair.sign(&bincode::serialize(&op)?);
@@ -55,18 +55,15 @@ pub use xor_name::{Prefix, XorName, XOR_NAME_LEN}; // TODO remove pub on API upd
 
 mod core {
     use crate::comm::Comm;
-    use crate::{
-        node::{
-            bootstrap::JoiningAsRelocated,
-            data::Capacity,
-            dkg::DkgVoter,
-            flow_ctrl::{cmds::Cmd, dysfunction::DysCmds},
-            handover::Handover,
-            membership::{elder_candidates, try_split_dkg, Membership},
-            messaging::Peers,
-            DataStorage, Error, Proposal, Result, XorName,
-        },
-        UsedSpace,
+    use crate::node::{
+        bootstrap::JoiningAsRelocated,
+        data::{Capacity, RegisterWatchers},
+        dkg::DkgVoter,
+        flow_ctrl::{cmds::Cmd, dysfunction::DysCmds},
+        handover::Handover,
+        membership::{elder_candidates, try_split_dkg, Membership},
+        messaging::Peers,
+        DataStorage, Error, Proposal, Result, XorName,
     };
 
     use sn_dysfunction::IssueType;
@@ -131,6 +128,8 @@ mod core {
         // Trackers
         pub(crate) capacity: Capacity,
         pub(crate) dysfunction_cmds_sender: mpsc::Sender<DysCmds>,
+        // Clients watching Registers (Elder only)
+        pub(crate) register_watchers: RegisterWatchers,
     }
 
     #[derive(custom_debug::Debug, Clone)]
@@ -146,6 +145,8 @@ mod core {
         #[debug(skip)]
         pub(crate) comm: Comm,
         pub(crate) joins_allowed: bool,
+        #[debug(skip)]
+        pub(crate) register_watchers: RegisterWatchers,
     }
 
     impl NodeContext {
@@ -176,6 +177,7 @@ mod core {
                 comm: self.comm.clone(),
                 joins_allowed: self.joins_allowed,
                 data_storage: self.data_storage.clone(),
+                register_watchers: self.register_watchers.clone(),
             }
         }
 
@@ -185,7 +187,7 @@ mod core {
             keypair: Arc<Keypair>,
             network_knowledge: NetworkKnowledge,
             section_key_share: Option<SectionKeyShare>,
-            used_space: UsedSpace,
+            data_storage: DataStorage,
             root_storage_dir: PathBuf,
             dysfunction_cmds_sender: mpsc::Sender<DysCmds>,
         ) -> Result<Self> {
@@ -213,8 +215,6 @@ mod core {
 
             let section_keys_provider = SectionKeysProvider::new(section_key_share.clone());
 
-            let data_storage = DataStorage::new(&root_storage_dir, used_space)?;
-
             // create handover
             let handover = if let Some(key) = section_key_share {
                 let secret_key = (key.index as u8, key.secret_key_share);
@@ -246,6 +246,7 @@ mod core {
                 capacity: Capacity::default(),
                 dysfunction_cmds_sender,
                 membership,
+                register_watchers: RegisterWatchers::default(),
             };
 
             let context = &node.context();
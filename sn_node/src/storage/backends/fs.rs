@@ -0,0 +1,222 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{ItemKey, ItemKind, StorageBackend};
+
+use crate::storage::{list_files_in, prefix_tree_path, Result, BIT_TREE_DEPTH};
+
+use async_trait::async_trait;
+use hex::FromHex;
+use std::{
+    collections::BTreeSet,
+    io::{self, ErrorKind},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+use tokio::{
+    fs::{create_dir_all, metadata, read, remove_dir, remove_file, File},
+    io::AsyncWriteExt,
+};
+use walkdir::WalkDir;
+use xor_name::XorName;
+
+const CHUNKS_STORE_DIR_NAME: &str = "chunks";
+const REGISTER_STORE_DIR_NAME: &str = "register";
+
+/// Backend storing each item in its own file, within a tree of directories named
+/// after the first bits of the item's id. Entries grouped under the same id are
+/// stored as files within a directory named after the id.
+#[derive(Debug)]
+pub(super) struct FsBackend {
+    root: PathBuf,
+}
+
+impl FsBackend {
+    pub(super) fn new(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+        }
+    }
+
+    fn kind_dir(&self, kind: ItemKind) -> PathBuf {
+        match kind {
+            ItemKind::Chunk => self.root.join(CHUNKS_STORE_DIR_NAME),
+            ItemKind::RegisterCmd => self.root.join(REGISTER_STORE_DIR_NAME),
+        }
+    }
+
+    fn id_path(&self, kind: ItemKind, id: XorName) -> PathBuf {
+        // we use hex to get full id, not just first bytes
+        prefix_tree_path(&self.kind_dir(kind), id).join(hex::encode(id))
+    }
+
+    fn item_path(&self, kind: ItemKind, key: &ItemKey) -> PathBuf {
+        let path = self.id_path(kind, key.id);
+        match &key.entry {
+            Some(entry) => path.join(entry),
+            None => path,
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for FsBackend {
+    async fn write(&self, kind: ItemKind, key: &ItemKey, value: &[u8]) -> Result<()> {
+        let path = self.item_path(kind, key);
+        if let Some(dirs) = path.parent() {
+            create_dir_all(dirs).await?;
+        }
+
+        let mut file = File::create(path).await?;
+        file.write_all(value).await?;
+        // Let's sync up OS data to disk to reduce the chances of
+        // concurrent reading failing by reading an empty/incomplete file
+        file.sync_data().await?;
+
+        Ok(())
+    }
+
+    async fn read(&self, kind: ItemKind, key: &ItemKey) -> Result<Option<Vec<u8>>> {
+        not_found_as_none(read(self.item_path(kind, key)).await)
+    }
+
+    async fn exists(&self, kind: ItemKind, key: &ItemKey) -> Result<bool> {
+        Ok(self.item_path(kind, key).is_file())
+    }
+
+    async fn written_at(&self, kind: ItemKind, key: &ItemKey) -> Result<Option<SystemTime>> {
+        let modified = metadata(self.item_path(kind, key))
+            .await
+            .and_then(|meta| meta.modified());
+        not_found_as_none(modified)
+    }
+
+    async fn remove(&self, kind: ItemKind, key: &ItemKey) -> Result<Option<usize>> {
+        let path = self.item_path(kind, key);
+        let size = match not_found_as_none(metadata(&path).await)? {
+            Some(meta) => meta.len() as usize,
+            None => return Ok(None),
+        };
+        remove_file(&path).await?;
+
+        if key.entry.is_some() {
+            // it only succeeds if that was the last entry grouped under the id
+            let _ = remove_dir(self.id_path(kind, key.id)).await;
+        }
+
+        Ok(Some(size))
+    }
+
+    async fn ids(&self, kind: ItemKind) -> Result<Vec<XorName>> {
+        let ids = list_files_in(&self.kind_dir(kind))
+            .iter()
+            .filter_map(|filepath| match kind {
+                ItemKind::Chunk => Some(filepath.as_path()),
+                // entries are files within a directory named after the id
+                ItemKind::RegisterCmd => filepath.parent(),
+            })
+            .filter_map(id_from_file_name)
+            .collect::<BTreeSet<_>>();
+
+        Ok(ids.into_iter().collect())
+    }
+
+    async fn ids_after(
+        &self,
+        kind: ItemKind,
+        after: Option<XorName>,
+        max: usize,
+    ) -> Result<Vec<XorName>> {
+        let root = self.kind_dir(kind);
+        if !root.exists() {
+            return Ok(vec![]);
+        }
+
+        // the tree is walked in order, without descending into the
+        // directories which only hold ids up to the `after` id
+        let after_bits = after.map(|after| format!("{after:b}"));
+        let walker = WalkDir::new(&root)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|entry| {
+                let depth = entry.depth();
+                match (&after_bits, after) {
+                    (Some(after_bits), Some(after)) if depth > 0 => {
+                        if depth <= BIT_TREE_DEPTH {
+                            let bits: String = entry
+                                .path()
+                                .strip_prefix(&root)
+                                .map(|path| path.iter().filter_map(|dir| dir.to_str()).collect())
+                                .unwrap_or_default();
+                            bits.as_str() >= &after_bits[..depth]
+                        } else if depth == BIT_TREE_DEPTH + 1 {
+                            id_from_file_name(entry.path()).is_some_and(|id| id > after)
+                        } else {
+                            true
+                        }
+                    }
+                    _ => true,
+                }
+            });
+
+        let mut ids = Vec::<XorName>::new();
+        for entry in walker {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    warn!("Store: failed to process filesystem entry: {}", err);
+                    continue;
+                }
+            };
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let id_path = match kind {
+                ItemKind::Chunk => Some(entry.path()),
+                // entries are files within a directory named after the id
+                ItemKind::RegisterCmd => entry.path().parent(),
+            };
+            if let Some(id) = id_path.and_then(id_from_file_name) {
+                if ids.last() != Some(&id) {
+                    if ids.len() == max {
+                        break;
+                    }
+                    ids.push(id);
+                }
+            }
+        }
+
+        Ok(ids)
+    }
+
+    async fn entries(&self, kind: ItemKind, id: XorName) -> Result<Vec<ItemKey>> {
+        let entries = list_files_in(&self.id_path(kind, id))
+            .iter()
+            .filter_map(|filepath| {
+                let entry = filepath.file_name()?.to_str()?;
+                Some(ItemKey::entry(id, entry.to_string()))
+            })
+            .collect();
+
+        Ok(entries)
+    }
+}
+
+// Items, and groups of entries, are stored under the hex-encoded id
+fn id_from_file_name(path: &Path) -> Option<XorName> {
+    let name = path.file_name()?.to_str()?;
+    <[u8; 32]>::from_hex(name).ok().map(XorName)
+}
+
+fn not_found_as_none<T>(result: io::Result<T>) -> Result<Option<T>> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
@@ -0,0 +1,165 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{ItemKey, ItemKind, StorageBackend};
+
+use crate::storage::Result;
+
+use async_trait::async_trait;
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use xor_name::{XorName, XOR_NAME_LEN};
+
+const KV_STORE_DIR_NAME: &str = "kv_store";
+// Values are prefixed with the time they were written at, as milliseconds since the epoch
+const TIMESTAMP_LEN: usize = 8;
+
+/// Backend storing all items in an embedded key-value database.
+///
+/// Keys are made of a byte identifying the kind of item, followed by the item's id,
+/// and the name of the entry if any, so all the entries grouped under the same id
+/// are found with a single prefix scan.
+#[derive(Debug)]
+pub(super) struct KvBackend {
+    db: sled::Db,
+}
+
+impl KvBackend {
+    pub(super) fn open(root: &Path) -> Result<Self> {
+        let db = sled::open(root.join(KV_STORE_DIR_NAME))?;
+        Ok(Self { db })
+    }
+
+    fn kind_prefix(kind: ItemKind) -> u8 {
+        match kind {
+            ItemKind::Chunk => 0,
+            ItemKind::RegisterCmd => 1,
+        }
+    }
+
+    fn id_prefix(kind: ItemKind, id: XorName) -> Vec<u8> {
+        let mut prefix = Vec::with_capacity(1 + XOR_NAME_LEN);
+        prefix.push(Self::kind_prefix(kind));
+        prefix.extend_from_slice(&id.0);
+        prefix
+    }
+
+    fn db_key(kind: ItemKind, key: &ItemKey) -> Vec<u8> {
+        let mut db_key = Self::id_prefix(kind, key.id);
+        if let Some(entry) = &key.entry {
+            db_key.extend_from_slice(entry.as_bytes());
+        }
+        db_key
+    }
+
+    // Splits a key found in the db into the item's id and entry name
+    fn item_key(db_key: &[u8]) -> Option<ItemKey> {
+        let id = XorName(db_key.get(1..1 + XOR_NAME_LEN)?.try_into().ok()?);
+        let entry = &db_key[1 + XOR_NAME_LEN..];
+        if entry.is_empty() {
+            Some(ItemKey::new(id))
+        } else {
+            Some(ItemKey::entry(id, String::from_utf8(entry.to_vec()).ok()?))
+        }
+    }
+}
+
+#[async_trait]
+impl StorageBackend for KvBackend {
+    async fn write(&self, kind: ItemKind, key: &ItemKey, value: &[u8]) -> Result<()> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+
+        let mut db_value = Vec::with_capacity(TIMESTAMP_LEN + value.len());
+        db_value.extend_from_slice(&millis.to_be_bytes());
+        db_value.extend_from_slice(value);
+
+        let _prev = self.db.insert(Self::db_key(kind, key), db_value)?;
+        let _ = self.db.flush_async().await?;
+        Ok(())
+    }
+
+    async fn read(&self, kind: ItemKind, key: &ItemKey) -> Result<Option<Vec<u8>>> {
+        let value = self.db.get(Self::db_key(kind, key))?;
+        Ok(value.map(|value| value[TIMESTAMP_LEN..].to_vec()))
+    }
+
+    async fn exists(&self, kind: ItemKind, key: &ItemKey) -> Result<bool> {
+        Ok(self.db.contains_key(Self::db_key(kind, key))?)
+    }
+
+    async fn written_at(&self, kind: ItemKind, key: &ItemKey) -> Result<Option<SystemTime>> {
+        let value = self.db.get(Self::db_key(kind, key))?;
+        Ok(value.and_then(|value| {
+            let millis = u64::from_be_bytes(value.get(..TIMESTAMP_LEN)?.try_into().ok()?);
+            Some(UNIX_EPOCH + Duration::from_millis(millis))
+        }))
+    }
+
+    async fn remove(&self, kind: ItemKind, key: &ItemKey) -> Result<Option<usize>> {
+        let removed = self.db.remove(Self::db_key(kind, key))?;
+        if removed.is_some() {
+            let _ = self.db.flush_async().await?;
+        }
+        Ok(removed.map(|value| value.len() - TIMESTAMP_LEN))
+    }
+
+    async fn ids(&self, kind: ItemKind) -> Result<Vec<XorName>> {
+        let mut ids = Vec::<XorName>::new();
+        for db_key in self.db.scan_prefix([Self::kind_prefix(kind)]).keys() {
+            if let Some(key) = Self::item_key(&db_key?) {
+                // keys are sorted, thus all the entries of an id are found one after the other
+                if ids.last() != Some(&key.id) {
+                    ids.push(key.id);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    async fn ids_after(
+        &self,
+        kind: ItemKind,
+        after: Option<XorName>,
+        max: usize,
+    ) -> Result<Vec<XorName>> {
+        let start = match after {
+            Some(after) => Self::id_prefix(kind, after),
+            None => vec![Self::kind_prefix(kind)],
+        };
+        let mut ids = Vec::<XorName>::new();
+        for db_key in self.db.range(start..).keys() {
+            let db_key = db_key?;
+            if db_key.first() != Some(&Self::kind_prefix(kind)) || ids.len() == max {
+                break;
+            }
+            if let Some(key) = Self::item_key(&db_key) {
+                if Some(key.id) != after && ids.last() != Some(&key.id) {
+                    ids.push(key.id);
+                }
+            }
+        }
+        Ok(ids)
+    }
+
+    async fn entries(&self, kind: ItemKind, id: XorName) -> Result<Vec<ItemKey>> {
+        let mut entries = vec![];
+        for db_key in self.db.scan_prefix(Self::id_prefix(kind, id)).keys() {
+            if let Some(key) = Self::item_key(&db_key?) {
+                if key.entry.is_some() {
+                    entries.push(key);
+                }
+            }
+        }
+        Ok(entries)
+    }
+}
@@ -0,0 +1,202 @@
+// Copyright 2022 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+mod fs;
+mod kv;
+
+use super::Result;
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::{fmt::Debug, path::Path, sync::Arc, time::SystemTime};
+use xor_name::XorName;
+
+/// The kind of backend used by a node to persist the data it holds.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize, clap::ArgEnum)]
+pub enum StorageBackendKind {
+    /// One file per chunk and per register cmd, in a tree of directories
+    /// named after the first bits of the data address.
+    #[default]
+    Filesystem,
+    /// An embedded key-value store, keeping all the data in a single database.
+    KeyValue,
+}
+
+/// Open the backend of the given kind found at the given root location,
+/// creating it if it doesn't exist yet.
+pub(super) fn open_backend(
+    kind: StorageBackendKind,
+    root: &Path,
+) -> Result<Arc<dyn StorageBackend>> {
+    let backend: Arc<dyn StorageBackend> = match kind {
+        StorageBackendKind::Filesystem => Arc::new(fs::FsBackend::new(root)),
+        StorageBackendKind::KeyValue => Arc::new(kv::KvBackend::open(root)?),
+    };
+    Ok(backend)
+}
+
+/// Kinds of items stored, each kind is kept apart from the others by the backends.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum ItemKind {
+    /// Chunks, stored under their name and without entries.
+    Chunk,
+    /// Register cmds, stored as entries grouped under an id of the Register.
+    RegisterCmd,
+}
+
+/// Key of an item stored in a backend.
+/// Items can either be stored under an `id`, or as a named `entry` grouped under an `id`.
+#[derive(Clone, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub(super) struct ItemKey {
+    pub(super) id: XorName,
+    pub(super) entry: Option<String>,
+}
+
+impl ItemKey {
+    pub(super) fn new(id: XorName) -> Self {
+        Self { id, entry: None }
+    }
+
+    pub(super) fn entry(id: XorName, entry: String) -> Self {
+        Self {
+            id,
+            entry: Some(entry),
+        }
+    }
+}
+
+/// Where and how the data held by a node is persisted.
+#[async_trait]
+pub(super) trait StorageBackend: Debug + Send + Sync {
+    /// Stores the value under the given key, overwriting any value already stored under it.
+    /// The value shall have been persisted by the time this returns.
+    async fn write(&self, kind: ItemKind, key: &ItemKey, value: &[u8]) -> Result<()>;
+
+    /// Returns the value stored under the given key, if any.
+    async fn read(&self, kind: ItemKind, key: &ItemKey) -> Result<Option<Vec<u8>>>;
+
+    /// Returns whether there is a value stored under the given key.
+    async fn exists(&self, kind: ItemKind, key: &ItemKey) -> Result<bool>;
+
+    /// Returns the time the value stored under the given key was written at, if any.
+    async fn written_at(&self, kind: ItemKind, key: &ItemKey) -> Result<Option<SystemTime>>;
+
+    /// Removes the value stored under the given key, returning its size if there was one.
+    async fn remove(&self, kind: ItemKind, key: &ItemKey) -> Result<Option<usize>>;
+
+    /// Returns the ids all the items of the given kind are stored under.
+    async fn ids(&self, kind: ItemKind) -> Result<Vec<XorName>>;
+
+    /// Returns, in order, up to `max` ids items of the given kind are stored under, following
+    /// the `after` id, so the ids can be walked through without listing all of them at once.
+    async fn ids_after(
+        &self,
+        kind: ItemKind,
+        after: Option<XorName>,
+        max: usize,
+    ) -> Result<Vec<XorName>>;
+
+    /// Returns the keys of all the entries grouped under the given id.
+    async fn entries(&self, kind: ItemKind, id: XorName) -> Result<Vec<ItemKey>>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{open_backend, ItemKey, ItemKind, StorageBackendKind};
+
+    use eyre::Result;
+    use std::time::SystemTime;
+    use tempfile::tempdir;
+    use xor_name::XorName;
+
+    #[tokio::test]
+    async fn storage_backends_basics() -> Result<()> {
+        for kind in [StorageBackendKind::Filesystem, StorageBackendKind::KeyValue] {
+            let tmp_dir = tempdir()?;
+            let backend = open_backend(kind, tmp_dir.path())?;
+
+            let id = XorName::random(&mut rand::thread_rng());
+            let chunk_key = ItemKey::new(id);
+            let cmd_key = ItemKey::entry(id, "cmd".to_string());
+
+            assert!(!backend.exists(ItemKind::Chunk, &chunk_key).await?);
+            backend
+                .write(ItemKind::Chunk, &chunk_key, b"chunk content")
+                .await?;
+            backend
+                .write(ItemKind::RegisterCmd, &cmd_key, b"cmd")
+                .await?;
+
+            // kinds of items are kept apart
+            assert!(backend.exists(ItemKind::Chunk, &chunk_key).await?);
+            assert!(!backend.exists(ItemKind::RegisterCmd, &chunk_key).await?);
+            assert_eq!(
+                backend.read(ItemKind::Chunk, &chunk_key).await?,
+                Some(b"chunk content".to_vec())
+            );
+            assert_eq!(backend.ids(ItemKind::Chunk).await?, vec![id]);
+            assert_eq!(backend.ids(ItemKind::RegisterCmd).await?, vec![id]);
+            assert_eq!(
+                backend.entries(ItemKind::RegisterCmd, id).await?,
+                vec![cmd_key.clone()]
+            );
+            let written_at = backend.written_at(ItemKind::Chunk, &chunk_key).await?;
+            assert!(matches!(written_at, Some(time) if time <= SystemTime::now()));
+
+            assert_eq!(backend.remove(ItemKind::Chunk, &chunk_key).await?, Some(13));
+            assert_eq!(backend.remove(ItemKind::Chunk, &chunk_key).await?, None);
+            assert_eq!(backend.read(ItemKind::Chunk, &chunk_key).await?, None);
+            assert!(backend.ids(ItemKind::Chunk).await?.is_empty());
+
+            assert_eq!(
+                backend.remove(ItemKind::RegisterCmd, &cmd_key).await?,
+                Some(3)
+            );
+            assert!(backend.ids(ItemKind::RegisterCmd).await?.is_empty());
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn storage_backends_walk_ids_in_order() -> Result<()> {
+        for kind in [StorageBackendKind::Filesystem, StorageBackendKind::KeyValue] {
+            let tmp_dir = tempdir()?;
+            let backend = open_backend(kind, tmp_dir.path())?;
+
+            let mut ids = (0..10)
+                .map(|_| XorName::random(&mut rand::thread_rng()))
+                .collect::<Vec<_>>();
+            ids.sort();
+            for id in &ids {
+                backend
+                    .write(ItemKind::Chunk, &ItemKey::new(*id), b"chunk")
+                    .await?;
+                for entry in ["a", "b"] {
+                    let key = ItemKey::entry(*id, entry.to_string());
+                    backend.write(ItemKind::RegisterCmd, &key, b"cmd").await?;
+                }
+            }
+
+            for item_kind in [ItemKind::Chunk, ItemKind::RegisterCmd] {
+                let first = backend.ids_after(item_kind, None, 4).await?;
+                assert_eq!(first, ids[..4]);
+                let next = backend.ids_after(item_kind, Some(ids[3]), 4).await?;
+                assert_eq!(next, ids[4..8]);
+                let last = backend.ids_after(item_kind, Some(ids[7]), 4).await?;
+                assert_eq!(last, ids[8..]);
+                assert!(backend
+                    .ids_after(item_kind, Some(ids[9]), 4)
+                    .await?
+                    .is_empty());
+            }
+        }
+
+        Ok(())
+    }
+}
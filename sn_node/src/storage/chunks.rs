@@ -6,7 +6,10 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{list_files_in, prefix_tree_path, Error, Result, UsedSpace};
+use super::{
+    backends::{ItemKey, ItemKind, StorageBackend},
+    Error, Result, UsedSpace,
+};
 
 use sn_interface::{
     messaging::system::NodeQueryResponse,
@@ -14,82 +17,62 @@ use sn_interface::{
 };
 
 use bytes::Bytes;
-use hex::FromHex;
 use std::{
     fmt::{self, Display, Formatter},
-    io::{self, ErrorKind},
-    path::{Path, PathBuf},
-};
-use tokio::{
-    fs::{create_dir_all, metadata, read, remove_file, File},
-    io::AsyncWriteExt,
+    sync::Arc,
+    time::Duration,
 };
 use tracing::info;
 use xor_name::XorName;
 
-const CHUNKS_STORE_DIR_NAME: &str = "chunks";
-
 /// Operations on data chunks.
 #[derive(Clone, Debug)]
 pub(super) struct ChunkStorage {
-    file_store_path: PathBuf,
+    backend: Arc<dyn StorageBackend>,
     used_space: UsedSpace,
 }
 
 impl ChunkStorage {
-    /// Creates a new `ChunkStorage` at the specified root location
+    /// Creates a new `ChunkStorage` on top of the given backend
     ///
-    /// If the location specified already contains a `ChunkStorage`, it is simply used
+    /// If the backend already contains chunks, they are simply used
     ///
-    /// Used space of the dir is tracked
-    pub(super) fn new(path: &Path, used_space: UsedSpace) -> Result<Self> {
-        Ok(Self {
-            file_store_path: path.join(CHUNKS_STORE_DIR_NAME),
+    /// Used space of the stored chunks is tracked
+    pub(super) fn new(backend: Arc<dyn StorageBackend>, used_space: UsedSpace) -> Self {
+        Self {
+            backend,
             used_space,
-        })
-    }
-
-    pub(super) fn addrs(&self) -> Vec<ChunkAddress> {
-        list_files_in(&self.file_store_path)
-            .iter()
-            .filter_map(|filepath| Self::chunk_filepath_to_address(filepath).ok())
-            .collect()
-    }
-
-    fn chunk_filepath_to_address(path: &Path) -> Result<ChunkAddress> {
-        let filename = path
-            .file_name()
-            .ok_or_else(|| Error::NoFilename(path.to_path_buf()))?
-            .to_str()
-            .ok_or_else(|| Error::InvalidFilename(path.to_path_buf()))?;
-
-        let xorname = XorName(<[u8; 32]>::from_hex(filename)?);
-        Ok(ChunkAddress(xorname))
+        }
     }
 
-    fn chunk_addr_to_filepath(&self, addr: &ChunkAddress) -> Result<PathBuf> {
-        let xorname = *addr.name();
-        let path = prefix_tree_path(&self.file_store_path, xorname);
-        let filename = hex::encode(xorname);
-        Ok(path.join(filename))
+    pub(super) async fn addrs(&self) -> Vec<ChunkAddress> {
+        match self.backend.ids(ItemKind::Chunk).await {
+            Ok(names) => names.into_iter().map(ChunkAddress).collect(),
+            Err(error) => {
+                warn!("Failed to list stored chunks: {error:?}");
+                vec![]
+            }
+        }
     }
 
-    #[allow(dead_code)]
     pub(super) async fn remove_chunk(&self, address: &ChunkAddress) -> Result<()> {
         trace!("Removing chunk, {:?}", address);
-        let filepath = self.chunk_addr_to_filepath(address)?;
-        let meta = metadata(filepath.clone()).await?;
-        remove_file(filepath).await?;
-        self.used_space.decrease(meta.len() as usize);
-        Ok(())
+        let key = ItemKey::new(*address.name());
+        match self.backend.remove(ItemKind::Chunk, &key).await? {
+            Some(size) => {
+                self.used_space.decrease(size);
+                Ok(())
+            }
+            None => Err(Error::ChunkNotFound(*address.name())),
+        }
     }
 
     pub(super) async fn get_chunk(&self, address: &ChunkAddress) -> Result<Chunk> {
         debug!("Getting chunk {:?}", address);
 
-        let file_path = self.chunk_addr_to_filepath(address)?;
-        match read(file_path).await {
-            Ok(bytes) => {
+        let key = ItemKey::new(*address.name());
+        match self.backend.read(ItemKind::Chunk, &key).await? {
+            Some(bytes) => {
                 let chunk = Chunk::new(Bytes::from(bytes));
                 if chunk.address() != address {
                     // This can happen if the content read is empty, or incomplete,
@@ -100,11 +83,66 @@ impl ChunkStorage {
                     Ok(chunk)
                 }
             }
-            Err(io_error @ io::Error { .. }) if io_error.kind() == ErrorKind::NotFound => {
-                Err(Error::ChunkNotFound(*address.name()))
+            None => Err(Error::ChunkNotFound(*address.name())),
+        }
+    }
+
+    /// Verifies the integrity of up to `max_chunks` stored chunks, following the `after` chunk in
+    /// address order, and wrapping around once the end of the store is reached. Those whose
+    /// content doesn't hash to their address anymore, e.g. due to bit-rot or having been truncated,
+    /// are removed from the store. Chunks written less than `min_age` ago are not verified since
+    /// they may still be being written.
+    /// Returns the addresses of the chunks removed, and the name of the last chunk visited,
+    /// which can be used as `after` to carry on walking the store.
+    pub(super) async fn scrub(
+        &self,
+        after: Option<XorName>,
+        max_chunks: usize,
+        min_age: Duration,
+    ) -> Result<(Vec<ChunkAddress>, Option<XorName>)> {
+        // only the chunks to verify are listed, walking the store in address order
+        let mut batch = self
+            .backend
+            .ids_after(ItemKind::Chunk, after, max_chunks)
+            .await?;
+        if batch.is_empty() && after.is_some() {
+            // the end of the store was reached, let's start over from the beginning
+            batch = self
+                .backend
+                .ids_after(ItemKind::Chunk, None, max_chunks)
+                .await?;
+        }
+        trace!("Scrubbing {} chunk/s after {after:?}", batch.len());
+
+        let mut corrupted = vec![];
+        for name in &batch {
+            let address = &ChunkAddress(*name);
+            let key = ItemKey::new(*name);
+            let recently_written = match self.backend.written_at(ItemKind::Chunk, &key).await {
+                Ok(Some(written_at)) => written_at.elapsed().map_or(true, |age| age < min_age),
+                // it may have just been removed
+                Ok(None) | Err(_) => continue,
+            };
+            if recently_written {
+                continue;
+            }
+
+            let is_intact =
+                self.backend.read(ItemKind::Chunk, &key).await.map(|data| {
+                    data.map(|bytes| Chunk::new(Bytes::from(bytes)).address() == address)
+                });
+            match is_intact {
+                Ok(Some(true)) | Ok(None) => {}
+                Ok(Some(false)) => {
+                    warn!("Removing corrupted chunk found in local store: {address:?}");
+                    self.remove_chunk(address).await?;
+                    corrupted.push(*address);
+                }
+                Err(err) => warn!("Failed to read chunk {address:?} while scrubbing: {err:?}"),
             }
-            Err(other) => Err(other.into()),
         }
+
+        Ok((corrupted, batch.last().copied()))
     }
 
     // Read chunk from local store and return NodeQueryResponse
@@ -113,13 +151,13 @@ impl ChunkStorage {
         NodeQueryResponse::GetChunk(self.get_chunk(address).await.map_err(|error| error.into()))
     }
 
-    /// Store a chunk in the local disk store unless it is already there
+    /// Store a chunk in the local store unless it is already there
     #[instrument(skip_all)]
     pub(super) async fn store(&self, chunk: &Chunk) -> Result<()> {
         let addr = chunk.address();
-        let filepath = self.chunk_addr_to_filepath(addr)?;
+        let key = ItemKey::new(*addr.name());
 
-        if filepath.exists() {
+        if self.backend.exists(ItemKind::Chunk, &key).await? {
             info!(
                 "{}: Chunk data already exists, not storing: {:?}",
                 self, addr
@@ -135,18 +173,11 @@ impl ChunkStorage {
             return Err(Error::NotEnoughSpace);
         }
 
-        // Store the data on disk
+        // Store the data
         trace!("{:?} {addr:?}", LogMarker::StoringChunk);
-        if let Some(dirs) = filepath.parent() {
-            create_dir_all(dirs).await?;
-        }
-
-        let mut file = File::create(filepath).await?;
-
-        file.write_all(chunk.value()).await?;
-        // Let's sync up OS data to disk to reduce the chances of
-        // concurrent reading failing by reading an empty/incomplete file
-        file.sync_data().await?;
+        self.backend
+            .write(ItemKind::Chunk, &key, chunk.value())
+            .await?;
 
         self.used_space.increase(chunk.value().len());
         trace!("{:?} {addr:?}", LogMarker::StoredNewChunk);
@@ -164,6 +195,7 @@ impl Display for ChunkStorage {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::backends::{open_backend, StorageBackendKind};
     use sn_interface::types::utils::random_bytes;
 
     use eyre::{eyre, Result};
@@ -173,8 +205,9 @@ mod tests {
 
     fn init_file_store() -> ChunkStorage {
         let root = tempdir().expect("Failed to create temporary directory for chunk disk store");
-        ChunkStorage::new(root.path(), UsedSpace::new(usize::MAX))
-            .expect("Failed to create chunk disk store")
+        let backend = open_backend(StorageBackendKind::Filesystem, root.path())
+            .expect("Failed to create chunk disk store");
+        ChunkStorage::new(backend, UsedSpace::new(usize::MAX))
     }
 
     #[tokio::test]
@@ -223,12 +256,8 @@ mod tests {
         let address = chunk.address();
 
         // create chunk file but with empty content
-        let filepath = storage.chunk_addr_to_filepath(address)?;
-        if let Some(dirs) = filepath.parent() {
-            create_dir_all(dirs).await?;
-        }
-        let mut file = File::create(&filepath).await?;
-        file.write_all(b"").await?;
+        let key = ItemKey::new(*address.name());
+        storage.backend.write(ItemKind::Chunk, &key, b"").await?;
 
         // trying to read the chunk shall return ChunkNotFound error since
         // its content shouldn't match chunk address
@@ -245,6 +274,63 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn test_scrub_chunks() -> Result<()> {
+        let storage = init_file_store();
+
+        let mut chunks = std::iter::repeat_with(|| Chunk::new(random_bytes(100)))
+            .take(5)
+            .collect::<Vec<_>>();
+        chunks.sort_by_key(|chunk| *chunk.name());
+        for chunk in &chunks {
+            storage.store(chunk).await?;
+        }
+
+        // corrupt the content of one and truncate another one
+        let key = ItemKey::new(*chunks[1].name());
+        storage
+            .backend
+            .write(ItemKind::Chunk, &key, b"bit-rot")
+            .await?;
+        let key = ItemKey::new(*chunks[3].name());
+        let truncated = &chunks[3].value()[..10];
+        storage
+            .backend
+            .write(ItemKind::Chunk, &key, truncated)
+            .await?;
+
+        // recently written chunks are skipped
+        let (corrupted, last) = storage.scrub(None, 10, Duration::from_secs(60)).await?;
+        assert!(corrupted.is_empty());
+        assert_eq!(last, Some(*chunks[4].name()));
+
+        // the store is walked in batches
+        let (corrupted, last) = storage.scrub(None, 2, Duration::ZERO).await?;
+        assert_eq!(corrupted, vec![*chunks[1].address()]);
+        assert_eq!(last, Some(*chunks[1].name()));
+        let (corrupted, last) = storage.scrub(last, 10, Duration::ZERO).await?;
+        assert_eq!(corrupted, vec![*chunks[3].address()]);
+        assert_eq!(last, Some(*chunks[4].name()));
+
+        // and it wraps around once the end is reached
+        let (corrupted, last) = storage.scrub(last, 1, Duration::ZERO).await?;
+        assert!(corrupted.is_empty());
+        assert_eq!(last, Some(*chunks[0].name()));
+
+        let mut addrs = storage.addrs().await;
+        addrs.sort();
+        assert_eq!(
+            addrs,
+            vec![
+                *chunks[0].address(),
+                *chunks[2].address(),
+                *chunks[4].address()
+            ]
+        );
+
+        Ok(())
+    }
+
     async fn write_and_read_chunks(chunks: &[Chunk], storage: ChunkStorage) {
         // write all chunks
         let mut tasks = Vec::new();
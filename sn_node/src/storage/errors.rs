@@ -29,6 +29,9 @@ pub enum Error {
     /// Register not found in local storage.
     #[error("Register data not found in local storage: {0:?}")]
     RegisterNotFound(RegisterAddress),
+    /// Register has been deleted by its owner.
+    #[error("Register has been deleted: {0:?}")]
+    RegisterDeleted(RegisterAddress),
     /// Chunk not found.
     #[error("Chunk not found: {0:?}")]
     ChunkNotFound(XorName),
@@ -47,6 +50,9 @@ pub enum Error {
     /// Bincode error.
     #[error("Bincode error:: {0}")]
     Bincode(#[from] bincode::Error),
+    /// Key-value store error.
+    #[error("Key-value store error:: {0}")]
+    KeyValueStore(#[from] sled::Error),
     /// Hex decoding error.
     #[error("Hex decoding error:: {0}")]
     HexDecoding(#[from] hex::FromHexError),
@@ -80,6 +86,9 @@ impl From<Error> for ErrorMsg {
             Error::RegisterNotFound(address) => {
                 ErrorMsg::DataNotFound(DataAddress::Register(address))
             }
+            Error::RegisterDeleted(address) => {
+                ErrorMsg::DataNotFound(DataAddress::Register(address))
+            }
             Error::ChunkNotFound(xorname) => {
                 ErrorMsg::DataNotFound(DataAddress::Bytes(ChunkAddress(xorname)))
             }
@@ -6,12 +6,14 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
+mod backends;
 mod chunks;
 mod errors;
 mod register_store;
 mod registers;
 mod used_space;
 
+pub use backends::StorageBackendKind;
 pub use used_space::UsedSpace;
 
 pub(crate) use errors::{Error, Result};
@@ -22,18 +24,29 @@ use registers::RegisterStorage;
 use sn_dbc::SpentProofShare;
 use sn_interface::{
     messaging::{
-        data::{DataQueryVariant, Error as MessagingError, RegisterQuery, StorageLevel},
+        data::{
+            DataQueryVariant, Error as MessagingError, RegisterQuery, SpentKeyImage,
+            SpentKeyImagesPage, SpentbookQuery, StorageLevel, MAX_SPENT_KEY_IMAGES_PER_PAGE,
+        },
         system::NodeQueryResponse,
     },
     types::{
-        register::User, DataAddress, Keypair, PublicKey, RegisterAddress, ReplicatedData,
+        register::{Register, User},
+        ChunkAddress, DataAddress, Keypair, PublicKey, RegisterAddress, ReplicatedData,
         SPENTBOOK_TYPE_TAG,
     },
 };
 
-use std::path::{Path, PathBuf};
+use std::{
+    collections::BTreeSet,
+    ops::Bound,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::sync::{RwLock, RwLockReadGuard};
 use walkdir::WalkDir;
-use xor_name::XorName;
+use xor_name::{Prefix, XorName};
 
 const BIT_TREE_DEPTH: usize = 20;
 
@@ -47,16 +60,25 @@ pub struct DataStorage {
     registers: RegisterStorage,
     used_space: UsedSpace,
     last_recorded_level: StorageLevel,
+    // names of the spentbooks held, see `spentbook_names`
+    spentbook_index: Arc<RwLock<Option<BTreeSet<XorName>>>>,
 }
 
 impl DataStorage {
-    /// Set up a new `DataStorage` instance
-    pub fn new(path: &Path, used_space: UsedSpace) -> Result<Self> {
+    /// Set up a new `DataStorage` instance, persisting the data at the given location
+    /// with the given kind of backend
+    pub fn new(
+        path: &Path,
+        used_space: UsedSpace,
+        backend_kind: StorageBackendKind,
+    ) -> Result<Self> {
+        let backend = backends::open_backend(backend_kind, path)?;
         Ok(Self {
-            chunks: ChunkStorage::new(path, used_space.clone())?,
-            registers: RegisterStorage::new(path, used_space.clone())?,
+            chunks: ChunkStorage::new(backend.clone(), used_space.clone()),
+            registers: RegisterStorage::new(backend, used_space.clone()),
             used_space,
             last_recorded_level: StorageLevel::zero(),
+            spentbook_index: Arc::new(RwLock::new(None)),
         })
     }
 
@@ -88,8 +110,12 @@ impl DataStorage {
                 self.registers
                     .write_spentbook_register(cmd, section_pk, node_keypair)
                     .await?;
+                self.index_spentbook(cmd.dst_address().name()).await;
+            }
+            ReplicatedData::SpentbookLog(data) => {
+                self.registers.update(data).await?;
+                self.index_spentbook(data.address.name()).await;
             }
-            ReplicatedData::SpentbookLog(data) => self.registers.update(data).await?,
         };
 
         // check if we've filled another approx. 10%-points of our storage
@@ -118,6 +144,13 @@ impl DataStorage {
         match query {
             DataQueryVariant::GetChunk(addr) => self.chunks.get(addr).await,
             DataQueryVariant::Register(read) => self.registers.read(read, requester).await,
+            DataQueryVariant::Spentbook(SpentbookQuery::SpentKeyImages {
+                prefix,
+                start_after,
+            }) => {
+                self.spent_key_images(prefix, start_after.as_ref(), requester)
+                    .await
+            }
             DataQueryVariant::Spentbook(read) => {
                 // TODO: this is temporary till spentbook native data type is implemented,
                 // we read from the Register where we store the spentbook data
@@ -132,24 +165,7 @@ impl DataStorage {
                         NodeQueryResponse::SpentProofShares(Ok(Vec::new()))
                     }
                     NodeQueryResponse::GetRegister(result) => {
-                        let proof_shares_result = result.map(|reg| {
-                            let mut proof_shares = Vec::new();
-                            let entries = reg.read();
-                            for (_, entry) in entries {
-                                // Deserialise spent proof share from the entry
-                                let spent_proof_share: SpentProofShare = match rmp_serde::from_slice(&entry) {
-                                    Ok(proof) => proof,
-                                    Err(err) => {
-                                        warn!("Ignoring entry found in Spentbook since it cannot be deserialised as a valid SpentProofShare: {:?}", err);
-                                        continue;
-                                    }
-                                };
-
-                                proof_shares.push(spent_proof_share);
-                            }
-                            proof_shares
-                        });
-
+                        let proof_shares_result = result.map(|reg| spent_proof_shares(&reg));
                         NodeQueryResponse::SpentProofShares(proof_shares_result)
                     }
                     other => {
@@ -162,6 +178,95 @@ impl DataStorage {
         }
     }
 
+    // List a page of the key images spent within the given prefix from the local
+    // spentbook registers, in ascending order of their address.
+    async fn spent_key_images(
+        &self,
+        prefix: &Prefix,
+        start_after: Option<&XorName>,
+        requester: User,
+    ) -> NodeQueryResponse {
+        let start = match start_after {
+            Some(after) => Bound::Excluded(*after),
+            None => Bound::Included(prefix.lower_bound()),
+        };
+        let mut names: Vec<XorName> = self
+            .spentbook_names()
+            .await
+            .iter()
+            .flat_map(|names| names.range((start, Bound::Unbounded)))
+            .take_while(|name| **name <= prefix.upper_bound())
+            .filter(|name| prefix.matches(name))
+            .take(MAX_SPENT_KEY_IMAGES_PER_PAGE + 1)
+            .copied()
+            .collect();
+
+        let next = if names.len() > MAX_SPENT_KEY_IMAGES_PER_PAGE {
+            names.truncate(MAX_SPENT_KEY_IMAGES_PER_PAGE);
+            names.last().copied()
+        } else {
+            None
+        };
+
+        let mut spent = Vec::new();
+        for name in names {
+            let reg_addr = RegisterAddress::new(name, SPENTBOOK_TYPE_TAG);
+            let reg = match self
+                .registers
+                .read(&RegisterQuery::Get(reg_addr), requester)
+                .await
+            {
+                NodeQueryResponse::GetRegister(Ok(reg)) => reg,
+                other => {
+                    warn!("Skipping spentbook at {name:?} which could not be read: {other:?}");
+                    continue;
+                }
+            };
+
+            let proof_shares = spent_proof_shares(&reg);
+            if let Some(share) = proof_shares.first() {
+                spent.push(SpentKeyImage {
+                    key_image: *share.key_image(),
+                    transaction_hashes: proof_shares
+                        .iter()
+                        .map(|share| share.transaction_hash())
+                        .collect(),
+                });
+            }
+        }
+
+        NodeQueryResponse::SpentKeyImages(Ok(SpentKeyImagesPage {
+            prefix: *prefix,
+            spent,
+            next,
+        }))
+    }
+
+    // Records a spentbook held, if the names of those held were already loaded
+    async fn index_spentbook(&self, name: &XorName) {
+        if let Some(index) = self.spentbook_index.write().await.as_mut() {
+            let _ = index.insert(*name);
+        }
+    }
+
+    // Names of the spentbooks held, kept in memory, in order, so pages of key images are listed
+    // without going through all the Registers held. They are loaded from the store on first use.
+    async fn spentbook_names(&self) -> RwLockReadGuard<'_, Option<BTreeSet<XorName>>> {
+        let mut index = self.spentbook_index.write().await;
+        if index.is_none() {
+            let names = self
+                .registers
+                .addrs()
+                .await
+                .into_iter()
+                .filter(|addr| addr.tag() == SPENTBOOK_TYPE_TAG)
+                .map(|addr| *addr.name())
+                .collect();
+            *index = Some(names);
+        }
+        index.downgrade()
+    }
+
     /// --- System calls ---
 
     // Read data from local store
@@ -195,6 +300,9 @@ impl DataStorage {
             DataAddress::Bytes(addr) => self.chunks.remove_chunk(addr).await,
             DataAddress::Register(addr) => self.registers.remove_register(addr).await,
             DataAddress::Spentbook(addr) => {
+                if let Some(index) = self.spentbook_index.write().await.as_mut() {
+                    let _ = index.remove(addr.name());
+                }
                 let reg_addr = RegisterAddress::new(*addr.name(), SPENTBOOK_TYPE_TAG);
                 self.registers.remove_register(&reg_addr).await
             }
@@ -202,11 +310,31 @@ impl DataStorage {
         }
     }
 
+    /// Verify the integrity of up to `max_chunks` stored chunks following the `after` one,
+    /// removing those found corrupted. Chunks written less than `min_age` ago are skipped.
+    /// Returns the addresses of the chunks removed, and the name of the last chunk visited.
+    pub(crate) async fn scrub_chunks(
+        &self,
+        after: Option<XorName>,
+        max_chunks: usize,
+        min_age: Duration,
+    ) -> Result<(Vec<ChunkAddress>, Option<XorName>)> {
+        self.chunks.scrub(after, max_chunks, min_age).await
+    }
+
+    /// Reclaim the space used by the entries of those Registers which were deleted by
+    /// their owners at least the given grace period ago.
+    /// Returns the number of Registers whose space was reclaimed.
+    pub(crate) async fn reclaim_deleted_registers(&self, grace_period: Duration) -> Result<usize> {
+        self.registers.reclaim_deleted(grace_period).await
+    }
+
     /// Retrieve all ReplicatedDataAddresses of stored data
     pub async fn data_addrs(&self) -> Vec<DataAddress> {
         // TODO: Parallelize this below loops
         self.chunks
             .addrs()
+            .await
             .into_iter()
             .map(DataAddress::Bytes)
             .chain(
@@ -250,9 +378,27 @@ fn list_files_in(path: &Path) -> Vec<PathBuf> {
         .collect()
 }
 
+// Deserialise the spent proof shares logged in a spentbook register
+fn spent_proof_shares(reg: &Register) -> Vec<SpentProofShare> {
+    let mut proof_shares = Vec::new();
+    for (_, entry) in reg.read() {
+        // Deserialise spent proof share from the entry
+        let spent_proof_share: SpentProofShare = match rmp_serde::from_slice(&entry) {
+            Ok(proof) => proof,
+            Err(err) => {
+                warn!("Ignoring entry found in Spentbook since it cannot be deserialised as a valid SpentProofShare: {:?}", err);
+                continue;
+            }
+        };
+
+        proof_shares.push(spent_proof_share);
+    }
+    proof_shares
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{DataStorage, Error, UsedSpace};
+    use super::{DataStorage, Error, StorageBackendKind, UsedSpace};
     use sn_interface::{
         init_logger,
         messaging::{
@@ -292,7 +438,7 @@ mod tests {
         let used_space = UsedSpace::new(usize::MAX);
 
         // Create instance
-        let mut storage = DataStorage::new(path, used_space)?;
+        let mut storage = DataStorage::new(path, used_space, StorageBackendKind::Filesystem)?;
 
         // 5mb random data chunk
         let bytes = random_bytes(5 * 1024 * 1024);
@@ -344,7 +490,7 @@ mod tests {
         let used_space = UsedSpace::new(usize::MAX);
 
         // Create instance
-        let storage = DataStorage::new(path, used_space)?;
+        let storage = DataStorage::new(path, used_space, StorageBackendKind::Filesystem)?;
 
         // 5mb random data chunk
         let bytes = random_bytes(5 * 1024 * 1024);
@@ -383,7 +529,7 @@ mod tests {
         let used_space = UsedSpace::new(usize::MAX);
 
         // Create instance
-        let storage = DataStorage::new(path, used_space)?;
+        let storage = DataStorage::new(path, used_space, StorageBackendKind::Filesystem)?;
 
         // create reg cmd
 
@@ -479,7 +625,7 @@ mod tests {
         let path = temp_dir.path();
         let used_space = UsedSpace::new(usize::MAX);
         let runtime = Runtime::new()?;
-        let mut storage = DataStorage::new(path, used_space)?;
+        let mut storage = DataStorage::new(path, used_space, StorageBackendKind::Filesystem)?;
         let owner_pk = PublicKey::Bls(bls::SecretKey::random().public_key());
         let owner_keypair = Keypair::new_ed25519();
         for op in ops {
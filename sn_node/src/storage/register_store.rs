@@ -6,28 +6,28 @@
 // KIND, either express or implied. Please review the Licences for the specific language governing
 // permissions and limitations relating to use of the SAFE Network Software.
 
-use super::{list_files_in, prefix_tree_path, Error, Result};
+use super::{
+    backends::{ItemKey, ItemKind, StorageBackend},
+    Error, Result,
+};
 
 use crate::UsedSpace;
 
 use sn_interface::{
-    messaging::data::SignedRegisterCreate,
+    messaging::{
+        data::{SignedRegisterCreate, SignedRegisterDelete},
+        VerifyAuthority,
+    },
     types::{
-        register::{EntryHash, Register},
+        register::{EntryHash, Register, User},
         utils::{deserialise, serialise},
         RegisterAddress, RegisterCmd,
     },
 };
 
 use bincode::serialize;
-use std::{
-    collections::{btree_map::Entry, BTreeMap},
-    mem::size_of,
-    path::{Path, PathBuf},
-};
+use std::{mem::size_of, sync::Arc, time::Duration};
 use tiny_keccak::{Hasher, Sha3};
-use tokio::fs::{create_dir_all, metadata, read, remove_file, File};
-use tokio::io::AsyncWriteExt;
 use xor_name::XorName;
 
 // Deterministic Id for a register Cmd, takes into account the underlying cmd, and all sigs
@@ -39,95 +39,166 @@ pub(super) type RegisterLog = Vec<RegisterCmd>;
 pub(super) struct StoredRegister {
     pub(super) state: Option<Register>,
     pub(super) op_log: RegisterLog,
-    pub(super) op_log_path: PathBuf,
+    pub(super) op_log_id: XorName,
+}
+
+impl StoredRegister {
+    /// Returns the owner-signed tombstone found in the log, if the Register was deleted.
+    /// A Delete cmd received before the Register was created is stored unverified, thus
+    /// a tombstone is only honoured once verified against the owner of the Register.
+    pub(super) fn tombstone(&self) -> Option<&SignedRegisterDelete> {
+        let owner = self.state.as_ref()?.owner();
+        self.op_log.iter().find_map(|cmd| match cmd {
+            RegisterCmd::Delete(delete_cmd) if is_signed_by(delete_cmd, owner) => Some(delete_cmd),
+            _ => None,
+        })
+    }
 }
 
-/// A disk store for Registers
+/// A store for Registers, keeping each Register as a log of cmds
 #[derive(Clone, Debug)]
 pub(super) struct RegisterStore {
-    file_store_path: PathBuf,
+    backend: Arc<dyn StorageBackend>,
     used_space: UsedSpace,
 }
 
 impl RegisterStore {
-    /// Creates a new `RegisterStore` at the specified root location
+    /// Creates a new `RegisterStore` on top of the given backend
     ///
-    /// If the location specified already contains a `RegisterStore`, it is simply used
+    /// If the backend already contains Registers, they are simply used
     ///
-    /// Used space of the dir is tracked
-    pub(super) fn new(file_store_path: PathBuf, used_space: UsedSpace) -> Result<Self> {
-        Ok(Self {
-            file_store_path,
+    /// Used space of the stored cmds is tracked
+    pub(super) fn new(backend: Arc<dyn StorageBackend>, used_space: UsedSpace) -> Self {
+        Self {
+            backend,
             used_space,
-        })
+        }
     }
 
-    pub(super) fn address_to_filepath(&self, addr: &RegisterAddress) -> Result<PathBuf> {
+    /// Returns the id the log of cmds of a Register is stored under
+    pub(super) fn address_to_id(&self, addr: &RegisterAddress) -> Result<XorName> {
         // this is a unique identifier of the Register,
         // since it encodes both the xorname and tag.
-        let reg_id = XorName::from_content(&serialize(addr)?);
-        let path = prefix_tree_path(&self.file_store_path, reg_id);
-
-        // we need to append a folder for the file specifically so bit depth is an issue when low.
-        // we use hex to get full id, not just first bytes
-        Ok(path.join(hex::encode(reg_id)))
+        Ok(XorName::from_content(&serialize(addr)?))
     }
 
     pub(super) async fn list_all_reg_addrs(&self) -> Vec<RegisterAddress> {
         trace!("Listening all register addrs");
-        let iter = list_files_in(&self.file_store_path)
-            .into_iter()
-            .filter_map(|e| e.parent().map(|parent| (parent.to_path_buf(), e.clone())));
-
-        let mut addrs = BTreeMap::<PathBuf, RegisterAddress>::new();
-        for (parent, op_file) in iter {
-            if let Entry::Vacant(vacant) = addrs.entry(parent) {
-                if let Ok(Ok(cmd)) = read(op_file)
-                    .await
-                    .map(|serialized_data| deserialise::<RegisterCmd>(&serialized_data))
+        let reg_ids = match self.backend.ids(ItemKind::RegisterCmd).await {
+            Ok(reg_ids) => reg_ids,
+            Err(error) => {
+                warn!("Failed to list stored registers: {error:?}");
+                return vec![];
+            }
+        };
+
+        let mut addrs = vec![];
+        for reg_id in reg_ids {
+            // the address can be obtained from any of the cmds in the log
+            let entries = self
+                .backend
+                .entries(ItemKind::RegisterCmd, reg_id)
+                .await
+                .unwrap_or_default();
+            for key in entries {
+                if let Ok(Some(Ok(cmd))) =
+                    self.backend
+                        .read(ItemKind::RegisterCmd, &key)
+                        .await
+                        .map(|data| {
+                            data.map(|serialized_data| deserialise::<RegisterCmd>(&serialized_data))
+                        })
                 {
-                    let _existing = vacant.insert(cmd.dst_address());
+                    addrs.push(cmd.dst_address());
+                    break;
                 }
             }
         }
 
         trace!("Listening all register addrs done");
-        addrs.into_values().collect()
+        addrs
     }
 
     pub(super) async fn delete_data(&self, addr: &RegisterAddress) -> Result<()> {
-        let filepath = self.address_to_filepath(addr)?;
-        let meta = metadata(filepath.clone()).await?;
-        remove_file(filepath).await?;
-        self.used_space.decrease(meta.len() as usize);
+        let reg_id = self.address_to_id(addr)?;
+        let entries = self.backend.entries(ItemKind::RegisterCmd, reg_id).await?;
+        if entries.is_empty() {
+            return Err(Error::RegisterNotFound(*addr));
+        }
+
+        for key in entries {
+            if self
+                .backend
+                .remove(ItemKind::RegisterCmd, &key)
+                .await?
+                .is_some()
+            {
+                // each cmd was accounted for with the same rough estimate when written
+                self.used_space.decrease(size_of::<RegisterCmd>());
+            }
+        }
         Ok(())
     }
 
+    /// Returns how long ago the given cmd was written to the log with the given id.
+    pub(super) async fn cmd_age(&self, cmd: &RegisterCmd, reg_id: XorName) -> Result<Duration> {
+        let key = ItemKey::entry(reg_id, register_operation_id(cmd)?);
+        let written_at = self.backend.written_at(ItemKind::RegisterCmd, &key).await?;
+        // a clock going backwards just makes the cmd look newer
+        Ok(written_at
+            .and_then(|written_at| written_at.elapsed().ok())
+            .unwrap_or_default())
+    }
+
+    /// Removes the given cmds from the log with the given id, releasing their space.
+    /// Returns the number of cmds removed.
+    pub(super) async fn remove_cmds(&self, cmds: &[RegisterCmd], reg_id: XorName) -> Result<usize> {
+        let mut removed = 0;
+        for cmd in cmds {
+            let key = ItemKey::entry(reg_id, register_operation_id(cmd)?);
+            if self
+                .backend
+                .remove(ItemKind::RegisterCmd, &key)
+                .await?
+                .is_some()
+            {
+                self.used_space.decrease(size_of::<RegisterCmd>());
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
     /// Opens the log of RegisterCmds for a given register address.
     /// Creates a new log if no data is found
     pub(super) async fn open_reg_log_from_disk(
         &self,
         addr: &RegisterAddress,
     ) -> Result<StoredRegister> {
-        let path = self.address_to_filepath(addr)?;
+        let reg_id = self.address_to_id(addr)?;
         let mut stored_reg = StoredRegister {
             state: None,
             op_log: RegisterLog::new(),
-            op_log_path: path.clone(),
+            op_log_id: reg_id,
         };
 
-        if !path.exists() {
-            trace!("Register log does not exist yet: {}", path.display());
+        let entries = self.backend.entries(ItemKind::RegisterCmd, reg_id).await?;
+        if entries.is_empty() {
+            trace!("Register log does not exist yet: {reg_id:?}");
             return Ok(stored_reg);
         }
 
-        trace!("Register log path exists: {}", path.display());
-        for filepath in list_files_in(&path) {
-            match read(&filepath)
+        trace!("Register log exists: {reg_id:?}");
+        for key in entries {
+            match self
+                .backend
+                .read(ItemKind::RegisterCmd, &key)
                 .await
-                .map(|serialized_data| deserialise::<RegisterCmd>(&serialized_data))
-            {
-                Ok(Ok(reg_cmd)) => {
+                .map(|data| {
+                    data.map(|serialized_data| deserialise::<RegisterCmd>(&serialized_data))
+                }) {
+                Ok(Some(Ok(reg_cmd))) => {
                     stored_reg.op_log.push(reg_cmd.clone());
 
                     if let RegisterCmd::Create { cmd, .. } = reg_cmd {
@@ -143,8 +214,7 @@ impl RegisterStore {
                 }
                 other => {
                     warn!(
-                        "Ignoring corrupted register cmd from storage found at {}: {other:?}",
-                        filepath.display()
+                        "Ignoring corrupted register cmd from storage found at {key:?}: {other:?}"
                     )
                 }
             }
@@ -153,22 +223,27 @@ impl RegisterStore {
         Ok(stored_reg)
     }
 
-    /// Persists a RegisterLog to disk
-    pub(super) async fn write_log_to_disk(&self, log: &RegisterLog, path: &Path) -> Result<()> {
+    /// Persists a RegisterLog to the store
+    ///
+    /// The verified tombstone of the Register, if any, is written even when running out of space
+    pub(super) async fn write_log_to_disk(
+        &self,
+        log: &RegisterLog,
+        reg_id: XorName,
+        tombstone: Option<&SignedRegisterDelete>,
+    ) -> Result<()> {
         trace!(
-            "Writing to register log with {} cmd/s at {}",
-            log.len(),
-            path.display()
+            "Writing to register log with {} cmd/s at {reg_id:?}",
+            log.len()
         );
         if log.is_empty() {
             return Ok(());
         }
 
-        create_dir_all(path).await?;
-
         let mut last_err = None;
         for cmd in log {
-            if let Err(err) = self.write_register_cmd(cmd, path).await {
+            let is_tombstone = matches!((cmd, tombstone), (RegisterCmd::Delete(delete_cmd), Some(tombstone)) if delete_cmd == tombstone);
+            if let Err(err) = self.write_register_cmd(cmd, reg_id, is_tombstone).await {
                 error!("Failed to write Register cmd {cmd:?} to disk: {err:?}");
                 last_err = Some(err);
             }
@@ -178,65 +253,66 @@ impl RegisterStore {
             Err(err)
         } else {
             trace!(
-                "Log of {} cmd/s written successfully at {}",
-                log.len(),
-                path.display()
+                "Log of {} cmd/s written successfully at {reg_id:?}",
+                log.len()
             );
             Ok(())
         }
     }
 
-    /// Persists a RegisterCmd to disk
-    pub(super) async fn write_register_cmd(&self, cmd: &RegisterCmd, path: &Path) -> Result<()> {
+    /// Persists a RegisterCmd to the store
+    pub(super) async fn write_register_cmd(
+        &self,
+        cmd: &RegisterCmd,
+        reg_id: XorName,
+        is_tombstone: bool,
+    ) -> Result<()> {
         // rough estimate of the RegisterCmd
         let required_space = size_of::<RegisterCmd>();
-        if !self.used_space.can_add(required_space) {
+        // deleting a Register shall be possible even when we are running out of space,
+        // since it leads to space being freed up, but only once verified against its owner,
+        // otherwise anyone could fill up the storage with Delete cmds
+        if !is_tombstone && !self.used_space.can_add(required_space) {
             return Err(Error::NotEnoughSpace);
         }
 
         let reg_cmd_id = register_operation_id(cmd)?;
-        let path = path.join(reg_cmd_id.clone());
+        let key = ItemKey::entry(reg_id, reg_cmd_id.clone());
         let addr = cmd.dst_address();
 
-        trace!(
-            "Writing cmd register log for {addr:?} at {}",
-            path.display()
-        );
+        trace!("Writing cmd register log for {addr:?} at {key:?}");
 
         let entry_hash = if let RegisterCmd::Edit(edit_cmd) = cmd {
             let entry_hash = EntryHash(edit_cmd.op.edit.crdt_op.hash());
             trace!(
-                "Writing RegisterEdit cmd log for {addr:?}, entry hash: {entry_hash}, at {}",
-                path.display()
+                "Writing RegisterEdit cmd log for {addr:?}, entry hash: {entry_hash}, at {key:?}"
             );
             Some(entry_hash)
         } else {
-            trace!(
-                "Writing RegisterCreate cmd log for {addr:?} at {}",
-                path.display()
-            );
+            let cmd_kind = match cmd {
+                RegisterCmd::Delete(_) => "RegisterDelete",
+                RegisterCmd::UpdatePolicy(_) => "RegisterPolicyUpdate",
+                _ => "RegisterCreate",
+            };
+            trace!("Writing {cmd_kind} cmd log for {addr:?} at {key:?}");
             None
         };
 
         // it's deterministic, so they are exactly the same op so we can leave
-        if path.exists() {
+        if self.backend.exists(ItemKind::RegisterCmd, &key).await? {
             trace!("RegisterCmd exists on disk for {addr:?}, entry hash: {entry_hash:?}, so was not written: {cmd:?}");
             return Ok(());
         }
 
-        let mut file = File::create(&path).await?;
-
         let serialized_data = serialise(cmd)?;
-        file.write_all(&serialized_data).await?;
-        // Let's sync up OS data to disk to reduce the chances of
-        // concurrent reading failing by reading an empty/incomplete file
-        file.sync_data().await?;
+        self.backend
+            .write(ItemKind::RegisterCmd, &key, &serialized_data)
+            .await?;
 
         self.used_space.increase(required_space);
 
         trace!(
-            "RegisterCmd writing successful for {addr:?}, id {reg_cmd_id}, at {}, entry hash: {entry_hash:?}",
-            path.display()
+            "RegisterCmd writing successful for {addr:?}, id {reg_cmd_id}, at {key:?}, entry hash: {entry_hash:?}"
         );
         Ok(())
     }
@@ -255,3 +331,12 @@ fn register_operation_id(cmd: &RegisterCmd) -> Result<RegisterCmdId> {
     let id = hex::encode(output);
     Ok(id)
 }
+
+// Returns whether the Delete cmd was signed by the given owner of the Register
+fn is_signed_by(delete_cmd: &SignedRegisterDelete, owner: User) -> bool {
+    let SignedRegisterDelete { op, auth } = delete_cmd;
+    User::Key(auth.public_key) == owner
+        && serialize(op)
+            .map(|bytes| auth.clone().verify_authority(bytes).is_ok())
+            .unwrap_or(false)
+}
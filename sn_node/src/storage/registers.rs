@@ -7,6 +7,7 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 use super::{
+    backends::StorageBackend,
     register_store::{RegisterStore, StoredRegister},
     Error, Result,
 };
@@ -14,16 +15,16 @@ use super::{
 use sn_interface::{
     messaging::{
         data::{
-            CreateRegister, EditRegister, RegisterCmd, RegisterQuery, SignedRegisterCreate,
-            SignedRegisterEdit,
+            CreateRegister, RegisterCmd, RegisterQuery, SignedRegisterCreate, SignedRegisterEdit,
+            SignedRegisterPolicyUpdate,
         },
         system::NodeQueryResponse,
-        ClientAuth, SectionSig, VerifyAuthority,
+        ClientAuth, SectionSig,
     },
     types::{
-        register::{Action, EntryHash, Permissions, Policy, Register, User},
-        DataAddress, Keypair, PublicKey, RegisterAddress, ReplicatedRegisterLog,
-        SPENTBOOK_TYPE_TAG,
+        register::{in_apply_order, Action, EntryHash, Permissions, Policy, Register, User},
+        DataAddress, Error as NetworkDataError, Keypair, PublicKey, RegisterAddress,
+        ReplicatedRegisterLog, SPENTBOOK_TYPE_TAG,
     },
 };
 
@@ -32,13 +33,12 @@ use bincode::serialize;
 use std::{
     collections::BTreeMap,
     fmt::{self, Display, Formatter},
-    path::Path,
+    sync::Arc,
+    time::Duration,
 };
 use tracing::info;
 use xor_name::XorName;
 
-const REGISTER_STORE_DIR_NAME: &str = "register";
-
 /// Operations over the Register data type and its storage.
 #[derive(Debug, Clone)]
 pub(super) struct RegisterStorage {
@@ -46,10 +46,10 @@ pub(super) struct RegisterStorage {
 }
 
 impl RegisterStorage {
-    /// Create new `RegisterStorage`
-    pub(super) fn new(path: &Path, used_space: UsedSpace) -> Result<Self> {
-        let file_store = RegisterStore::new(path.join(REGISTER_STORE_DIR_NAME), used_space)?;
-        Ok(Self { file_store })
+    /// Create new `RegisterStorage` on top of the given backend
+    pub(super) fn new(backend: Arc<dyn StorageBackend>, used_space: UsedSpace) -> Self {
+        let file_store = RegisterStore::new(backend, used_space);
+        Self { file_store }
     }
 
     #[allow(dead_code)]
@@ -68,37 +68,91 @@ impl RegisterStorage {
         address: &RegisterAddress,
     ) -> Result<ReplicatedRegisterLog> {
         let stored_reg = self.try_load_stored_register(address).await?;
+        let is_deleted = stored_reg.tombstone().is_some();
         // Build the replicated register log assuming ops stored are all valid and correctly
         // signed since we performed such validations before storing them.
+        // The content of a deleted Register is not replicated anymore, but only its
+        // creation and tombstone cmds, so other nodes also stop serving it.
+        let op_log = stored_reg
+            .op_log
+            .into_iter()
+            .filter(|cmd| !is_deleted || !matches!(cmd, RegisterCmd::Edit(_)))
+            .collect();
+
         Ok(ReplicatedRegisterLog {
             address: *address,
-            op_log: stored_reg.op_log,
+            op_log,
         })
     }
 
+    /// Removes the entries of those Registers which were deleted by their owners
+    /// at least the given grace period ago, releasing the space they were using.
+    /// The creation and tombstone cmds are kept, so stale replicas held by
+    /// other nodes cannot bring a deleted Register back to life.
+    /// Returns the number of Registers whose space was reclaimed.
+    pub(super) async fn reclaim_deleted(&self, grace_period: Duration) -> Result<usize> {
+        let mut reclaimed = 0;
+        for address in self.addrs().await {
+            let stored_reg = self.file_store.open_reg_log_from_disk(&address).await?;
+            let tombstone = match stored_reg.tombstone() {
+                Some(tombstone) => RegisterCmd::Delete(tombstone.clone()),
+                None => continue,
+            };
+
+            let edits = stored_reg
+                .op_log
+                .iter()
+                .filter(|cmd| matches!(cmd, RegisterCmd::Edit(_)))
+                .cloned()
+                .collect::<Vec<_>>();
+            if edits.is_empty() {
+                continue;
+            }
+
+            let age = self
+                .file_store
+                .cmd_age(&tombstone, stored_reg.op_log_id)
+                .await?;
+            if age < grace_period {
+                continue;
+            }
+
+            let removed = self
+                .file_store
+                .remove_cmds(&edits, stored_reg.op_log_id)
+                .await?;
+            debug!("Reclaimed space of {removed} cmd/s from deleted Register {address:?}");
+            reclaimed += 1;
+        }
+
+        Ok(reclaimed)
+    }
+
     /// Update our Register's replica on receiving data from other nodes.
     pub(super) async fn update(&self, data: &ReplicatedRegisterLog) -> Result<()> {
         debug!("Updating Register store: {:?}", data.address);
         let mut stored_reg = self.try_load_stored_register(&data.address).await?;
 
+        // the policy updates are applied before the edits, which are each checked against
+        // the version of the policy they reference, thus the order they are received in
+        // doesn't change which of them are accepted
         let mut log_to_write = Vec::new();
-        for replicated_cmd in &data.op_log {
-            if let Err(err) = self
+        for replicated_cmd in in_apply_order(&data.op_log) {
+            match self
                 .try_to_apply_cmd_against_register_state(replicated_cmd, &mut stored_reg)
                 .await
             {
-                warn!(
+                Ok(()) => log_to_write.push(replicated_cmd.clone()),
+                Err(err) => warn!(
                     "Discarding ReplicatedRegisterLog cmd {:?}: {:?}",
                     replicated_cmd, err
-                );
-            } else {
-                log_to_write.push(replicated_cmd.clone());
+                ),
             }
         }
 
         // Write the new cmds all to disk
         self.file_store
-            .write_log_to_disk(&log_to_write, &stored_reg.op_log_path)
+            .write_log_to_disk(&log_to_write, stored_reg.op_log_id, stored_reg.tombstone())
             .await
     }
 
@@ -110,12 +164,26 @@ impl RegisterStorage {
         // we have in local storage, to then try to apply the new command onto it.
         let mut stored_reg = self.try_load_stored_register(&cmd.dst_address()).await?;
 
+        // A new edit has to reference the current version of the policy, otherwise a user
+        // whose permissions were revoked could keep writing by referencing a previous one.
+        if let (RegisterCmd::Edit(SignedRegisterEdit { op, .. }), Some(register)) =
+            (cmd, &stored_reg.state)
+        {
+            if op.stamp.policy_version != register.policy_version() {
+                return Err(Error::NetworkData(NetworkDataError::InvalidStamp(op.stamp)));
+            }
+        }
+
         self.try_to_apply_cmd_against_register_state(cmd, &mut stored_reg)
             .await?;
 
         // Everything went fine, let's write the single cmd to disk
         self.file_store
-            .write_log_to_disk(&vec![cmd.clone()], &stored_reg.op_log_path)
+            .write_log_to_disk(
+                &vec![cmd.clone()],
+                stored_reg.op_log_id,
+                stored_reg.tombstone(),
+            )
             .await
     }
 
@@ -126,6 +194,7 @@ impl RegisterStorage {
         use RegisterQuery::*;
         match read {
             Get(address) => self.get(*address, requester).await,
+            GetLog(address) => self.get_log(*address, requester).await,
             Read(address) => self.read_register(*address, requester).await,
             GetOwner(address) => self.get_owner(*address, requester).await,
             GetEntry { address, hash } => self.get_entry(*address, *hash, requester).await,
@@ -133,6 +202,7 @@ impl RegisterStorage {
             GetUserPermissions { address, user } => {
                 self.get_user_permissions(*address, *user, requester).await
             }
+            Watch(address) => self.watch(*address, requester).await,
         }
     }
 
@@ -144,9 +214,15 @@ impl RegisterStorage {
         requester: User,
     ) -> Result<Register> {
         let stored_reg = self.try_load_stored_register(address).await?;
+        if stored_reg.tombstone().is_some() {
+            return Err(Error::RegisterDeleted(*address));
+        }
+
         if let Some(register) = stored_reg.state {
+            // queries are not replicated, so they're simply evaluated as of now
+            let stamp = register.stamp();
             register
-                .check_permissions(action, Some(requester))
+                .check_permissions(action, Some(requester), stamp)
                 .map_err(Error::from)?;
 
             Ok(register)
@@ -168,6 +244,19 @@ impl RegisterStorage {
         NodeQueryResponse::GetRegister(result)
     }
 
+    /// Get the log of signed cmds of the Register, for the requester to verify them.
+    async fn get_log(&self, address: RegisterAddress, requester: User) -> NodeQueryResponse {
+        let result = match self.get_register(&address, Action::Read, requester).await {
+            Ok(_) => self.get_register_replica(&address).await,
+            Err(error) => Err(error),
+        };
+
+        NodeQueryResponse::GetRegisterLog(result.map_err(|error| {
+            error!("Error reading register log from disk {error:?}");
+            error.into()
+        }))
+    }
+
     async fn read_register(&self, address: RegisterAddress, requester: User) -> NodeQueryResponse {
         let result = match self.get_register(&address, Action::Read, requester).await {
             Ok(register) => Ok(register.read()),
@@ -235,6 +324,16 @@ impl RegisterStorage {
         NodeQueryResponse::GetRegisterPolicy(result)
     }
 
+    // The Register is watched by the Elders, we only check it can be read.
+    async fn watch(&self, address: RegisterAddress, requester: User) -> NodeQueryResponse {
+        let result = match self.get_register(&address, Action::Read, requester).await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(error.into()),
+        };
+
+        NodeQueryResponse::WatchRegister(result)
+    }
+
     // ========================================================================
     // =========================== Helpers ====================================
     // ========================================================================
@@ -279,91 +378,85 @@ impl RegisterStorage {
         // it can be reconstructed from all cmds we hold in the log. If this is a 'Register create'
         // cmd let's verify it's valid before accepting it, however 'Edits cmds' cannot be
         // verified untill we have the `Register create` cmd.
+        // Once deleted, a Register doesn't accept any further edits nor policy updates.
+        if matches!(cmd, RegisterCmd::Edit(_) | RegisterCmd::UpdatePolicy(_))
+            && stored_reg.tombstone().is_some()
+        {
+            return Err(Error::RegisterDeleted(cmd.dst_address()));
+        }
+        // A policy update we already hold was applied when the state was loaded,
+        // and it cannot be applied twice as it bumps the policy version.
+        if matches!(cmd, RegisterCmd::UpdatePolicy(_)) && stored_reg.op_log.contains(cmd) {
+            return Ok(());
+        }
+
         match (stored_reg.state.as_mut(), cmd) {
-            (Some(ref mut register), cmd) => self.apply(cmd, register).await?,
-            (None, RegisterCmd::Create { cmd, .. }) => {
+            (Some(register), RegisterCmd::UpdatePolicy(SignedRegisterPolicyUpdate { op, .. }))
+                if op.version <= register.policy_version() =>
+            {
+                // it competes with the update we applied with the same version, only the one
+                // coming first in the order of the log is kept, so let's rebuild the state
+                // from the log including this one to find out which
+                verify_signature(cmd)?;
+                register.check_cmd_permissions(cmd)?;
+                let mut op_log = stored_reg.op_log.clone();
+                op_log.push(cmd.clone());
+                *register = rebuild_register(&op_log, op.address)?;
+            }
+            (Some(ref mut register), cmd) => {
+                verify_signature(cmd)?;
+                apply_permitted(cmd, register)?;
+            }
+            (
+                None,
+                RegisterCmd::Create {
+                    cmd: create_cmd, ..
+                },
+            ) => {
                 // the target Register is not in our store or we don't have the 'Register create',
                 // let's verify the create cmd we received is valid and try to apply stored cmds we may have.
-                let SignedRegisterCreate { op, auth } = cmd;
-                let public_key = auth.public_key;
-                let _ = auth
-                    .clone()
-                    .verify_authority(serialize(op)?)
-                    .or(Err(Error::InvalidSignature(public_key)))?;
+                verify_signature(cmd)?;
 
                 trace!("Creating new register: {:?}", cmd.dst_address());
                 // let's do a final check, let's try to apply all cmds to it,
                 // those which are new cmds were not validated yet, so let's do it now.
+                let SignedRegisterCreate { op, .. } = create_cmd;
                 let mut register =
                     Register::new(*op.policy.owner(), op.name, op.tag, op.policy.clone());
-
-                for cmd in &stored_reg.op_log {
-                    self.apply(cmd, &mut register).await?;
+                for cmd in in_apply_order(&stored_reg.op_log) {
+                    let result =
+                        verify_signature(cmd).and_then(|()| apply_permitted(cmd, &mut register));
+                    match result {
+                        Ok(()) => {}
+                        // a Delete cmd stored before the creation wasn't verified, and it's
+                        // simply not honoured if invalid, see `StoredRegister::tombstone`
+                        Err(err) if matches!(cmd, RegisterCmd::Delete(_)) => {
+                            warn!("Ignoring invalid Delete cmd held for Register: {err:?}");
+                        }
+                        Err(err) => return Err(err),
+                    }
                 }
 
                 stored_reg.state = Some(register);
             }
-            (None, _edit_cmd) => { /* we cannot validate it right now, but we'll store it */ }
+            (None, _edit_delete_or_policy_cmd) => {
+                /* we cannot validate it right now, but we'll store it */
+            }
         }
 
         stored_reg.op_log.push(cmd.clone());
         Ok(())
     }
 
-    // Try to apply the provided cmd to the register state, performing all op validations
-    async fn apply(&self, cmd: &RegisterCmd, register: &mut Register) -> Result<()> {
-        let addr = cmd.dst_address();
-        if &addr != register.address() {
-            return Err(Error::RegisterAddrMismatch {
-                cmd_dst_addr: addr,
-                reg_addr: *register.address(),
-            });
-        }
-
-        match cmd {
-            RegisterCmd::Create { .. } => Err(Error::DataExists(DataAddress::Register(addr))),
-            RegisterCmd::Edit(SignedRegisterEdit { op, auth }) => {
-                let public_key = auth.public_key;
-                let _ = auth
-                    .clone()
-                    .verify_authority(serialize(op)?)
-                    .or(Err(Error::InvalidSignature(public_key)))?;
-
-                info!("Editing Register: {:?}", addr);
-                register.check_permissions(Action::Write, Some(User::Key(public_key)))?;
-                let result = register
-                    .apply_op(op.edit.clone())
-                    .map_err(Error::NetworkData);
-
-                match result {
-                    Ok(()) => {
-                        trace!("Editing Register success: {:?}", addr);
-                        Ok(())
-                    }
-                    Err(err) => {
-                        trace!("Editing Register failed {:?}: {:?}", addr, err);
-                        Err(err)
-                    }
-                }
-            }
-        }
-    }
-
     // Gets stored register log from disk, trying to reconstruct the Register
-    // Note this doesn't perform any cmd sig/perms validation, it's only used when the log
-    // is read from disk which has already been validated before storing it.
+    // Note this doesn't verify the signatures of the cmds, as the log read from disk has already
+    // been verified before storing it, but the cmds are checked against the policy, so the state
+    // is the same whatever the order the cmds were received in.
     async fn try_load_stored_register(&self, addr: &RegisterAddress) -> Result<StoredRegister> {
         let mut stored_reg = self.file_store.open_reg_log_from_disk(addr).await?;
         // if we have the Register creation cmd, apply all ops to reconstruct the Register
-        if let Some(register) = &mut stored_reg.state {
-            for cmd in &stored_reg.op_log {
-                if let RegisterCmd::Edit(SignedRegisterEdit { op, .. }) = cmd {
-                    let EditRegister { edit, .. } = op;
-                    register
-                        .apply_op(edit.clone())
-                        .map_err(Error::NetworkData)?;
-                }
-            }
+        if stored_reg.state.is_some() {
+            stored_reg.state = Some(rebuild_register(&stored_reg.op_log, *addr)?);
         }
 
         Ok(stored_reg)
@@ -376,6 +469,46 @@ impl Display for RegisterStorage {
     }
 }
 
+// Reconstructs the Register from the cmds of its log, which were verified before being stored.
+fn rebuild_register(op_log: &[RegisterCmd], address: RegisterAddress) -> Result<Register> {
+    Register::from_log(op_log).ok_or(Error::RegisterNotFound(address))
+}
+
+// Verify the signature of the cmd against the key of its signer.
+fn verify_signature(cmd: &RegisterCmd) -> Result<()> {
+    cmd.verify_signature()
+        .or(Err(Error::InvalidSignature(cmd.signer())))
+}
+
+// Try to apply the provided cmd to the register state, performing all op validations
+// but the verification of its signature.
+fn apply_permitted(cmd: &RegisterCmd, register: &mut Register) -> Result<()> {
+    let addr = cmd.dst_address();
+    if &addr != register.address() {
+        return Err(Error::RegisterAddrMismatch {
+            cmd_dst_addr: addr,
+            reg_addr: *register.address(),
+        });
+    }
+
+    match cmd {
+        RegisterCmd::Create { .. } => return Err(Error::DataExists(DataAddress::Register(addr))),
+        RegisterCmd::Edit(_) => info!("Editing Register: {:?}", addr),
+        RegisterCmd::Delete(_) => info!("Deleting Register: {:?}", addr),
+        RegisterCmd::UpdatePolicy(SignedRegisterPolicyUpdate { op, .. }) => info!(
+            "Updating policy of Register {:?} to version {}",
+            addr, op.version
+        ),
+    }
+
+    let result = register.apply_cmd(cmd).map_err(Error::NetworkData);
+    match &result {
+        Ok(()) => trace!("Applying cmd to Register success: {:?}", addr),
+        Err(err) => trace!("Applying cmd to Register failed {:?}: {:?}", addr, err),
+    }
+    result
+}
+
 // Helper functions temporarily used for spentbook logic, but also used for tests.
 // This shouldn't be required outside of tests once we have a Spentbook data type.
 fn create_reg_w_policy(
@@ -411,23 +544,28 @@ fn section_sig() -> SectionSig {
 
 #[cfg(test)]
 mod test {
-    use super::{create_reg_w_policy, Error, RegisterStorage, UsedSpace};
+    use super::{create_reg_w_policy, Error, RegisterStorage, ReplicatedRegisterLog, UsedSpace};
+    use crate::storage::backends::{open_backend, StorageBackendKind};
     use sn_interface::{
         messaging::{
-            data::{EditRegister, RegisterCmd, RegisterQuery, SignedRegisterEdit},
+            data::{
+                DeleteRegister, EditRegister, Error as ErrorMsg, RegisterCmd, RegisterQuery,
+                SignedRegisterDelete, SignedRegisterEdit, SignedRegisterPolicyUpdate,
+                UpdateRegisterPolicy,
+            },
             system::NodeQueryResponse,
             ClientAuth,
         },
         types::{
-            register::{EntryHash, Policy, Register, User},
-            DataAddress, Keypair,
+            register::{EntryHash, Permissions, Policy, Register, Stamp, User},
+            DataAddress, Error as NetworkDataError, Keypair, RegisterAddress,
         },
     };
 
     use bincode::serialize;
     use eyre::{bail, eyre, Result};
     use rand::{distributions::Alphanumeric, Rng};
-    use std::collections::BTreeSet;
+    use std::{collections::BTreeSet, mem::size_of, time::Duration};
     use tempfile::tempdir;
     use xor_name::XorName;
 
@@ -437,21 +575,21 @@ mod test {
 
         let (cmd_create, _, keypair, name, policy) = create_register()?;
         let addr = cmd_create.dst_address();
-        let log_path = store.file_store.address_to_filepath(&addr)?;
+        let log_id = store.file_store.address_to_id(&addr)?;
         let mut register = Register::new(*policy.owner(), name, 0, policy);
 
         let stored_reg = store.try_load_stored_register(&addr).await?;
         // it should *not* contain the create cmd
         assert!(stored_reg.state.is_none());
         assert!(stored_reg.op_log.is_empty());
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
 
         store.write(&cmd_create).await?;
         let stored_reg = store.try_load_stored_register(&addr).await?;
         // it should contain the create cmd
         assert_eq!(stored_reg.state.as_ref(), Some(&register));
         assert_eq!(stored_reg.op_log, vec![cmd_create.clone()]);
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
         assert_eq!(stored_reg.state.map(|reg| reg.size()), Some(0));
 
         // let's now edit the register
@@ -469,7 +607,7 @@ mod test {
                 .all(|op| [&cmd_create, &cmd_edit].contains(&op)),
             "Op log doesn't match"
         );
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
         assert_eq!(stored_reg.state.map(|reg| reg.size()), Some(1));
 
         Ok(())
@@ -481,7 +619,7 @@ mod test {
 
         let (cmd_create, _, keypair, name, policy) = create_register()?;
         let addr = cmd_create.dst_address();
-        let log_path = store.file_store.address_to_filepath(&addr)?;
+        let log_id = store.file_store.address_to_id(&addr)?;
         let mut register = Register::new(*policy.owner(), name, 0, policy);
 
         // let's first store an edit cmd for the register
@@ -492,7 +630,7 @@ mod test {
         // it should contain the edit cmd only
         assert_eq!(stored_reg.state, None);
         assert_eq!(stored_reg.op_log, vec![cmd_edit.clone()]);
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
 
         // and now store the create cmd for the register
         store.write(&cmd_create).await?;
@@ -508,7 +646,7 @@ mod test {
                 .all(|op| [&cmd_create, &cmd_edit].contains(&op)),
             "Op log doesn't match"
         );
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
         assert_eq!(stored_reg.state.map(|reg| reg.size()), Some(1));
 
         Ok(())
@@ -520,7 +658,7 @@ mod test {
 
         let (cmd_create, _, keypair, name, policy) = create_register()?;
         let addr = cmd_create.dst_address();
-        let log_path = store.file_store.address_to_filepath(&addr)?;
+        let log_id = store.file_store.address_to_id(&addr)?;
         let mut register = Register::new(*policy.owner(), name, 0, policy);
         let mut stored_reg = store.try_load_stored_register(&addr).await?;
 
@@ -531,7 +669,7 @@ mod test {
         // it should contain the create cmd
         assert_eq!(stored_reg.state.as_ref(), Some(&register));
         assert_eq!(stored_reg.op_log, vec![cmd_create.clone()]);
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
         assert_eq!(stored_reg.state.as_ref().map(|reg| reg.size()), Some(0));
 
         // apply the create cmd again should fail with DataExists
@@ -561,7 +699,7 @@ mod test {
                 .all(|op| [&cmd_create, &cmd_edit].contains(&op)),
             "Op log doesn't match"
         );
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
         assert_eq!(stored_reg.state.as_ref().map(|reg| reg.size()), Some(1));
 
         // applying the edit cmd again shouldn't fail or alter the register content,
@@ -578,7 +716,7 @@ mod test {
                 .all(|op| [&cmd_create, &cmd_edit].contains(&op)),
             "Op log doesn't match"
         );
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
         assert_eq!(stored_reg.state.map(|reg| reg.size()), Some(1));
 
         Ok(())
@@ -590,7 +728,7 @@ mod test {
 
         let (cmd_create, _, keypair, name, policy) = create_register()?;
         let addr = cmd_create.dst_address();
-        let log_path = store.file_store.address_to_filepath(&addr)?;
+        let log_id = store.file_store.address_to_id(&addr)?;
         let mut register = Register::new(*policy.owner(), name, 0, policy);
         let mut stored_reg = store.try_load_stored_register(&addr).await?;
 
@@ -602,7 +740,7 @@ mod test {
         // it should contain the edit cmd
         assert_eq!(stored_reg.state, None);
         assert_eq!(stored_reg.op_log, vec![cmd_edit.clone()]);
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
 
         // applying the edit cmd again shouldn't fail,
         // although the log will contain the edit cmd duplicated
@@ -615,7 +753,7 @@ mod test {
             stored_reg.op_log.iter().all(|op| op == &cmd_edit),
             "Op log doesn't match"
         );
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
 
         // let's apply the create cmd now
         store
@@ -631,7 +769,7 @@ mod test {
                 .all(|op| [&cmd_create, &cmd_edit].contains(&op)),
             "Op log doesn't match"
         );
-        assert_eq!(stored_reg.op_log_path, log_path);
+        assert_eq!(stored_reg.op_log_id, log_id);
         assert_eq!(stored_reg.state.as_ref().map(|reg| reg.size()), Some(1));
 
         // apply the create cmd again should fail with DataExists
@@ -813,11 +951,251 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_register_delete() -> Result<()> {
+        let store = new_store()?;
+
+        let (cmd_create, authority, keypair, name, policy) = create_register()?;
+        let addr = cmd_create.dst_address();
+        let mut register = Register::new(*policy.owner(), name, 0, policy);
+        store.write(&cmd_create).await?;
+        store
+            .write(&edit_register(&mut register, &keypair)?)
+            .await?;
+
+        // only the owner can delete it
+        let (_, other_keypair) = random_user();
+        match store.write(&delete_register(addr, &other_keypair)?).await {
+            Ok(()) => bail!("An error should occur for this test case"),
+            Err(err) => assert_eq!(
+                ErrorMsg::from(err),
+                ErrorMsg::AccessDenied(User::Key(other_keypair.public_key()))
+            ),
+        }
+
+        let cmd_delete = delete_register(addr, &keypair)?;
+        store.write(&cmd_delete).await?;
+
+        // it's not served anymore
+        match store.read(&RegisterQuery::Get(addr), authority).await {
+            NodeQueryResponse::GetRegister(Err(err)) => {
+                assert_eq!(err, ErrorMsg::DataNotFound(DataAddress::Register(addr)))
+            }
+            other => bail!("Deleted register shouldn't be readable: {:?}", other),
+        }
+
+        // and it doesn't accept edits anymore
+        match store.write(&edit_register(&mut register, &keypair)?).await {
+            Ok(()) => bail!("An error should occur for this test case"),
+            Err(Error::RegisterDeleted(reported_addr)) => assert_eq!(addr, reported_addr),
+            Err(err) => bail!("A Error::RegisterDeleted variant was expected: {:?}", err),
+        }
+
+        // its content is not replicated, only its creation and tombstone
+        let replica = store.get_register_replica(&addr).await?;
+        assert_eq!(replica.op_log.len(), 2);
+        assert!(replica.op_log.contains(&cmd_create));
+        assert!(replica.op_log.contains(&cmd_delete));
+
+        // the tombstone is honoured by the nodes it's replicated to
+        let new_store = new_store()?;
+        new_store.update(&replica).await?;
+        match new_store.read(&RegisterQuery::Get(addr), authority).await {
+            NodeQueryResponse::GetRegister(Err(err)) => {
+                assert_eq!(err, ErrorMsg::DataNotFound(DataAddress::Register(addr)))
+            }
+            other => bail!("Deleted register shouldn't be readable: {:?}", other),
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_unverified_delete_is_not_honoured() -> Result<()> {
+        let store = new_store()?;
+
+        let (cmd_create, authority, keypair, name, policy) = create_register()?;
+        let addr = cmd_create.dst_address();
+        let mut register = Register::new(*policy.owner(), name, 0, policy);
+
+        // a Delete cmd not signed by the owner, received before the creation cmd,
+        // is stored as it cannot be verified yet
+        let (_, other_keypair) = random_user();
+        store.write(&delete_register(addr, &other_keypair)?).await?;
+        store.write(&cmd_create).await?;
+
+        // but it neither hides the Register nor blocks edits to it
+        match store.read(&RegisterQuery::Get(addr), authority).await {
+            NodeQueryResponse::GetRegister(Ok(_)) => {}
+            other => bail!("Register should be readable: {:?}", other),
+        }
+        store
+            .write(&edit_register(&mut register, &keypair)?)
+            .await?;
+        let replica = store.get_register_replica(&addr).await?;
+        assert!(replica
+            .op_log
+            .iter()
+            .any(|cmd| matches!(cmd, RegisterCmd::Edit(_))));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_reclaim_deleted() -> Result<()> {
+        let tmp_dir = tempdir()?;
+        let used_space = UsedSpace::new(100 * size_of::<RegisterCmd>());
+        let backend = open_backend(StorageBackendKind::Filesystem, tmp_dir.path())?;
+        let store = RegisterStorage::new(backend, used_space.clone());
+
+        let (cmd_create, _, keypair, name, policy) = create_register()?;
+        let addr = cmd_create.dst_address();
+        let mut register = Register::new(*policy.owner(), name, 0, policy);
+        store.write(&cmd_create).await?;
+        for _ in 0..10 {
+            store
+                .write(&edit_register(&mut register, &keypair)?)
+                .await?;
+        }
+        store.write(&delete_register(addr, &keypair)?).await?;
+        assert_eq!(used_space.ratio(), 0.12);
+
+        // nothing is reclaimed within the grace period
+        assert_eq!(store.reclaim_deleted(Duration::from_secs(3600)).await?, 0);
+        assert_eq!(used_space.ratio(), 0.12);
+
+        assert_eq!(store.reclaim_deleted(Duration::ZERO).await?, 1);
+        assert_eq!(used_space.ratio(), 0.02);
+        let stored_reg = store.try_load_stored_register(&addr).await?;
+        assert_eq!(stored_reg.op_log.len(), 2);
+
+        // the edits can't be brought back by stale replicas
+        let stale_edit = edit_register(&mut register, &keypair)?;
+        store
+            .update(&ReplicatedRegisterLog {
+                address: addr,
+                op_log: vec![cmd_create, stale_edit],
+            })
+            .await?;
+        assert_eq!(used_space.ratio(), 0.02);
+        assert_eq!(store.reclaim_deleted(Duration::ZERO).await?, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_register_update_policy() -> Result<()> {
+        let store = new_store()?;
+
+        let (cmd_create, owner, keypair, name, policy) = create_register()?;
+        let addr = cmd_create.dst_address();
+        let mut register = Register::new(owner, name, 0, policy);
+        store.write(&cmd_create).await?;
+
+        let (writer, writer_keypair) = random_user();
+        match store
+            .write(&edit_register(&mut register, &writer_keypair)?)
+            .await
+        {
+            Ok(()) => bail!("An error should occur for this test case"),
+            Err(err) => assert_eq!(ErrorMsg::from(err), ErrorMsg::AccessDenied(writer)),
+        }
+
+        // only the owner, or an admin, can update the policy
+        let mut policy = Policy {
+            owner,
+            permissions: [(writer, Permissions::new(true))].into(),
+        };
+        match store
+            .write(&update_register_policy(
+                addr,
+                policy.clone(),
+                1,
+                &writer_keypair,
+            )?)
+            .await
+        {
+            Ok(()) => bail!("An error should occur for this test case"),
+            Err(err) => assert_eq!(ErrorMsg::from(err), ErrorMsg::AccessDenied(writer)),
+        }
+
+        let cmd_grant = update_register_policy(addr, policy.clone(), 1, &keypair)?;
+        store.write(&cmd_grant).await?;
+        register.update_policy(policy.clone(), 1)?;
+        // the same update can be sent again
+        store.write(&cmd_grant).await?;
+        // another update with the same version is accepted too, but only one of them is
+        // kept, the same on all nodes
+        let mut other_policy = policy.clone();
+        let _ = other_policy
+            .permissions
+            .insert(random_user().0, Permissions::new(true));
+        let cmd_competing = update_register_policy(addr, other_policy, 1, &keypair)?;
+        store.write(&cmd_competing).await?;
+
+        let cmd_edit = edit_register(&mut register, &writer_keypair)?;
+        store.write(&cmd_edit).await?;
+
+        let _ = policy.permissions.insert(writer, Permissions::new(false));
+        let cmd_revoke = update_register_policy(addr, policy.clone(), 2, &keypair)?;
+        store.write(&cmd_revoke).await?;
+        // a new edit cannot reference a previous version of the policy
+        let cmd_stale = edit_register(&mut register, &writer_keypair)?;
+        match store.write(&cmd_stale).await {
+            Ok(()) => bail!("An error should occur for this test case"),
+            Err(Error::NetworkData(err)) => {
+                assert!(matches!(err, NetworkDataError::InvalidStamp(_)))
+            }
+            Err(err) => bail!("A Error::NetworkData variant was expected: {:?}", err),
+        }
+        register.update_policy(policy, 2)?;
+        match store
+            .write(&edit_register(&mut register, &writer_keypair)?)
+            .await
+        {
+            Ok(()) => bail!("An error should occur for this test case"),
+            Err(err) => assert_eq!(ErrorMsg::from(err), ErrorMsg::AccessDenied(writer)),
+        }
+
+        // the updates are applied in order on the nodes the log is replicated to,
+        // whatever the order they receive the cmds in
+        let stored_reg = store.try_load_stored_register(&addr).await?;
+        let state = stored_reg
+            .state
+            .ok_or_else(|| eyre!("Register not found"))?;
+        let mut op_log = vec![cmd_edit, cmd_revoke, cmd_competing, cmd_grant, cmd_create];
+        for _ in 0..2 {
+            let new_store = new_store()?;
+            new_store
+                .update(&ReplicatedRegisterLog {
+                    address: addr,
+                    op_log: op_log.clone(),
+                })
+                .await?;
+            let stored_reg = new_store.try_load_stored_register(&addr).await?;
+            let replica = stored_reg
+                .state
+                .ok_or_else(|| eyre!("Register not found"))?;
+            assert_eq!(replica.policy_version(), 2);
+            assert_eq!(replica.size(), 1);
+            assert_eq!(
+                replica.permissions(writer)?,
+                Permissions::new(false),
+                "The writer's permission should be revoked"
+            );
+            assert_eq!(replica.policy_at(1), state.policy_at(1));
+            op_log.reverse();
+        }
+
+        Ok(())
+    }
+
     fn new_store() -> Result<RegisterStorage> {
         let tmp_dir = tempdir()?;
         let path = tmp_dir.path();
         let used_space = UsedSpace::new(usize::MAX);
-        let store = RegisterStorage::new(path, used_space)?;
+        let backend = open_backend(StorageBackendKind::Filesystem, path)?;
+        let store = RegisterStorage::new(backend, used_space);
         Ok(store)
     }
 
@@ -839,15 +1217,54 @@ mod test {
         Ok((cmd, authority, keypair, xorname, policy))
     }
 
+    fn delete_register(address: RegisterAddress, keypair: &Keypair) -> Result<RegisterCmd> {
+        let op = DeleteRegister { address };
+        let signature = keypair.sign(&serialize(&op)?);
+
+        Ok(RegisterCmd::Delete(SignedRegisterDelete {
+            op,
+            auth: ClientAuth {
+                public_key: keypair.public_key(),
+                signature,
+            },
+        }))
+    }
+
+    fn update_register_policy(
+        address: RegisterAddress,
+        policy: Policy,
+        version: u64,
+        keypair: &Keypair,
+    ) -> Result<RegisterCmd> {
+        let op = UpdateRegisterPolicy {
+            address,
+            policy,
+            version,
+            stamp: Stamp::now(0, version - 1),
+        };
+        let signature = keypair.sign(&serialize(&op)?);
+
+        Ok(RegisterCmd::UpdatePolicy(SignedRegisterPolicyUpdate {
+            op,
+            auth: ClientAuth {
+                public_key: keypair.public_key(),
+                signature,
+            },
+        }))
+    }
+
     fn edit_register(register: &mut Register, keypair: &Keypair) -> Result<RegisterCmd> {
         let data = rand::thread_rng()
             .sample_iter(&Alphanumeric)
             .take(15)
             .collect();
-        let (_, edit) = register.write(data, BTreeSet::default())?;
+        let author = User::Key(keypair.public_key());
+        let stamp = register.stamp();
+        let (_, edit) = register.write_as(data, BTreeSet::default(), author)?;
         let op = EditRegister {
             address: *register.address(),
             edit,
+            stamp,
         };
         let signature = keypair.sign(&serialize(&op)?);
 